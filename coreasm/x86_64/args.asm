@@ -143,14 +143,17 @@ _get_parsed_arg:
 ; Parse signed integer from string
 ; Args: rdi = string pointer
 ; Returns: rax = parsed integer (0 on empty/invalid prefix)
+; Sets _last_error to ERR_PARSE unless the whole string is digits
 global _parse_i64
 _parse_i64:
     push rbx
     push rcx
     push rdx
+    push r8
 
     xor rax, rax            ; accumulator
     xor rcx, rcx            ; sign flag (0=+,1=-)
+    xor r8, r8              ; digits seen
     mov rbx, rdi
 
     mov dl, [rbx]
@@ -164,22 +167,33 @@ _parse_i64:
     test dl, dl
     jz .parse_done
     cmp dl, '0'
-    jl .parse_done
+    jl .parse_invalid
     cmp dl, '9'
-    jg .parse_done
+    jg .parse_invalid
     imul rax, rax, 10
     sub dl, '0'
     movzx rdx, dl
     add rax, rdx
+    inc r8
     inc rbx
     jmp .parse_loop
 
+.parse_invalid:
+    mov qword [rel _last_error], ERR_PARSE
+    jmp .parse_sign
+
 .parse_done:
+    test r8, r8
+    jnz .parse_sign
+    mov qword [rel _last_error], ERR_PARSE
+
+.parse_sign:
     test rcx, rcx
     jz .parse_ret
     neg rax
 
 .parse_ret:
+    pop r8
     pop rdx
     pop rcx
     pop rbx
@@ -0,0 +1,926 @@
+; bignum.asm - Big numbers for Vox Compiler
+; Provides: whole numbers of any size - add, subtract, multiply, divide,
+; modulo, comparison, conversion to and from 64-bit numbers and text
+
+; ============================================================================
+; BIG NUMBER STRUCTURE
+; ============================================================================
+; A big number variable holds a pointer to an immutable value; every
+; operation returns a new value, so copies of the pointer never change.
+;
+;   offset 0:  capacity (8 bytes) - limbs there is room for, a power of
+;              two; 0 for constants, which are never released
+;   offset 8:  size (8 bytes) - limbs in use, 0 for zero
+;   offset 16: sign (8 bytes) - 1 if negative (never for zero)
+;   offset 24: limbs - 64-bit words, least significant first
+;
+; Values are cut from 1 MiB chunks taken from _alloc_buffer_sized, so
+; resource.asm tracks them and _cleanup_all frees them on exit. A value
+; larger than half a chunk gets a buffer of its own. The generated code
+; gives each big number variable its own value and hands the old one to
+; _big_release when the variable changes; released values wait in a list
+; per capacity for _big_alloc to reuse, cleared first.
+;
+; When memory runs out _last_error is set and the result is zero.
+
+%define BIG_CAPACITY        0
+%define BIG_SIZE            8
+%define BIG_SIGN            16
+%define BIG_LIMBS           24
+
+%define BIG_CHUNK_BYTES     1048576
+%define BIG_CLASSES         64          ; capacities 2^0 to 2^63 limbs
+%define BIG_TEN_POW_19      0x8AC7230489E80000  ; 10^19, the most digits per limb
+%define ENOMEM              12
+
+section .bss
+    _big_chunk_next: resq 1     ; next free byte in the current chunk
+    _big_chunk_left: resq 1     ; bytes left in the current chunk
+    _big_released: resq BIG_CLASSES ; released values by capacity, linked through their size
+
+section .data
+    _big_zero: dq 0, 0, 0       ; zero, returned when memory ran out
+
+section .text
+
+; _big_alloc - New value with room for rdi limbs (zero, limbs cleared)
+; The room is rounded up to a power of two, and a released value of that
+; capacity is reused when there is one
+; Args: rdi = limbs
+; Returns: rax = value, or 0 when memory ran out (_last_error is set)
+; Clobbers: rcx
+_big_alloc:
+    push rdi
+    push rdx
+    xor edx, edx                ; capacity class: 2^rdx limbs
+    cmp rdi, 1
+    jbe .class
+    lea rcx, [rdi - 1]
+    bsr rdx, rcx
+    inc edx
+.class:
+    mov ecx, edx
+    mov edi, 1
+    shl rdi, cl                 ; capacity
+    lea rcx, [rel _big_released]
+    lea rcx, [rcx + rdx*8]
+    mov rax, [rcx]
+    test rax, rax
+    jz .carve
+    mov rdx, [rax + BIG_SIZE]   ; the next one released
+    mov [rcx], rdx
+    push rax
+    push rdi
+    mov rcx, rdi
+    lea rdi, [rax + BIG_LIMBS]
+    xor eax, eax
+    rep stosq
+    pop rdi
+    pop rax
+    jmp .header
+.carve:
+    push rdi
+    lea rdi, [rdi*8 + BIG_LIMBS]
+    cmp rdi, [rel _big_chunk_left]
+    ja .new_chunk
+    mov rax, [rel _big_chunk_next]
+    add [rel _big_chunk_next], rdi
+    sub [rel _big_chunk_left], rdi
+    jmp .carved
+.new_chunk:
+    cmp rdi, BIG_CHUNK_BYTES / 2
+    ja .own_buffer              ; large: own buffer, the chunk stays current
+    push rdi
+    mov rdi, BIG_CHUNK_BYTES
+    call _alloc_buffer_sized
+    pop rdi
+    test rax, rax
+    jz .fail
+    add rax, BUF_DATA
+    lea rcx, [rax + rdi]
+    mov [rel _big_chunk_next], rcx
+    mov rcx, BIG_CHUNK_BYTES
+    sub rcx, rdi
+    mov [rel _big_chunk_left], rcx
+    jmp .carved
+.own_buffer:
+    call _alloc_buffer_sized
+    test rax, rax
+    jz .fail
+    add rax, BUF_DATA
+.carved:
+    pop rdi
+.header:
+    mov [rax + BIG_CAPACITY], rdi
+    mov qword [rax + BIG_SIZE], 0
+    mov qword [rax + BIG_SIGN], 0
+    pop rdx
+    pop rdi
+    ret
+.fail:
+    pop rdi
+    pop rdx
+    pop rdi
+    mov qword [rel _last_error], ENOMEM
+    xor eax, eax
+    ret
+
+; _big_release - Hands back a value nothing uses any more, for _big_alloc
+; to reuse; constants and zero pointers are left alone
+; Args: rdi = value
+; Clobbers: rcx
+_big_release:
+    test rdi, rdi
+    jz .done
+    mov rcx, [rdi + BIG_CAPACITY]
+    test rcx, rcx
+    jz .done
+    push rdx
+    bsr rcx, rcx                ; capacity class
+    lea rdx, [rel _big_released]
+    lea rcx, [rdx + rcx*8]
+    mov rdx, [rcx]
+    mov [rdi + BIG_SIZE], rdx
+    mov [rcx], rdi
+    pop rdx
+.done:
+    ret
+
+; _big_normalize - Drop leading zero limbs; zero is never negative
+; Args: rdi = value (changed in place)
+; Clobbers: rcx
+_big_normalize:
+    mov rcx, [rdi + BIG_SIZE]
+.trim:
+    test rcx, rcx
+    jz .zero
+    cmp qword [rdi + BIG_LIMBS + rcx*8 - 8], 0
+    jne .done
+    dec rcx
+    jmp .trim
+.zero:
+    mov qword [rdi + BIG_SIGN], 0
+.done:
+    mov [rdi + BIG_SIZE], rcx
+    ret
+
+; _big_from_int - Big number from a 64-bit number
+; Args: rdi = number
+; Returns: rax = value
+; Clobbers: rcx, rdi
+_big_from_int:
+    push rbx
+    mov rbx, rdi
+    mov edi, 1
+    call _big_alloc
+    test rax, rax
+    jz .fail
+    mov rcx, rbx
+    test rcx, rcx
+    jz .done                    ; zero: no limbs
+    jns .store
+    neg rcx                     ; the smallest number stays 2^63, its magnitude
+    mov qword [rax + BIG_SIGN], 1
+.store:
+    mov [rax + BIG_LIMBS], rcx
+    mov qword [rax + BIG_SIZE], 1
+.done:
+    pop rbx
+    ret
+.fail:
+    lea rax, [rel _big_zero]
+    pop rbx
+    ret
+
+; _big_to_int - 64-bit number from a big number
+; Args: rdi = value
+; Returns: rax = number (0 and ERR_OVERFLOW when it does not fit)
+; Clobbers: rcx
+_big_to_int:
+    xor eax, eax
+    mov rcx, [rdi + BIG_SIZE]
+    test rcx, rcx
+    jz .done
+    cmp rcx, 1
+    ja .overflow
+    mov rax, [rdi + BIG_LIMBS]
+    cmp qword [rdi + BIG_SIGN], 0
+    jne .negative
+    test rax, rax
+    js .overflow                ; 2^63 and up
+    ret
+.negative:
+    neg rax
+    jns .overflow               ; below -2^63
+.done:
+    ret
+.overflow:
+    mov qword [rel _last_error], ERR_OVERFLOW
+    xor eax, eax
+    ret
+
+; _big_compare_magnitude - Compare sizes, ignoring signs
+; Args: rdi = a, rsi = b (both left unchanged)
+; Returns: rax = -1 if |a| < |b|, 0 if equal, 1 if |a| > |b|
+; Clobbers: rcx, rdx
+_big_compare_magnitude:
+    mov rcx, [rdi + BIG_SIZE]
+    cmp rcx, [rsi + BIG_SIZE]
+    ja .greater
+    jb .less
+.limb:
+    test rcx, rcx
+    jz .equal
+    mov rdx, [rdi + BIG_LIMBS + rcx*8 - 8]
+    cmp rdx, [rsi + BIG_LIMBS + rcx*8 - 8]
+    ja .greater
+    jb .less
+    dec rcx
+    jmp .limb
+.equal:
+    xor eax, eax
+    ret
+.greater:
+    mov eax, 1
+    ret
+.less:
+    mov rax, -1
+    ret
+
+; _big_compare - Compare two big numbers
+; Args: rdi = a, rsi = b
+; Returns: rax = -1 if a < b, 0 if equal, 1 if a > b
+; Clobbers: rcx, rdx
+_big_compare:
+    mov rax, [rdi + BIG_SIGN]
+    cmp rax, [rsi + BIG_SIGN]
+    jne .signs_differ
+    call _big_compare_magnitude
+    cmp qword [rdi + BIG_SIGN], 0
+    je .done
+    neg rax                     ; both negative: larger magnitude is smaller
+.done:
+    ret
+.signs_differ:
+    test rax, rax
+    jnz .less                   ; a negative, b not
+    mov eax, 1
+    ret
+.less:
+    mov rax, -1
+    ret
+
+; _big_add_magnitudes - |a| + |b|
+; Args: rdi = a, rsi = b
+; Returns: rax = new non-negative value
+; Clobbers: rcx, rdx, rsi, rdi, r8, r9, r11
+_big_add_magnitudes:
+    push r12
+    push r13
+    mov r12, rdi
+    mov r13, rsi
+    mov rdi, [r12 + BIG_SIZE]
+    cmp rdi, [r13 + BIG_SIZE]
+    jae .sized
+    xchg r12, r13               ; r12 is the longer one
+    mov rdi, [r12 + BIG_SIZE]
+.sized:
+    inc rdi                     ; room for the last carry
+    call _big_alloc
+    test rax, rax
+    jz .fail
+    mov rsi, [r12 + BIG_SIZE]
+    mov rdx, [r13 + BIG_SIZE]
+    xor ecx, ecx
+    xor r11d, r11d              ; carry
+.limb:
+    cmp rcx, rsi
+    jae .last
+    mov r8, [r12 + BIG_LIMBS + rcx*8]
+    xor r9d, r9d
+    cmp rcx, rdx
+    jae .add
+    mov r9, [r13 + BIG_LIMBS + rcx*8]
+.add:
+    add r8, r11
+    mov r11d, 0                 ; keeps the carry flag
+    adc r11, 0
+    add r8, r9
+    adc r11, 0
+    mov [rax + BIG_LIMBS + rcx*8], r8
+    inc rcx
+    jmp .limb
+.last:
+    mov [rax + BIG_LIMBS + rcx*8], r11
+    inc rcx
+    mov [rax + BIG_SIZE], rcx
+    mov rdi, rax
+    call _big_normalize
+    mov rax, rdi
+    pop r13
+    pop r12
+    ret
+.fail:
+    lea rax, [rel _big_zero]
+    pop r13
+    pop r12
+    ret
+
+; _big_subtract_magnitudes - |a| - |b|, where |a| >= |b|
+; Args: rdi = a, rsi = b
+; Returns: rax = new non-negative value
+; Clobbers: rcx, rdx, rsi, rdi, r8, r9, r11
+_big_subtract_magnitudes:
+    push r12
+    push r13
+    mov r12, rdi
+    mov r13, rsi
+    mov rdi, [r12 + BIG_SIZE]
+    call _big_alloc
+    test rax, rax
+    jz .fail
+    mov rsi, [r12 + BIG_SIZE]
+    mov rdx, [r13 + BIG_SIZE]
+    xor ecx, ecx
+    xor r11d, r11d              ; borrow
+.limb:
+    cmp rcx, rsi
+    jae .done
+    mov r8, [r12 + BIG_LIMBS + rcx*8]
+    xor r9d, r9d
+    cmp rcx, rdx
+    jae .subtract
+    mov r9, [r13 + BIG_LIMBS + rcx*8]
+.subtract:
+    sub r8, r11
+    mov r11d, 0                 ; keeps the carry flag
+    adc r11, 0
+    sub r8, r9
+    adc r11, 0
+    mov [rax + BIG_LIMBS + rcx*8], r8
+    inc rcx
+    jmp .limb
+.done:
+    mov [rax + BIG_SIZE], rsi
+    mov rdi, rax
+    call _big_normalize
+    mov rax, rdi
+    pop r13
+    pop r12
+    ret
+.fail:
+    lea rax, [rel _big_zero]
+    pop r13
+    pop r12
+    ret
+
+; _big_add - a + b
+; Args: rdi = a, rsi = b
+; Returns: rax = new value
+; Clobbers: rcx, rdx, rsi, rdi, r8, r9, r11
+_big_add:
+    xor edx, edx
+    jmp _big_add_signed
+
+; _big_subtract - a - b
+; Args: rdi = a, rsi = b
+; Returns: rax = new value
+; Clobbers: rcx, rdx, rsi, rdi, r8, r9, r11
+_big_subtract:
+    mov edx, 1
+    jmp _big_add_signed
+
+; _big_add_signed - a + b, with b's sign flipped when rdx is 1
+_big_add_signed:
+    push rbx
+    push r12
+    push r13
+    mov r12, rdi
+    mov r13, rsi
+    mov rbx, [r13 + BIG_SIGN]
+    xor rbx, rdx                ; sign b counts with
+    cmp rbx, [r12 + BIG_SIGN]
+    jne .signs_differ
+    call _big_add_magnitudes
+    mov rcx, rbx
+    jmp .sign
+.signs_differ:
+    call _big_compare_magnitude
+    test rax, rax
+    js .b_larger
+    mov rdi, r12
+    mov rsi, r13
+    call _big_subtract_magnitudes
+    mov rcx, [r12 + BIG_SIGN]
+    jmp .sign
+.b_larger:
+    mov rdi, r13
+    mov rsi, r12
+    call _big_subtract_magnitudes
+    mov rcx, rbx
+.sign:
+    cmp qword [rax + BIG_SIZE], 0
+    je .done
+    mov [rax + BIG_SIGN], rcx
+.done:
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+; _big_copy - New value equal to a
+; Args: rdi = a
+; Returns: rax = new value
+; Clobbers: rcx, rdx, rsi, rdi
+_big_copy:
+    push r12
+    mov r12, rdi
+    mov rdi, [r12 + BIG_SIZE]
+    call _big_alloc
+    test rax, rax
+    jz .fail
+    mov rcx, [r12 + BIG_SIZE]
+    mov [rax + BIG_SIZE], rcx
+    mov rdx, [r12 + BIG_SIGN]
+    mov [rax + BIG_SIGN], rdx
+    lea rsi, [r12 + BIG_LIMBS]
+    lea rdi, [rax + BIG_LIMBS]
+    rep movsq
+    pop r12
+    ret
+.fail:
+    lea rax, [rel _big_zero]
+    pop r12
+    ret
+
+; _big_negate - -a
+; Args: rdi = a
+; Returns: rax = new value
+; Clobbers: rcx, rdx, rsi, rdi
+_big_negate:
+    call _big_copy
+    cmp qword [rax + BIG_SIZE], 0
+    je .done
+    xor qword [rax + BIG_SIGN], 1
+.done:
+    ret
+
+; _big_multiply - a * b
+; Args: rdi = a, rsi = b
+; Returns: rax = new value
+; Clobbers: rcx, rdx, rsi, rdi, r8-r11
+_big_multiply:
+    push rbx
+    push r12
+    push r13
+    mov r12, rdi
+    mov r13, rsi
+    mov rdi, [r12 + BIG_SIZE]
+    add rdi, [r13 + BIG_SIZE]
+    call _big_alloc
+    test rax, rax
+    jz .fail
+    mov rbx, rax
+    xor ecx, ecx                ; limb of a
+.row:
+    cmp rcx, [r12 + BIG_SIZE]
+    jae .finish
+    mov r8, [r12 + BIG_LIMBS + rcx*8]
+    xor r9d, r9d                ; carry
+    xor r10d, r10d              ; limb of b
+.column:
+    cmp r10, [r13 + BIG_SIZE]
+    jae .row_done
+    mov rax, [r13 + BIG_LIMBS + r10*8]
+    mul r8
+    lea r11, [rcx + r10]
+    add rax, [rbx + BIG_LIMBS + r11*8]
+    adc rdx, 0
+    add rax, r9
+    adc rdx, 0
+    mov [rbx + BIG_LIMBS + r11*8], rax
+    mov r9, rdx
+    inc r10
+    jmp .column
+.row_done:
+    lea r11, [rcx + r10]
+    mov [rbx + BIG_LIMBS + r11*8], r9
+    inc rcx
+    jmp .row
+.finish:
+    mov rcx, [r12 + BIG_SIZE]
+    add rcx, [r13 + BIG_SIZE]
+    mov [rbx + BIG_SIZE], rcx
+    mov rcx, [r12 + BIG_SIGN]
+    xor rcx, [r13 + BIG_SIGN]
+    mov [rbx + BIG_SIGN], rcx
+    mov rdi, rbx
+    call _big_normalize
+    mov rax, rbx
+    pop r13
+    pop r12
+    pop rbx
+    ret
+.fail:
+    lea rax, [rel _big_zero]
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+; _big_divide_small - Divide a value in place by a 64-bit divisor
+; Args: rdi = value, rsi = divisor (not zero)
+; Returns: rax = remainder
+; Clobbers: rcx, rdx, r8
+_big_divide_small:
+    mov rcx, [rdi + BIG_SIZE]
+    xor edx, edx
+.limb:
+    test rcx, rcx
+    jz .done
+    mov rax, [rdi + BIG_LIMBS + rcx*8 - 8]
+    div rsi
+    mov [rdi + BIG_LIMBS + rcx*8 - 8], rax
+    dec rcx
+    jmp .limb
+.done:
+    mov r8, rdx
+    call _big_normalize
+    mov rax, r8
+    ret
+
+; _big_multiply_add_small - value = value * rsi + rdx, in place
+; Args: rdi = value (room for one more limb), rsi = multiplier, rdx = addend
+; Clobbers: rax, rcx, rdx, r8
+_big_multiply_add_small:
+    mov r8, rdx                 ; carry
+    xor ecx, ecx
+.limb:
+    cmp rcx, [rdi + BIG_SIZE]
+    jae .done
+    mov rax, [rdi + BIG_LIMBS + rcx*8]
+    mul rsi
+    add rax, r8
+    adc rdx, 0
+    mov [rdi + BIG_LIMBS + rcx*8], rax
+    mov r8, rdx
+    inc rcx
+    jmp .limb
+.done:
+    test r8, r8
+    jz .ret
+    mov [rdi + BIG_LIMBS + rcx*8], r8
+    inc rcx
+    mov [rdi + BIG_SIZE], rcx
+.ret:
+    ret
+
+; _big_divide_magnitudes - |a| / |b| and |a| mod |b|
+; Args: rdi = a, rsi = b (not zero)
+; Returns: rax = quotient, rdx = remainder (signs not set)
+; Clobbers: rcx, rsi, rdi, r8-r11
+_big_divide_magnitudes:
+    push rbx
+    push rbp
+    push r12
+    push r13
+    push r14
+    push r15
+    mov r12, rdi
+    mov r13, rsi
+    cmp qword [r13 + BIG_SIZE], 1
+    ja .long
+
+    ; one-limb divisor: divide limb by limb
+    call _big_copy
+    mov r14, rax
+    mov rdi, r14
+    mov rsi, [r13 + BIG_LIMBS]
+    call _big_divide_small
+    mov rbx, rax
+    mov edi, 1
+    call _big_alloc
+    test rax, rax
+    jz .fail
+    mov [rax + BIG_LIMBS], rbx
+    mov qword [rax + BIG_SIZE], 1
+    mov rdi, rax
+    call _big_normalize
+    mov rdx, rdi
+    mov rax, r14
+    jmp .done
+
+.long:
+    ; bit by bit: shift the next bit of a into the remainder, subtract b
+    ; whenever it fits and set that bit of the quotient
+    mov rbx, [r13 + BIG_SIZE]
+    lea rdi, [rbx + 1]          ; remainder width: b plus a limb for the shift
+    call _big_alloc
+    test rax, rax
+    jz .fail
+    mov rbp, rax
+    mov rdi, [r12 + BIG_SIZE]
+    call _big_alloc
+    test rax, rax
+    jz .fail
+    mov r14, rax
+    mov r15, [r12 + BIG_SIZE]
+    shl r15, 6
+    dec r15                     ; highest bit of a
+    js .long_done
+.bit:
+    bt [r12 + BIG_LIMBS], r15
+    lea rsi, [rbp + BIG_LIMBS]
+    lea rcx, [rbx + 1]
+.shift:
+    rcl qword [rsi], 1
+    lea rsi, [rsi + 8]
+    dec rcx
+    jnz .shift
+
+    mov rcx, rbx
+    cmp qword [rbp + BIG_LIMBS + rcx*8], 0
+    jne .subtract
+.compare:
+    test rcx, rcx
+    jz .subtract                ; equal
+    mov rax, [rbp + BIG_LIMBS + rcx*8 - 8]
+    cmp rax, [r13 + BIG_LIMBS + rcx*8 - 8]
+    ja .subtract
+    jb .next_bit
+    dec rcx
+    jmp .compare
+.subtract:
+    lea rsi, [r13 + BIG_LIMBS]
+    lea rdi, [rbp + BIG_LIMBS]
+    mov rcx, rbx
+    clc
+.borrow:
+    mov rax, [rsi]
+    sbb [rdi], rax
+    lea rsi, [rsi + 8]
+    lea rdi, [rdi + 8]
+    dec rcx
+    jnz .borrow
+    sbb qword [rdi], 0
+    bts [r14 + BIG_LIMBS], r15
+.next_bit:
+    dec r15
+    jns .bit
+
+.long_done:
+    mov rcx, [r12 + BIG_SIZE]
+    mov [r14 + BIG_SIZE], rcx
+    mov rdi, r14
+    call _big_normalize
+    lea rcx, [rbx + 1]
+    mov [rbp + BIG_SIZE], rcx
+    mov rdi, rbp
+    call _big_normalize
+    mov rax, r14
+    mov rdx, rbp
+    jmp .done
+
+.fail:
+    lea rax, [rel _big_zero]
+    mov rdx, rax
+.done:
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop rbp
+    pop rbx
+    ret
+
+; _big_divide - a / b, rounded toward zero
+; Args: rdi = a, rsi = b
+; Returns: rax = new value (zero and ERR_DIVIDE_BY_ZERO when b is zero)
+; Clobbers: rcx, rdx, rsi, rdi, r8-r11
+_big_divide:
+    xor edx, edx
+    jmp _big_division
+
+; _big_modulo - a mod b, taking the sign of a
+; Args: rdi = a, rsi = b
+; Returns: rax = new value (zero and ERR_DIVIDE_BY_ZERO when b is zero)
+; Clobbers: rcx, rdx, rsi, rdi, r8-r11
+_big_modulo:
+    mov edx, 1
+    jmp _big_division
+
+; _big_division - Quotient (rdx = 0) or remainder (rdx = 1) of a and b
+_big_division:
+    push rbx
+    push r12
+    push r13
+    mov r12, rdi
+    mov r13, rsi
+    mov rbx, rdx
+    cmp qword [r13 + BIG_SIZE], 0
+    je .by_zero
+    call _big_divide_magnitudes
+    test rbx, rbx
+    jnz .remainder
+    mov rdi, rdx
+    call _big_release
+    mov rcx, [r12 + BIG_SIGN]
+    xor rcx, [r13 + BIG_SIGN]
+    jmp .sign
+.remainder:
+    mov rdi, rax
+    call _big_release
+    mov rax, rdx
+    mov rcx, [r12 + BIG_SIGN]
+.sign:
+    cmp qword [rax + BIG_SIZE], 0
+    je .done
+    mov [rax + BIG_SIGN], rcx
+.done:
+    pop r13
+    pop r12
+    pop rbx
+    ret
+.by_zero:
+    mov qword [rel _last_error], ERR_DIVIDE_BY_ZERO
+    lea rax, [rel _big_zero]
+    jmp .done
+
+; _big_parse - Big number from text: an optional '-' then digits
+; Args: rdi = null-terminated text
+; Returns: rax = new value (the digits before anything invalid)
+; Sets _last_error to ERR_PARSE unless the whole text is digits
+; Clobbers: rcx, rdx, rsi, rdi, r8
+_big_parse:
+    push rbx
+    push rbp
+    push r12
+    push r13
+    push r14
+    push r15
+    mov r12, rdi
+    xor ecx, ecx
+.length:
+    cmp byte [r12 + rcx], 0
+    je .sized
+    inc rcx
+    jmp .length
+.sized:
+    mov rax, rcx
+    xor edx, edx
+    mov ecx, 19
+    div rcx
+    lea rdi, [rax + 2]          ; 19 digits fit in each limb
+    call _big_alloc
+    test rax, rax
+    jz .fail
+    mov r13, rax
+    xor ebx, ebx                ; sign
+    xor ebp, ebp                ; digits seen
+    cmp byte [r12], '-'
+    jne .chunk
+    mov ebx, 1
+    inc r12
+.chunk:
+    xor r14d, r14d              ; value of the digits in this chunk
+    mov r15d, 1                 ; 10 to the number of them
+.digit:
+    movzx eax, byte [r12]
+    test eax, eax
+    jz .end
+    sub eax, '0'
+    cmp eax, 9
+    ja .invalid
+    imul r14, r14, 10
+    add r14, rax
+    imul r15, r15, 10
+    inc rbp
+    inc r12
+    mov rax, BIG_TEN_POW_19
+    cmp r15, rax
+    jne .digit
+    mov rdi, r13
+    mov rsi, r15
+    mov rdx, r14
+    call _big_multiply_add_small
+    jmp .chunk
+
+.invalid:
+    mov qword [rel _last_error], ERR_PARSE
+    jmp .flush
+.end:
+    test rbp, rbp
+    jnz .flush
+    mov qword [rel _last_error], ERR_PARSE
+.flush:
+    mov rdi, r13
+    mov rsi, r15
+    mov rdx, r14
+    call _big_multiply_add_small
+    cmp qword [r13 + BIG_SIZE], 0
+    je .positive
+    mov [r13 + BIG_SIGN], rbx
+.positive:
+    mov rax, r13
+    jmp .done
+.fail:
+    lea rax, [rel _big_zero]
+.done:
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop rbp
+    pop rbx
+    ret
+
+; _big_to_text - Decimal text of a big number
+; Args: rdi = value
+; Returns: rax = new text ("" when memory ran out)
+; Clobbers: rcx, rdx, rsi, rdi, r8-r11
+_big_to_text:
+    push rbx
+    push r12
+    push r13
+    push r14
+    mov r12, rdi
+    mov rdi, [r12 + BIG_SIZE]
+    imul rdi, rdi, 20           ; each limb is under 20 digits
+    add rdi, 2                  ; sign and terminator
+    call _str_alloc
+    test rax, rax
+    jz .fail
+    mov r13, rax
+    mov rdi, r12
+    call _big_copy              ; divided down to zero while writing
+    lea rcx, [rel _big_zero]
+    cmp rax, rcx
+    je .fail
+    mov r14, rax
+    mov rbx, [r12 + BIG_SIZE]
+    imul rbx, rbx, 20
+    lea rbx, [r13 + rbx + 1]    ; digits are written backwards from here
+    mov byte [rbx], 0
+.chunk:
+    mov rdi, r14
+    mov rsi, BIG_TEN_POW_19
+    call _big_divide_small
+    mov esi, 10
+    mov ecx, 19
+.digit:
+    xor edx, edx
+    div rsi
+    add dl, '0'
+    dec rbx
+    mov [rbx], dl
+    cmp qword [r14 + BIG_SIZE], 0
+    jne .pad                    ; lower chunks keep their leading zeros
+    test rax, rax
+    jz .chunk_done
+.pad:
+    dec ecx
+    jnz .digit
+.chunk_done:
+    cmp qword [r14 + BIG_SIZE], 0
+    jne .chunk
+    cmp qword [r12 + BIG_SIGN], 0
+    je .move
+    dec rbx
+    mov byte [rbx], '-'
+.move:
+    mov rcx, [r12 + BIG_SIZE]
+    imul rcx, rcx, 20
+    lea rcx, [r13 + rcx + 2]
+    sub rcx, rbx                ; digits, sign and terminator
+    mov rsi, rbx
+    mov rdi, r13
+    rep movsb
+    mov rdi, r14
+    call _big_release           ; the copy divided down to zero
+    mov rax, r13
+    jmp .done
+.fail:
+    lea rax, [rel _str_empty]
+.done:
+    pop r14
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+; _big_sign_parity - A small number with the same sign and parity
+; Args: rdi = value
+; Returns: rax = 0 for zero, 1 or -1 when odd, 2 or -2 when even
+_big_sign_parity:
+    xor eax, eax
+    cmp qword [rdi + BIG_SIZE], 0
+    je .done
+    mov eax, [rdi + BIG_LIMBS]
+    and eax, 1
+    neg eax
+    add eax, 2
+    cmp qword [rdi + BIG_SIGN], 0
+    je .done
+    neg rax
+.done:
+    ret
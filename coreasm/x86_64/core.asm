@@ -4,7 +4,22 @@
 ; Global error flag - set by runtime checks (bounds, syscalls, etc.)
 ; This is always available so bounds checks can set it
 section .bss
-    _last_error: resq 1      ; 0 = no error, non-zero = error code
+    _last_error: resq 1      ; 0 = no error, otherwise errno or ERR_* code
+
+; Where Print writes: 1 = standard output, 2 = standard error
+section .data
+    _output_fd: dq 1
+
+; Error codes: failing syscalls store their positive errno (1..255);
+; Vox runtime error codes are kept above the errno range
+%define ERR_OVERFLOW        256     ; integer result does not fit in 64 bits
+%define ERR_DIVIDE_BY_ZERO  257     ; integer division or modulo by zero
+%define ERR_OUT_OF_BOUNDS   258     ; list index or seek position out of range
+%define ERR_BUFFER_FULL     259     ; fixed-size buffer could not hold the data
+%define ERR_PARSE           260     ; text is not a valid number
+%define ERR_ZERO_STEP       261     ; range stepped by zero
+%define ERR_MISSING_KEY     262     ; map has no entry for the key
+%define ERR_DOMAIN          263     ; math function outside its domain
 
 section .text
 
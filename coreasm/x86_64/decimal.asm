@@ -0,0 +1,306 @@
+; decimal.asm - Decimals for Vox Compiler
+; Provides: fixed-point numbers with a set number of places - exact add,
+; subtract and multiply, division with a rounding mode, rescaling,
+; conversion to and from numbers, floats and text
+
+; ============================================================================
+; DECIMAL VALUES
+; ============================================================================
+; A decimal is a 64-bit number counting units of its last place: 19.99
+; with 2 places is 1999. The places are fixed when compiling, so the
+; generated code brings operands to the same places before adding,
+; subtracting or comparing, and hands the places to the routines here.
+;
+; Overflow sets _last_error to ERR_OVERFLOW and the result is zero.
+
+%define DEC_ROUND_NEAREST   0   ; halves away from zero
+%define DEC_ROUND_UP        1   ; toward +infinity
+%define DEC_ROUND_DOWN      2   ; toward -infinity
+%define DEC_ROUND_TRUNCATE  3   ; toward zero
+
+section .rodata
+    ; 10^0 to 10^18, the most places a decimal has
+    _dec_powers: dq 1, 10, 100, 1000, 10000, 100000, 1000000, 10000000, 100000000, 1000000000, 10000000000, 100000000000, 1000000000000, 10000000000000, 100000000000000, 1000000000000000, 10000000000000000, 100000000000000000, 1000000000000000000
+    _dec_float_powers: dq 1.0, 1.0e1, 1.0e2, 1.0e3, 1.0e4, 1.0e5, 1.0e6, 1.0e7, 1.0e8, 1.0e9, 1.0e10, 1.0e11, 1.0e12, 1.0e13, 1.0e14, 1.0e15, 1.0e16, 1.0e17, 1.0e18
+
+section .text
+
+; _dec_overflow - Reports overflow
+; Returns: rax = 0 (_last_error is set)
+_dec_overflow:
+    mov qword [rel _last_error], ERR_OVERFLOW
+    xor eax, eax
+    ret
+
+; _dec_add - Sum of two decimals with the same places
+; Args: rdi, rsi = decimals
+; Returns: rax = sum, or 0 on overflow
+_dec_add:
+    mov rax, rdi
+    add rax, rsi
+    jo _dec_overflow
+    ret
+
+; _dec_subtract - Difference of two decimals with the same places
+; Args: rdi, rsi = decimals
+; Returns: rax = rdi - rsi, or 0 on overflow
+_dec_subtract:
+    mov rax, rdi
+    sub rax, rsi
+    jo _dec_overflow
+    ret
+
+; _dec_multiply - Exact product; its places are the sum of the operands'
+; Args: rdi, rsi = decimals
+; Returns: rax = product, or 0 on overflow
+_dec_multiply:
+    mov rax, rdi
+    imul rax, rsi
+    jo _dec_overflow
+    ret
+
+; _dec_divide - Quotient of two decimals, rounded to a whole unit
+; Args: rdi = dividend, rsi = divisor, rdx = places to move the dividend
+;       left first (0-18), rcx = rounding mode (DEC_ROUND_*)
+; Returns: rax = rdi * 10^rdx / rsi rounded, or 0 on overflow or division
+;          by zero (_last_error is set)
+; Clobbers: rcx, rdx, r8-r11
+_dec_divide:
+    test rsi, rsi
+    jz .by_zero
+    mov r8, rcx                 ; rounding mode
+    mov r9, rdi
+    xor r9, rsi                 ; the quotient's sign in bit 63
+    mov r11, rdx
+    mov rax, rdi
+    neg rax
+    cmovs rax, rdi              ; |dividend|, 2^63 for the smallest
+    mov r10, rsi
+    neg r10
+    cmovs r10, rsi              ; |divisor|
+    lea rcx, [rel _dec_powers]
+    mul qword [rcx + r11*8]     ; rdx:rax = |dividend| * 10^places
+    cmp rdx, r10
+    jae _dec_overflow           ; the quotient needs more than 64 bits
+    div r10                     ; rax = quotient, rdx = remainder
+    test rdx, rdx
+    jz .signed
+    cmp r8, DEC_ROUND_NEAREST
+    jne .directed
+    sub r10, rdx
+    cmp rdx, r10
+    jb .signed                  ; less than half
+    jmp .away
+.directed:
+    cmp r8, DEC_ROUND_TRUNCATE
+    je .signed
+    test r9, r9
+    js .negative
+    cmp r8, DEC_ROUND_UP        ; up moves positive quotients away from zero
+    jne .signed
+    jmp .away
+.negative:
+    cmp r8, DEC_ROUND_DOWN      ; down moves negative ones away from zero
+    jne .signed
+.away:
+    add rax, 1
+    jc _dec_overflow
+.signed:
+    test r9, r9
+    js .negate
+    test rax, rax
+    js _dec_overflow            ; past the largest positive value
+    ret
+.negate:
+    neg rax
+    test rax, rax
+    jg _dec_overflow            ; past the smallest negative value
+    ret
+.by_zero:
+    mov qword [rel _last_error], ERR_DIVIDE_BY_ZERO
+    xor eax, eax
+    ret
+
+; _dec_rescale - A decimal with more or fewer places
+; Args: rdi = decimal, rsi = places to add (negative to drop, -18 to 18),
+;       rdx = rounding mode for dropped places (DEC_ROUND_*)
+; Returns: rax = rescaled decimal, or 0 on overflow
+; Clobbers: rcx, rdx, rsi, r8-r11
+_dec_rescale:
+    mov rax, rdi
+    test rsi, rsi
+    jz .done
+    js .drop
+    lea rcx, [rel _dec_powers]
+    imul rax, [rcx + rsi*8]
+    jo _dec_overflow
+.done:
+    ret
+.drop:
+    neg rsi
+    mov rcx, rdx
+    lea rdx, [rel _dec_powers]
+    mov rsi, [rdx + rsi*8]
+    xor edx, edx
+    jmp _dec_divide
+
+; _dec_from_float - Decimal nearest to a float, halves away from zero
+; Args: rdi = float bits, rsi = places
+; Returns: rax = decimal, or 0 when out of range or not a number
+; Clobbers: rcx, xmm0, xmm1
+_dec_from_float:
+    movq xmm0, rdi
+    lea rax, [rel _dec_float_powers]
+    mulsd xmm0, [rax + rsi*8]
+    mov rax, 0x8000000000000000
+    and rax, rdi
+    mov rcx, 0x3FE0000000000000 ; 0.5 with the float's sign
+    or rax, rcx
+    movq xmm1, rax
+    addsd xmm0, xmm1
+    cvttsd2si rax, xmm0
+    mov rcx, 0x8000000000000000
+    cmp rax, rcx
+    je _dec_overflow            ; cvttsd2si's answer for out of range
+    ret
+
+; _dec_to_float - Float nearest to a decimal
+; Args: rdi = decimal, rsi = places
+; Returns: rax = float bits
+; Clobbers: xmm0
+_dec_to_float:
+    cvtsi2sd xmm0, rdi
+    lea rax, [rel _dec_float_powers]
+    divsd xmm0, [rax + rsi*8]
+    movq rax, xmm0
+    ret
+
+; _dec_parse - Decimal from text: an optional '-', digits and an optional
+; '.' with more digits. Digits past the places round halves away from zero.
+; Args: rdi = null-terminated text, rsi = places
+; Returns: rax = decimal (the digits before anything invalid)
+; Sets _last_error to ERR_PARSE unless the whole text is a decimal, or to
+; ERR_OVERFLOW (returning 0) when it is too large
+; Clobbers: rcx, rdx, rsi, rdi, r8-r11
+_dec_parse:
+    mov r8, rsi                 ; places still to fill
+    xor esi, esi                ; 1 to round away from zero
+    xor edx, edx                ; digits past the places
+    xor eax, eax
+    xor r9d, r9d                ; 1 when negative
+    xor r10d, r10d              ; digits seen
+    xor r11d, r11d              ; 1 after the point
+    cmp byte [rdi], '-'
+    jne .digit
+    mov r9d, 1
+    inc rdi
+.digit:
+    movzx ecx, byte [rdi]
+    test ecx, ecx
+    jz .end
+    inc rdi
+    cmp ecx, '.'
+    je .point
+    sub ecx, '0'
+    cmp ecx, 9
+    ja .invalid
+    inc r10
+    test r11, r11
+    jz .append
+    test r8, r8
+    jz .past
+    dec r8
+.append:
+    imul rax, rax, 10
+    jo _dec_overflow
+    add rax, rcx
+    jo _dec_overflow
+    jmp .digit
+.past:
+    inc rdx
+    cmp rdx, 1
+    jne .digit                  ; only the first one decides
+    cmp ecx, 5
+    jb .digit
+    mov esi, 1
+    jmp .digit
+.point:
+    test r11, r11
+    jnz .invalid
+    mov r11d, 1
+    jmp .digit
+
+.end:
+    test r10, r10
+    jnz .fill
+.invalid:
+    mov qword [rel _last_error], ERR_PARSE
+.fill:
+    lea rcx, [rel _dec_powers]
+    imul rax, [rcx + r8*8]      ; places not written
+    jo _dec_overflow
+    add rax, rsi
+    jo _dec_overflow
+    test r9, r9
+    jz .done
+    neg rax
+.done:
+    ret
+
+; _dec_to_text - Text of a decimal with all of its places
+; Args: rdi = decimal, rsi = places
+; Returns: rax = new text ("" when memory ran out)
+; Clobbers: rcx, rdx, rsi, rdi, r8-r11
+_dec_to_text:
+    push rbx
+    push r12
+    sub rsp, 48
+    mov r12, rdi
+    lea rbx, [rsp + 40]         ; written backwards from here
+    mov byte [rbx], 0
+    mov rax, rdi
+    neg rax
+    cmovs rax, rdi              ; magnitude
+    mov ecx, 10
+    test rsi, rsi
+    jz .whole
+.fraction:
+    xor edx, edx
+    div rcx
+    add dl, '0'
+    dec rbx
+    mov [rbx], dl
+    dec rsi
+    jnz .fraction
+    dec rbx
+    mov byte [rbx], '.'
+.whole:
+    xor edx, edx
+    div rcx
+    add dl, '0'
+    dec rbx
+    mov [rbx], dl
+    test rax, rax
+    jnz .whole
+    test r12, r12
+    jns .copy
+    dec rbx
+    mov byte [rbx], '-'
+.copy:
+    mov edi, 32
+    call _str_alloc
+    test rax, rax
+    jz .fail
+    mov rdi, rax
+    mov rsi, rbx
+    lea rcx, [rsp + 41]
+    sub rcx, rbx                ; text and terminator
+    rep movsb
+    jmp .done
+.fail:
+    lea rax, [rel _str_empty]
+.done:
+    add rsp, 48
+    pop r12
+    pop rbx
+    ret
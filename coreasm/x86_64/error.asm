@@ -0,0 +1,148 @@
+; error.asm - Runtime error messages for Vox Compiler
+; Maps the code in _last_error (errno or ERR_* from core.asm) to text.
+; Message text matches the names accepted by `On error "<name>"`.
+
+%ifndef ERROR_ASM_INCLUDED
+%define ERROR_ASM_INCLUDED 1
+
+section .text
+
+; Get the message for an error code
+; Args: error code in rdi
+; Returns: pointer to null-terminated message in rax
+; Clobbers: rsi
+global _error_message
+_error_message:
+    lea rsi, [rel _error_table]
+.scan:
+    mov rax, [rsi]
+    cmp rax, -1
+    je .unknown
+    cmp rax, rdi
+    je .found
+    add rsi, 16                  ; next (code, message) entry
+    jmp .scan
+.found:
+    mov rax, [rsi + 8]
+    ret
+.unknown:
+    lea rax, [rel _err_msg_unknown]
+    ret
+
+; Print last error to stderr as "Error: <message>"
+; No args, uses _last_error global
+global _print_last_error
+_print_last_error:
+    xor rdi, rdi                 ; no location line
+    ; fall through
+
+; Print last error to stderr, followed by a location line
+; Args: rdi = null-terminated location text, or 0 for none
+; Does nothing when _last_error is 0
+global _print_error_at
+_print_error_at:
+    push rbx
+    push r12
+    
+    mov r12, rdi
+    mov rdi, [rel _last_error]
+    test rdi, rdi
+    jz .done
+    call _error_message
+    mov rbx, rax
+    
+    lea rdi, [rel _err_prefix]
+    call _write_stderr_cstr
+    mov rdi, rbx
+    call _write_stderr_cstr
+    lea rdi, [rel _err_newline]
+    call _write_stderr_cstr
+    
+    test r12, r12
+    jz .done
+    mov rdi, r12
+    call _write_stderr_cstr
+    lea rdi, [rel _err_newline]
+    call _write_stderr_cstr
+    
+.done:
+    pop r12
+    pop rbx
+    ret
+
+; Write a null-terminated string to stderr
+; Args: rdi = string pointer
+; Clobbers: rax, rcx, rdx, rsi, rdi, r11
+_write_stderr_cstr:
+    mov rsi, rdi
+    xor rdx, rdx
+.measure:
+    cmp byte [rsi + rdx], 0
+    je .write
+    inc rdx
+    jmp .measure
+.write:
+    mov rax, 1                   ; sys_write
+    mov rdi, 2                   ; stderr
+    syscall
+    ret
+
+section .data
+; (code, message) pairs, terminated by code -1
+_error_table:
+    dq 0,                   _err_msg_none
+    dq 1,                   _err_msg_not_permitted
+    dq 2,                   _err_msg_not_found
+    dq 5,                   _err_msg_io
+    dq 9,                   _err_msg_bad_fd
+    dq 12,                  _err_msg_no_memory
+    dq 13,                  _err_msg_permission
+    dq 17,                  _err_msg_exists
+    dq 20,                  _err_msg_not_dir
+    dq 21,                  _err_msg_is_dir
+    dq 22,                  _err_msg_invalid
+    dq 24,                  _err_msg_too_many_files
+    dq 28,                  _err_msg_no_space
+    dq 30,                  _err_msg_read_only
+    dq 36,                  _err_msg_name_too_long
+    dq ERR_OVERFLOW,        _err_msg_overflow
+    dq ERR_DIVIDE_BY_ZERO,  _err_msg_divide_by_zero
+    dq ERR_OUT_OF_BOUNDS,   _err_msg_out_of_bounds
+    dq ERR_BUFFER_FULL,     _err_msg_buffer_full
+    dq ERR_PARSE,           _err_msg_parse
+    dq ERR_ZERO_STEP,       _err_msg_zero_step
+    dq ERR_MISSING_KEY,     _err_msg_missing_key
+    dq ERR_DOMAIN,          _err_msg_domain
+    dq -1,                  0
+
+section .rodata
+_err_prefix:             db "Error: ", 0
+_err_newline:            db 10, 0
+_err_msg_none:           db "no error", 0
+_err_msg_not_permitted:  db "not permitted", 0
+_err_msg_not_found:      db "not found", 0
+_err_msg_io:             db "input/output error", 0
+_err_msg_bad_fd:         db "bad file descriptor", 0
+_err_msg_no_memory:      db "out of memory", 0
+_err_msg_permission:     db "permission denied", 0
+_err_msg_exists:         db "already exists", 0
+_err_msg_not_dir:        db "not a directory", 0
+_err_msg_is_dir:         db "is a directory", 0
+_err_msg_invalid:        db "invalid argument", 0
+_err_msg_too_many_files: db "too many open files", 0
+_err_msg_no_space:       db "no space left", 0
+_err_msg_read_only:      db "read-only file system", 0
+_err_msg_name_too_long:  db "name too long", 0
+_err_msg_overflow:       db "overflow", 0
+_err_msg_divide_by_zero: db "division by zero", 0
+_err_msg_out_of_bounds:  db "out of bounds", 0
+_err_msg_buffer_full:    db "buffer full", 0
+_err_msg_parse:          db "parse failure", 0
+_err_msg_zero_step:      db "zero step", 0
+_err_msg_missing_key:    db "missing key", 0
+_err_msg_domain:         db "domain error", 0
+_err_msg_unknown:        db "system error", 0
+
+section .text
+
+%endif ; ERROR_ASM_INCLUDED
@@ -175,7 +175,7 @@ _print_float:
     ; Print minus sign
     mov byte [rbp-48], '-'
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [rbp-48]
     mov rdx, 1
     syscall
@@ -221,13 +221,13 @@ _pf_print_int:
     mov rax, 1
     mov rdx, rcx
     mov rsi, rdi
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     syscall
     
     ; Print decimal point
     mov byte [rbp-48], '.'
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [rbp-48]
     mov rdx, 1
     syscall
@@ -290,7 +290,7 @@ _pf_print_frac:
     sub rdx, rsi
     inc rdx                   ; length = end - start + 1
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     syscall
     
     pop r15
@@ -301,6 +301,38 @@ _pf_print_frac:
     leave
     ret
 
+; Number of values in a float range: xmm0 = distance from start toward end,
+; xmm1 = step (> 0), rdi = 1 when the end is included. Returns the count in
+; rax, 0 when the end lies behind the start. The tolerance keeps steps like
+; 0.1 from losing the last value to rounding.
+_float_range_count:
+    divsd xmm0, xmm1              ; steps from start to end
+    xorpd xmm1, xmm1
+    test rdi, rdi
+    jz .exclusive
+    addsd xmm0, [rel _float_range_tolerance]
+    roundsd xmm0, xmm0, 1         ; floor
+    ucomisd xmm0, xmm1
+    jb .empty                     ; also taken for NaN
+    cvttsd2si rax, xmm0
+    inc rax
+    ret
+.exclusive:
+    subsd xmm0, [rel _float_range_tolerance]
+    roundsd xmm0, xmm0, 2         ; ceil
+    ucomisd xmm0, xmm1
+    jbe .empty
+    cvttsd2si rax, xmm0
+    ret
+.empty:
+    xor eax, eax
+    ret
+
+section .rodata
+_float_range_tolerance: dq 1.0e-9
+
+section .text
+
 ; Negate float in xmm0 and leave result in xmm0
 _float_negate:
     push rbp
@@ -122,7 +122,7 @@ _print_hex_impl:
     mov rsi, rdi                ; string pointer
     
     mov rax, 1                  ; sys_write
-    mov rdi, 1                  ; stdout
+    mov rdi, [rel _output_fd]   ; stdout or stderr
     syscall
     
     pop r13
@@ -192,7 +192,7 @@ _print_binary_impl:
     mov rsi, rdi
     
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     syscall
     
     pop r12
@@ -264,7 +264,7 @@ _print_octal_impl:
     mov rsi, rdi
     
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     syscall
     
     pop r12
@@ -595,7 +595,7 @@ _print_hex_zeropad_impl:
     push r14
     push r15
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [_format_buffer + 40]
     mov rdx, 2
     syscall
@@ -615,7 +615,7 @@ _print_hex_zeropad_impl:
     push r15
     mov byte [_format_buffer + 40], '0'
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [_format_buffer + 40]
     mov rdx, 1
     syscall
@@ -628,7 +628,7 @@ _print_hex_zeropad_impl:
 .print_digits:
     ; Print the actual digits
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     mov rsi, r14
     mov rdx, r15
     syscall
@@ -669,7 +669,7 @@ _print_binary_zeropad_impl:
     push r14
     mov byte [_format_buffer + 40], '0'
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [_format_buffer + 40]
     mov rdx, 1
     syscall
@@ -682,7 +682,7 @@ _print_binary_zeropad_impl:
 .print_digits:
     ; Print the actual digits
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     mov rsi, r13
     mov rdx, r14
     syscall
@@ -715,7 +715,7 @@ _print_octal_zeropad_impl:
     push r13
     push r14
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [_format_buffer + 40]
     mov rdx, 2
     syscall
@@ -735,7 +735,7 @@ _print_octal_zeropad_impl:
     push r14
     mov byte [_format_buffer + 40], '0'
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [_format_buffer + 40]
     mov rdx, 1
     syscall
@@ -748,7 +748,7 @@ _print_octal_zeropad_impl:
 .print_digits:
     ; Print the actual digits
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     mov rsi, r13
     mov rdx, r14
     syscall
@@ -844,7 +844,7 @@ _print_int_padded_impl:
     push rax
     mov byte [_format_buffer + 32], r9b
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [_format_buffer + 32]
     mov rdx, 1
     syscall
@@ -855,7 +855,7 @@ _print_int_padded_impl:
 .print_result:
     ; Print the actual number
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     mov rsi, r15                ; number string pointer
     mov rdx, r8                 ; number length
     syscall
@@ -895,7 +895,7 @@ _print_float_precision:
     mov byte [_format_buffer], '-'
     push rax
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [_format_buffer]
     mov rdx, 1
     syscall
@@ -922,7 +922,7 @@ _print_float_precision:
     ; Print decimal point
     mov byte [_format_buffer], '.'
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [_format_buffer]
     mov rdx, 1
     syscall
@@ -982,7 +982,7 @@ _print_float_precision:
     push r14
     push r15
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [_format_buffer]
     mov rdx, 1
     syscall
@@ -1019,7 +1019,7 @@ section .text
 ; Print string without trailing newline
 %macro PRINT_STR_NO_NEWLINE 2
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [%1]
     mov rdx, %2
     syscall
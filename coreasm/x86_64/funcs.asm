@@ -47,6 +47,35 @@
     ret
 %endmacro
 
+; FUNC_RETURN_VALUES count
+; - Returns several values, pushed in order on the stack (the last on top)
+; - The first lands in RAX and the second in RDX, as SysV returns a
+;   two-word struct; the rest follow in RCX, RSI, RDI, R8 and R9
+; - count is 2..7
+%macro FUNC_RETURN_VALUES 1
+%if %1 > 7
+    %error "FUNC_RETURN_VALUES: at most 7 values"
+%endif
+%if %1 >= 7
+    pop r9
+%endif
+%if %1 >= 6
+    pop r8
+%endif
+%if %1 >= 5
+    pop rdi
+%endif
+%if %1 >= 4
+    pop rsi
+%endif
+%if %1 >= 3
+    pop rcx
+%endif
+    pop rdx
+    pop rax
+    FUNC_EPILOGUE
+%endmacro
+
 ; ----------------------------
 ; Convenience labels
 ; ----------------------------
@@ -16,15 +16,95 @@ section .text
     imul rax, rbx
 %endmacro
 
+; Integer division never traps: a zero divisor sets _last_error to
+; ERR_DIVIDE_BY_ZERO and yields 0, and MIN / -1 wraps to MIN
 %macro INT_DIV 0
+    test rbx, rbx
+    jnz %%nonzero
+    mov qword [rel _last_error], ERR_DIVIDE_BY_ZERO
+    xor eax, eax
+    jmp %%done
+%%nonzero:
+    cmp rbx, -1
+    jne %%divide
+    neg rax
+    jmp %%done
+%%divide:
     cqo
     idiv rbx
+%%done:
 %endmacro
 
+; Modulo by zero yields 0 and sets _last_error; x mod -1 is always 0
 %macro INT_MOD 0
+    test rbx, rbx
+    jnz %%nonzero
+    mov qword [rel _last_error], ERR_DIVIDE_BY_ZERO
+    xor eax, eax
+    jmp %%done
+%%nonzero:
+    cmp rbx, -1
+    jne %%divide
+    xor eax, eax
+    jmp %%done
+%%divide:
     cqo
     idiv rbx
     mov rax, rdx
+%%done:
+%endmacro
+
+; Checked arithmetic (--checked-arithmetic)
+; Same operands as above; on overflow the wrapped result is kept and
+; _last_error is set so 'On error' can catch it
+%macro CHECK_OVERFLOW 0
+    jno %%ok
+    mov qword [rel _last_error], ERR_OVERFLOW
+%%ok:
+%endmacro
+
+%macro INT_ADD_CHECKED 0
+    add rax, rbx
+    CHECK_OVERFLOW
+%endmacro
+
+%macro INT_SUB_CHECKED 0
+    sub rax, rbx
+    CHECK_OVERFLOW
+%endmacro
+
+%macro INT_MUL_CHECKED 0
+    imul rax, rbx
+    CHECK_OVERFLOW
+%endmacro
+
+; Same as INT_DIV, but MIN / -1 also reports overflow
+%macro INT_DIV_CHECKED 0
+    test rbx, rbx
+    jnz %%nonzero
+    mov qword [rel _last_error], ERR_DIVIDE_BY_ZERO
+    xor eax, eax
+    jmp %%done
+%%nonzero:
+    cmp rbx, -1
+    jne %%divide
+    neg rax
+    CHECK_OVERFLOW
+    jmp %%done
+%%divide:
+    cqo
+    idiv rbx
+%%done:
+%endmacro
+
+; Modulo cannot overflow
+%macro INT_MOD_CHECKED 0
+    INT_MOD
+%endmacro
+
+%macro INT_NEG_CHECKED 0
+    neg rax
+    CHECK_OVERFLOW
 %endmacro
 
 ; Integer comparisons - compares rax with rbx, result (0 or 1) in rax
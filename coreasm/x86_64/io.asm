@@ -8,7 +8,7 @@ section .text
 
 %macro PRINT_STR 2
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [%1]
     mov rdx, %2
     syscall
@@ -16,7 +16,7 @@ section .text
 
 %macro PRINT_NEWLINE 0
     mov rax, 1
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     lea rsi, [newline_char]
     mov rdx, 1
     syscall
@@ -58,7 +58,7 @@ _print_cstr_impl:
 .do_print:
     mov rax, 1            ; sys_write
     mov rdx, rcx          ; length
-    mov rdi, 1            ; stdout
+    mov rdi, [rel _output_fd] ; stdout or stderr
     syscall
     
     pop rbx
@@ -135,7 +135,7 @@ _print_int_impl:
     mov rax, 1
     mov rsi, rdi
     mov rdx, rcx
-    mov rdi, 1
+    mov rdi, [rel _output_fd]
     syscall
     
     leave
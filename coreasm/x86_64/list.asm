@@ -395,13 +395,7 @@ _list_append:
     mov qword [rdi + LIST_ELEMSIZE_OFFSET], r14     ; same elem size
     
     ; Copy existing data
-    push rdi
-    push rsi
-    
-    lea rsi, [rbx + LIST_DATA_OFFSET]       ; source
-    lea rdi, [rdi + LIST_DATA_OFFSET]       ; dest (note: rdi was saved)
-    pop rdi                                  ; restore new list base
-    push rdi
+    push rdi                                ; save new list base
     lea rdi, [rdi + LIST_DATA_OFFSET]       ; dest = new list data
     lea rsi, [rbx + LIST_DATA_OFFSET]       ; source = old list data
     
@@ -444,3 +438,475 @@ _list_append:
     pop rbx
     ret
 
+
+; ============================================================================
+; LIST OPERATIONS
+; ============================================================================
+; Positions are 1-based. A position out of range sets _last_error to
+; ERR_OUT_OF_BOUNDS and leaves the list unchanged. Elements are 8 bytes.
+;
+; Searching and sorting take a compare routine: rdi = a, rsi = b, returning
+; rax < 0, 0 or > 0 as a sorts before, equal to or after b. It may clobber
+; rcx, rdx, rsi, rdi, r8-r11 and xmm0-xmm1. _str_compare (string.asm) orders
+; text; the two below order numbers.
+
+section .data
+    _list_none: dq 0, 0, 8          ; handed out when memory runs out
+
+section .text
+
+; _list_compare_integers - Order two whole numbers (or booleans)
+; Args: rdi = a, rsi = b
+; Returns: rax = -1, 0 or 1
+_list_compare_integers:
+    xor eax, eax
+    cmp rdi, rsi
+    je .done
+    mov eax, 1
+    jg .done
+    mov rax, -1
+.done:
+    ret
+
+; _list_compare_floats - Order two floats, passed as their bits
+; Args: rdi = a, rsi = b
+; Returns: rax = -1, 0 or 1
+_list_compare_floats:
+    movq xmm0, rdi
+    movq xmm1, rsi
+    xor eax, eax
+    ucomisd xmm0, xmm1
+    je .done
+    mov eax, 1
+    ja .done
+    mov rax, -1
+.done:
+    ret
+
+; _list_new - Allocate an empty list
+; Args: rdi = capacity (at least 8 is used)
+; Returns: rax = new list (a list with no room when memory ran out)
+_list_new:
+    push rbx
+    cmp rdi, 8
+    jge .sized
+    mov rdi, 8
+.sized:
+    mov rbx, rdi                            ; capacity
+    lea rsi, [rdi*8 + LIST_DATA_OFFSET]     ; size
+    xor edi, edi                            ; addr = NULL
+    mov rdx, 3                              ; PROT_READ | PROT_WRITE
+    mov r10, 0x22                           ; MAP_PRIVATE | MAP_ANONYMOUS
+    mov r8, -1                              ; fd = -1
+    xor r9d, r9d                            ; offset = 0
+    mov rax, 9                              ; sys_mmap
+    syscall
+    test rax, rax
+    js .fail
+    mov [rax + LIST_CAPACITY_OFFSET], rbx
+    mov qword [rax + LIST_LENGTH_OFFSET], 0
+    mov qword [rax + LIST_ELEMSIZE_OFFSET], 8
+    pop rbx
+    ret
+.fail:
+    neg rax
+    mov [rel _last_error], rax
+    lea rax, [rel _list_none]
+    pop rbx
+    ret
+
+; _list_insert - Insert an element, moving later elements up
+; Args: rdi = list, rsi = position (1 to length + 1), rdx = value
+; Returns: rax = list pointer (may differ if reallocated)
+_list_insert:
+    push rbx
+    push r12
+    push r13
+    mov rbx, rdi
+    mov r12, rsi
+    mov r13, rdx
+
+    cmp r12, 1
+    jl .out_of_bounds
+    mov rax, [rbx + LIST_LENGTH_OFFSET]
+    inc rax
+    cmp r12, rax
+    jg .out_of_bounds
+
+    ; Appending makes room (growing if needed); then the tail moves up one
+    mov rdi, rbx
+    mov rsi, r13
+    call _list_append
+    mov rbx, rax
+    mov rcx, [rbx + LIST_LENGTH_OFFSET]
+    dec rcx                                 ; index of the appended slot
+    lea rdx, [r12 - 1]                      ; index the value goes to
+.shift:
+    cmp rcx, rdx
+    jle .place
+    mov rax, [rbx + LIST_DATA_OFFSET + rcx*8 - 8]
+    mov [rbx + LIST_DATA_OFFSET + rcx*8], rax
+    dec rcx
+    jmp .shift
+.place:
+    mov [rbx + LIST_DATA_OFFSET + rcx*8], r13
+    mov rax, rbx
+    jmp .done
+.out_of_bounds:
+    mov qword [rel _last_error], ERR_OUT_OF_BOUNDS
+    mov rax, rbx
+.done:
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+; _list_remove_at - Remove an element, moving later elements down
+; Args: rdi = list, rsi = position (1 to length)
+; Returns: rax = the removed element (0 when out of range)
+_list_remove_at:
+    cmp rsi, 1
+    jl .out_of_bounds
+    mov rcx, [rdi + LIST_LENGTH_OFFSET]
+    cmp rsi, rcx
+    jg .out_of_bounds
+    lea rdx, [rsi - 1]                      ; index being removed
+    mov rax, [rdi + LIST_DATA_OFFSET + rdx*8]
+    dec rcx                                 ; new length
+    mov [rdi + LIST_LENGTH_OFFSET], rcx
+.shift:
+    cmp rdx, rcx
+    jge .done
+    mov r8, [rdi + LIST_DATA_OFFSET + rdx*8 + 8]
+    mov [rdi + LIST_DATA_OFFSET + rdx*8], r8
+    inc rdx
+    jmp .shift
+.out_of_bounds:
+    mov qword [rel _last_error], ERR_OUT_OF_BOUNDS
+    xor eax, eax
+.done:
+    ret
+
+; _list_find - Position of the first element equal to a value
+; Args: rdi = list, rsi = value, rdx = compare routine
+; Returns: rax = 1-based position, or 0 when no element is equal
+_list_find:
+    push rbx
+    push r12
+    push r13
+    push r14
+    mov rbx, rdi
+    mov r12, rsi
+    mov r13, rdx
+    xor r14, r14                            ; index
+.loop:
+    cmp r14, [rbx + LIST_LENGTH_OFFSET]
+    jge .absent
+    mov rdi, [rbx + LIST_DATA_OFFSET + r14*8]
+    mov rsi, r12
+    call r13
+    inc r14
+    test rax, rax
+    jnz .loop
+    mov rax, r14
+    jmp .done
+.absent:
+    xor eax, eax
+.done:
+    pop r14
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+; _list_remove_value - Remove the first element equal to a value, if any
+; Args: rdi = list, rsi = value, rdx = compare routine
+; Returns: rax = 1 when an element was removed, 0 otherwise
+_list_remove_value:
+    push rbx
+    mov rbx, rdi
+    call _list_find
+    test rax, rax
+    jz .done
+    mov rdi, rbx
+    mov rsi, rax
+    call _list_remove_at
+    mov eax, 1
+.done:
+    pop rbx
+    ret
+
+; _list_reverse - Reverse the order of the elements in place
+; Args: rdi = list
+_list_reverse:
+    mov rcx, [rdi + LIST_LENGTH_OFFSET]
+    lea rsi, [rdi + LIST_DATA_OFFSET]       ; front
+    lea rdx, [rsi + rcx*8 - 8]              ; back
+.loop:
+    cmp rsi, rdx
+    jae .done
+    mov rax, [rsi]
+    mov r8, [rdx]
+    mov [rsi], r8
+    mov [rdx], rax
+    add rsi, 8
+    sub rdx, 8
+    jmp .loop
+.done:
+    ret
+
+; _list_slice - Copy a run of elements into a new list
+; Args: rdi = list, rsi = first position, rdx = last position
+; Returns: rax = new list (empty when the positions are out of range)
+_list_slice:
+    push rbx
+    push r12
+    push r13
+    mov rbx, rdi
+    mov r12, rsi
+    mov r13, rdx
+
+    cmp r12, 1
+    jl .out_of_bounds
+    cmp r13, r12
+    jl .out_of_bounds
+    cmp r13, [rbx + LIST_LENGTH_OFFSET]
+    jg .out_of_bounds
+
+    sub r13, r12
+    inc r13                                 ; number of elements
+    mov rdi, r13
+    call _list_new
+    cmp [rax + LIST_CAPACITY_OFFSET], r13
+    jl .done                                ; no memory: the list with no room
+    mov [rax + LIST_LENGTH_OFFSET], r13
+    lea rsi, [rbx + LIST_DATA_OFFSET + r12*8 - 8]
+    lea rdi, [rax + LIST_DATA_OFFSET]
+    mov rcx, r13
+    rep movsq
+    jmp .done
+.out_of_bounds:
+    mov qword [rel _last_error], ERR_OUT_OF_BOUNDS
+    xor edi, edi
+    call _list_new
+.done:
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+; _list_concat - A new list with the elements of one list, then another
+; Args: rdi = first list, rsi = second list
+; Returns: rax = new list
+_list_concat:
+    push rbx
+    push r12
+    push r13
+    mov rbx, rdi
+    mov r12, rsi
+    mov r13, [rbx + LIST_LENGTH_OFFSET]
+    add r13, [r12 + LIST_LENGTH_OFFSET]     ; combined length
+
+    mov rdi, r13
+    call _list_new
+    cmp [rax + LIST_CAPACITY_OFFSET], r13
+    jl .done                                ; no memory: the list with no room
+    mov [rax + LIST_LENGTH_OFFSET], r13
+    lea rdi, [rax + LIST_DATA_OFFSET]
+    lea rsi, [rbx + LIST_DATA_OFFSET]
+    mov rcx, [rbx + LIST_LENGTH_OFFSET]
+    rep movsq
+    lea rsi, [r12 + LIST_DATA_OFFSET]
+    mov rcx, [r12 + LIST_LENGTH_OFFSET]
+    rep movsq
+.done:
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+; _list_sort - Sort the elements in place (stable bottom-up merge sort)
+; Args: rdi = list, rsi = compare routine, rdx = 1 for descending order
+; Sets _last_error and leaves the list as it was when no scratch memory is left
+_list_sort:
+    push rbp
+    mov rbp, rsp
+    push rbx                                ; i: next element of the left run
+    push r12                                ; j: next element of the right run
+    push r13                                ; k: next slot of the merged run
+    push r14                                ; elements being merged from
+    push r15                                ; elements being merged into
+    sub rsp, 72
+    mov [rbp-48], rdi                       ; list
+    mov [rbp-56], rsi                       ; compare routine
+    mov [rbp-64], rdx                       ; descending
+    mov rax, [rdi + LIST_LENGTH_OFFSET]
+    mov [rbp-72], rax                       ; n
+    cmp rax, 2
+    jl .done
+
+    ; Scratch space for one copy of the elements
+    lea rsi, [rax*8]                        ; size
+    xor edi, edi                            ; addr = NULL
+    mov rdx, 3                              ; PROT_READ | PROT_WRITE
+    mov r10, 0x22                           ; MAP_PRIVATE | MAP_ANONYMOUS
+    mov r8, -1                              ; fd = -1
+    xor r9d, r9d                            ; offset = 0
+    mov rax, 9                              ; sys_mmap
+    syscall
+    test rax, rax
+    js .no_memory
+    mov [rbp-80], rax                       ; scratch
+    mov r14, [rbp-48]
+    add r14, LIST_DATA_OFFSET
+    mov r15, rax
+    mov qword [rbp-88], 1                   ; run width
+
+.pass:
+    mov rax, [rbp-88]
+    cmp rax, [rbp-72]
+    jge .sorted
+    mov qword [rbp-96], 0                   ; start of the left run
+.run:
+    mov rax, [rbp-96]
+    cmp rax, [rbp-72]
+    jge .pass_done
+    mov rbx, rax
+    mov r13, rax
+    add rax, [rbp-88]
+    cmp rax, [rbp-72]
+    jle .mid_ok
+    mov rax, [rbp-72]
+.mid_ok:
+    mov [rbp-104], rax                      ; end of the left run
+    mov r12, rax
+    add rax, [rbp-88]
+    cmp rax, [rbp-72]
+    jle .end_ok
+    mov rax, [rbp-72]
+.end_ok:
+    mov [rbp-112], rax                      ; end of the right run
+
+.merge:
+    cmp r13, [rbp-112]
+    jge .run_done
+    cmp rbx, [rbp-104]
+    jge .take_right
+    cmp r12, [rbp-112]
+    jge .take_left
+    ; Take from the right only when it sorts strictly first, so equal elements keep their order
+    mov rdi, [r14 + r12*8]
+    mov rsi, [r14 + rbx*8]
+    call [rbp-56]
+    cmp qword [rbp-64], 0
+    je .compared
+    neg rax
+.compared:
+    test rax, rax
+    js .take_right
+.take_left:
+    mov rax, [r14 + rbx*8]
+    inc rbx
+    jmp .store
+.take_right:
+    mov rax, [r14 + r12*8]
+    inc r12
+.store:
+    mov [r15 + r13*8], rax
+    inc r13
+    jmp .merge
+
+.run_done:
+    mov rax, [rbp-88]
+    shl rax, 1
+    add [rbp-96], rax
+    jmp .run
+.pass_done:
+    xchg r14, r15
+    shl qword [rbp-88], 1
+    jmp .pass
+
+.sorted:
+    ; The last pass may have left the elements in the scratch space
+    mov rdi, [rbp-48]
+    add rdi, LIST_DATA_OFFSET
+    cmp r14, rdi
+    je .release
+    mov rsi, r14
+    mov rcx, [rbp-72]
+    rep movsq
+.release:
+    mov rdi, [rbp-80]
+    mov rsi, [rbp-72]
+    shl rsi, 3
+    mov rax, 11                             ; sys_munmap
+    syscall
+    jmp .done
+.no_memory:
+    neg rax
+    mov [rel _last_error], rax
+.done:
+    lea rsp, [rbp-40]
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop rbx
+    pop rbp
+    ret
+
+; ============================================================================
+; MIXED LISTS
+; ============================================================================
+; A list whose elements have different types holds pointers to boxes of
+; [tag][value], so printing can tell text and floats from whole numbers.
+
+%define LIST_TAG_NUMBER         0
+%define LIST_TAG_FLOAT          1
+%define LIST_TAG_TEXT           2
+%define LIST_BOX_CHUNK          65536
+
+section .bss
+    _list_box_next: resq 1          ; next free box in the current chunk
+    _list_box_left: resq 1          ; bytes left in the current chunk
+
+section .data
+    _list_no_box: dq LIST_TAG_NUMBER, 0 ; handed out when memory runs out
+
+section .text
+
+; _list_box - Box a value with its tag
+; Args: rdi = tag, rsi = value
+; Returns: rax = box
+; Clobbers: rcx, rdx, r8-r11
+_list_box:
+    cmp qword [rel _list_box_left], 16
+    jae .have_room
+    push rdi
+    push rsi
+    mov rax, 9                      ; sys_mmap
+    xor rdi, rdi                    ; addr = NULL
+    mov rsi, LIST_BOX_CHUNK
+    mov rdx, 3                      ; PROT_READ | PROT_WRITE
+    mov r10, 0x22                   ; MAP_PRIVATE | MAP_ANONYMOUS
+    mov r8, -1                      ; fd = -1
+    xor r9, r9                      ; offset = 0
+    syscall
+    pop rsi
+    pop rdi
+    test rax, rax
+    js .fail
+    mov [rel _list_box_next], rax
+    mov qword [rel _list_box_left], LIST_BOX_CHUNK
+.have_room:
+    mov rax, [rel _list_box_next]
+    add qword [rel _list_box_next], 16
+    sub qword [rel _list_box_left], 16
+    mov [rax], rdi
+    mov [rax + 8], rsi
+    ret
+.fail:
+    neg rax
+    mov [rel _last_error], rax
+    lea rax, [rel _list_no_box]
+    ret
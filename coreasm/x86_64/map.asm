@@ -0,0 +1,711 @@
+; map.asm - Text-keyed hash maps for Vox Compiler
+; Provides: create, set, get, has, remove, iteration, cleanup on exit
+
+; ============================================================================
+; MAP STRUCTURE
+; ============================================================================
+; A map variable holds a fixed handle; growing only replaces the slot array,
+; so every copy of the handle (function arguments too) stays valid.
+;
+; Handle:
+;   offset 0:  capacity (8 bytes) - number of slots, a power of two
+;   offset 8:  count (8 bytes) - live entries (what 's size reports)
+;   offset 16: used (8 bytes) - live entries plus removed markers
+;   offset 24: keys (8 bytes) - newest chunk of copied key text
+;   offset 32: slots (8 bytes) - pointer to the slot array
+;   offset 40: next (8 bytes) - the map made before this one (freed at exit)
+;
+; Slot (32 bytes): [hash][key pointer][value][owned size]
+;   key pointer 0 = empty, MAP_REMOVED = removed entry
+;   owned size 0 = the value is not the map's; otherwise the value is the
+;   map's own copy of a text, unmapped when the entry changes or goes.
+;   Reading such a value hands out a new text, so nothing outside the map
+;   points into the copy.
+;
+; Keys are copied into chunks: [next chunk][used bytes][chunk size][text...]
+
+%define MAP_CAPACITY        0
+%define MAP_COUNT           8
+%define MAP_USED            16
+%define MAP_KEYS            24
+%define MAP_SLOTS           32
+%define MAP_NEXT            40
+%define MAP_HANDLE_SIZE     48
+
+%define MAP_SLOT_HASH       0
+%define MAP_SLOT_KEY        8
+%define MAP_SLOT_VALUE      16
+%define MAP_SLOT_OWNED      24
+%define MAP_SLOT_SIZE       32
+
+%define MAP_REMOVED         1
+%define MAP_INITIAL_CAP     16
+
+%define KEYS_NEXT           0
+%define KEYS_USED           8
+%define KEYS_SIZE           16
+%define KEYS_DATA           24
+%define KEYS_CHUNK_BYTES    65536
+
+section .bss
+    ; Newest map handle; each links to the one made before it
+    map_list: resq 1
+
+section .text
+
+; Allocate zeroed memory with mmap
+; Args: rsi = size in bytes
+; Returns: rax = pointer, or a negative errno
+; Clobbers: rcx, r11 (syscall)
+_map_mmap:
+    push rdi
+    push rdx
+    push r8
+    push r9
+    push r10
+    mov rax, 9                  ; sys_mmap
+    xor rdi, rdi                ; addr = NULL
+    mov rdx, 3                  ; PROT_READ | PROT_WRITE
+    mov r10, 0x22               ; MAP_PRIVATE | MAP_ANONYMOUS
+    mov r8, -1                  ; fd = -1
+    xor r9, r9                  ; offset = 0
+    syscall
+    pop r10
+    pop r9
+    pop r8
+    pop rdx
+    pop rdi
+    ret
+
+; Create an empty map and register it for cleanup
+; Returns: rax = map handle (or 0 on failure)
+global _map_new
+_map_new:
+    push rbx
+    push rcx
+    push rsi
+    push r11
+
+    mov rsi, MAP_HANDLE_SIZE
+    call _map_mmap
+    test rax, rax
+    js .failed
+    mov rbx, rax
+
+    mov rsi, MAP_INITIAL_CAP * MAP_SLOT_SIZE
+    call _map_mmap
+    test rax, rax
+    js .failed
+    mov [rbx + MAP_SLOTS], rax
+    mov qword [rbx + MAP_CAPACITY], MAP_INITIAL_CAP
+
+    ; Link the handle in so cleanup finds it
+    mov rax, [rel map_list]
+    mov [rbx + MAP_NEXT], rax
+    mov [rel map_list], rbx
+    mov rax, rbx
+    jmp .done
+
+.failed:
+    xor rax, rax
+.done:
+    pop r11
+    pop rsi
+    pop rcx
+    pop rbx
+    ret
+
+; FNV-1a hash of a null-terminated string
+; Args: rsi = key
+; Returns: rax = hash
+_map_hash:
+    push rcx
+    push rdx
+    push rsi
+    mov rax, 0xcbf29ce484222325 ; FNV offset basis
+    mov rcx, 0x100000001b3      ; FNV prime
+.loop:
+    movzx rdx, byte [rsi]
+    test rdx, rdx
+    jz .done
+    xor rax, rdx
+    imul rax, rcx
+    inc rsi
+    jmp .loop
+.done:
+    pop rsi
+    pop rdx
+    pop rcx
+    ret
+
+; Find the slot for a key
+; Args: rdi = map, rsi = key
+; Returns: rax = slot holding the key, or 0
+;          rdx = slot the key would be inserted into
+;          r8 = the key's hash
+_map_find:
+    push rbx
+    push rcx
+    push r9
+    push r10
+    push r11
+
+    call _map_hash
+    mov r8, rax
+    mov r9, [rdi + MAP_CAPACITY]
+    dec r9                      ; index mask
+    mov rcx, r8
+    and rcx, r9                 ; first probe
+    xor rdx, rdx                ; no insert slot yet
+
+.probe:
+    imul rbx, rcx, MAP_SLOT_SIZE
+    add rbx, [rdi + MAP_SLOTS]  ; rbx = slot address
+    mov r10, [rbx + MAP_SLOT_KEY]
+    test r10, r10
+    jz .empty
+    cmp r10, MAP_REMOVED
+    je .removed
+    cmp [rbx + MAP_SLOT_HASH], r8
+    jne .next
+
+    ; Same hash - compare the text
+    mov r11, rsi
+.compare:
+    mov al, [r10]
+    cmp al, [r11]
+    jne .next
+    test al, al
+    jz .found
+    inc r10
+    inc r11
+    jmp .compare
+
+.removed:
+    ; Reuse the first removed slot on the probe path
+    test rdx, rdx
+    jnz .next
+    mov rdx, rbx
+.next:
+    inc rcx
+    and rcx, r9
+    jmp .probe
+
+.empty:
+    test rdx, rdx
+    jnz .not_found
+    mov rdx, rbx
+.not_found:
+    xor rax, rax
+    jmp .done
+.found:
+    mov rax, rbx
+.done:
+    pop r11
+    pop r10
+    pop r9
+    pop rcx
+    pop rbx
+    ret
+
+; Copy key text into the map's key chunks
+; Args: rdi = map, rsi = key
+; Returns: rax = pointer to the copy (or 0 on failure)
+_map_copy_key:
+    push rbx
+    push rcx
+    push rdx
+    push rsi
+    push rdi
+    push r11
+    push r12
+    push r13
+
+    mov r12, rdi                ; map
+    mov r13, rsi                ; key
+
+    ; Length including the null terminator
+    xor rcx, rcx
+.length:
+    cmp byte [r13 + rcx], 0
+    je .length_done
+    inc rcx
+    jmp .length
+.length_done:
+    inc rcx
+
+    ; Use the newest chunk if the text fits
+    mov rbx, [r12 + MAP_KEYS]
+    test rbx, rbx
+    jz .new_chunk
+    mov rax, [rbx + KEYS_USED]
+    add rax, rcx
+    cmp rax, [rbx + KEYS_SIZE]
+    jbe .copy
+
+.new_chunk:
+    ; Chunk size: KEYS_CHUNK_BYTES, or enough for one long key
+    mov rsi, rcx
+    add rsi, KEYS_DATA
+    cmp rsi, KEYS_CHUNK_BYTES
+    jae .size_ok
+    mov rsi, KEYS_CHUNK_BYTES
+.size_ok:
+    push rcx
+    call _map_mmap
+    pop rcx
+    test rax, rax
+    js .failed
+    mov [rax + KEYS_SIZE], rsi
+    mov qword [rax + KEYS_USED], KEYS_DATA
+    mov rdx, [r12 + MAP_KEYS]
+    mov [rax + KEYS_NEXT], rdx
+    mov [r12 + MAP_KEYS], rax
+    mov rbx, rax
+
+.copy:
+    mov rdi, rbx
+    add rdi, [rbx + KEYS_USED]  ; destination
+    add [rbx + KEYS_USED], rcx
+    mov rax, rdi
+    mov rsi, r13
+    rep movsb                   ; copy rcx bytes, terminator included
+    jmp .done
+
+.failed:
+    xor rax, rax
+.done:
+    pop r13
+    pop r12
+    pop r11
+    pop rdi
+    pop rsi
+    pop rdx
+    pop rcx
+    pop rbx
+    ret
+
+; Double the slot array and re-insert live entries, dropping removed markers
+; Args: rdi = map
+_map_grow:
+    push rax
+    push rbx
+    push rcx
+    push rdx
+    push rsi
+    push rdi
+    push r8
+    push r9
+    push r10
+    push r11
+    push r12
+    push r13
+    push r14
+    push r15
+
+    mov r12, rdi                ; map
+    mov r13, [r12 + MAP_SLOTS]  ; old slots
+    mov r14, [r12 + MAP_CAPACITY] ; old capacity
+    mov r15, r14
+    shl r15, 1                  ; new capacity
+
+    imul rsi, r15, MAP_SLOT_SIZE
+    call _map_mmap
+    test rax, rax
+    js .done                    ; keep the old slots if memory is exhausted
+
+    mov [r12 + MAP_SLOTS], rax
+    mov [r12 + MAP_CAPACITY], r15
+    mov rax, [r12 + MAP_COUNT]
+    mov [r12 + MAP_USED], rax
+    mov r9, r15
+    dec r9                      ; index mask
+
+    xor rbx, rbx                ; old slot index
+.move:
+    cmp rbx, r14
+    jae .moved
+    imul rdx, rbx, MAP_SLOT_SIZE
+    add rdx, r13                ; old slot
+    mov r10, [rdx + MAP_SLOT_KEY]
+    cmp r10, MAP_REMOVED
+    jbe .next                   ; empty or removed
+
+    mov r8, [rdx + MAP_SLOT_HASH]
+    mov rcx, r8
+    and rcx, r9
+.probe:
+    imul r11, rcx, MAP_SLOT_SIZE
+    add r11, [r12 + MAP_SLOTS]
+    cmp qword [r11 + MAP_SLOT_KEY], 0
+    je .place
+    inc rcx
+    and rcx, r9
+    jmp .probe
+.place:
+    mov [r11 + MAP_SLOT_HASH], r8
+    mov [r11 + MAP_SLOT_KEY], r10
+    mov r10, [rdx + MAP_SLOT_VALUE]
+    mov [r11 + MAP_SLOT_VALUE], r10
+    mov r10, [rdx + MAP_SLOT_OWNED]
+    mov [r11 + MAP_SLOT_OWNED], r10
+.next:
+    inc rbx
+    jmp .move
+
+.moved:
+    ; Free the old slot array
+    mov rdi, r13
+    imul rsi, r14, MAP_SLOT_SIZE
+    mov rax, 11                 ; sys_munmap
+    syscall
+
+.done:
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop r11
+    pop r10
+    pop r9
+    pop r8
+    pop rdi
+    pop rsi
+    pop rdx
+    pop rcx
+    pop rbx
+    pop rax
+    ret
+
+; Unmap a slot's own copy of its value, if it has one
+; Args: rax = slot
+_map_release:
+    cmp qword [rax + MAP_SLOT_OWNED], 0
+    je .done
+    push rax
+    push rcx
+    push rsi
+    push rdi
+    push r11
+    mov rdi, [rax + MAP_SLOT_VALUE]
+    mov rsi, [rax + MAP_SLOT_OWNED]
+    mov qword [rax + MAP_SLOT_OWNED], 0
+    mov rax, 11                 ; sys_munmap
+    syscall
+    pop r11
+    pop rdi
+    pop rsi
+    pop rcx
+    pop rax
+.done:
+    ret
+
+; Copy a text the map owns out to a new text, so the value read stays
+; valid after its entry changes
+; Args: rax = text
+; Returns: rax = new text ("" when memory ran out)
+_map_text_out:
+    push rcx
+    push rdx
+    push rsi
+    push rdi
+    push r8
+    push r9
+    push r10
+    push r11
+    mov rsi, rax
+    xor edx, edx
+.length:
+    cmp byte [rsi + rdx], 0
+    je .copy
+    inc rdx
+    jmp .length
+.copy:
+    call _str_new
+    pop r11
+    pop r10
+    pop r9
+    pop r8
+    pop rdi
+    pop rsi
+    pop rdx
+    pop rcx
+    ret
+
+; Set a key's value, copying the key text when it is new
+; Args: rdi = map, rsi = key, rdx = value
+global _map_set
+_map_set:
+    push r9
+    xor r9, r9                  ; the value is not the map's
+    call _map_put
+    pop r9
+    ret
+
+; Set a key to the map's own copy of a text, released when the entry
+; changes or is removed
+; Args: rdi = map, rsi = key, rdx = text
+global _map_set_text
+_map_set_text:
+    push rax
+    push rcx
+    push rdx
+    push r9
+    push r11
+
+    ; Length including the null terminator
+    xor rcx, rcx
+.length:
+    cmp byte [rdx + rcx], 0
+    je .length_done
+    inc rcx
+    jmp .length
+.length_done:
+    inc rcx
+
+    push rsi
+    push rdi
+    lea rsi, [rcx + 4095]
+    and rsi, ~4095
+    mov r9, rsi                 ; owned size
+    push rcx
+    call _map_mmap
+    pop rcx
+    test rax, rax
+    js .failed
+    mov rdi, rax
+    mov rsi, rdx
+    rep movsb                   ; copy rcx bytes, terminator included
+    mov rdx, rax                ; the copy
+    pop rdi
+    pop rsi
+    call _map_put
+    jmp .done
+
+.failed:
+    neg rax
+    mov [rel _last_error], rax
+    pop rdi
+    pop rsi
+.done:
+    pop r11
+    pop r9
+    pop rdx
+    pop rcx
+    pop rax
+    ret
+
+; Store a value under a key, releasing the value it replaces
+; Args: rdi = map, rsi = key, rdx = value, r9 = owned size (0 if not owned)
+_map_put:
+    push rax
+    push rbx
+    push rcx
+    push rdx
+    push r8
+    push r12
+
+    mov r12, rdx                ; value
+
+    ; Grow past 3/4 full so every probe ends at an empty slot
+    mov rax, [rdi + MAP_USED]
+    inc rax
+    shl rax, 2
+    mov rcx, [rdi + MAP_CAPACITY]
+    imul rcx, rcx, 3
+    cmp rax, rcx
+    jbe .room
+    call _map_grow
+.room:
+    call _map_find
+    test rax, rax
+    jz .insert
+    call _map_release
+    mov [rax + MAP_SLOT_VALUE], r12
+    mov [rax + MAP_SLOT_OWNED], r9
+    jmp .done
+
+.insert:
+    mov rbx, rdx                ; free slot
+    call _map_copy_key
+    test rax, rax
+    jz .failed
+    cmp qword [rbx + MAP_SLOT_KEY], MAP_REMOVED
+    je .reuse
+    inc qword [rdi + MAP_USED]
+.reuse:
+    inc qword [rdi + MAP_COUNT]
+    mov [rbx + MAP_SLOT_HASH], r8
+    mov [rbx + MAP_SLOT_KEY], rax
+    mov [rbx + MAP_SLOT_VALUE], r12
+    mov [rbx + MAP_SLOT_OWNED], r9
+    jmp .done
+
+.failed:
+    mov qword [rel _last_error], 12 ; ENOMEM
+    test r9, r9
+    jz .done
+    push rsi                    ; the value's copy has nowhere to go
+    push rdi
+    push r11
+    mov rdi, r12
+    mov rsi, r9
+    mov rax, 11                 ; sys_munmap
+    syscall
+    pop r11
+    pop rdi
+    pop rsi
+.done:
+    pop r12
+    pop r8
+    pop rdx
+    pop rcx
+    pop rbx
+    pop rax
+    ret
+
+; Look up a key
+; Args: rdi = map, rsi = key
+; Returns: rax = value, or 0 with _last_error = ERR_MISSING_KEY
+global _map_get
+_map_get:
+    push rdx
+    push r8
+    call _map_find
+    test rax, rax
+    jz .missing
+    cmp qword [rax + MAP_SLOT_OWNED], 0
+    mov rax, [rax + MAP_SLOT_VALUE]
+    je .done
+    call _map_text_out
+    jmp .done
+.missing:
+    mov qword [rel _last_error], ERR_MISSING_KEY
+.done:
+    pop r8
+    pop rdx
+    ret
+
+; Check whether a key is present
+; Args: rdi = map, rsi = key
+; Returns: rax = 1 if present, 0 if not
+global _map_has
+_map_has:
+    push rdx
+    push r8
+    call _map_find
+    test rax, rax
+    setnz al
+    movzx rax, al
+    pop r8
+    pop rdx
+    ret
+
+; Remove a key; removing an absent key does nothing
+; Args: rdi = map, rsi = key
+global _map_remove
+_map_remove:
+    push rax
+    push rdx
+    push r8
+    call _map_find
+    test rax, rax
+    jz .done
+    ; The key text stays in its chunk until cleanup
+    call _map_release
+    mov qword [rax + MAP_SLOT_KEY], MAP_REMOVED
+    dec qword [rdi + MAP_COUNT]
+.done:
+    pop r8
+    pop rdx
+    pop rax
+    ret
+
+; Find the next live entry for iteration
+; Args: rdi = map, rsi = slot index to start from
+; Returns: rax = slot index of the entry, or -1 when there are no more
+;          rdx = key, rcx = value
+global _map_next
+_map_next:
+    push rbx
+    mov rax, rsi
+.scan:
+    cmp rax, [rdi + MAP_CAPACITY]
+    jae .end
+    imul rbx, rax, MAP_SLOT_SIZE
+    add rbx, [rdi + MAP_SLOTS]
+    mov rdx, [rbx + MAP_SLOT_KEY]
+    cmp rdx, MAP_REMOVED
+    ja .live
+    inc rax
+    jmp .scan
+.live:
+    mov rcx, [rbx + MAP_SLOT_VALUE]
+    cmp qword [rbx + MAP_SLOT_OWNED], 0
+    je .done
+    push rax
+    mov rax, rcx
+    call _map_text_out
+    mov rcx, rax
+    pop rax
+    jmp .done
+.end:
+    mov rax, -1
+.done:
+    pop rbx
+    ret
+
+; Free every map, its slots, its own values and its key text
+; Called before program exit
+global _cleanup_maps
+_cleanup_maps:
+    push rbx
+    push r12
+    push r13
+
+    mov rbx, [rel map_list]
+.free_loop:
+    test rbx, rbx
+    jz .done
+
+    xor r12, r12
+.free_values:
+    cmp r12, [rbx + MAP_CAPACITY]
+    jae .values_freed
+    imul rax, r12, MAP_SLOT_SIZE
+    add rax, [rbx + MAP_SLOTS]
+    call _map_release
+    inc r12
+    jmp .free_values
+.values_freed:
+
+    mov r13, [rbx + MAP_KEYS]
+.free_keys:
+    test r13, r13
+    jz .free_slots
+    mov rdi, r13
+    mov rsi, [r13 + KEYS_SIZE]
+    mov r13, [r13 + KEYS_NEXT]
+    mov rax, 11                 ; sys_munmap
+    syscall
+    jmp .free_keys
+
+.free_slots:
+    mov rdi, [rbx + MAP_SLOTS]
+    imul rsi, [rbx + MAP_CAPACITY], MAP_SLOT_SIZE
+    mov rax, 11                 ; sys_munmap
+    syscall
+    mov r12, [rbx + MAP_NEXT]
+    mov rdi, rbx
+    mov rsi, MAP_HANDLE_SIZE
+    mov rax, 11                 ; sys_munmap
+    syscall
+    mov rbx, r12
+    jmp .free_loop
+
+.done:
+    mov qword [rel map_list], 0
+    pop r13
+    pop r12
+    pop rbx
+    ret
@@ -0,0 +1,843 @@
+; math.asm - Math library for Vox Compiler
+; Provides: square root, sine, cosine, tangent, natural logarithm,
+; exponential, powers, rounding
+;
+; Only SSE2 instructions (rounding truncates with cvttsd2si, as roundsd
+; needs SSE4.1). Angles are reduced to [-pi/4, pi/4] and logarithms and
+; exponentials to a range around 1, then evaluated with fdlibm's
+; polynomials; powers follow fdlibm's pow, which carries log2 x to about
+; 64 bits. Results are within 3 units in the last place of libm's (tested
+; in tests/math_accuracy.rs). Angles of 2^20 * pi/2 (about 1.6 million)
+; or more give ERR_DOMAIN, as reducing them would need more digits of pi.
+;
+; Floats go in xmm0 (xmm1 for a second operand) and come back in xmm0.
+; Everything here clobbers rax, rcx, rdx and xmm1-xmm7 only.
+
+section .rodata
+    _math_one:          dq 1.0
+    _math_two:          dq 2.0
+    _math_half:         dq 0.5
+    _math_three:        dq 3.0
+    _math_ten:          dq 10.0
+    _math_sign_bit:     dq 0x8000000000000000
+    _math_abs_bits:     dq 0x7FFFFFFFFFFFFFFF
+    _math_largest:      dq 0x7FEFFFFFFFFFFFFF       ; largest finite float
+    _math_two52:        dq 4503599627370496.0       ; 2^52: floats this large are whole
+    _math_two53:        dq 9007199254740992.0       ; 2^53
+    _math_two54:        dq 18014398509481984.0      ; 2^54
+    _math_two62:        dq 4611686018427387904.0    ; 2^62
+    _math_two63:        dq 9223372036854775808.0    ; 2^63
+    _math_two_m1000:    dq 0x0170000000000000       ; 2^-1000
+    _math_high_bits:    dq 0xFFFFFFFF00000000       ; keeps a float's top 21 bits
+
+    ; pi/2 in three parts; the first two have 33 bits, so n times them is
+    ; exact for n < 2^20, which angles below the limit keep to
+    _math_angle_limit:  dq 1647098.0                ; (2^20 - 1/2) * pi/2, rounded down
+    _math_two_over_pi:  dq 6.36619772367581382433e-01
+    _math_pio2_1:       dq 1.57079632673412561417e+00
+    _math_pio2_2:       dq 6.07710050630396597660e-11
+    _math_pio2_2t:      dq 2.02226624879595063154e-21
+
+    ; sin(r) = r + r^3 * (S1 + r^2 * (S2 + ...))
+    _math_s1:           dq -1.66666666666666324348e-01
+    _math_s2:           dq 8.33333333332248946124e-03
+    _math_s3:           dq -1.98412698298579493134e-04
+    _math_s4:           dq 2.75573137070700676789e-06
+    _math_s5:           dq -2.50507602534068634195e-08
+    _math_s6:           dq 1.58969099521155010221e-10
+
+    ; cos(r) = 1 - r^2/2 + r^4 * (C1 + r^2 * (C2 + ...))
+    _math_c1:           dq 4.16666666666666019037e-02
+    _math_c2:           dq -1.38888888888741095749e-03
+    _math_c3:           dq 2.48015872894767294178e-05
+    _math_c4:           dq -2.75573143513906633035e-07
+    _math_c5:           dq 2.08757232129817482790e-09
+    _math_c6:           dq -1.13596475577881948265e-11
+
+    ; ln 2 in two parts, so k * high part is exact
+    _math_ln2_hi:       dq 6.93147180369123816490e-01
+    _math_ln2_lo:       dq 1.90821492927058770002e-10
+    _math_inv_ln2:      dq 1.44269504088896338700e+00
+    _math_sqrt2:        dq 1.41421356237309504880e+00
+
+    ; log(1+f) = f - f^2/2 + s * (f^2/2 + R(s^2)), s = f / (2 + f)
+    _math_lg1:          dq 6.666666666666735130e-01
+    _math_lg2:          dq 3.999999999940941908e-01
+    _math_lg3:          dq 2.857142874366239149e-01
+    _math_lg4:          dq 2.222219843214978396e-01
+    _math_lg5:          dq 1.818357216161805012e-01
+    _math_lg6:          dq 1.531383769920937332e-01
+    _math_lg7:          dq 1.479819860511658591e-01
+
+    ; exp(r) = 1 + r + r * c / (2 - c), c = r - r^2 * (P1 + r^2 * (P2 + ...))
+    _math_p1:           dq 1.66666666666666019037e-01
+    _math_p2:           dq -2.77777777770155933842e-03
+    _math_p3:           dq 6.61375632143793436117e-05
+    _math_p4:           dq -1.65339022054652515390e-06
+    _math_p5:           dq 4.13813679705723846039e-08
+    _math_exp_max:      dq 7.09782712893383973096e+02   ; above: overflow
+    _math_exp_min:      dq -7.45133219101941108420e+02  ; below: 0
+
+    ; x^y = 2^(y log2 x), with log2 x in two parts (fdlibm's e_pow.c).
+    ; m is compared with 1 or 1.5, whose log2 is dp_h + dp_l
+    _math_pow_bp:       dq 1.0, 1.5
+    _math_pow_dp_h:     dq 0.0, 5.84962487220764160156e-01
+    _math_pow_dp_l:     dq 0.0, 1.35003920212974897128e-08
+    ; log(m) = 2s + s^3 * (2/3 + L(s^2)), s = (m - bp) / (m + bp)
+    _math_pow_l1:       dq 5.99999999999994648725e-01
+    _math_pow_l2:       dq 4.28571428578550184252e-01
+    _math_pow_l3:       dq 3.33333329818377432918e-01
+    _math_pow_l4:       dq 2.72728123808534006489e-01
+    _math_pow_l5:       dq 2.30660745775561754067e-01
+    _math_pow_l6:       dq 2.06975017800338417784e-01
+    _math_pow_cp:       dq 9.61796693925975554329e-01   ; 2 / (3 ln 2)
+    _math_pow_cp_h:     dq 9.61796700954437255859e-01   ; its top 21 bits
+    _math_pow_cp_l:     dq -7.02846165095275826516e-09  ; and the rest
+    _math_pow_lg2:      dq 6.93147180559945286227e-01   ; ln 2
+    _math_pow_lg2_h:    dq 6.93147182464599609375e-01   ; its top 21 bits
+    _math_pow_lg2_l:    dq -1.90465429995776804525e-09  ; and the rest
+    _math_pow_ovt:      dq 8.0085662595372944372e-17    ; rounding that still overflows at 2^1024
+    _math_pow_max:      dq 1024.0                       ; log2 of the results that overflow
+    _math_pow_min:      dq -1075.0                      ; and of those that are 0
+
+section .text
+
+; Sets ERR_DOMAIN and gives 0
+_math_domain_error:
+    mov qword [rel _last_error], ERR_DOMAIN
+    xorpd xmm0, xmm0
+    ret
+
+; _math_sqrt - Square root
+; Args: xmm0 = x (ERR_DOMAIN below 0)
+_math_sqrt:
+    xorpd xmm1, xmm1
+    ucomisd xmm0, xmm1
+    jb _math_domain_error                   ; also taken for NaN
+    sqrtsd xmm0, xmm0
+    ret
+
+; _math_reduce - Split x into n * pi/2 + r with |r| <= pi/4
+; Args: xmm0 = x (|x| below _math_angle_limit)
+; Returns: xmm0 = r, rax = n
+_math_reduce:
+    movsd xmm1, xmm0
+    mulsd xmm1, [rel _math_two_over_pi]
+    cvtsd2si rax, xmm1                      ; nearest n
+    cvtsi2sd xmm1, rax
+    movsd xmm2, xmm1
+    mulsd xmm2, [rel _math_pio2_1]
+    subsd xmm0, xmm2                        ; exact
+    movsd xmm2, xmm1
+    mulsd xmm2, [rel _math_pio2_2]
+    subsd xmm0, xmm2
+    mulsd xmm1, [rel _math_pio2_2t]
+    subsd xmm0, xmm1
+    ret
+
+; _math_kernel_sin - sin(r) for |r| <= pi/4
+; Args: xmm0 = r
+_math_kernel_sin:
+    movsd xmm1, xmm0
+    mulsd xmm1, xmm0                        ; z = r^2
+    movsd xmm2, [rel _math_s6]
+    mulsd xmm2, xmm1
+    addsd xmm2, [rel _math_s5]
+    mulsd xmm2, xmm1
+    addsd xmm2, [rel _math_s4]
+    mulsd xmm2, xmm1
+    addsd xmm2, [rel _math_s3]
+    mulsd xmm2, xmm1
+    addsd xmm2, [rel _math_s2]
+    mulsd xmm2, xmm1
+    addsd xmm2, [rel _math_s1]
+    mulsd xmm2, xmm1
+    mulsd xmm2, xmm0                        ; r^3 * (S1 + ...)
+    addsd xmm0, xmm2
+    ret
+
+; _math_kernel_cos - cos(r) for |r| <= pi/4
+; Args: xmm0 = r
+_math_kernel_cos:
+    mulsd xmm0, xmm0                        ; z = r^2
+    movsd xmm1, [rel _math_c6]
+    mulsd xmm1, xmm0
+    addsd xmm1, [rel _math_c5]
+    mulsd xmm1, xmm0
+    addsd xmm1, [rel _math_c4]
+    mulsd xmm1, xmm0
+    addsd xmm1, [rel _math_c3]
+    mulsd xmm1, xmm0
+    addsd xmm1, [rel _math_c2]
+    mulsd xmm1, xmm0
+    addsd xmm1, [rel _math_c1]
+    mulsd xmm1, xmm0
+    mulsd xmm1, xmm0                        ; z^2 * (C1 + ...)
+    movsd xmm2, xmm0
+    mulsd xmm2, [rel _math_half]            ; z/2
+    movsd xmm3, [rel _math_one]
+    subsd xmm3, xmm2                        ; w = 1 - z/2
+    movsd xmm0, [rel _math_one]
+    subsd xmm0, xmm3
+    subsd xmm0, xmm2                        ; what w lost to rounding
+    addsd xmm0, xmm1
+    addsd xmm0, xmm3
+    ret
+
+; Jumps to the domain error for angles that cannot be reduced exactly
+; (infinite, NaN or from _math_angle_limit on). Use at the top of a
+; function: xmm0 = x
+%macro MATH_CHECK_ANGLE 0
+    movsd xmm1, xmm0
+    movsd xmm2, [rel _math_abs_bits]
+    andpd xmm1, xmm2
+    ucomisd xmm1, [rel _math_angle_limit]
+    jae _math_domain_error
+    jp _math_domain_error
+%endmacro
+
+; _math_sin / _math_cos - Sine and cosine of an angle in radians
+; Args: xmm0 = x
+_math_sin:
+    xor ecx, ecx
+    jmp _math_sin_cos
+_math_cos:
+    mov ecx, 1                              ; cos x = sin(x + pi/2)
+_math_sin_cos:
+    MATH_CHECK_ANGLE
+    call _math_reduce
+    add rax, rcx                            ; quarter turns
+    test al, 1
+    jnz .cosine
+    call _math_kernel_sin
+    jmp .sign
+.cosine:
+    call _math_kernel_cos
+.sign:
+    test al, 2                              ; second half of the turn
+    jz .done
+    movsd xmm1, [rel _math_sign_bit]
+    xorpd xmm0, xmm1
+.done:
+    ret
+
+; _math_tan - Tangent of an angle in radians
+; Args: xmm0 = x
+_math_tan:
+    MATH_CHECK_ANGLE
+    call _math_reduce
+    movsd xmm4, xmm0                        ; r
+    call _math_kernel_sin
+    movsd xmm5, xmm0                        ; sin r
+    movsd xmm0, xmm4
+    call _math_kernel_cos                   ; cos r
+    test al, 1
+    jnz .odd
+    divsd xmm5, xmm0                        ; sin r / cos r
+    movsd xmm0, xmm5
+    ret
+.odd:
+    divsd xmm0, xmm5                        ; tan(r + pi/2) = -cos r / sin r
+    movsd xmm1, [rel _math_sign_bit]
+    xorpd xmm0, xmm1
+    ret
+
+; _math_log - Natural logarithm
+; Args: xmm0 = x (ERR_DOMAIN at 0 or below)
+_math_log:
+    xorpd xmm1, xmm1
+    ucomisd xmm0, xmm1
+    jbe _math_domain_error                  ; also taken for NaN
+    movq rax, xmm0
+    mov rdx, rax
+    shr rdx, 52                             ; biased exponent
+    xor ecx, ecx
+    test rdx, rdx
+    jnz .normal
+    mulsd xmm0, [rel _math_two54]           ; subnormal: scale into range
+    movq rax, xmm0
+    mov rdx, rax
+    shr rdx, 52
+    mov rcx, -54
+.normal:
+    cmp rdx, 0x7FF
+    je .done                                ; log of infinity
+    lea rdx, [rdx + rcx - 1023]             ; k: x = 2^k * m
+    mov rcx, 0x000FFFFFFFFFFFFF
+    and rax, rcx
+    mov rcx, 0x3FF0000000000000
+    or rax, rcx
+    movq xmm0, rax                          ; m in [1, 2)
+    ucomisd xmm0, [rel _math_sqrt2]
+    jbe .reduced
+    mulsd xmm0, [rel _math_half]            ; keep m within sqrt(2)/2 .. sqrt(2)
+    inc rdx
+.reduced:
+    subsd xmm0, [rel _math_one]             ; f = m - 1
+    movsd xmm1, [rel _math_two]
+    addsd xmm1, xmm0
+    movsd xmm2, xmm0
+    divsd xmm2, xmm1                        ; s = f / (2 + f)
+    movsd xmm3, xmm2
+    mulsd xmm3, xmm2                        ; z = s^2
+    movsd xmm4, xmm3
+    mulsd xmm4, xmm3                        ; w = z^2
+    movsd xmm5, [rel _math_lg6]
+    mulsd xmm5, xmm4
+    addsd xmm5, [rel _math_lg4]
+    mulsd xmm5, xmm4
+    addsd xmm5, [rel _math_lg2]
+    mulsd xmm5, xmm4                        ; even terms
+    movsd xmm6, [rel _math_lg7]
+    mulsd xmm6, xmm4
+    addsd xmm6, [rel _math_lg5]
+    mulsd xmm6, xmm4
+    addsd xmm6, [rel _math_lg3]
+    mulsd xmm6, xmm4
+    addsd xmm6, [rel _math_lg1]
+    mulsd xmm6, xmm3                        ; odd terms
+    addsd xmm5, xmm6                        ; R
+    movsd xmm1, xmm0
+    mulsd xmm1, xmm0
+    mulsd xmm1, [rel _math_half]            ; f^2 / 2
+    addsd xmm5, xmm1
+    mulsd xmm5, xmm2                        ; s * (f^2/2 + R)
+    cvtsi2sd xmm7, rdx
+    movsd xmm6, xmm7
+    mulsd xmm6, [rel _math_ln2_lo]
+    addsd xmm5, xmm6
+    subsd xmm1, xmm5
+    subsd xmm1, xmm0
+    mulsd xmm7, [rel _math_ln2_hi]
+    subsd xmm7, xmm1                        ; k ln 2 + log(1 + f)
+    movsd xmm0, xmm7
+.done:
+    ret
+
+; _math_exp - e to the power of x
+; Args: xmm0 = x (ERR_OVERFLOW above about 709.78)
+_math_exp:
+    ucomisd xmm0, [rel _math_exp_max]
+    jp _math_domain_error
+    ja .overflow
+    ucomisd xmm0, [rel _math_exp_min]
+    jb .zero
+    movsd xmm1, xmm0
+    mulsd xmm1, [rel _math_inv_ln2]
+    cvtsd2si rax, xmm1                      ; k: x = k ln 2 + r
+    cvtsi2sd xmm1, rax
+    movsd xmm2, xmm1
+    mulsd xmm2, [rel _math_ln2_hi]
+    subsd xmm0, xmm2                        ; hi
+    mulsd xmm1, [rel _math_ln2_lo]          ; lo
+    movsd xmm2, xmm0
+    subsd xmm2, xmm1                        ; r = hi - lo
+    movsd xmm3, xmm2
+    mulsd xmm3, xmm2                        ; z = r^2
+    movsd xmm4, [rel _math_p5]
+    mulsd xmm4, xmm3
+    addsd xmm4, [rel _math_p4]
+    mulsd xmm4, xmm3
+    addsd xmm4, [rel _math_p3]
+    mulsd xmm4, xmm3
+    addsd xmm4, [rel _math_p2]
+    mulsd xmm4, xmm3
+    addsd xmm4, [rel _math_p1]
+    mulsd xmm4, xmm3
+    movsd xmm5, xmm2
+    subsd xmm5, xmm4                        ; c
+    movsd xmm6, [rel _math_two]
+    subsd xmm6, xmm5
+    mulsd xmm5, xmm2
+    divsd xmm5, xmm6                        ; r * c / (2 - c)
+    subsd xmm1, xmm5
+    subsd xmm1, xmm0
+    movsd xmm0, [rel _math_one]
+    subsd xmm0, xmm1                        ; e^r
+    jmp _math_scale
+.overflow:
+    mov qword [rel _last_error], ERR_OVERFLOW
+.zero:
+    xorpd xmm0, xmm0
+    ret
+
+; _math_scale - xmm0 times 2^rax, for rax from -1075 to 1024
+; In two steps when 2^rax alone is not a normal float
+_math_scale:
+    cmp rax, -1021
+    jge .high
+    add rax, 1000
+    mulsd xmm0, [rel _math_two_m1000]
+.high:
+    cmp rax, 1023
+    jle .scale
+    dec rax
+    addsd xmm0, xmm0
+.scale:
+    add rax, 1023
+    shl rax, 52
+    movq xmm1, rax
+    mulsd xmm0, xmm1
+    ret
+
+; _math_power - x to the power of y
+; Args: xmm0 = x, xmm1 = y
+; Negative x needs a whole y, and 0 a positive one (ERR_DOMAIN otherwise).
+; Results too large for a float set ERR_OVERFLOW.
+_math_power:
+    push rbx
+    ; Is y whole, and if so odd? (from 2^62 on it is whole and even)
+    xor ebx, ebx                            ; bit 0: whole, bit 1: odd
+    movsd xmm2, xmm1
+    movsd xmm3, [rel _math_abs_bits]
+    andpd xmm2, xmm3
+    ucomisd xmm2, [rel _math_two62]
+    jp .domain
+    jae .whole
+    cvttsd2si rcx, xmm1
+    cvtsi2sd xmm3, rcx
+    ucomisd xmm3, xmm1
+    jne .classified
+    mov ebx, 1
+    test cl, 1
+    jz .classified
+    mov ebx, 3
+    jmp .classified
+.whole:
+    mov ebx, 1
+    xor ecx, ecx
+.classified:
+    xorpd xmm3, xmm3
+    ucomisd xmm1, xmm3
+    je .one                                 ; anything to the power of 0
+    ucomisd xmm0, xmm3
+    jp .domain
+    je .zero_base
+    jb .negative_base
+    xor ebx, ebx                            ; a positive base keeps its sign
+    jmp .positive_base
+.negative_base:
+    test ebx, 1
+    jz .domain                              ; negative base, fractional exponent
+    movsd xmm3, [rel _math_sign_bit]
+    xorpd xmm0, xmm3                        ; |x|; the sign comes back for odd y
+.positive_base:
+    ucomisd xmm0, [rel _math_largest]
+    ja .infinite
+    ucomisd xmm2, [rel _math_largest]
+    ja .infinite
+    call _math_power_finite
+    jmp .sign
+.infinite:
+    ; An infinite x or y goes through exp(y ln x)
+    sub rsp, 8
+    movsd [rsp], xmm1
+    call _math_log
+    mulsd xmm0, [rsp]                       ; y ln |x|
+    add rsp, 8
+    call _math_exp
+.sign:
+    test ebx, 2
+    jz .done
+    movsd xmm1, [rel _math_sign_bit]
+    xorpd xmm0, xmm1
+    jmp .done
+
+.zero_base:
+    ucomisd xmm1, xmm3
+    ja .result_zero                         ; 0 to a positive power
+    jmp .domain
+.result_zero:
+    xorpd xmm0, xmm0
+    jmp .done
+.one:
+    movsd xmm0, [rel _math_one]
+    jmp .done
+.domain:
+    call _math_domain_error
+.done:
+    pop rbx
+    ret
+
+; _math_power_finite - x^y for finite x > 0 and finite y (fdlibm's pow)
+; Args: xmm0 = x, xmm1 = y
+; log2 x comes out as t1 + t2 and y * log2 x as p_h + p_l, each high part
+; cut to 21 bits so products with it are exact; 2^(p_h + p_l) follows.
+; Results too large for a float set ERR_OVERFLOW; those too small give 0.
+_math_power_finite:
+    push r8
+    sub rsp, 8
+    movsd [rsp], xmm1                       ; y
+    ; x = 2^n * m, with subnormals scaled up first
+    movq rax, xmm0
+    mov rcx, rax
+    shr rcx, 52
+    xor edx, edx
+    test rcx, rcx
+    jnz .normal
+    mulsd xmm0, [rel _math_two53]
+    movq rax, xmm0
+    mov rcx, rax
+    shr rcx, 52
+    mov rdx, -53
+.normal:
+    lea rcx, [rcx + rdx - 1023]             ; n
+    mov rdx, 0x000FFFFFFFFFFFFF
+    and rax, rdx
+    mov rdx, 0x3FF0000000000000
+    or rax, rdx                             ; m in [1, 2)
+    mov rdx, rax
+    shr rdx, 32                             ; m's high word
+    xor r8d, r8d                            ; below sqrt(3/2): compared with 1
+    cmp edx, 0x3FF3988E
+    jbe .interval
+    mov r8d, 8                              ; below sqrt(3): with 1.5
+    cmp edx, 0x3FFBB67A
+    jb .interval
+    xor r8d, r8d                            ; otherwise m/2, with 1
+    inc rcx
+    mov rdx, 0x0010000000000000
+    sub rax, rdx
+    mov rdx, rax
+    shr rdx, 32
+.interval:
+    movq xmm0, rax                          ; m
+    lea rax, [rel _math_pow_bp]
+    movsd xmm1, xmm0
+    subsd xmm1, [rax + r8]                  ; u = m - bp
+    movsd xmm2, xmm0
+    addsd xmm2, [rax + r8]
+    movsd xmm3, [rel _math_one]
+    divsd xmm3, xmm2                        ; v = 1 / (m + bp)
+    movsd xmm2, xmm1
+    mulsd xmm2, xmm3                        ; s = u * v
+    movsd xmm4, [rel _math_high_bits]
+    andpd xmm4, xmm2                        ; s_h
+    ; t_h: m + bp cut short, built from m's high word
+    shr edx, 1
+    or edx, 0x20000000
+    add edx, 0x00080000
+    mov eax, r8d
+    shl eax, 15                             ; 1 << 18 for 1.5
+    add edx, eax
+    shl rdx, 32
+    movq xmm5, rdx
+    lea rax, [rel _math_pow_bp]
+    movsd xmm6, xmm5
+    subsd xmm6, [rax + r8]
+    movsd xmm7, xmm0
+    subsd xmm7, xmm6                        ; t_l = m - (t_h - bp)
+    mulsd xmm5, xmm4
+    subsd xmm1, xmm5                        ; u - s_h * t_h
+    mulsd xmm7, xmm4
+    subsd xmm1, xmm7                        ; - s_h * t_l
+    mulsd xmm1, xmm3                        ; s_l: what s_h leaves of s
+    ; r = s^4 * L(s^2) + s_l * (s_h + s)
+    movsd xmm3, xmm2
+    mulsd xmm3, xmm2                        ; s^2
+    movsd xmm5, [rel _math_pow_l6]
+    mulsd xmm5, xmm3
+    addsd xmm5, [rel _math_pow_l5]
+    mulsd xmm5, xmm3
+    addsd xmm5, [rel _math_pow_l4]
+    mulsd xmm5, xmm3
+    addsd xmm5, [rel _math_pow_l3]
+    mulsd xmm5, xmm3
+    addsd xmm5, [rel _math_pow_l2]
+    mulsd xmm5, xmm3
+    addsd xmm5, [rel _math_pow_l1]
+    mulsd xmm5, xmm3
+    mulsd xmm5, xmm3
+    movsd xmm6, xmm4
+    addsd xmm6, xmm2
+    mulsd xmm6, xmm1
+    addsd xmm5, xmm6                        ; r
+    ; t_h + t_l = 3 + s_h^2 + r
+    movsd xmm3, xmm4
+    mulsd xmm3, xmm4                        ; s_h^2
+    movsd xmm6, [rel _math_three]
+    addsd xmm6, xmm3
+    addsd xmm6, xmm5
+    movq rdx, xmm6
+    shr rdx, 32
+    shl rdx, 32
+    movq xmm6, rdx                          ; t_h
+    movsd xmm7, xmm6
+    subsd xmm7, [rel _math_three]
+    subsd xmm7, xmm3
+    subsd xmm5, xmm7                        ; t_l
+    ; p_h + p_l = s * (t_h + t_l), about 3 log(m) / 2
+    mulsd xmm1, xmm6                        ; s_l * t_h
+    mulsd xmm5, xmm2                        ; t_l * s
+    addsd xmm1, xmm5                        ; v
+    mulsd xmm4, xmm6                        ; u = s_h * t_h
+    movsd xmm2, xmm4
+    addsd xmm2, xmm1
+    movq rdx, xmm2
+    shr rdx, 32
+    shl rdx, 32
+    movq xmm2, rdx                          ; p_h
+    movsd xmm3, xmm2
+    subsd xmm3, xmm4
+    subsd xmm1, xmm3                        ; p_l = v - (p_h - u)
+    ; z_h + z_l = (p_h + p_l) * 2 / (3 ln 2) + dp_l, the log2 of m / bp
+    movsd xmm3, xmm2
+    mulsd xmm3, [rel _math_pow_cp_h]        ; z_h
+    mulsd xmm2, [rel _math_pow_cp_l]
+    mulsd xmm1, [rel _math_pow_cp]
+    addsd xmm2, xmm1
+    lea rax, [rel _math_pow_dp_l]
+    addsd xmm2, [rax + r8]                  ; z_l
+    ; log2 x = n + dp_h + z_h + z_l = t1 + t2
+    cvtsi2sd xmm4, rcx                      ; n
+    lea rax, [rel _math_pow_dp_h]
+    movsd xmm5, xmm3
+    addsd xmm5, xmm2
+    addsd xmm5, [rax + r8]
+    addsd xmm5, xmm4
+    movq rdx, xmm5
+    shr rdx, 32
+    shl rdx, 32
+    movq xmm5, rdx                          ; t1
+    movsd xmm6, xmm5
+    subsd xmm6, xmm4
+    subsd xmm6, [rax + r8]
+    subsd xmm6, xmm3
+    subsd xmm2, xmm6                        ; t2 = z_l - (((t1 - n) - dp_h) - z_h)
+    ; y * log2 x = p_h + p_l, with y = y1 + (y - y1)
+    movsd xmm0, [rsp]
+    movsd xmm1, [rel _math_high_bits]
+    andpd xmm1, xmm0                        ; y1
+    movsd xmm3, xmm0
+    subsd xmm3, xmm1
+    mulsd xmm3, xmm5                        ; (y - y1) * t1
+    mulsd xmm0, xmm2                        ; y * t2
+    addsd xmm3, xmm0                        ; p_l
+    mulsd xmm1, xmm5                        ; p_h = y1 * t1
+    movsd xmm0, xmm1
+    addsd xmm0, xmm3                        ; z = p_h + p_l
+    ; Beyond 2^1024 overflows and below 2^-1075 is 0; at exactly those
+    ; the part lost from z decides
+    ucomisd xmm0, [rel _math_pow_max]
+    ja .overflow
+    jb .below_max
+    movsd xmm2, xmm3
+    addsd xmm2, [rel _math_pow_ovt]
+    movsd xmm4, xmm0
+    subsd xmm4, xmm1
+    ucomisd xmm2, xmm4
+    ja .overflow
+.below_max:
+    ucomisd xmm0, [rel _math_pow_min]
+    jb .zero
+    ja .in_range
+    movsd xmm4, xmm0
+    subsd xmm4, xmm1
+    ucomisd xmm3, xmm4
+    jbe .zero
+.in_range:
+    ; 2^z = 2^k * 2^(p_h + p_l - k), k the nearest whole number
+    cvtsd2si rax, xmm0                      ; k
+    cvtsi2sd xmm2, rax
+    subsd xmm1, xmm2                        ; p_h - k (exact)
+    movsd xmm2, xmm3
+    addsd xmm2, xmm1
+    movq rdx, xmm2
+    shr rdx, 32
+    shl rdx, 32
+    movq xmm2, rdx                          ; t
+    movsd xmm4, xmm2
+    mulsd xmm4, [rel _math_pow_lg2_h]       ; u = t * lg2_h
+    movsd xmm5, xmm2
+    subsd xmm5, xmm1
+    subsd xmm3, xmm5
+    mulsd xmm3, [rel _math_pow_lg2]
+    mulsd xmm2, [rel _math_pow_lg2_l]
+    addsd xmm3, xmm2                        ; v = (p_l - (t - p_h)) * ln 2 + t * lg2_l
+    movsd xmm0, xmm4
+    addsd xmm0, xmm3                        ; r = u + v, the power of e
+    movsd xmm1, xmm0
+    subsd xmm1, xmm4
+    subsd xmm3, xmm1                        ; w = v - (r - u)
+    ; e^r as in _math_exp, with w what r lost
+    movsd xmm1, xmm0
+    mulsd xmm1, xmm0                        ; r^2
+    movsd xmm2, [rel _math_p5]
+    mulsd xmm2, xmm1
+    addsd xmm2, [rel _math_p4]
+    mulsd xmm2, xmm1
+    addsd xmm2, [rel _math_p3]
+    mulsd xmm2, xmm1
+    addsd xmm2, [rel _math_p2]
+    mulsd xmm2, xmm1
+    addsd xmm2, [rel _math_p1]
+    mulsd xmm2, xmm1
+    movsd xmm4, xmm0
+    subsd xmm4, xmm2                        ; c
+    movsd xmm5, xmm0
+    mulsd xmm5, xmm4
+    subsd xmm4, [rel _math_two]
+    divsd xmm5, xmm4                        ; r * c / (c - 2)
+    movsd xmm6, xmm0
+    mulsd xmm6, xmm3
+    addsd xmm6, xmm3
+    subsd xmm5, xmm6                        ; - (w + r * w)
+    subsd xmm5, xmm0
+    movsd xmm0, [rel _math_one]
+    subsd xmm0, xmm5                        ; e^r
+    add rsp, 8
+    pop r8
+    jmp _math_scale
+.overflow:
+    mov qword [rel _last_error], ERR_OVERFLOW
+.zero:
+    xorpd xmm0, xmm0
+    add rsp, 8
+    pop r8
+    ret
+
+; _math_power_int - A number to the power of a number
+; Args: rdi = base, rsi = exponent
+; Returns: rax (ERR_OVERFLOW when it does not fit in 64 bits; negative
+; exponents give the whole part of 1 / base^n, ERR_DIVIDE_BY_ZERO for 0)
+_math_power_int:
+    test rsi, rsi
+    js .negative
+    mov eax, 1
+.bit:
+    test sil, 1
+    jz .square
+    imul rax, rdi
+    jo .overflow
+.square:
+    shr rsi, 1
+    jz .done
+    imul rdi, rdi                           ; only overflows when the result would
+    jo .overflow
+    jmp .bit
+.done:
+    ret
+.negative:
+    mov eax, 1
+    cmp rdi, 1
+    je .done
+    cmp rdi, -1
+    jne .fraction
+    test sil, 1                             ; (-1)^n
+    jz .done
+    neg rax
+    ret
+.fraction:
+    xor eax, eax
+    test rdi, rdi
+    jnz .done
+    mov qword [rel _last_error], ERR_DIVIDE_BY_ZERO
+    ret
+.overflow:
+    mov qword [rel _last_error], ERR_OVERFLOW
+    xor eax, eax
+    ret
+
+; _math_round - Round to a whole float
+; Args: xmm0 = x, rdi = mode (0: nearest, halves away from zero; 1: up; 2: down)
+_math_round:
+    movsd xmm1, xmm0
+    movsd xmm2, [rel _math_abs_bits]
+    andpd xmm1, xmm2
+    ucomisd xmm1, [rel _math_two52]
+    jp .done
+    jae .done                               ; already whole
+    cvttsd2si rax, xmm0
+    cvtsi2sd xmm1, rax                      ; toward zero
+    subsd xmm0, xmm1                        ; what was cut off (exact)
+    xorpd xmm3, xmm3
+    cmp rdi, 1
+    je .up
+    ja .down
+    andpd xmm2, xmm0
+    ucomisd xmm2, [rel _math_half]
+    jb .whole
+    ucomisd xmm0, xmm3
+    ja .add_one
+    jmp .subtract_one
+.up:
+    ucomisd xmm0, xmm3
+    ja .add_one
+    jmp .whole
+.down:
+    ucomisd xmm0, xmm3
+    jb .subtract_one
+    jmp .whole
+.add_one:
+    addsd xmm1, [rel _math_one]
+    jmp .whole
+.subtract_one:
+    subsd xmm1, [rel _math_one]
+.whole:
+    movsd xmm0, xmm1
+.done:
+    ret
+
+; _math_round_whole - Round to a number
+; Args: xmm0 = x, rdi = mode (as _math_round)
+; Returns: rax (ERR_OVERFLOW when it does not fit in 64 bits)
+_math_round_whole:
+    call _math_round
+    movsd xmm1, xmm0
+    movsd xmm2, [rel _math_abs_bits]
+    andpd xmm1, xmm2
+    ucomisd xmm1, [rel _math_two63]
+    jp .overflow
+    jae .overflow
+    cvttsd2si rax, xmm0
+    ret
+.overflow:
+    mov qword [rel _last_error], ERR_OVERFLOW
+    xor eax, eax
+    ret
+
+; _math_round_places - Round to a number of decimal places
+; Args: xmm0 = x, rdi = mode (as _math_round), rsi = places (negative
+; rounds to tens, hundreds, ...)
+_math_round_places:
+    movsd xmm4, [rel _math_one]             ; 10^|places|
+    mov rcx, rsi
+    test rcx, rcx
+    jns .count
+    neg rcx
+.count:
+    cmp rcx, 308
+    jbe .power
+    mov ecx, 308                            ; beyond this 10^n is not a float
+.power:
+    jrcxz .scale
+    mulsd xmm4, [rel _math_ten]
+    dec rcx
+    jmp .power
+.scale:
+    movsd xmm5, xmm0                        ; x
+    test rsi, rsi
+    js .shrink
+    mulsd xmm0, xmm4
+    jmp .scaled
+.shrink:
+    divsd xmm0, xmm4
+.scaled:
+    ; With no digits past the place, x stays as it is
+    movsd xmm1, xmm0
+    movsd xmm2, [rel _math_abs_bits]
+    andpd xmm1, xmm2
+    ucomisd xmm1, [rel _math_two52]
+    jp .unchanged
+    jae .unchanged
+    call _math_round
+    test rsi, rsi
+    js .grow
+    divsd xmm0, xmm4
+    ret
+.grow:
+    mulsd xmm0, xmm4
+    ret
+.unchanged:
+    movsd xmm0, xmm5
+    ret
@@ -0,0 +1,256 @@
+; random.asm - Random numbers for Vox Compiler
+; Provides: kernel randomness, a seedable generator, numbers in a range,
+; floats, random list elements, shuffling, filling buffers
+;
+; Until the program seeds the generator, every value comes from the kernel
+; (getrandom). Seeding switches to xoshiro256**, so the same seed gives the
+; same values on every run.
+
+%define SYS_GETRANDOM       318
+%define EINTR               4
+
+section .data
+    _random_seeded: dq 0                    ; 1 once seeded: values come from the generator
+    _random_state: dq 0, 0, 0, 0            ; xoshiro256** state
+    _random_float_scale: dq 0x3CA0000000000000  ; 2^-53
+
+section .text
+
+; _random_next - 64 random bits
+; Returns: rax
+; Clobbers: rcx, rdx, rsi, rdi, r8, r11
+_random_next:
+    cmp qword [rel _random_seeded], 0
+    jne .generator
+    sub rsp, 8                              ; room for the bits
+.kernel:
+    mov rax, SYS_GETRANDOM
+    mov rdi, rsp
+    mov rsi, 8
+    xor edx, edx
+    syscall
+    cmp rax, -EINTR
+    je .kernel                              ; interrupted: ask again
+    test rax, rax
+    js .failed
+    pop rax
+    ret
+.failed:
+    neg rax
+    mov [rel _last_error], rax
+    add rsp, 8
+    xor eax, eax
+    ret
+
+.generator:
+    mov rdx, [rel _random_state + 8]        ; s1
+    lea rax, [rdx + rdx*4]                  ; s1 * 5
+    rol rax, 7
+    lea rax, [rax + rax*8]                  ; * 9
+    mov rcx, rdx
+    shl rcx, 17                             ; t = s1 << 17
+    mov rsi, [rel _random_state]            ; s0
+    mov rdi, [rel _random_state + 16]       ; s2
+    mov r8, [rel _random_state + 24]        ; s3
+    xor rdi, rsi                            ; s2 ^= s0
+    xor r8, rdx                             ; s3 ^= s1
+    xor rdx, rdi                            ; s1 ^= s2
+    xor rsi, r8                             ; s0 ^= s3
+    xor rdi, rcx                            ; s2 ^= t
+    rol r8, 45                              ; s3 = rotl(s3, 45)
+    mov [rel _random_state], rsi
+    mov [rel _random_state + 8], rdx
+    mov [rel _random_state + 16], rdi
+    mov [rel _random_state + 24], r8
+    ret
+
+; _random_seed - Start the generator from a seed (expanded with splitmix64)
+; Args: rdi = seed
+_random_seed:
+    mov rax, rdi
+    xor ecx, ecx
+.next_word:
+    mov r8, 0x9E3779B97F4A7C15
+    add rax, r8
+    mov rdx, rax
+    mov rsi, rdx
+    shr rsi, 30
+    xor rdx, rsi
+    mov r8, 0xBF58476D1CE4E5B9
+    imul rdx, r8
+    mov rsi, rdx
+    shr rsi, 27
+    xor rdx, rsi
+    mov r8, 0x94D049BB133111EB
+    imul rdx, r8
+    mov rsi, rdx
+    shr rsi, 31
+    xor rdx, rsi
+    lea rsi, [rel _random_state]
+    mov [rsi + rcx*8], rdx
+    inc rcx
+    cmp rcx, 4
+    jb .next_word
+    mov qword [rel _random_seeded], 1
+    ret
+
+; _random_between - A number from low to high, both included
+; Args: rdi = low, rsi = high (either order)
+; Returns: rax
+_random_between:
+    push rbx
+    push r12
+    push r13
+    cmp rdi, rsi
+    jle .ordered
+    xchg rdi, rsi
+.ordered:
+    mov rbx, rdi                            ; low
+    mov r12, rsi
+    sub r12, rdi
+    inc r12                                 ; how many values (0: all 2^64)
+    jz .any
+    ; Multiply-and-shift, drawing again below 2^64 mod span so that
+    ; every value is equally likely
+    mov rax, r12
+    neg rax
+    xor edx, edx
+    div r12
+    mov r13, rdx                            ; 2^64 mod span
+.draw:
+    call _random_next
+    mul r12                                 ; rdx:rax = bits * span
+    cmp rax, r13
+    jb .draw
+    lea rax, [rbx + rdx]
+    jmp .done
+.any:
+    call _random_next
+.done:
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+; _random_float - A float from 0 up to but not including 1
+; Returns: rax = float bits
+_random_float:
+    call _random_next
+    shr rax, 11                             ; 53 bits fill the mantissa
+    cvtsi2sd xmm0, rax
+    mulsd xmm0, [rel _random_float_scale]
+    movq rax, xmm0
+    ret
+
+; _random_element - A random element of a list
+; Args: rdi = list
+; Returns: rax = element (0 and ERR_OUT_OF_BOUNDS when the list is empty)
+_random_element:
+    push rbx
+    mov rbx, rdi
+    mov rsi, [rbx + 8]                      ; length
+    test rsi, rsi
+    jz .empty
+    dec rsi
+    xor edi, edi
+    call _random_between
+    mov rax, [rbx + 24 + rax*8]             ; data starts at offset 24
+    pop rbx
+    ret
+.empty:
+    mov qword [rel _last_error], ERR_OUT_OF_BOUNDS
+    xor eax, eax
+    pop rbx
+    ret
+
+; _list_shuffle - Put the elements in a random order (Fisher-Yates)
+; Args: rdi = list
+_list_shuffle:
+    push rbx
+    push r12
+    mov rbx, rdi
+    mov r12, [rbx + 8]                      ; length
+.next:
+    dec r12                                 ; last position not yet settled
+    jle .done
+    xor edi, edi
+    mov rsi, r12
+    call _random_between                    ; swap it with any position up to it
+    lea rcx, [rbx + 24]
+    mov rdx, [rcx + r12*8]
+    mov r8, [rcx + rax*8]
+    mov [rcx + r12*8], r8
+    mov [rcx + rax*8], rdx
+    jmp .next
+.done:
+    pop r12
+    pop rbx
+    ret
+
+; _random_bytes - Fill a buffer with random bytes
+; Args: rdi = buffer, rsi = byte count (-1: the buffer's capacity)
+; A count over the capacity fills the buffer and sets ERR_BUFFER_FULL.
+_random_bytes:
+    push rbx
+    push r12
+    push r13
+    mov rbx, rdi
+    mov r12, rsi
+    cmp r12, [rbx]                          ; capacity (buffers: see resource.asm)
+    jbe .sized
+    cmp r12, -1
+    je .whole
+    mov qword [rel _last_error], ERR_BUFFER_FULL
+.whole:
+    mov r12, [rbx]
+.sized:
+    mov [rbx + 8], r12                      ; length
+    lea r13, [rbx + 24]                     ; data
+    mov byte [r13 + r12], 0                 ; keep the data null-terminated
+    cmp qword [rel _random_seeded], 0
+    jne .generator
+
+.kernel:
+    test r12, r12
+    jz .done
+    mov rax, SYS_GETRANDOM
+    mov rdi, r13
+    mov rsi, r12
+    xor edx, edx
+    syscall
+    cmp rax, -EINTR
+    je .kernel
+    test rax, rax
+    js .failed
+    add r13, rax                            ; large requests may come back short
+    sub r12, rax
+    jmp .kernel
+.failed:
+    neg rax
+    mov [rel _last_error], rax
+    jmp .done
+
+.generator:
+    cmp r12, 8
+    jb .tail
+    call _random_next
+    mov [r13], rax
+    add r13, 8
+    sub r12, 8
+    jmp .generator
+.tail:
+    test r12, r12
+    jz .done
+    call _random_next
+.tail_byte:
+    mov [r13], al
+    shr rax, 8
+    inc r13
+    dec r12
+    jnz .tail_byte
+
+.done:
+    pop r13
+    pop r12
+    pop rbx
+    ret
@@ -1,9 +1,10 @@
 ; resource.asm - Runtime resource tracking for automatic cleanup
 ; Tracks file descriptors and buffers for safe cleanup on exit
 
-; Maximum tracked resources
+; Maximum tracked file descriptors; the buffer table starts with room for
+; INITIAL_BUFFERS and doubles whenever it fills
 %define MAX_FDS 64
-%define MAX_BUFFERS 64
+%define INITIAL_BUFFERS 64
 
 ; Buffer structure offsets
 %define BUF_CAPACITY 0      ; 8 bytes: allocated size
@@ -17,6 +18,9 @@
 ; Initial buffer capacity
 %define INITIAL_BUF_CAP 4096
 
+; Records (struct values) are carved from tracked buffers of this size
+%define RECORD_CHUNK_BYTES 65536
+
 section .bss
     ; File descriptor tracking table
     ; Each entry: 8 bytes (fd value, 0 = unused)
@@ -25,11 +29,21 @@ section .bss
     
     ; Buffer tracking table
     ; Each entry: 8 bytes (pointer to buffer struct, 0 = unused)
-    buf_table: resq MAX_BUFFERS
+    buf_table_initial: resq INITIAL_BUFFERS
     buf_count: resq 1
     
+    ; Record allocation: the rest of the current chunk
+    _record_chunk_next: resq 1
+    _record_chunk_left: resq 1
+    _record_spare: resb RECORD_CHUNK_BYTES   ; handed out when memory ran out
+    
     ; Note: _last_error is defined in core.asm (always available)
     line_read_tmp: resb 1
+    _line_at_end: resq 1     ; 1 when the last line read found nothing left to read
+
+section .data
+    buf_table: dq buf_table_initial  ; the table in use, mmap'd once it has grown
+    buf_table_cap: dq INITIAL_BUFFERS
 
 section .text
 
@@ -70,6 +84,8 @@ _register_fd:
 ;   - Newline is preserved in the destination buffer
 ;   - Buffer is always null-terminated
 ;   - Fixed buffer overflow truncates line, sets error, and drains remainder of the line
+;   - _line_at_end is set to 1 when input ended (or failed) before any byte was read,
+;     so an empty line ("\n") can be told apart from the end of the file
 global _read_line_into_buffer
 _read_line_into_buffer:
     push rbx
@@ -84,6 +100,7 @@ _read_line_into_buffer:
     mov r13, rsi                 ; buffer
     xor r14, r14                 ; bytes read this call
     mov r15, [rsi + BUF_FLAGS]   ; buffer flags
+    mov qword [rel _line_at_end], 0
 
 .line_loop:
     ; Ensure at least 1 byte of data capacity remains
@@ -116,7 +133,7 @@ _read_line_into_buffer:
 
     ; rax == 0 => EOF, rax < 0 => read error
     cmp rax, 0
-    je .line_done
+    je .line_eof
     js .line_read_error
 
     movzx ebx, byte [rel line_read_tmp]
@@ -147,8 +164,8 @@ _read_line_into_buffer:
     jmp .line_loop
 
 .fixed_overflow:
-    ; Truncated line in fixed-size buffer: set overflow error and drain until newline/EOF
-    mov qword [rel _last_error], 1
+    ; Truncated line in fixed-size buffer: set buffer-full error and drain until newline/EOF
+    mov qword [rel _last_error], ERR_BUFFER_FULL
 
 .drain_line:
     mov rax, 0                   ; SYS_READ
@@ -167,7 +184,14 @@ _read_line_into_buffer:
     jmp .line_done
 
 .line_read_error:
-    mov qword [rel _last_error], 2
+    neg rax                      ; syscall returned -errno
+    mov [rel _last_error], rax
+
+.line_eof:
+    ; A final line without a newline still counts as a line
+    test r14, r14
+    jnz .line_done
+    mov qword [rel _line_at_end], 1
 
 .line_done:
     ; Always null-terminate
@@ -195,7 +219,7 @@ _seek_fd_byte:
     push rbx
 
     cmp rsi, 1
-    jl .seek_byte_error
+    jl .seek_byte_out_of_bounds
 
     dec rsi                      ; convert 1-indexed -> 0-indexed offset
     mov rax, 8                   ; SYS_LSEEK
@@ -209,7 +233,14 @@ _seek_fd_byte:
     ret
 
 .seek_byte_error:
-    mov qword [rel _last_error], 2
+    neg rax                      ; syscall returned -errno
+    mov [rel _last_error], rax
+    mov rax, -1
+    pop rbx
+    ret
+
+.seek_byte_out_of_bounds:
+    mov qword [rel _last_error], ERR_OUT_OF_BOUNDS
     mov rax, -1
     pop rbx
     ret
@@ -229,7 +260,7 @@ _seek_fd_line:
     mov r13, rsi                 ; target line
 
     cmp r13, 1
-    jl .seek_line_error
+    jl .seek_line_out_of_bounds
 
     ; Rewind to start first
     mov rax, 8                   ; SYS_LSEEK
@@ -254,7 +285,7 @@ _seek_fd_line:
     syscall
 
     cmp rax, 0
-    je .seek_line_error          ; hit EOF before requested line
+    je .seek_line_out_of_bounds  ; hit EOF before requested line
     js .seek_line_error
 
     movzx ebx, byte [rel line_read_tmp]
@@ -282,8 +313,15 @@ _seek_fd_line:
     pop rbx
     ret
 
+.seek_line_out_of_bounds:
+    mov qword [rel _last_error], ERR_OUT_OF_BOUNDS
+    jmp .seek_line_fail
+
 .seek_line_error:
-    mov qword [rel _last_error], 2
+    neg rax                      ; syscall returned -errno
+    mov [rel _last_error], rax
+
+.seek_line_fail:
     mov rax, -1
     pop r13
     pop r12
@@ -360,7 +398,7 @@ _cleanup_fds:
     ret
 
 ; Allocate a new dynamic buffer
-; Returns: pointer to buffer struct in rax (or 0 on failure)
+; Returns: pointer to buffer struct in rax (or 0 on failure, _last_error is set)
 global _alloc_buffer
 _alloc_buffer:
     push rbx
@@ -385,9 +423,9 @@ _alloc_buffer:
     xor r9, r9              ; offset = 0
     syscall
     
-    ; Check for error
-    cmp rax, -1
-    je .failed
+    ; Check for error (mmap returns -errno)
+    test rax, rax
+    js .failed
     
     ; Initialize buffer header (dynamic buffer)
     mov qword [rax + BUF_CAPACITY], INITIAL_BUF_CAP
@@ -403,6 +441,8 @@ _alloc_buffer:
     jmp .done
     
 .failed:
+    neg rax
+    mov [rel _last_error], rax
     xor rax, rax
     
 .done:
@@ -419,7 +459,7 @@ _alloc_buffer:
 
 ; Allocate a fixed-size buffer (no auto-grow, bounds checked)
 ; Args: size in rdi
-; Returns: pointer to buffer struct in rax (or 0 on failure)
+; Returns: pointer to buffer struct in rax (or 0 on failure, _last_error is set)
 global _alloc_buffer_sized
 _alloc_buffer_sized:
     push rbx
@@ -448,9 +488,9 @@ _alloc_buffer_sized:
     xor r9, r9              ; offset = 0
     syscall
     
-    ; Check for error
-    cmp rax, -1
-    je .sized_failed
+    ; Check for error (mmap returns -errno)
+    test rax, rax
+    js .sized_failed
     
     ; Initialize buffer header (fixed size buffer)
     mov [rax + BUF_CAPACITY], r12
@@ -466,6 +506,8 @@ _alloc_buffer_sized:
     jmp .sized_done
     
 .sized_failed:
+    neg rax
+    mov [rel _last_error], rax
     xor rax, rax
     
 .sized_done:
@@ -481,34 +523,137 @@ _alloc_buffer_sized:
     pop rbx
     ret
 
-; Register a buffer for tracking
+; Allocate zeroed memory for a record (the fields of a struct value)
+; Args: size in rdi (at most RECORD_CHUNK_BYTES)
+; Returns: pointer in rax; when memory ran out _last_error is set and rax
+;          points at a shared spare area, so stores into it stay harmless
+; Clobbers: rdi
+global _alloc_record
+_alloc_record:
+    add rdi, 7
+    and rdi, ~7
+    cmp rdi, [rel _record_chunk_left]
+    ja .new_chunk
+    mov rax, [rel _record_chunk_next]
+    add [rel _record_chunk_next], rdi
+    sub [rel _record_chunk_left], rdi
+    ret
+.new_chunk:
+    push rdi
+    mov rdi, RECORD_CHUNK_BYTES
+    call _alloc_buffer_sized
+    pop rdi
+    test rax, rax
+    jz .record_failed
+    add rax, BUF_DATA
+    push rcx
+    lea rcx, [rax + rdi]
+    mov [rel _record_chunk_next], rcx
+    mov rcx, RECORD_CHUNK_BYTES
+    sub rcx, rdi
+    mov [rel _record_chunk_left], rcx
+    pop rcx
+    ret
+.record_failed:
+    lea rax, [rel _record_spare]
+    ret
+
+; Register a buffer for tracking, growing the table when it is full
 ; Args: buffer pointer in rdi
+; Sets _last_error (and leaves the buffer untracked) if the table cannot grow
 global _register_buffer
 _register_buffer:
     push rbx
     push rcx
     
+    mov rbx, [rel buf_table]
+    mov rcx, [rel buf_table_cap]
+    cmp [rel buf_count], rcx
+    jae .table_full             ; rcx = the first entry of the grown table
+    
     xor rcx, rcx
 .find_slot:
-    cmp rcx, MAX_BUFFERS
-    jge .table_full
-    
-    mov rax, [buf_table + rcx*8]
+    mov rax, [rbx + rcx*8]
     test rax, rax
     jz .found_slot
-    
     inc rcx
     jmp .find_slot
     
+.table_full:
+    call _grow_buffer_table
+    test rax, rax
+    jz .done
+    mov rbx, rax
+    
 .found_slot:
-    mov [buf_table + rcx*8], rdi
-    inc qword [buf_count]
+    mov [rbx + rcx*8], rdi
+    inc qword [rel buf_count]
     
-.table_full:
+.done:
     pop rcx
     pop rbx
     ret
 
+; Double the buffer table, copying its entries
+; Returns: new table in rax, or 0 when memory ran out (_last_error is set)
+_grow_buffer_table:
+    push rcx
+    push rdx
+    push rsi
+    push rdi
+    push r8
+    push r9
+    push r10
+    push r11
+    
+    mov rsi, [rel buf_table_cap]
+    shl rsi, 4              ; twice the entries, 8 bytes each
+    mov rax, 9              ; SYS_MMAP
+    xor rdi, rdi            ; addr = NULL
+    mov rdx, 3              ; PROT_READ | PROT_WRITE
+    mov r10, 34             ; MAP_PRIVATE | MAP_ANONYMOUS
+    mov r8, -1              ; fd = -1
+    xor r9, r9              ; offset = 0
+    syscall
+    test rax, rax
+    js .failed
+    
+    mov rdi, rax
+    mov rsi, [rel buf_table]
+    mov rcx, [rel buf_table_cap]
+    rep movsq
+    mov rdi, [rel buf_table]    ; old table
+    mov rsi, [rel buf_table_cap]
+    mov [rel buf_table], rax
+    shl qword [rel buf_table_cap], 1
+    
+    ; Unmap the old table unless it is the initial one
+    lea rcx, [rel buf_table_initial]
+    cmp rdi, rcx
+    je .done
+    push rax
+    shl rsi, 3
+    mov rax, 11             ; SYS_MUNMAP
+    syscall
+    pop rax
+    jmp .done
+    
+.failed:
+    neg rax
+    mov [rel _last_error], rax
+    xor eax, eax
+    
+.done:
+    pop r11
+    pop r10
+    pop r9
+    pop r8
+    pop rdi
+    pop rsi
+    pop rdx
+    pop rcx
+    ret
+
 ; Unregister a buffer from tracking (without freeing)
 ; Args: buffer pointer in rdi
 global _unregister_buffer
@@ -516,12 +661,13 @@ _unregister_buffer:
     push rbx
     push rcx
     
+    mov rbx, [rel buf_table]
     xor rcx, rcx
 .find_unreg:
-    cmp rcx, MAX_BUFFERS
+    cmp rcx, [rel buf_table_cap]
     jge .not_found_unreg
     
-    mov rax, [buf_table + rcx*8]
+    mov rax, [rbx + rcx*8]
     cmp rax, rdi
     je .found_unreg
     
@@ -529,8 +675,8 @@ _unregister_buffer:
     jmp .find_unreg
     
 .found_unreg:
-    mov qword [buf_table + rcx*8], 0
-    dec qword [buf_count]
+    mov qword [rbx + rcx*8], 0
+    dec qword [rel buf_count]
     
 .not_found_unreg:
     pop rcx
@@ -546,12 +692,13 @@ _free_buffer:
     push rsi
     
     ; Find and remove from table
+    mov rbx, [rel buf_table]
     xor rcx, rcx
 .find_buf:
-    cmp rcx, MAX_BUFFERS
+    cmp rcx, [rel buf_table_cap]
     jge .not_found
     
-    mov rax, [buf_table + rcx*8]
+    mov rax, [rbx + rcx*8]
     cmp rax, rdi
     je .found_buf
     
@@ -559,8 +706,8 @@ _free_buffer:
     jmp .find_buf
     
 .found_buf:
-    mov qword [buf_table + rcx*8], 0
-    dec qword [buf_count]
+    mov qword [rbx + rcx*8], 0
+    dec qword [rel buf_count]
     
     ; munmap the buffer
     mov rsi, [rdi + BUF_CAPACITY]
@@ -583,12 +730,13 @@ _cleanup_buffers:
     push r13
     push r14
     
+    mov r14, [rel buf_table]
     xor r12, r12        ; r12 = loop counter
 .free_loop:
-    cmp r12, MAX_BUFFERS
+    cmp r12, [rel buf_table_cap]
     jge .done
     
-    mov rdi, [buf_table + r12*8]
+    mov rdi, [r14 + r12*8]
     test rdi, rdi
     jz .next
     
@@ -601,14 +749,14 @@ _cleanup_buffers:
     mov rax, 11             ; SYS_MUNMAP
     syscall
     
-    mov qword [buf_table + r12*8], 0
+    mov qword [r14 + r12*8], 0
     
 .next:
     inc r12
     jmp .free_loop
     
 .done:
-    mov qword [buf_count], 0
+    mov qword [rel buf_count], 0
     pop r14
     pop r13
     pop r12
@@ -655,8 +803,8 @@ _grow_buffer:
     xor r9, r9
     syscall
     
-    cmp rax, -1
-    je .failed_pop_r14
+    test rax, rax
+    js .failed_pop_r14
     
     mov rbx, rax            ; new buffer
     
@@ -674,17 +822,18 @@ _grow_buffer:
     rep movsb
     
     ; Update buffer table entry
+    mov rdx, [rel buf_table]
     xor rcx, rcx
 .find_entry:
-    cmp rcx, MAX_BUFFERS
+    cmp rcx, [rel buf_table_cap]
     jge .no_entry
-    mov rax, [buf_table + rcx*8]
+    mov rax, [rdx + rcx*8]
     cmp rax, r12
     je .update_entry
     inc rcx
     jmp .find_entry
 .update_entry:
-    mov [buf_table + rcx*8], rbx
+    mov [rdx + rcx*8], rbx
 .no_entry:
     
     ; Free old buffer (+1 for null terminator)
@@ -698,6 +847,8 @@ _grow_buffer:
     jmp .done
     
 .failed_pop_r14:
+    neg rax
+    mov [rel _last_error], rax
     pop r14                 ; balance the push
 .failed:
     mov rax, r12            ; return old buffer on failure
@@ -777,7 +928,8 @@ _read_into_buffer:
     
     ; Check result
     cmp rax, 0
-    jle .done               ; EOF or error
+    je .done                ; EOF
+    jl .read_error
     
     ; Update length
     add [r13 + BUF_LENGTH], rax
@@ -796,12 +948,17 @@ _read_into_buffer:
     
 .fixed_full:
     ; Fixed buffer is full - set error flag and stop reading
-    mov qword [rel _last_error], 1  ; buffer overflow error
+    mov qword [rel _last_error], ERR_BUFFER_FULL
+    jmp .done
+
+.read_error:
+    neg rax                 ; syscall returned -errno
+    mov [rel _last_error], rax
     jmp .done
     
 .overflow_error:
     ; Fixed buffer has no space - set error and return 0 bytes read
-    mov qword [rel _last_error], 1  ; buffer overflow error
+    mov qword [rel _last_error], ERR_BUFFER_FULL
     mov rax, 0              ; return 0 (no bytes read)
     mov rsi, r13            ; return buffer pointer unchanged
     jmp .exit
@@ -915,47 +1072,6 @@ _realloc_buffer:
     pop rbx
     ret
 
-; Print last error to stderr (for auto error catching)
-; No args, uses _last_error global
-global _print_last_error
-_print_last_error:
-    push rbx
-    
-    mov rax, [rel _last_error]
-    cmp rax, 1
-    je .buffer_overflow
-    cmp rax, 2
-    je .file_error
-    jmp .done
-    
-.buffer_overflow:
-    ; Write "Error: Buffer overflow\n" to stderr
-    mov rax, 1              ; sys_write
-    mov rdi, 2              ; stderr
-    lea rsi, [rel .err_buf_overflow]
-    mov rdx, 23             ; length including newline
-    syscall
-    jmp .done
-    
-.file_error:
-    ; Write "Error: File operation failed\n" to stderr
-    mov rax, 1              ; sys_write
-    mov rdi, 2              ; stderr
-    lea rsi, [rel .err_file]
-    mov rdx, 29             ; length
-    syscall
-    jmp .done
-    
-.done:
-    pop rbx
-    ret
-
-section .rodata
-.err_buf_overflow: db "Error: Buffer overflow", 10
-.err_file: db "Error: File operation failed", 10
-
-section .text
-
 ; Cleanup all resources - call before exit
 global _cleanup_all
 _cleanup_all:
@@ -166,3 +166,455 @@ _str_eq:
     mov rax, 1
     pop rbx
     ret
+
+; ============================================================================
+; TEXT OPERATIONS
+; ============================================================================
+; Results are new texts; the inputs are never changed. Small results are
+; carved out of 64 KiB chunks, larger ones get a mapping of their own.
+; Positions are 1-based, like `byte N of` and `element N of`.
+
+%define STR_CHUNK_BYTES     65536
+%define STR_LIST_HEADER     24          ; [capacity][length][elem_size], as in list.asm
+
+section .bss
+    _str_chunk_next: resq 1     ; next free byte in the current chunk
+    _str_chunk_left: resq 1     ; bytes left in the current chunk
+
+section .data
+    _str_empty: db 0
+    _str_no_pieces: dq 0, 0, 8  ; empty list returned when memory ran out
+
+section .text
+
+; Allocate memory for a text result
+; Args: rdi = size in bytes
+; Returns: rax = pointer, or 0 when memory ran out (_last_error is set)
+; Clobbers: rcx, rdx, rsi, rdi, r8-r11
+_str_alloc:
+    add rdi, 7
+    and rdi, ~7
+    cmp rdi, [rel _str_chunk_left]
+    ja .new_chunk
+    mov rax, [rel _str_chunk_next]
+    add [rel _str_chunk_next], rdi
+    sub [rel _str_chunk_left], rdi
+    ret
+.new_chunk:
+    push rdi
+    mov rsi, rdi
+    cmp rsi, STR_CHUNK_BYTES / 2
+    ja .map                     ; large: own mapping, the chunk stays current
+    mov rsi, STR_CHUNK_BYTES
+.map:
+    add rsi, 4095
+    and rsi, ~4095
+    push rsi
+    mov rax, 9                  ; sys_mmap
+    xor rdi, rdi                ; addr = NULL
+    mov rdx, 3                  ; PROT_READ | PROT_WRITE
+    mov r10, 0x22               ; MAP_PRIVATE | MAP_ANONYMOUS
+    mov r8, -1                  ; fd = -1
+    xor r9, r9                  ; offset = 0
+    syscall
+    pop rsi                     ; mapped size
+    pop rdi                     ; requested size
+    test rax, rax
+    js .fail
+    cmp rdi, STR_CHUNK_BYTES / 2
+    ja .done
+    lea rcx, [rax + rdi]
+    mov [rel _str_chunk_next], rcx
+    sub rsi, rdi
+    mov [rel _str_chunk_left], rsi
+.done:
+    ret
+.fail:
+    neg rax
+    mov [rel _last_error], rax
+    xor rax, rax
+    ret
+
+; Copy bytes into a new text
+; Args: rsi = first byte, rdx = number of bytes
+; Returns: rax = new text ("" when memory ran out)
+; Clobbers: rcx, rdx, rsi, rdi, r8-r11
+global _str_new
+_str_new:
+    push rsi
+    push rdx
+    lea rdi, [rdx + 1]
+    call _str_alloc
+    pop rdx
+    pop rsi
+    test rax, rax
+    jz .fail
+    mov rdi, rax
+    mov rcx, rdx
+    rep movsb
+    mov byte [rdi], 0
+    ret
+.fail:
+    lea rax, [rel _str_empty]
+    ret
+
+; Length of a text in bytes
+; Args: rdi = text
+; Returns: rax = length (only rax changes)
+global _str_length
+_str_length:
+    xor rax, rax
+.loop:
+    cmp byte [rdi + rax], 0
+    je .done
+    inc rax
+    jmp .loop
+.done:
+    ret
+
+; Characters start to end of a text, both ends included
+; Args: rdi = text, rsi = start, rdx = end
+; Returns: rax = new text; "" and ERR_OUT_OF_BOUNDS unless 1 <= start <= end <= length
+global _str_slice
+_str_slice:
+    push rbx
+    mov rbx, rdi
+    call _str_length
+    cmp rsi, 1
+    jl .out_of_bounds
+    cmp rdx, rsi
+    jl .out_of_bounds
+    cmp rdx, rax
+    jg .out_of_bounds
+    sub rdx, rsi
+    inc rdx                     ; number of characters
+    lea rsi, [rbx + rsi - 1]
+    call _str_new
+    pop rbx
+    ret
+.out_of_bounds:
+    mov qword [rel _last_error], ERR_OUT_OF_BOUNDS
+    lea rax, [rel _str_empty]
+    pop rbx
+    ret
+
+; Order of two texts, byte by byte
+; Args: rdi = text, rsi = other text
+; Returns: rax < 0, 0 or > 0 as the text sorts before, equal to or after the other
+; Clobbers: rcx, rdx
+global _str_compare
+_str_compare:
+    xor ecx, ecx
+.loop:
+    movzx eax, byte [rdi + rcx]
+    movzx edx, byte [rsi + rcx]
+    cmp eax, edx
+    jne .differ
+    test eax, eax
+    jz .done
+    inc rcx
+    jmp .loop
+.differ:
+    sub eax, edx
+    movsxd rax, eax
+.done:
+    ret
+
+; Position of the first occurrence of a part in a text
+; Args: rdi = text, rsi = part
+; Returns: rax = 1-based position, or 0 when the part does not occur
+; Clobbers: rcx, rdx
+global _str_find
+_str_find:
+    mov rax, rdi                ; where the part might start
+.next:
+    xor rcx, rcx
+.compare:
+    mov dl, [rsi + rcx]
+    test dl, dl
+    jz .found
+    cmp dl, [rax + rcx]
+    jne .advance
+    inc rcx
+    jmp .compare
+.advance:
+    cmp byte [rax], 0
+    je .missing
+    inc rax
+    jmp .next
+.found:
+    sub rax, rdi
+    inc rax
+    ret
+.missing:
+    xor rax, rax
+    ret
+
+; Args: rdi = text, rsi = part
+; Returns: rax = 1 if the part occurs in the text, 0 if not
+global _str_contains
+_str_contains:
+    call _str_find
+    test rax, rax
+    setnz al
+    movzx rax, al
+    ret
+
+; Args: rdi = text, rsi = prefix
+; Returns: rax = 1 if the text begins with the prefix, 0 if not
+global _str_starts_with
+_str_starts_with:
+    xor rcx, rcx
+.loop:
+    mov dl, [rsi + rcx]
+    test dl, dl
+    jz .yes
+    cmp dl, [rdi + rcx]
+    jne .no
+    inc rcx
+    jmp .loop
+.yes:
+    mov rax, 1
+    ret
+.no:
+    xor rax, rax
+    ret
+
+; Args: rdi = text, rsi = suffix
+; Returns: rax = 1 if the text ends with the suffix, 0 if not
+global _str_ends_with
+_str_ends_with:
+    push rbx
+    mov rbx, rdi
+    call _str_length
+    mov rdx, rax                ; text length
+    mov rdi, rsi
+    call _str_length            ; suffix length
+    cmp rax, rdx
+    ja .no
+    sub rdx, rax
+    lea rdi, [rbx + rdx]        ; where the suffix has to start
+    pop rbx
+    jmp _str_eq
+.no:
+    xor rax, rax
+    pop rbx
+    ret
+
+; Copy of a text with ASCII letters in upper or lower case
+; Args: rdi = text
+; Returns: rax = new text
+global _str_upper
+_str_upper:
+    mov r8b, 'a'                ; first letter of the case to change
+    jmp _str_change_case
+global _str_lower
+_str_lower:
+    mov r8b, 'A'
+_str_change_case:
+    call _str_length
+    mov rsi, rdi
+    mov rdx, rax
+    push r8
+    call _str_new
+    pop r8
+    mov rcx, rax
+.loop:
+    mov dl, [rcx]
+    test dl, dl
+    jz .done
+    sub dl, r8b
+    cmp dl, 25
+    ja .next
+    xor byte [rcx], 0x20        ; flip the case bit
+.next:
+    inc rcx
+    jmp .loop
+.done:
+    ret
+
+; Copy of a text without spaces, tabs or line breaks at either end
+; Args: rdi = text
+; Returns: rax = new text
+global _str_trim
+_str_trim:
+    mov rsi, rdi
+.leading:
+    movzx eax, byte [rsi]
+    cmp al, ' '
+    je .skip
+    sub al, 9                   ; \t \n \v \f \r are 9 to 13
+    cmp al, 4
+    ja .leading_done
+.skip:
+    inc rsi
+    jmp .leading
+.leading_done:
+    mov rdi, rsi
+    call _str_length
+    mov rdx, rax
+.trailing:
+    test rdx, rdx
+    jz .copy
+    movzx eax, byte [rsi + rdx - 1]
+    cmp al, ' '
+    je .drop
+    sub al, 9
+    cmp al, 4
+    ja .copy
+.drop:
+    dec rdx
+    jmp .trailing
+.copy:
+    jmp _str_new
+
+; Split a text at every occurrence of a separator
+; Args: rdi = text, rsi = separator
+; Returns: rax = list of the pieces; an empty separator gives the whole text
+global _str_split
+_str_split:
+    push rbx
+    push r12
+    push r13
+    push r14
+    push r15
+    mov r12, rdi                ; rest of the text
+    mov r13, rsi                ; separator
+    mov rdi, rsi
+    call _str_length
+    mov r14, rax                ; separator length
+
+    ; Count the pieces
+    mov rbx, 1
+    test r14, r14
+    jz .allocate
+    mov r15, r12
+.count:
+    mov rdi, r15
+    mov rsi, r13
+    call _str_find
+    test rax, rax
+    jz .allocate
+    inc rbx
+    lea r15, [r15 + rax - 1]
+    add r15, r14
+    jmp .count
+
+.allocate:
+    lea rdi, [rbx * 8 + STR_LIST_HEADER]
+    call _str_alloc
+    test rax, rax
+    jz .fail
+    mov [rax], rbx              ; capacity
+    mov qword [rax + 8], 0      ; length, counts up as pieces are stored
+    mov qword [rax + 16], 8     ; element size
+    mov r15, rax
+
+.piece:
+    test r14, r14
+    jz .last
+    mov rdi, r12
+    mov rsi, r13
+    call _str_find
+    test rax, rax
+    jz .last
+    lea rdx, [rax - 1]          ; bytes before the separator
+    mov rsi, r12
+    lea r12, [r12 + rdx]
+    add r12, r14                ; continue after the separator
+    call _str_new
+    mov rcx, [r15 + 8]
+    mov [r15 + STR_LIST_HEADER + rcx * 8], rax
+    inc qword [r15 + 8]
+    jmp .piece
+
+.last:
+    mov rdi, r12
+    call _str_length
+    mov rsi, r12
+    mov rdx, rax
+    call _str_new
+    mov rcx, [r15 + 8]
+    mov [r15 + STR_LIST_HEADER + rcx * 8], rax
+    inc qword [r15 + 8]
+    mov rax, r15
+    jmp .done
+.fail:
+    lea rax, [rel _str_no_pieces]
+.done:
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop rbx
+    ret
+
+; Join the texts in a list with a separator between each pair
+; Args: rdi = list, rsi = separator
+; Returns: rax = new text
+global _str_join
+_str_join:
+    push rbx
+    push r12
+    push r13
+    push r14
+    push r15
+    mov r12, rdi                ; list
+    mov r13, rsi                ; separator
+    mov rdi, rsi
+    call _str_length
+    mov r14, rax                ; separator length
+    mov rbx, [r12 + 8]          ; number of texts
+    test rbx, rbx
+    jz .empty
+
+    ; Size: every text, the separators between them, and the NUL
+    lea r15, [rbx - 1]
+    imul r15, r14
+    xor rcx, rcx
+.measure:
+    mov rdi, [r12 + STR_LIST_HEADER + rcx * 8]
+    call _str_length
+    add r15, rax
+    inc rcx
+    cmp rcx, rbx
+    jl .measure
+    lea rdi, [r15 + 1]
+    call _str_alloc
+    test rax, rax
+    jz .empty
+    mov r15, rax
+
+    mov rdi, rax                ; write position
+    xor rdx, rdx                ; index of the text being copied
+.copy:
+    mov rsi, [r12 + STR_LIST_HEADER + rdx * 8]
+.copy_text:
+    mov al, [rsi]
+    test al, al
+    jz .copied
+    mov [rdi], al
+    inc rsi
+    inc rdi
+    jmp .copy_text
+.copied:
+    inc rdx
+    cmp rdx, rbx
+    jge .finish
+    mov rsi, r13
+    mov rcx, r14
+    rep movsb
+    jmp .copy
+.finish:
+    mov byte [rdi], 0
+    mov rax, r15
+    jmp .done
+.empty:
+    lea rax, [rel _str_empty]
+.done:
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop rbx
+    ret
@@ -0,0 +1,379 @@
+use crate::parser::ast::*;
+
+pub type BlockId = usize;
+
+/// A single step inside a basic block.
+#[derive(Debug)]
+pub enum Node<'a> {
+    /// A straight-line statement (declaration, print, call, file I/O, ...)
+    Statement(&'a Statement),
+    /// An expression evaluated for control flow (condition, loop bound, exit code)
+    Eval(&'a Expr),
+    /// A loop variable bound at the start of every iteration, or a name set
+    /// from one of a function's returned values
+    Bind(&'a str),
+}
+
+#[derive(Debug, Clone)]
+pub enum Terminator<'a> {
+    Goto(BlockId),
+    /// Two-way branch on a condition; the analysis may learn facts from it
+    Branch {
+        condition: &'a Expr,
+        then_to: BlockId,
+        else_to: BlockId,
+    },
+    /// Flow may continue into any of the targets (loop entry, on error, ...)
+    Either(Vec<BlockId>),
+    /// Exit, return, or the end of the statement list
+    Stop,
+}
+
+#[derive(Debug)]
+pub struct BasicBlock<'a> {
+    pub nodes: Vec<Node<'a>>,
+    pub terminator: Terminator<'a>,
+}
+
+/// Control-flow graph of one scope (the top level or a function body).
+/// Block 0 is the entry; blocks are numbered in source order.
+#[derive(Debug)]
+pub struct Cfg<'a> {
+    pub blocks: Vec<BasicBlock<'a>>,
+}
+
+struct LoopTargets<'a> {
+    name: Option<&'a str>,
+    continue_to: BlockId,
+    breaks: Vec<BlockId>,
+}
+
+struct Builder<'a> {
+    blocks: Vec<BasicBlock<'a>>,
+    current: BlockId,
+    loops: Vec<LoopTargets<'a>>,
+    /// Name of the loop about to be laid out, from `NamedLoop`
+    next_loop_name: Option<&'a str>,
+}
+
+impl<'a> Cfg<'a> {
+    pub fn build(statements: &'a [Statement]) -> Self {
+        let mut builder = Builder {
+            blocks: Vec::new(),
+            current: 0,
+            loops: Vec::new(),
+            next_loop_name: None,
+        };
+        builder.current = builder.new_block();
+        builder.statements(statements);
+        Cfg { blocks: builder.blocks }
+    }
+}
+
+impl<'a> Builder<'a> {
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(BasicBlock {
+            nodes: Vec::new(),
+            terminator: Terminator::Stop,
+        });
+        self.blocks.len() - 1
+    }
+
+    fn push(&mut self, node: Node<'a>) {
+        self.blocks[self.current].nodes.push(node);
+    }
+
+    fn seal(&mut self, block: BlockId, terminator: Terminator<'a>) {
+        self.blocks[block].terminator = terminator;
+    }
+
+    /// Ends the current block and continues in a fresh one. Nothing jumps to
+    /// the fresh block, so statements after break/continue/exit land in
+    /// unreachable code.
+    fn start_unreachable(&mut self) {
+        self.current = self.new_block();
+    }
+
+    fn statements(&mut self, statements: &'a [Statement]) {
+        for stmt in statements {
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &'a Statement) {
+        match stmt {
+            Statement::If { condition, then_block, else_if_blocks, else_block } => {
+                let arms = std::iter::once((condition, then_block))
+                    .chain(else_if_blocks.iter().map(|(c, b)| (c, b)));
+                let mut arm_ends = Vec::new();
+
+                for (cond, block) in arms {
+                    self.push(Node::Eval(cond));
+                    let test = self.current;
+                    let arm_start = self.new_block();
+                    self.current = arm_start;
+                    self.statements(block);
+                    arm_ends.push(self.current);
+
+                    let next = self.new_block();
+                    self.seal(test, Terminator::Branch {
+                        condition: cond,
+                        then_to: arm_start,
+                        else_to: next,
+                    });
+                    self.current = next;
+                }
+
+                if let Some(block) = else_block {
+                    self.statements(block);
+                }
+                arm_ends.push(self.current);
+
+                let join = self.new_block();
+                for end in arm_ends {
+                    self.seal(end, Terminator::Goto(join));
+                }
+                self.current = join;
+            }
+
+            Statement::Depending { arms, otherwise, .. } => {
+                // The statement node stands for evaluating the subject and the values
+                self.push(Node::Statement(stmt));
+                let test = self.current;
+                let mut targets = Vec::new();
+                let mut arm_ends = Vec::new();
+
+                for block in arms.iter().map(|(_, block)| block).chain(otherwise) {
+                    let arm_start = self.new_block();
+                    targets.push(arm_start);
+                    self.current = arm_start;
+                    self.statements(block);
+                    arm_ends.push(self.current);
+                }
+
+                let join = self.new_block();
+                if otherwise.is_none() {
+                    targets.push(join);
+                }
+                self.seal(test, Terminator::Either(targets));
+                for end in arm_ends {
+                    self.seal(end, Terminator::Goto(join));
+                }
+                self.current = join;
+            }
+
+            Statement::While { condition, body } => {
+                let header = self.new_block();
+                self.seal(self.current, Terminator::Goto(header));
+                self.current = header;
+                self.push(Node::Eval(condition));
+
+                let body_start = self.new_block();
+                self.current = body_start;
+                let breaks = self.loop_body(header, body, &[]);
+
+                let exit = self.new_block();
+                self.seal(header, Terminator::Branch {
+                    condition,
+                    then_to: body_start,
+                    else_to: exit,
+                });
+                self.finish_loop(exit, breaks);
+            }
+
+            Statement::Until { condition, body } => {
+                let header = self.new_block();
+                self.seal(self.current, Terminator::Goto(header));
+                self.current = header;
+                self.push(Node::Eval(condition));
+
+                let body_start = self.new_block();
+                self.current = body_start;
+                let breaks = self.loop_body(header, body, &[]);
+
+                let exit = self.new_block();
+                self.seal(header, Terminator::Branch {
+                    condition,
+                    then_to: exit,
+                    else_to: body_start,
+                });
+                self.finish_loop(exit, breaks);
+            }
+
+            Statement::DoUntil { body, condition } => {
+                // The body always runs once; continue jumps to the test.
+                let body_start = self.new_block();
+                let test = self.new_block();
+                self.seal(self.current, Terminator::Goto(body_start));
+
+                self.current = body_start;
+                let breaks = self.loop_body(test, body, &[]);
+
+                self.current = test;
+                self.push(Node::Eval(condition));
+                let exit = self.new_block();
+                self.seal(test, Terminator::Branch {
+                    condition,
+                    then_to: exit,
+                    else_to: body_start,
+                });
+                self.finish_loop(exit, breaks);
+            }
+
+            Statement::Loop { body } => {
+                // Only break leads to the block after the loop.
+                let body_start = self.new_block();
+                self.seal(self.current, Terminator::Goto(body_start));
+
+                self.current = body_start;
+                let breaks = self.loop_body(body_start, body, &[]);
+
+                let exit = self.new_block();
+                self.finish_loop(exit, breaks);
+            }
+
+            Statement::ForRange { variable, range, body } => {
+                self.push(Node::Eval(range));
+                let runs_once = Self::range_runs_at_least_once(range);
+                self.counted_loop(&[variable], runs_once, body);
+            }
+
+            Statement::ForEach { variable, collection, body } => {
+                self.push(Node::Eval(collection));
+                let runs_once = matches!(collection, Expr::ListLit { elements } if !elements.is_empty());
+                self.counted_loop(&[variable], runs_once, body);
+            }
+
+            Statement::ForEachEntry { key, value, body, .. } => {
+                // The statement node stands for reading the map
+                self.push(Node::Statement(stmt));
+                self.counted_loop(&[key, value], false, body);
+            }
+
+            Statement::Repeat { count, body } => {
+                self.push(Node::Eval(count));
+                let runs_once = matches!(count, Expr::IntegerLit(n) if *n > 0);
+                self.counted_loop(&[], runs_once, body);
+            }
+
+            Statement::NamedLoop { name, inner } => {
+                self.next_loop_name = Some(name);
+                self.statement(inner);
+            }
+
+            Statement::Break { loop_name } => {
+                let current = self.current;
+                if let Some(targets) = self.target_loop(loop_name) {
+                    targets.breaks.push(current);
+                    self.start_unreachable();
+                }
+            }
+
+            Statement::Continue { loop_name } => {
+                if let Some(target) = self.target_loop(loop_name).map(|t| t.continue_to) {
+                    self.seal(self.current, Terminator::Goto(target));
+                    self.start_unreachable();
+                }
+            }
+
+            Statement::Exit { code } => {
+                self.push(Node::Eval(code));
+                self.seal(self.current, Terminator::Stop);
+                self.start_unreachable();
+            }
+
+            Statement::Return { .. } => {
+                // The statement node stands for evaluating the returned values
+                self.push(Node::Statement(stmt));
+                self.seal(self.current, Terminator::Stop);
+                self.start_unreachable();
+            }
+
+            Statement::OnError { actions, .. } => {
+                // The actions only run when the previous operation failed.
+                let before = self.current;
+                let handler = self.new_block();
+                self.current = handler;
+                self.statements(actions);
+                let handler_end = self.current;
+
+                let after = self.new_block();
+                self.seal(before, Terminator::Either(vec![handler, after]));
+                self.seal(handler_end, Terminator::Goto(after));
+                self.current = after;
+            }
+
+            Statement::Destructure { names, .. } => {
+                self.push(Node::Statement(stmt));
+                for name in names {
+                    self.push(Node::Bind(name));
+                }
+            }
+
+            _ => self.push(Node::Statement(stmt)),
+        }
+    }
+
+    /// Lays out for/for each/repeat: a header that either enters the body or
+    /// leaves the loop. When the body is known to run at least once, the
+    /// first iteration is entered directly instead of through the header.
+    fn counted_loop(&mut self, variables: &[&'a str], runs_once: bool, body: &'a [Statement]) {
+        let before = self.current;
+        let header = self.new_block();
+        let body_start = self.new_block();
+        self.seal(before, Terminator::Goto(if runs_once { body_start } else { header }));
+
+        self.current = body_start;
+        let breaks = self.loop_body(header, body, variables);
+
+        let exit = self.new_block();
+        self.seal(header, Terminator::Either(vec![body_start, exit]));
+        self.finish_loop(exit, breaks);
+    }
+
+    fn loop_body(&mut self, header: BlockId, body: &'a [Statement], variables: &[&'a str]) -> Vec<BlockId> {
+        for name in variables {
+            self.push(Node::Bind(name));
+        }
+        self.loops.push(LoopTargets {
+            name: self.next_loop_name.take(),
+            continue_to: header,
+            breaks: Vec::new(),
+        });
+        self.statements(body);
+        self.seal(self.current, Terminator::Goto(header));
+        self.loops.pop().map(|t| t.breaks).unwrap_or_default()
+    }
+
+    /// The innermost loop, or the enclosing loop with the given name
+    fn target_loop(&mut self, loop_name: &Option<String>) -> Option<&mut LoopTargets<'a>> {
+        match loop_name {
+            Some(name) => self.loops.iter_mut().rev().find(|t| t.name == Some(name.as_str())),
+            None => self.loops.last_mut(),
+        }
+    }
+
+    fn finish_loop(&mut self, exit: BlockId, breaks: Vec<BlockId>) {
+        for block in breaks {
+            self.seal(block, Terminator::Goto(exit));
+        }
+        self.current = exit;
+    }
+
+    fn range_runs_at_least_once(range: &Expr) -> bool {
+        match range {
+            Expr::Range { start, end, step, inclusive, descending } => {
+                // A step only known at run time may be zero, which skips the loop
+                let step_known = step.as_ref().is_none_or(|s| s.as_number_literal().is_some_and(|n| n != 0.0));
+                match (start.as_number_literal(), end.as_number_literal()) {
+                    (Some(s), Some(e)) if step_known => {
+                        let (low, high) = if *descending { (e, s) } else { (s, e) };
+                        if *inclusive { low <= high } else { low < high }
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,585 @@
+use crate::parser::ast::*;
+use super::cfg::{Cfg, Node, Terminator};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+/// Above this many distinct paths a state stops tracking branch facts and
+/// keeps only the variables assigned on every path.
+const MAX_PATHS: usize = 32;
+
+/// Branch outcomes known on a path, keyed by the condition's rendering.
+pub type Facts = BTreeMap<String, bool>;
+
+/// Definite-assignment state at a program point.
+///
+/// Each path is identified by the branch outcomes taken to reach the point,
+/// so two `If`s on the same unchanged condition stay correlated: a variable
+/// declared under the first is known assigned under the second.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowState {
+    paths: BTreeMap<Facts, BTreeSet<String>>,
+    imprecise: bool,
+}
+
+impl FlowState {
+    pub fn entry<I: IntoIterator<Item = String>>(assigned: I) -> Self {
+        let mut paths = BTreeMap::new();
+        paths.insert(Facts::new(), assigned.into_iter().collect());
+        FlowState { paths, imprecise: false }
+    }
+
+    pub fn is_assigned(&self, name: &str) -> bool {
+        self.paths.values().all(|vars| vars.contains(name))
+    }
+
+    /// Every variable assigned on at least one path.
+    pub fn assigned_names(&self) -> HashSet<String> {
+        self.paths.values().flatten().cloned().collect()
+    }
+
+    /// The facts of the first path on which `name` is not assigned.
+    pub fn unassigned_path(&self, name: &str) -> Option<&Facts> {
+        self.paths
+            .iter()
+            .find(|(_, vars)| !vars.contains(name))
+            .map(|(facts, _)| facts)
+    }
+
+    pub fn assign(&mut self, name: &str) {
+        for vars in self.paths.values_mut() {
+            vars.insert(name.to_string());
+        }
+    }
+
+    fn insert_path(&mut self, facts: Facts, vars: BTreeSet<String>) {
+        match self.paths.get_mut(&facts) {
+            Some(existing) => existing.retain(|v| vars.contains(v)),
+            None => {
+                self.paths.insert(facts, vars);
+            }
+        }
+    }
+
+    fn collapse_if_needed(&mut self) {
+        if self.imprecise || self.paths.len() > MAX_PATHS {
+            let mut common: Option<BTreeSet<String>> = None;
+            for vars in self.paths.values() {
+                common = Some(match common {
+                    None => vars.clone(),
+                    Some(c) => c.intersection(vars).cloned().collect(),
+                });
+            }
+            self.paths.clear();
+            self.paths.insert(Facts::new(), common.unwrap_or_default());
+            self.imprecise = true;
+        }
+    }
+
+    /// Merges `other` into `self`; returns whether `self` changed.
+    fn join(&mut self, other: &FlowState) -> bool {
+        let before = self.clone();
+        self.imprecise |= other.imprecise;
+        for (facts, vars) in &other.paths {
+            self.insert_path(facts.clone(), vars.clone());
+        }
+        self.collapse_if_needed();
+        *self != before
+    }
+}
+
+/// Solves definite assignment over a CFG and exposes the state in front of
+/// every node.
+pub struct Dataflow {
+    /// Variables each branch fact depends on; assigning one of them
+    /// invalidates the fact.
+    fact_dependencies: HashMap<String, HashSet<String>>,
+    /// Variables some function body writes; a call may change any of them.
+    call_writes: HashSet<String>,
+    block_entry: Vec<Option<FlowState>>,
+}
+
+impl Dataflow {
+    pub fn solve(cfg: &Cfg, entry: FlowState, call_writes: &HashSet<String>) -> Self {
+        let mut flow = Dataflow {
+            fact_dependencies: HashMap::new(),
+            call_writes: call_writes.clone(),
+            block_entry: vec![None; cfg.blocks.len()],
+        };
+        for block in &cfg.blocks {
+            if let Terminator::Branch { condition, .. } = &block.terminator {
+                flow.register_condition(condition);
+            }
+            for node in &block.nodes {
+                if let Some(name) = Self::boolean_assignment(node).map(|(n, _)| n) {
+                    flow.fact_dependencies
+                        .entry(variable_fact(name))
+                        .or_default()
+                        .insert(name.to_string());
+                }
+            }
+        }
+
+        if cfg.blocks.is_empty() {
+            return flow;
+        }
+        flow.block_entry[0] = Some(entry);
+        let mut worklist: VecDeque<usize> = VecDeque::from([0]);
+        let mut queued: HashSet<usize> = HashSet::from([0]);
+
+        while let Some(block) = worklist.pop_front() {
+            queued.remove(&block);
+            let mut state = match &flow.block_entry[block] {
+                Some(s) => s.clone(),
+                None => continue,
+            };
+            for node in &cfg.blocks[block].nodes {
+                flow.transfer(node, &mut state);
+            }
+
+            let mut outgoing: Vec<(usize, FlowState)> = Vec::new();
+            match &cfg.blocks[block].terminator {
+                Terminator::Goto(target) => outgoing.push((*target, state)),
+                Terminator::Either(targets) => {
+                    for target in targets {
+                        outgoing.push((*target, state.clone()));
+                    }
+                }
+                Terminator::Branch { condition, then_to, else_to } => {
+                    if let Some(s) = flow.assume(&state, condition, true) {
+                        outgoing.push((*then_to, s));
+                    }
+                    if let Some(s) = flow.assume(&state, condition, false) {
+                        outgoing.push((*else_to, s));
+                    }
+                }
+                Terminator::Stop => {}
+            }
+
+            for (target, incoming) in outgoing {
+                let changed = match &mut flow.block_entry[target] {
+                    Some(existing) => existing.join(&incoming),
+                    slot @ None => {
+                        *slot = Some(incoming);
+                        true
+                    }
+                };
+                if changed && queued.insert(target) {
+                    worklist.push_back(target);
+                }
+            }
+        }
+
+        flow
+    }
+
+    /// State on entry to `block`, or `None` if the block is unreachable.
+    pub fn entry_state(&self, block: usize) -> Option<&FlowState> {
+        self.block_entry[block].as_ref()
+    }
+
+    pub fn transfer(&self, node: &Node, state: &mut FlowState) {
+        if let Some((name, assigns)) = written_variable(node) {
+            if assigns {
+                state.assign(name);
+            }
+            self.forget_facts_about(state, name);
+        }
+        // A function may change the globals a fact was learned from
+        if calls_function(node) {
+            for name in &self.call_writes {
+                self.forget_facts_about(state, name);
+            }
+        }
+        // `a boolean called "verbose" is true` lets later `If "verbose"` know
+        // which way it goes.
+        if let Some((name, value)) = Self::boolean_assignment(node) {
+            if !state.imprecise {
+                let key = variable_fact(name);
+                let paths = std::mem::take(&mut state.paths);
+                for (mut facts, vars) in paths {
+                    facts.insert(key.clone(), value);
+                    state.insert_path(facts, vars);
+                }
+            }
+        }
+    }
+
+    fn boolean_assignment<'n>(node: &'n Node) -> Option<(&'n str, bool)> {
+        match node {
+            Node::Statement(Statement::VarDecl {
+                name,
+                var_type: None | Some(Type::Boolean),
+                value: Some(Expr::BoolLit(b)),
+            })
+            | Node::Statement(Statement::ConstDecl {
+                name,
+                var_type: None | Some(Type::Boolean),
+                value: Expr::BoolLit(b),
+            }) => Some((name.as_str(), *b)),
+            Node::Statement(Statement::Assignment { name, value: Expr::BoolLit(b) }) => Some((name.as_str(), *b)),
+            _ => None,
+        }
+    }
+
+    fn forget_facts_about(&self, state: &mut FlowState, name: &str) {
+        if state.imprecise {
+            return;
+        }
+        let stale: HashSet<&String> = self
+            .fact_dependencies
+            .iter()
+            .filter(|(_, deps)| deps.contains(name))
+            .map(|(key, _)| key)
+            .collect();
+        if stale.is_empty() {
+            return;
+        }
+        let paths = std::mem::take(&mut state.paths);
+        for (mut facts, vars) in paths {
+            facts.retain(|key, _| !stale.contains(key));
+            state.insert_path(facts, vars);
+        }
+    }
+
+    fn register_condition(&mut self, condition: &Expr) {
+        match condition {
+            Expr::UnaryOp { op: UnaryOperator::Not, operand } => self.register_condition(operand),
+            Expr::BinaryOp { left, op: BinaryOperator::And | BinaryOperator::Or, right } => {
+                self.register_condition(left);
+                self.register_condition(right);
+            }
+            _ => {}
+        }
+        if let Some(key) = describe_condition(condition) {
+            let mut names = HashSet::new();
+            collect_names(condition, &mut names);
+            self.fact_dependencies.entry(key).or_default().extend(names);
+        }
+    }
+
+    /// Splits `state` along a branch, keeping only the paths on which
+    /// `condition` can evaluate to `value`.
+    fn assume(&self, state: &FlowState, condition: &Expr, value: bool) -> Option<FlowState> {
+        let mut result = FlowState {
+            paths: BTreeMap::new(),
+            imprecise: state.imprecise,
+        };
+        for (facts, vars) in &state.paths {
+            let mut facts = facts.clone();
+            if assume_fact(&mut facts, condition, value) {
+                if state.imprecise {
+                    facts.clear();
+                }
+                result.insert_path(facts, vars.clone());
+            }
+        }
+        result.collapse_if_needed();
+        if result.paths.is_empty() {
+            None
+        } else {
+            Some(result)
+        }
+    }
+}
+
+/// The variable a node writes, and whether the node gives it a value
+/// rather than changing the one it has.
+pub fn written_variable<'n>(node: &'n Node) -> Option<(&'n str, bool)> {
+    match node {
+        Node::Eval(_) => None,
+        Node::Bind(name) => Some((name, true)),
+        Node::Statement(stmt) => match stmt {
+            Statement::VarDecl { name, .. }
+            | Statement::ConstDecl { name, .. }
+            | Statement::Assignment { name, .. }
+            | Statement::FlagSchemaDecl { name, .. }
+            | Statement::Allocate { name, .. }
+            | Statement::BufferDecl { name, .. }
+            | Statement::FileOpen { name, .. }
+            | Statement::TimerDecl { name }
+            | Statement::GetTime { into: name } => Some((name.as_str(), true)),
+            Statement::Increment { name }
+            | Statement::Decrement { name }
+            | Statement::Free { name }
+            | Statement::BufferResize { name, .. }
+            | Statement::ListAppend { list: name, .. }
+            | Statement::ListInsert { list: name, .. }
+            | Statement::ListRemoveAt { list: name, .. }
+            | Statement::ListRemove { list: name, .. }
+            | Statement::ListSort { list: name, .. }
+            | Statement::ListReverse { list: name }
+            | Statement::ListShuffle { list: name }
+            | Statement::RandomBytes { buffer: name, .. }
+            | Statement::ElementSet { list: name, .. }
+            | Statement::MapSet { map: name, .. }
+            | Statement::MapRemove { map: name, .. }
+            | Statement::FieldSet { object: name, .. }
+            | Statement::ByteSet { buffer: name, .. }
+            | Statement::FileRead { buffer: name, .. }
+            | Statement::FileReadLine { buffer: name, .. } => Some((name.as_str(), false)),
+            _ => None,
+        },
+    }
+}
+
+/// Whether running a node may call a user function.
+fn calls_function(node: &Node) -> bool {
+    let stmt = match node {
+        Node::Eval(expr) => return expr_calls_function(expr),
+        Node::Bind(_) => return false,
+        Node::Statement(stmt) => stmt,
+    };
+    let values: Vec<&Expr> = match stmt {
+        Statement::FunctionCall { .. } => return true,
+        Statement::Print { value, .. }
+        | Statement::ConstDecl { value, .. }
+        | Statement::Assignment { value, .. }
+        | Statement::Destructure { value, .. }
+        | Statement::FieldSet { value, .. }
+        | Statement::ListAppend { value, .. }
+        | Statement::ListRemove { value, .. }
+        | Statement::FileWrite { value, .. } => vec![value],
+        Statement::VarDecl { value, .. } => value.iter().collect(),
+        Statement::FlagSchemaDecl { default, .. } => default.iter().collect(),
+        Statement::RandomBytes { count, .. } => count.iter().collect(),
+        Statement::Depending { subject, arms, .. } => {
+            let mut values = vec![subject];
+            for pattern in arms.iter().flat_map(|(patterns, _)| patterns) {
+                match pattern {
+                    MatchPattern::Value(value) => values.push(value),
+                    MatchPattern::Range { start, end } => values.extend([start, end]),
+                }
+            }
+            values
+        }
+        Statement::Return { values } => values.iter().collect(),
+        Statement::Ensure { condition, message, .. } => std::iter::once(condition).chain(message).collect(),
+        Statement::Allocate { size: value, .. }
+        | Statement::BufferDecl { size: value, .. }
+        | Statement::BufferResize { new_size: value, .. }
+        | Statement::ListRemoveAt { position: value, .. }
+        | Statement::MapRemove { key: value, .. }
+        | Statement::FileOpen { path: value, .. }
+        | Statement::FileDelete { path: value }
+        | Statement::FileSeekLine { line: value, .. }
+        | Statement::FileSeekByte { byte: value, .. }
+        | Statement::Wait { duration: value, .. }
+        | Statement::SeedRandom { seed: value } => vec![value],
+        Statement::ByteSet { index, value, .. } | Statement::ElementSet { index, value, .. } => vec![index, value],
+        Statement::ListInsert { value, position, .. } => vec![value, position],
+        Statement::MapSet { key, value, .. } => vec![key, value],
+        _ => Vec::new(),
+    };
+    values.into_iter().any(expr_calls_function)
+}
+
+fn expr_calls_function(expr: &Expr) -> bool {
+    let parts: Vec<&Expr> = match expr {
+        Expr::FunctionCall { .. } => return true,
+        Expr::BinaryOp { left, right, .. }
+        | Expr::ListConcat { left, right }
+        | Expr::MinMax { left, right, .. } => vec![left, right],
+        Expr::UnaryOp { operand: value, .. }
+        | Expr::PropertyCheck { value, .. }
+        | Expr::ArgumentAt { index: value }
+        | Expr::ArgumentHas { value }
+        | Expr::EnvironmentVariable { name: value }
+        | Expr::EnvironmentVariableAt { index: value }
+        | Expr::EnvironmentVariableExists { name: value }
+        | Expr::Cast { value, .. }
+        | Expr::DurationCast { value, .. }
+        | Expr::MapGet { key: value, .. }
+        | Expr::MapHas { key: value, .. }
+        | Expr::TextCase { text: value, .. }
+        | Expr::TextTrim { text: value }
+        | Expr::TextBytes { text: value }
+        | Expr::RandomElement { list: value }
+        | Expr::MathFunction { operand: value, .. } => vec![value],
+        Expr::Range { start, end, step, .. } => [start, end].into_iter().chain(step).map(Box::as_ref).collect(),
+        Expr::ListLit { elements } => elements.iter().collect(),
+        Expr::StructLit { fields, .. } => fields.iter().map(|(_, value)| value).collect(),
+        Expr::ListAccess { list, index }
+        | Expr::ByteAccess { buffer: list, index }
+        | Expr::ElementAccess { list, index } => vec![list, index],
+        Expr::ListSlice { list, start, end } => vec![list, start, end],
+        Expr::TreatingAs { value, match_value, replacement } => vec![value, match_value, replacement],
+        Expr::Quantifier { collection, condition, .. } => vec![collection, condition],
+        Expr::TextSlice { text, start, end } => [text, start].into_iter().chain(end).map(Box::as_ref).collect(),
+        Expr::TextFind { part, text }
+        | Expr::TextCheck { text, part, .. } => vec![text, part],
+        Expr::TextSplit { text, separator } => vec![text, separator],
+        Expr::TextJoin { list, separator } => vec![list, separator],
+        Expr::RandomNumber { low, high } => vec![low, high],
+        Expr::Power { base, exponent } => vec![base, exponent],
+        Expr::Rounded { value, places, .. } => std::iter::once(value).chain(places).map(Box::as_ref).collect(),
+        Expr::FormatString { parts } => {
+            return parts.iter().any(|part| matches!(part, FormatPart::Expression { expr, .. } if expr_calls_function(expr)));
+        }
+        _ => Vec::new(),
+    };
+    parts.into_iter().any(expr_calls_function)
+}
+
+/// Records that `condition` evaluated to `value`; returns false if that
+/// contradicts what the path already knows.
+fn assume_fact(facts: &mut Facts, condition: &Expr, value: bool) -> bool {
+    match condition {
+        Expr::BoolLit(b) => *b == value,
+        Expr::UnaryOp { op: UnaryOperator::Not, operand } => assume_fact(facts, operand, !value),
+        Expr::BinaryOp { left, op, right } => {
+            let consistent = match (op, value) {
+                (BinaryOperator::And, true) | (BinaryOperator::Or, false) => {
+                    assume_fact(facts, left, value) && assume_fact(facts, right, value)
+                }
+                (BinaryOperator::And, false) | (BinaryOperator::Or, true) => {
+                    evaluate(facts, condition) != Some(!value)
+                }
+                _ => true,
+            };
+            consistent && record_fact(facts, condition, value)
+        }
+        _ => record_fact(facts, condition, value),
+    }
+}
+
+fn record_fact(facts: &mut Facts, condition: &Expr, value: bool) -> bool {
+    match describe_condition(condition) {
+        Some(key) => match facts.get(&key) {
+            Some(known) => *known == value,
+            None => {
+                facts.insert(key, value);
+                true
+            }
+        },
+        None => true,
+    }
+}
+
+fn evaluate(facts: &Facts, condition: &Expr) -> Option<bool> {
+    match condition {
+        Expr::BoolLit(b) => Some(*b),
+        Expr::UnaryOp { op: UnaryOperator::Not, operand } => evaluate(facts, operand).map(|b| !b),
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+            match (evaluate(facts, left), evaluate(facts, right)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => describe_condition(condition).and_then(|k| facts.get(&k).copied()),
+            }
+        }
+        Expr::BinaryOp { left, op: BinaryOperator::Or, right } => {
+            match (evaluate(facts, left), evaluate(facts, right)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => describe_condition(condition).and_then(|k| facts.get(&k).copied()),
+            }
+        }
+        _ => describe_condition(condition).and_then(|k| facts.get(&k).copied()),
+    }
+}
+
+/// Renders a side-effect-free condition the way it reads in source. Two
+/// conditions with the same rendering always evaluate the same way as long
+/// as none of their variables change in between. Conditions that call
+/// functions, read time or touch files have no rendering.
+pub fn describe_condition(condition: &Expr) -> Option<String> {
+    let operand = |e: &Expr| -> Option<String> {
+        let text = describe_condition(e)?;
+        if matches!(e, Expr::BinaryOp { .. }) {
+            Some(format!("({})", text))
+        } else {
+            Some(text)
+        }
+    };
+
+    match condition {
+        Expr::Identifier(name) | Expr::StringLit(name) => Some(variable_fact(name)),
+        Expr::IntegerLit(n) => Some(n.to_string()),
+        Expr::FloatLit(f) => Some(f.to_string()),
+        Expr::BoolLit(b) => Some(b.to_string()),
+        Expr::UnaryOp { op, operand: inner } => {
+            let inner = operand(inner)?;
+            Some(match op {
+                UnaryOperator::Not => format!("not {}", inner),
+                UnaryOperator::Negate => format!("-{}", inner),
+            })
+        }
+        Expr::BinaryOp { left, op, right } => {
+            let word = match op {
+                BinaryOperator::Add => "plus",
+                BinaryOperator::Subtract => "minus",
+                BinaryOperator::Multiply => "times",
+                BinaryOperator::Divide => "divided by",
+                BinaryOperator::Modulo => "modulo",
+                BinaryOperator::Equal => "is equal to",
+                BinaryOperator::NotEqual => "is not equal to",
+                BinaryOperator::Greater => "is greater than",
+                BinaryOperator::Less => "is less than",
+                BinaryOperator::GreaterEqual => "is at least",
+                BinaryOperator::LessEqual => "is at most",
+                BinaryOperator::And => "and",
+                BinaryOperator::Or => "or",
+                BinaryOperator::BitAnd => "bit-and",
+                BinaryOperator::BitOr => "bit-or",
+                BinaryOperator::BitXor => "bit-xor",
+                BinaryOperator::ShiftLeft => "shifted left by",
+                BinaryOperator::ShiftRight => "shifted right by",
+            };
+            Some(format!("{} {} {}", operand(left)?, word, operand(right)?))
+        }
+        Expr::PropertyCheck { value, property } => {
+            let word = match property {
+                Property::Even => "even",
+                Property::Odd => "odd",
+                Property::Positive => "positive",
+                Property::Negative => "negative",
+                Property::Zero => "zero",
+                Property::Empty => "empty",
+            };
+            Some(format!("{} is {}", operand(value)?, word))
+        }
+        Expr::MapHas { map, key } => Some(format!("{} has {}", variable_fact(map), operand(key)?)),
+        Expr::ArgumentCount => Some("the argument count".to_string()),
+        Expr::ArgumentEmpty => Some("the arguments are empty".to_string()),
+        Expr::ArgumentHas { value } => Some(format!("the arguments have {}", operand(value)?)),
+        Expr::EnvironmentVariableExists { name } => {
+            Some(format!("environment variable {} exists", operand(name)?))
+        }
+        _ => None,
+    }
+}
+
+fn variable_fact(name: &str) -> String {
+    format!("\"{}\"", name)
+}
+
+fn collect_names(expr: &Expr, names: &mut HashSet<String>) {
+    match expr {
+        Expr::Identifier(name) | Expr::StringLit(name) => {
+            names.insert(name.clone());
+        }
+        Expr::UnaryOp { operand, .. } => collect_names(operand, names),
+        Expr::BinaryOp { left, right, .. } => {
+            collect_names(left, names);
+            collect_names(right, names);
+        }
+        Expr::PropertyCheck { value, .. } => collect_names(value, names),
+        Expr::MapHas { map, key } => {
+            names.insert(map.clone());
+            collect_names(key, names);
+        }
+        Expr::ArgumentHas { value } => collect_names(value, names),
+        Expr::EnvironmentVariableExists { name } => collect_names(name, names),
+        _ => {}
+    }
+}
+
+/// Human-readable description of a path, for error messages.
+pub fn describe_path(facts: &Facts) -> String {
+    if facts.is_empty() {
+        return "on a path that skips its assignment".to_string();
+    }
+    let parts: Vec<String> = facts
+        .iter()
+        .map(|(condition, value)| format!("{} is {}", condition, value))
+        .collect();
+    format!("on the path where {}", parts.join(" and "))
+}
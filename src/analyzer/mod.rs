@@ -1,5 +1,10 @@
+mod cfg;
+mod dataflow;
+
 use crate::parser::ast::*;
 use crate::errors::{CompileError, SourceFile, SourceLocation, find_similar_keyword, ENGLISH_KEYWORDS};
+use cfg::{Cfg, Node};
+use dataflow::{Dataflow, FlowState, describe_path, written_variable};
 use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Default)]
@@ -135,6 +140,253 @@ mod guard_env_tests {
         );
     }
 
+    #[test]
+    fn variable_assigned_on_one_branch_reports_the_missing_path() {
+        let input = r#"
+            if "verbose" then,
+                a number called "level" is 2.
+
+            Print "{level}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        assert!(
+            analyzer.errors.iter().any(|e| e.message.contains("Unknown variable: level")
+                && e.message.contains("\"verbose\" is false")),
+            "expected path-naming error, got: {:?}",
+            analyzer.errors
+        );
+    }
+
+    #[test]
+    fn variable_assigned_on_every_branch_is_available() {
+        let input = r#"
+            if "verbose" then,
+                a number called "level" is 2.
+            otherwise,
+                a number called "level" is 1.
+
+            Print "{level}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        assert!(
+            analyzer.errors.is_empty(),
+            "unexpected errors: {:?}",
+            analyzer.errors
+        );
+    }
+
+    #[test]
+    fn variable_assigned_after_loop_exit_paths_is_checked() {
+        let input = r#"
+            while true,
+                a number called "found" is 1,
+                break.
+
+            Print "{found}".
+
+            repeat 3 times,
+                a number called "total" is 1.
+
+            Print "{total}".
+
+            while "running",
+                a number called "partial" is 1.
+
+            Print "{partial}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        let unknown: Vec<&str> = analyzer
+            .errors
+            .iter()
+            .filter(|e| e.message.starts_with("Unknown variable"))
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(unknown.len(), 1, "unexpected errors: {:?}", analyzer.errors);
+        assert!(unknown[0].contains("partial") && unknown[0].contains("\"running\" is false"));
+    }
+
+    #[test]
+    fn literal_ranges_run_at_least_once_in_either_direction() {
+        let input = r#"
+            For each i from 3 down to 1,
+                a number called "down" is i.
+
+            Print "{down}".
+
+            For each i from 0 to 10 by 5,
+                a number called "stepped" is i.
+
+            Print "{stepped}".
+
+            a number called "stride" is 2.
+            For each i from 0 to 10 by stride,
+                a number called "unknown step" is i.
+
+            Print "{unknown step}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        let unknown: Vec<&str> = analyzer
+            .errors
+            .iter()
+            .filter(|e| e.message.starts_with("Unknown variable"))
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(unknown.len(), 1, "unexpected errors: {:?}", analyzer.errors);
+        assert!(unknown[0].contains("unknown step"));
+    }
+
+    #[test]
+    fn zero_range_step_is_a_compile_error() {
+        let analyzer = analyze_input("For each i from 1 to 10 by 0, Print i.");
+        assert!(
+            analyzer.errors.iter().any(|e| e.message.contains("Range step cannot be zero")),
+            "got: {:?}",
+            analyzer.errors
+        );
+    }
+
+    #[test]
+    fn branch_on_a_guard_set_false_is_never_taken() {
+        let input = r#"
+            a boolean called "ready" is true.
+            if "ready" then,
+                a number called "value" is 1.
+
+            Set "ready" to false.
+            if "ready" then,
+                Print "{value}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        assert!(
+            analyzer.errors.is_empty(),
+            "unexpected errors: {:?}",
+            analyzer.errors
+        );
+    }
+
+    #[test]
+    fn guard_is_invalidated_when_its_variable_changes() {
+        let input = r#"
+            a boolean called "ready" is arguments's empty.
+            a boolean called "other" is not "ready".
+            if "ready" then,
+                a number called "value" is 1.
+
+            Set "ready" to "other".
+            if "ready" then,
+                Print "{value}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        assert!(
+            analyzer.errors.iter().any(|e| e.message.contains("Unknown variable: value")),
+            "expected an error for value, got: {:?}",
+            analyzer.errors
+        );
+    }
+
+    #[test]
+    fn guard_on_a_map_is_invalidated_when_the_map_changes() {
+        for change in [r#"set "bob" in ages to 25."#, r#"remove "bob" from ages."#] {
+            let input = format!(
+                r#"
+            a map called "ages".
+            if ages has "bob" then,
+                a number called "value" is 1.
+
+            {}
+            if ages has "bob" then,
+                Print "{{value}}".
+        "#,
+                change
+            );
+
+            let analyzer = analyze_input(&input);
+            assert!(
+                analyzer.errors.iter().any(|e| e.message.contains("Unknown variable: value")),
+                "expected an error for value after {}, got: {:?}",
+                change,
+                analyzer.errors
+            );
+        }
+
+        let input = r#"
+            a map called "ages".
+            if ages is empty then,
+                a number called "value" is 1.
+
+            set "bob" in ages to 25.
+            if ages is empty then,
+                Print "{value}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        assert!(
+            analyzer.errors.iter().any(|e| e.message.contains("Unknown variable: value")),
+            "expected an error for value, got: {:?}",
+            analyzer.errors
+        );
+
+        // Reading the map leaves the guard alone
+        let input = r#"
+            a map called "ages".
+            if ages has "bob" then,
+                a number called "value" is 1.
+
+            Print ages's size.
+            if ages has "bob" then,
+                Print "{value}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "unexpected errors: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn guard_on_a_global_is_invalidated_by_a_function_that_changes_it() {
+        let input = r#"
+            a boolean called "ready" is arguments's empty.
+            To "flip",
+                Set "ready" to not "ready".
+
+            if "ready" then,
+                a number called "value" is 1.
+
+            "flip".
+            if "ready" then,
+                Print "{value}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        assert!(
+            analyzer.errors.iter().any(|e| e.message.contains("Unknown variable: value")),
+            "expected an error for value, got: {:?}",
+            analyzer.errors
+        );
+
+        // A function that leaves the global alone keeps the guard
+        let input = r#"
+            a boolean called "ready" is arguments's empty.
+            To "greet",
+                Print "hello".
+
+            if "ready" then,
+                a number called "value" is 1.
+
+            "greet".
+            if "ready" then,
+                Print "{value}".
+        "#;
+
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "unexpected errors: {:?}", analyzer.errors);
+    }
+
     #[test]
     fn unknown_variable_inside_function_is_reported() {
         let input = r#"
@@ -289,6 +541,12 @@ mod guard_env_tests {
     }
 }
 
+/// File handle names for the always-open standard streams
+const STANDARD_STREAMS: [&str; 3] = ["stdin", "stdout", "stderr"];
+
+/// Returned values travel in rax, rdx, rcx, rsi, rdi, r8 and r9 (see funcs.asm)
+const MAX_RETURN_VALUES: usize = 7;
+
 pub struct Analyzer {
     pub deps: Dependencies,
     pub variables: HashSet<String>,
@@ -296,19 +554,32 @@ pub struct Analyzer {
     pub used_identifiers: HashSet<String>,  // Track all identifiers seen
     typo_candidates: HashSet<String>,
     pub errors: Vec<CompileError>,
+    pub warnings: Vec<CompileError>,
     source_file: Option<SourceFile>,
-    guarded_scopes: HashMap<String, HashSet<String>>,
     symbol_error_counts: HashMap<String, usize>,
-    active_guards: Vec<String>,
-    block_depth: usize,
     global_variables: HashSet<String>,
+    /// Every variable some function body writes
+    function_writes: HashSet<String>,
     flag_variables: HashSet<String>,
-}
-
-#[derive(Clone, Default)]
-struct AnalysisEnv {
-    always: HashSet<String>,
-    guarded: HashMap<String, HashSet<String>>,
+    struct_types: HashMap<String, Vec<(String, Type)>>,
+    /// The struct each variable holds, and the struct each list holds
+    struct_variables: HashMap<String, String>,
+    struct_lists: HashMap<String, String>,
+    /// Each map's value type, once a `Set ... in` with a known type is seen
+    map_value_types: HashMap<String, Option<Type>>,
+    /// Declared types of plain variables; None when declarations disagree
+    declared_types: HashMap<String, Option<Type>>,
+    /// Each function's declared return type, and the function being analyzed
+    function_returns: HashMap<String, Type>,
+    /// Each function's parameter names, and whether each has a default
+    function_params: HashMap<String, Vec<(String, bool)>>,
+    /// Each constant's value; an include may declare the same one again
+    constants: HashMap<String, Expr>,
+    current_function: Option<String>,
+    /// Definite-assignment state in front of the node being analyzed;
+    /// `None` inside unreachable code.
+    flow_state: Option<FlowState>,
+    checked_arithmetic: bool,
 }
 
 impl Analyzer {
@@ -320,13 +591,23 @@ impl Analyzer {
             used_identifiers: HashSet::new(),
             typo_candidates: HashSet::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
             source_file: None,
-            guarded_scopes: HashMap::new(),
             symbol_error_counts: HashMap::new(),
-            active_guards: Vec::new(),
-            block_depth: 0,
             global_variables: HashSet::new(),
+            function_writes: HashSet::new(),
             flag_variables: HashSet::new(),
+            struct_types: HashMap::new(),
+            struct_variables: HashMap::new(),
+            struct_lists: HashMap::new(),
+            map_value_types: HashMap::new(),
+            declared_types: HashMap::new(),
+            function_returns: HashMap::new(),
+            function_params: HashMap::new(),
+            constants: HashMap::new(),
+            current_function: None,
+            flow_state: None,
+            checked_arithmetic: false,
         }
     }
 
@@ -334,6 +615,13 @@ impl Analyzer {
         self.source_file = Some(SourceFile::new(filename, content));
         self
     }
+
+    /// With --checked-arithmetic, integer overflow in constant expressions
+    /// is reported at compile time.
+    pub fn with_checked_arithmetic(mut self, enabled: bool) -> Self {
+        self.checked_arithmetic = enabled;
+        self
+    }
     
     pub fn analyze(&mut self, program: &mut Program) {
         // First pass: collect function definitions, global declarations, and flag schemas.
@@ -341,8 +629,24 @@ impl Analyzer {
 
         for stmt in &program.statements {
             match stmt {
-                Statement::FunctionDef { name, .. } => {
+                Statement::FunctionDef { name, params, defaults, return_type, body } => {
                     self.functions.insert(name.clone());
+                    let cfg = Cfg::build(body);
+                    let written = cfg.blocks.iter().flat_map(|block| &block.nodes).filter_map(written_variable);
+                    self.function_writes.extend(written.map(|(variable, _)| variable.to_string()));
+                    self.function_returns.insert(name.clone(), return_type.clone());
+                    let params = params
+                        .iter()
+                        .map(|(param, _)| (param.clone(), defaults.iter().any(|(d, _)| d == param)))
+                        .collect();
+                    self.function_params.insert(name.clone(), params);
+                }
+                Statement::Destructure { names, .. } => {
+                    self.global_variables.extend(names.iter().cloned());
+                }
+                Statement::ConstDecl { name, value, .. } => {
+                    self.global_variables.insert(name.clone());
+                    self.constants.entry(name.clone()).or_insert_with(|| value.clone());
                 }
                 Statement::VarDecl { name, .. }
                 | Statement::BufferDecl { name, .. }
@@ -402,12 +706,12 @@ impl Analyzer {
             }
         }
 
-        self.variables = self.global_variables.clone();
-        
-        // Second pass: analyze all statements
-        for stmt in &program.statements {
-            self.analyze_statement(stmt);
-        }
+        self.check_error_catching(&program.statements, &mut false, &mut 0);
+        self.check_loop_names(&program.statements, &mut Vec::new());
+        self.collect_types(&program.statements);
+
+        // Second pass: analyze all statements along the control-flow graph
+        self.analyze_scope(&program.statements, FlowState::entry(Vec::new()));
         
         // Third pass: check for typos in unknown identifiers
         self.check_for_typos();
@@ -418,90 +722,898 @@ impl Analyzer {
         program.uses_args = self.deps.uses_args;
     }
     
-    fn check_for_typos(&mut self) {
-        let unknown: Vec<String> = self.typo_candidates.iter().cloned().collect();
-        let mut typo_errors = Vec::new();
-
-        for id in unknown {
-            // Skip if this identifier already has an error
-            if self.errors.iter().any(|e| e.message.contains(&id)) {
-                continue;
-            }
-
-            // Skip common internal identifiers
-            if id.starts_with('_') || id == "stdin" || id == "stdout" || id == "stderr" {
-                continue;
-            }
-            
-            if let Some(suggestion) = find_similar_keyword(&id, ENGLISH_KEYWORDS) {
-                let mut err = CompileError::new(&format!("Unknown identifier '{}'", id))
-                    .with_suggestion(&suggestion);
-                if let Some(loc) = self.find_symbol_location(&id, 0) {
-                    err = err.with_location(loc);
+    /// Enable/disable error catching cover the source text that follows them,
+    /// so they are checked in source order rather than along the CFG.
+    fn check_error_catching(&mut self, statements: &[Statement], enabled: &mut bool, seen: &mut usize) {
+        for stmt in statements {
+            match stmt {
+                Statement::ErrorCatching { enabled: enable } => {
+                    let message = match (*enable, *enabled) {
+                        (true, true) => Some("Error catching is already enabled"),
+                        (false, false) => Some("Cannot disable error catching: it is not enabled"),
+                        _ => None,
+                    };
+                    if let Some(message) = message {
+                        let mut err = CompileError::new(message)
+                            .with_hint("'enable error catching' and 'disable error catching' must alternate");
+                        if let Some(loc) = self.find_symbol_location("error catching", *seen) {
+                            err = err.with_location(loc);
+                        }
+                        self.errors.push(err);
+                    }
+                    *enabled = *enable;
+                    *seen += 1;
                 }
-                typo_errors.push(err);
+                Statement::If { then_block, else_if_blocks, else_block, .. } => {
+                    self.check_error_catching(then_block, enabled, seen);
+                    for (_, block) in else_if_blocks {
+                        self.check_error_catching(block, enabled, seen);
+                    }
+                    if let Some(block) = else_block {
+                        self.check_error_catching(block, enabled, seen);
+                    }
+                }
+                Statement::Depending { arms, otherwise, .. } => {
+                    for (_, block) in arms {
+                        self.check_error_catching(block, enabled, seen);
+                    }
+                    if let Some(block) = otherwise {
+                        self.check_error_catching(block, enabled, seen);
+                    }
+                }
+                Statement::While { body, .. }
+                | Statement::Until { body, .. }
+                | Statement::DoUntil { body, .. }
+                | Statement::Loop { body }
+                | Statement::ForRange { body, .. }
+                | Statement::ForEach { body, .. }
+                | Statement::ForEachEntry { body, .. }
+                | Statement::Repeat { body, .. }
+                | Statement::FunctionDef { body, .. }
+                | Statement::Example { body, .. } => self.check_error_catching(body, enabled, seen),
+                Statement::NamedLoop { inner, .. } => {
+                    self.check_error_catching(std::slice::from_ref(inner), enabled, seen)
+                }
+                Statement::OnError { actions, .. } => self.check_error_catching(actions, enabled, seen),
+                _ => {}
             }
         }
-        
-        // Prepend typo errors so they appear first
-        typo_errors.append(&mut self.errors);
-        self.errors = typo_errors;
-    }
-    
-    fn track_identifier(&mut self, name: &str) {
-        self.used_identifiers.insert(name.to_string());
     }
 
-    fn track_typo_candidate(&mut self, name: &str) {
-        self.typo_candidates.insert(name.to_string());
+    /// `Break out of rows.` and `Continue rows.` must be inside the loop
+    /// called "rows"; `enclosing` holds the names of the loops around them.
+    fn check_loop_names(&mut self, statements: &[Statement], enclosing: &mut Vec<String>) {
+        for stmt in statements {
+            match stmt {
+                Statement::NamedLoop { name, inner } => {
+                    if enclosing.contains(name) {
+                        self.push_error(format!("There is already a loop called '{}' around this one", name), Some(name));
+                    }
+                    enclosing.push(name.clone());
+                    self.check_loop_names(std::slice::from_ref(inner), enclosing);
+                    enclosing.pop();
+                }
+                Statement::Break { loop_name: Some(name) } | Statement::Continue { loop_name: Some(name) }
+                    if !enclosing.contains(name) =>
+                {
+                    let action = if matches!(stmt, Statement::Break { .. }) { "break out of" } else { "continue" };
+                    let around = if enclosing.is_empty() {
+                        "it is not inside a named loop".to_string()
+                    } else {
+                        let names: Vec<String> = enclosing.iter().map(|n| format!("'{}'", n)).collect();
+                        format!("the loops around it are {}", names.join(", "))
+                    };
+                    let mut err = CompileError::new(&format!(
+                        "There is no loop called '{}' around this '{} {}' - {}",
+                        name, action, name, around
+                    ))
+                    .with_hint(&format!(
+                        "Name a loop after its comma: For each row from 1 to 10, called \"{}\", ...",
+                        name
+                    ));
+                    let phrase = format!("{} {}", action, name);
+                    let occurrence = *self.symbol_error_counts.get(&phrase).unwrap_or(&0);
+                    if let Some(loc) = self.find_phrase_location(&phrase, occurrence) {
+                        err = err.with_location(loc);
+                    }
+                    self.symbol_error_counts.insert(phrase, occurrence + 1);
+                    self.errors.push(err);
+                }
+                Statement::If { then_block, else_if_blocks, else_block, .. } => {
+                    self.check_loop_names(then_block, enclosing);
+                    for (_, block) in else_if_blocks {
+                        self.check_loop_names(block, enclosing);
+                    }
+                    if let Some(block) = else_block {
+                        self.check_loop_names(block, enclosing);
+                    }
+                }
+                Statement::Depending { arms, otherwise, .. } => {
+                    for (_, block) in arms {
+                        self.check_loop_names(block, enclosing);
+                    }
+                    if let Some(block) = otherwise {
+                        self.check_loop_names(block, enclosing);
+                    }
+                }
+                Statement::While { body, .. }
+                | Statement::Until { body, .. }
+                | Statement::DoUntil { body, .. }
+                | Statement::Loop { body }
+                | Statement::ForRange { body, .. }
+                | Statement::ForEach { body, .. }
+                | Statement::ForEachEntry { body, .. }
+                | Statement::Repeat { body, .. } => self.check_loop_names(body, enclosing),
+                // A function's loops are its own
+                Statement::FunctionDef { body, .. } | Statement::Example { body, .. } => {
+                    self.check_loop_names(body, &mut Vec::new())
+                }
+                Statement::OnError { actions, .. } => self.check_loop_names(actions, enclosing),
+                _ => {}
+            }
+        }
     }
 
-    fn expr_uses_flag(&self, expr: &Expr) -> Option<String> {
-        match expr {
-            Expr::Identifier(name) => {
-                if self.flag_variables.contains(name) {
-                    Some(name.clone())
-                } else {
-                    None
+    /// Records struct definitions, which variables and lists hold structs,
+    /// and what maps hold, in source order. Like the codegen's variable types
+    /// this does not follow scopes.
+    fn collect_types(&mut self, statements: &[Statement]) {
+        for stmt in statements {
+            match stmt {
+                Statement::StructDef { name, fields } => {
+                    if self.struct_types.contains_key(name) {
+                        self.push_error(format!("Struct '{}' is already defined", name), Some(name));
+                    }
+                    self.struct_types.insert(name.clone(), fields.clone());
                 }
-            }
-            Expr::FormatString { parts } => {
-                for part in parts {
-                    match part {
-                        FormatPart::Variable { name, .. } => {
-                            if self.flag_variables.contains(name) {
-                                return Some(name.clone());
+                Statement::VarDecl { name, var_type: Some(Type::Struct(struct_name)), .. } => {
+                    self.struct_variables.insert(name.clone(), struct_name.clone());
+                }
+                Statement::VarDecl { name, var_type: Some(Type::Map), .. } => {
+                    self.map_value_types.entry(name.clone()).or_insert(None);
+                }
+                Statement::VarDecl { name, var_type, value } => {
+                    if let Some(Expr::ListLit { elements }) = value {
+                        if let Some(Expr::Identifier(item)) = elements.first() {
+                            if let Some(struct_name) = self.struct_variables.get(item).cloned() {
+                                self.struct_lists.insert(name.clone(), struct_name);
                             }
                         }
-                        FormatPart::Expression { expr, .. } => {
-                            if let Some(name) = self.expr_uses_flag(expr) {
-                                return Some(name);
+                    }
+                    let given = value.as_ref().and_then(|v| self.value_type(v));
+                    let declared = match (var_type, given) {
+                        // "a list called x is [1, 2]" holds numbers
+                        (Some(Type::List(element)), Some(Type::List(known)))
+                            if **element == Type::Unknown => Some(Type::List(known)),
+                        (Some(declared), _) => Some(declared.clone()),
+                        (None, given) => given,
+                    };
+                    self.declare_type(name, declared);
+                }
+                Statement::ConstDecl { name, var_type, value } => {
+                    let declared = var_type.clone().or_else(|| self.value_type(value));
+                    self.declare_type(name, declared);
+                }
+                Statement::MapSet { map, value, .. } => {
+                    let value_type = self.value_type(value);
+                    if let Some(slot @ None) = self.map_value_types.get_mut(map) {
+                        *slot = value_type;
+                    }
+                }
+                Statement::ForEachEntry { key, value, map, body } => {
+                    self.declare_type(key, Some(Type::String));
+                    let value_type = self.map_value_types.get(map).cloned().flatten();
+                    self.declare_type(value, value_type);
+                    self.collect_types(body);
+                }
+                Statement::ListAppend { list, value } | Statement::ListInsert { list, value, .. } => {
+                    if let Expr::Identifier(item) = value {
+                        if let Some(struct_name) = self.struct_variables.get(item).cloned() {
+                            self.struct_lists.insert(list.clone(), struct_name);
+                        }
+                    }
+                    // An empty list takes the type of the first value put into it
+                    let value_type = self.value_type(value);
+                    if let (Some(Some(Type::List(element))), Some(given)) = (self.declared_types.get_mut(list), value_type) {
+                        if **element == Type::Unknown {
+                            **element = given;
+                        }
+                    }
+                }
+                Statement::ForEach { variable, collection, body } => {
+                    if let Expr::Identifier(list) = collection {
+                        if let Some(struct_name) = self.struct_lists.get(list).cloned() {
+                            self.struct_variables.insert(variable.clone(), struct_name);
+                        }
+                    }
+                    // Lines arrive in a buffer; text gives characters, or bytes as numbers
+                    let (collection, bytes) = match collection {
+                        Expr::TextBytes { text } => (&**text, true),
+                        other => (other, false),
+                    };
+                    if let Expr::FileLines { .. } = collection {
+                        self.declare_type(variable, Some(Type::Buffer));
+                    } else if self.value_type(collection) == Some(Type::String) {
+                        let element = if bytes { Type::Integer } else { Type::String };
+                        self.declare_type(variable, Some(element));
+                    } else if let Some(element) = self.list_element(collection).filter(|t| *t != Type::Any) {
+                        self.declare_type(variable, Some(element));
+                    }
+                    self.collect_types(body);
+                }
+                Statement::Destructure { names, value: Expr::FunctionCall { name, .. } } => {
+                    if let Some(Type::Tuple(types)) = self.function_returns.get(name).cloned() {
+                        for (variable, value_type) in names.iter().zip(types) {
+                            match value_type {
+                                Type::Struct(struct_name) => {
+                                    self.struct_variables.insert(variable.clone(), struct_name);
+                                }
+                                Type::Map => {
+                                    self.map_value_types.entry(variable.clone()).or_insert(None);
+                                }
+                                Type::Unknown | Type::Void => {}
+                                value_type => self.declare_type(variable, Some(value_type)),
                             }
                         }
-                        FormatPart::Literal(_) => {}
                     }
                 }
-                None
+                Statement::FunctionDef { params, body, .. } => {
+                    for (param_name, param_type) in params {
+                        match param_type {
+                            Type::Struct(struct_name) => {
+                                self.struct_variables.insert(param_name.clone(), struct_name.clone());
+                            }
+                            Type::Map => {
+                                self.map_value_types.entry(param_name.clone()).or_insert(None);
+                            }
+                            Type::List(_) => self.declare_type(param_name, Some(param_type.clone())),
+                            _ => {}
+                        }
+                    }
+                    self.collect_types(body);
+                }
+                Statement::If { then_block, else_if_blocks, else_block, .. } => {
+                    self.collect_types(then_block);
+                    for (_, block) in else_if_blocks {
+                        self.collect_types(block);
+                    }
+                    if let Some(block) = else_block {
+                        self.collect_types(block);
+                    }
+                }
+                Statement::Depending { arms, otherwise, .. } => {
+                    for (_, block) in arms {
+                        self.collect_types(block);
+                    }
+                    if let Some(block) = otherwise {
+                        self.collect_types(block);
+                    }
+                }
+                Statement::While { body, .. }
+                | Statement::Until { body, .. }
+                | Statement::DoUntil { body, .. }
+                | Statement::Loop { body }
+                | Statement::ForRange { body, .. }
+                | Statement::Repeat { body, .. }
+                | Statement::Example { body, .. } => self.collect_types(body),
+                Statement::NamedLoop { inner, .. } => self.collect_types(std::slice::from_ref(inner)),
+                Statement::OnError { actions, .. } => self.collect_types(actions),
+                _ => {}
             }
-            Expr::BinaryOp { left, right, .. } => self.expr_uses_flag(left).or_else(|| self.expr_uses_flag(right)),
-            Expr::UnaryOp { operand, .. } => self.expr_uses_flag(operand),
-            Expr::Range { start, end, .. } => self.expr_uses_flag(start).or_else(|| self.expr_uses_flag(end)),
-            Expr::PropertyCheck { value, .. } => self.expr_uses_flag(value),
-            Expr::FunctionCall { args, .. } => args.iter().find_map(|a| self.expr_uses_flag(a)),
-            Expr::ListLit { elements } => elements.iter().find_map(|e| self.expr_uses_flag(e)),
-            Expr::ListAccess { list, index } => self.expr_uses_flag(list).or_else(|| self.expr_uses_flag(index)),
-            Expr::ByteAccess { buffer, index } => self.expr_uses_flag(buffer).or_else(|| self.expr_uses_flag(index)),
-            Expr::ElementAccess { list, index } => self.expr_uses_flag(list).or_else(|| self.expr_uses_flag(index)),
-            Expr::Cast { value, .. } => self.expr_uses_flag(value),
-            Expr::DurationCast { value, .. } => self.expr_uses_flag(value),
-            Expr::TreatingAs { value, match_value, replacement } => self
-                .expr_uses_flag(value)
-                .or_else(|| self.expr_uses_flag(match_value))
-                .or_else(|| self.expr_uses_flag(replacement)),
-            Expr::ArgumentAt { index } => self.expr_uses_flag(index),
-            Expr::EnvironmentVariable { name } => self.expr_uses_flag(name),
-            Expr::EnvironmentVariableAt { index } => self.expr_uses_flag(index),
-            Expr::EnvironmentVariableExists { name } => self.expr_uses_flag(name),
+        }
+    }
+
+    /// Records a constant; top-level ones are already known from the first
+    /// pass. Declaring one again is only allowed with the same value, so
+    /// files that include the same definitions do not clash.
+    fn declare_constant(&mut self, name: &str, value: &Expr) {
+        match self.constants.get(name) {
+            Some(earlier) if format!("{:?}", earlier) != format!("{:?}", value) => {
+                self.push_error(format!("The constant '{}' is already declared with a different value", name), Some(name));
+            }
+            Some(_) => {}
+            None => {
+                self.constants.insert(name.to_string(), value.clone());
+            }
+        }
+    }
+    
+    fn check_not_constant(&mut self, name: &str) {
+        if self.constants.contains_key(name) {
+            self.push_error(
+                format!(
+                    "Cannot change '{}' - it is a constant\n  \
+                     Tip: Declare it as a variable if it needs to change: a number called \"{}\" is ...",
+                    name, name
+                ),
+                Some(name),
+            );
+        }
+    }
+    
+    fn declare_type(&mut self, name: &str, declared: Option<Type>) {
+        let merged = match self.declared_types.get(name) {
+            Some(earlier) if *earlier != declared => None,
+            _ => declared,
+        };
+        self.declared_types.insert(name.to_string(), merged);
+    }
+    
+    /// The type of a value when it is certain; None when it can't be told
+    /// without running the program
+    fn value_type(&self, expr: &Expr) -> Option<Type> {
+        match expr {
+            Expr::IntegerLit(_) => Some(Type::Integer),
+            Expr::BigLit(_) | Expr::Cast { target_type: Type::BigNumber, .. } => Some(Type::BigNumber),
+            Expr::UnaryOp { op: UnaryOperator::Negate, operand } if self.value_type(operand) == Some(Type::BigNumber) => {
+                Some(Type::BigNumber)
+            }
+            Expr::Cast { target_type: Type::Decimal(places), .. } => Some(Type::Decimal(*places)),
+            Expr::UnaryOp { op: UnaryOperator::Negate, operand } if self.is_decimal(operand) => self.value_type(operand),
+            // A decimal rounds to a decimal with the places written out
+            Expr::Rounded { value, places: Some(places), .. } if self.is_decimal(value) => match **places {
+                Expr::IntegerLit(places) => u32::try_from(places).ok().map(Type::Decimal),
+                _ => None,
+            },
+            Expr::FloatLit(_) => Some(Type::Float),
+            Expr::StringLit(_) | Expr::FormatString { .. } => Some(Type::String),
+            Expr::BoolLit(_) | Expr::MapHas { .. } | Expr::Quantifier { .. } | Expr::TextCheck { .. } => {
+                Some(Type::Boolean)
+            }
+            Expr::TextSlice { .. } | Expr::TextJoin { .. } | Expr::TextCase { .. } | Expr::TextTrim { .. } => {
+                Some(Type::String)
+            }
+            Expr::TextFind { .. } | Expr::RandomNumber { .. } => Some(Type::Integer),
+            Expr::RandomFloat | Expr::MathFunction { .. } | Expr::Rounded { places: Some(_), .. } => Some(Type::Float),
+            Expr::Rounded { places: None, .. } => Some(Type::Integer),
+            Expr::Identifier(name) if self.is_math_constant(name) => Some(Type::Float),
+            Expr::Power { base: left, exponent: right } | Expr::MinMax { left, right, .. } => {
+                match (self.value_type(left), self.value_type(right)) {
+                    (Some(Type::Float), _) | (_, Some(Type::Float)) => Some(Type::Float),
+                    (Some(Type::Integer), Some(Type::Integer)) => Some(Type::Integer),
+                    _ => None,
+                }
+            }
+            Expr::TextSplit { .. } => Some(Type::List(Box::new(Type::String))),
+            Expr::ListLit { elements } => {
+                // Elements of different types are tagged at runtime
+                let mut element = Type::Unknown;
+                for given in elements.iter().filter_map(|e| self.value_type(e)) {
+                    element = match element {
+                        Type::Unknown => given,
+                        known if known == given => known,
+                        _ => Type::Any,
+                    };
+                }
+                Some(Type::List(Box::new(element)))
+            }
+            Expr::ElementAccess { list, .. } | Expr::RandomElement { list } => {
+                self.list_element(list).filter(|t| *t != Type::Any)
+            }
+            Expr::PropertyAccess { object, property: ObjectProperty::First | ObjectProperty::Last } => {
+                self.list_element(&Expr::Identifier(object.clone())).filter(|t| *t != Type::Any)
+            }
+            Expr::ListSlice { list, .. } | Expr::ListConcat { left: list, .. } => match self.value_type(list) {
+                Some(Type::List(element)) => Some(Type::List(element)),
+                _ => Some(Type::List(Box::new(Type::Unknown))),
+            },
+            // A buffer holds text
+            Expr::Identifier(name) => match self.declared_types.get(name).cloned().flatten() {
+                Some(Type::Buffer) => Some(Type::String),
+                known => known,
+            },
+            Expr::MapGet { map, .. } => self.map_value_types.get(map).cloned().flatten(),
+            Expr::BinaryOp { left, op, right } => match op {
+                BinaryOperator::Add
+                | BinaryOperator::Subtract
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Modulo => match (self.value_type(left), self.value_type(right)) {
+                    (left_type @ Some(Type::Decimal(_)), right_type) | (left_type, right_type @ Some(Type::Decimal(_))) => {
+                        let (left, right) = (Self::places_of(left_type, left), Self::places_of(right_type, right));
+                        if matches!(op, BinaryOperator::Multiply) {
+                            Some(Type::Decimal(left + right))
+                        } else {
+                            Some(Type::Decimal(left.max(right)))
+                        }
+                    }
+                    (Some(Type::Float), _) | (_, Some(Type::Float)) => Some(Type::Float),
+                    (Some(Type::Integer), Some(Type::Integer)) => Some(Type::Integer),
+                    (Some(Type::BigNumber), Some(Type::Integer | Type::BigNumber))
+                    | (Some(Type::Integer), Some(Type::BigNumber)) => Some(Type::BigNumber),
+                    _ => None,
+                },
+                _ => Some(Type::Boolean),
+            },
+            _ => None,
+        }
+    }
+    
+    /// Reports `name` unless it is an available map
+    fn check_map(&mut self, map: &str, action: &str) {
+        if !self.is_variable_available(map) {
+            self.push_unknown_variable(map);
+        } else if !self.map_value_types.contains_key(map) {
+            self.push_error(
+                format!(
+                    "Cannot {} '{}' - it is not a map\n  \
+                     Tip: declare it with: a map called \"{}\".",
+                    action, map, map
+                ),
+                Some(map),
+            );
+        }
+    }
+    
+    /// Reports `name` unless it is an available list (or its type is unknown)
+    fn check_list(&mut self, list: &str, action: &str) {
+        if !self.is_variable_available(list) {
+            self.push_unknown_variable(list);
+        } else if let Some(other) = self.value_type(&Expr::Identifier(list.to_string())) {
+            if !matches!(other, Type::List(_)) {
+                self.push_error(
+                    format!(
+                        "Cannot {} '{}' - it is not a list\n  \
+                         Tip: declare it with: a list called \"{}\" is [].",
+                        action, list, list
+                    ),
+                    Some(list),
+                );
+            }
+        }
+    }
+    
+    /// The type of a list's elements, when it is known
+    fn list_element(&self, list: &Expr) -> Option<Type> {
+        match self.value_type(list) {
+            Some(Type::List(element)) if *element != Type::Unknown => Some(*element),
+            _ => None,
+        }
+    }
+    
+    /// Lists of mixed types box each element with a tag for a number, float
+    /// or text, so big numbers and decimals can't be among them
+    fn check_mixed_elements(&mut self, list: &Expr, values: &[Expr]) {
+        if self.list_element(list) != Some(Type::Any) {
+            return;
+        }
+        let untagged = values.iter().find_map(|v| {
+            self.value_type(v)
+                .filter(|t| matches!(t, Type::BigNumber | Type::Decimal(_)))
+                .map(|t| (v, t))
+        });
+        if let Some((value, value_type)) = untagged {
+            let err = format!(
+                "A list of mixed types cannot hold {}; keep them in a list of their own",
+                describe_type(&value_type)
+            );
+            self.push_error(err, Self::operand_name(list).or(Self::operand_name(value)));
+        }
+    }
+    
+    /// Whether a value can be stored where `holds` is kept: numbers and
+    /// written-out amounts like 0.25 become decimals when stored as one
+    fn stores_as(holds: &Type, given: &Type, value: &Expr) -> bool {
+        match holds {
+            Type::Any => true,
+            Type::Decimal(_) => {
+                matches!(given, Type::Integer | Type::Decimal(_))
+                    || (*given == Type::Float && value.as_number_literal().is_some())
+            }
+            _ => holds == given,
+        }
+    }
+    
+    /// A value put into or looked for in a list must match its elements
+    fn check_list_value(&mut self, list: &str, value: &Expr) {
+        let holds = self.list_element(&Expr::Identifier(list.to_string()));
+        if let (Some(holds), Some(given)) = (holds, self.value_type(value)) {
+            if !Self::stores_as(&holds, &given, value) {
+                let err = format!(
+                    "Elements of '{}' are {}, but this value is {}",
+                    list,
+                    describe_type(&holds),
+                    describe_type(&given)
+                );
+                self.push_error(err, Some(list));
+            }
+        }
+    }
+    
+    fn check_element_position(&mut self, position: &Expr) {
+        if let Some(other) = self.value_type(position).filter(|t| *t != Type::Integer) {
+            let err = format!("Element positions are numbers, but this is {}", describe_type(&other));
+            self.push_error(err, Self::operand_name(position));
+        }
+    }
+    
+    /// Map keys are text; anything known to be something else is an error
+    fn check_map_key(&mut self, map: &str, key: &Expr) {
+        if let Some(key_type) = self.value_type(key) {
+            if key_type != Type::String {
+                self.push_error(
+                    format!("Keys of '{}' are text, but this key is {}", map, describe_type(&key_type)),
+                    Some(map),
+                );
+            }
+        }
+    }
+    
+    /// Reports an operand of a text operation that is known not to be text
+    fn check_text_operand(&mut self, operation: &str, operand: &Expr) {
+        if let Some(other) = self.value_type(operand).filter(|t| *t != Type::String) {
+            let err = format!("'{}' needs text, but this is {}", operation, describe_type(&other));
+            self.push_error(err, Self::operand_name(operand));
+        }
+    }
+    
+    /// Reports a random bound, seed or byte count known not to be a whole number
+    fn check_whole_number(&mut self, what: &str, value: &Expr) {
+        if let Some(other) = self.value_type(value).filter(|t| *t != Type::Integer) {
+            let err = format!("{} must be a number, but this is {}", what, describe_type(&other));
+            self.push_error(err, Self::operand_name(value));
+        }
+    }
+    
+    /// Reports an operand of a math operation that is neither a number nor a float
+    fn check_math_operand(&mut self, operation: &str, operand: &Expr) {
+        if let Some(other) = self.value_type(operand).filter(|t| !matches!(t, Type::Integer | Type::Float)) {
+            let err = format!("'{}' needs a number or a float, but this is {}", operation, describe_type(&other));
+            self.push_error(err, Self::operand_name(operand));
+        }
+    }
+    
+    /// Big numbers mix only with numbers, and only in arithmetic and comparisons
+    fn check_big_operands(&mut self, left: &Expr, op: &BinaryOperator, right: &Expr) {
+        let (big, other, other_type) = match (self.value_type(left), self.value_type(right)) {
+            (Some(Type::BigNumber), other_type) => (left, right, other_type),
+            (other_type, Some(Type::BigNumber)) => (right, left, other_type),
+            _ => return,
+        };
+        if matches!(
+            op,
+            BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor |
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight |
+            BinaryOperator::And | BinaryOperator::Or
+        ) {
+            let err = "Bitwise and boolean operations do not work on big numbers".to_string();
+            self.push_error(err, Self::operand_name(big));
+        } else if let Some(other_type) = other_type.filter(|t| !matches!(t, Type::Integer | Type::BigNumber)) {
+            let err = format!(
+                "A big number works with numbers and big numbers, but this is {}",
+                describe_type(&other_type)
+            );
+            self.push_error(err, Self::operand_name(other));
+        }
+    }
+    
+    /// A big number only becomes a number or text, and only those become one
+    fn check_big_cast(&mut self, value: &Expr, target_type: &Type) {
+        let given = self.value_type(value);
+        if *target_type == Type::BigNumber {
+            if let Some(other) = given.filter(|t| !matches!(t, Type::Integer | Type::String | Type::BigNumber)) {
+                let err = format!("Only numbers and text can become big numbers, but this is {}", describe_type(&other));
+                self.push_error(err, Self::operand_name(value));
+            }
+        } else if given == Some(Type::BigNumber) && !matches!(target_type, Type::Integer | Type::String) {
+            let err = format!("A big number can only become a number or text, not {}", describe_type(target_type));
+            self.push_error(err, Self::operand_name(value));
+        }
+    }
+    
+    /// A number variable cannot hold a big number, and a big number
+    /// variable holds only whole numbers
+    fn check_big_assignment(&mut self, name: &str, declared: Option<&Type>, value: &Expr) {
+        let given = self.value_type(value);
+        match (declared, given) {
+            (Some(Type::Integer), Some(Type::BigNumber)) => {
+                let err = format!(
+                    "'{}' is a number, but this value is a big number\n  \
+                     Tip: declare it as: a big number called \"{}\" is ..., or use 'as a number'",
+                    name, name
+                );
+                self.push_error(err, Some(name));
+            }
+            (Some(Type::BigNumber), Some(other)) if !matches!(other, Type::Integer | Type::BigNumber) => {
+                let err = format!(
+                    "'{}' is a big number, but this value is {}\n  \
+                     Tip: convert it with 'as a big number'",
+                    name,
+                    describe_type(&other)
+                );
+                self.push_error(err, Some(name));
+            }
+            _ => {}
+        }
+    }
+    
+    fn is_decimal(&self, expr: &Expr) -> bool {
+        matches!(self.value_type(expr), Some(Type::Decimal(_)))
+    }
+    
+    /// The places of a decimal, or of a number or written-out amount used with one
+    fn operand_places(&self, expr: &Expr) -> u32 {
+        Self::places_of(self.value_type(expr), expr)
+    }
+    
+    fn places_of(given: Option<Type>, expr: &Expr) -> u32 {
+        match given {
+            Some(Type::Decimal(places)) => places,
+            _ => expr.as_number_literal().map_or(0, literal_places),
+        }
+    }
+    
+    /// Decimals mix with numbers, decimals and written-out amounts in
+    /// arithmetic and comparisons; a float variable could lose cents
+    fn check_decimal_operands(&mut self, expr: &Expr, left: &Expr, op: &BinaryOperator, right: &Expr) {
+        let (decimal, other, other_type) = match (self.value_type(left), self.value_type(right)) {
+            (Some(Type::Decimal(_)), other_type) => (left, right, other_type),
+            (other_type, Some(Type::Decimal(_))) => (right, left, other_type),
+            _ => return,
+        };
+        if matches!(
+            op,
+            BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor |
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight |
+            BinaryOperator::And | BinaryOperator::Or | BinaryOperator::Modulo
+        ) {
+            let err = "Bitwise, boolean and modulo operations do not work on decimals".to_string();
+            self.push_error(err, Self::operand_name(decimal));
+        } else if other_type == Some(Type::Float) && other.as_number_literal().is_none() {
+            let err = format!(
+                "A decimal works with numbers, decimals and written-out amounts, but this is a float\n  \
+                 Tip: convert it with 'as a decimal with {} places'",
+                self.operand_places(decimal)
+            );
+            self.push_error(err, Self::operand_name(other));
+        } else if let Some(other_type) =
+            other_type.filter(|t| !matches!(t, Type::Integer | Type::Float | Type::Decimal(_)))
+        {
+            let err = format!(
+                "A decimal works with numbers, decimals and written-out amounts, but this is {}",
+                describe_type(&other_type)
+            );
+            self.push_error(err, Self::operand_name(other));
+        } else if let Some(Type::Decimal(places)) = self.value_type(expr) {
+            if places > DECIMAL_MAX_PLACES {
+                let err = format!(
+                    "This result would have {} places, but a decimal keeps at most {}\n  \
+                     Tip: round a side first, like: price rounded to 2 places",
+                    places, DECIMAL_MAX_PLACES
+                );
+                self.push_error(err, Self::operand_name(decimal));
+            }
+        }
+    }
+    
+    /// A decimal becomes and comes from numbers, floats, text and other decimals
+    fn check_decimal_cast(&mut self, value: &Expr, target_type: &Type) {
+        let given = self.value_type(value);
+        if let Type::Decimal(_) = target_type {
+            let other = given.filter(|t| !matches!(t, Type::Integer | Type::Float | Type::String | Type::Decimal(_)));
+            if let Some(other) = other {
+                let err = format!("Only numbers, floats and text can become decimals, but this is {}", describe_type(&other));
+                self.push_error(err, Self::operand_name(value));
+            }
+        } else if matches!(given, Some(Type::Decimal(_))) && *target_type == Type::Boolean {
+            let err = "A decimal can become a number, a float or text, not a boolean".to_string();
+            self.push_error(err, Self::operand_name(value));
+        }
+    }
+    
+    /// A decimal variable takes numbers, decimals and written-out amounts;
+    /// a number or float variable takes a decimal only through a cast
+    fn check_decimal_assignment(&mut self, name: &str, declared: Option<&Type>, value: &Expr) {
+        let given = self.value_type(value);
+        match (declared, given) {
+            (Some(Type::Decimal(places)), Some(Type::Float)) if value.as_number_literal().is_none() => {
+                let err = format!(
+                    "'{}' is a decimal, but this value is a float\n  \
+                     Tip: convert it with 'as a decimal with {} places'",
+                    name, places
+                );
+                self.push_error(err, Some(name));
+            }
+            (Some(Type::Decimal(_)), Some(other))
+                if !matches!(other, Type::Integer | Type::Float | Type::Decimal(_)) =>
+            {
+                let err = format!("'{}' is a decimal, but this value is {}", name, describe_type(&other));
+                self.push_error(err, Some(name));
+            }
+            (Some(declared @ (Type::Integer | Type::Float)), Some(Type::Decimal(_))) => {
+                let err = format!(
+                    "'{}' is {}, but this value is a decimal\n  \
+                     Tip: convert it with 'as {}'",
+                    name,
+                    describe_type(declared),
+                    describe_type(declared)
+                );
+                self.push_error(err, Some(name));
+            }
+            _ => {}
+        }
+    }
+    
+    /// `pi` and `e` are the math constants unless the scope has its own
+    fn is_math_constant(&self, name: &str) -> bool {
+        math_constant(name).is_some() && !self.variables.contains(name) && !self.constants.contains_key(name)
+    }
+    
+    fn check_list_operand(&mut self, operation: &str, operand: &Expr) {
+        if let Some(other) = self.value_type(operand).filter(|t| !matches!(t, Type::List(_))) {
+            let err = format!("'{}' needs a list, but this is {}", operation, describe_type(&other));
+            self.push_error(err, Self::operand_name(operand));
+        }
+    }
+    
+    /// The variable an operand names, to point errors at it
+    fn operand_name(operand: &Expr) -> Option<&str> {
+        match operand {
+            Expr::Identifier(name) => Some(name),
+            _ => None,
+        }
+    }
+    
+    fn check_struct_exists(&mut self, struct_name: &str) {
+        if self.struct_types.contains_key(struct_name) {
+            return;
+        }
+        let mut err = format!("Unknown struct: {}", struct_name);
+        let names: Vec<&str> = self.struct_types.keys().map(String::as_str).collect();
+        if let Some(suggestion) = find_similar_keyword(struct_name, &names) {
+            err.push_str(&format!(" (did you mean '{}'?)", suggestion));
+        }
+        self.push_error(err, Some(struct_name));
+    }
+
+    /// Reports `object's field` unless the object holds a struct with that field
+    fn check_struct_field(&mut self, object: &str, field: &str) {
+        let Some(struct_name) = self.struct_variables.get(object).cloned() else {
+            self.push_error(
+                format!(
+                    "Unknown property '{}' of '{}'\n  \
+                     Tip: declare '{}' with its struct type, like: a Dog called \"{}\" is ...",
+                    field, object, object, object
+                ),
+                Some(field),
+            );
+            return;
+        };
+        // An unknown struct is reported where the variable is declared
+        let Some(fields) = self.struct_types.get(&struct_name) else {
+            return;
+        };
+        if fields.iter().any(|(name, _)| name == field) {
+            return;
+        }
+        let mut err = format!("{} has no property called '{}'", struct_name, field);
+        let names: Vec<&str> = fields.iter().map(|(name, _)| name.as_str()).collect();
+        if let Some(suggestion) = find_similar_keyword(field, &names) {
+            err.push_str(&format!(" (did you mean '{}'?)", suggestion));
+        }
+        self.push_error(err, Some(field));
+    }
+
+    fn check_for_typos(&mut self) {
+        let unknown: Vec<String> = self.typo_candidates.iter().cloned().collect();
+        let mut typo_errors = Vec::new();
+
+        for id in unknown {
+            // Skip if this identifier already has an error
+            if self.errors.iter().any(|e| e.message.contains(&id)) {
+                continue;
+            }
+
+            // Skip common internal identifiers
+            if id.starts_with('_') || STANDARD_STREAMS.contains(&id.as_str()) {
+                continue;
+            }
+            
+            if let Some(suggestion) = find_similar_keyword(&id, ENGLISH_KEYWORDS) {
+                let mut err = CompileError::new(&format!("Unknown identifier '{}'", id))
+                    .with_suggestion(&suggestion);
+                if let Some(loc) = self.find_symbol_location(&id, 0) {
+                    err = err.with_location(loc);
+                }
+                typo_errors.push(err);
+            }
+        }
+        
+        // Prepend typo errors so they appear first
+        typo_errors.append(&mut self.errors);
+        self.errors = typo_errors;
+    }
+    
+    fn track_identifier(&mut self, name: &str) {
+        self.used_identifiers.insert(name.to_string());
+    }
+
+    fn track_typo_candidate(&mut self, name: &str) {
+        self.typo_candidates.insert(name.to_string());
+    }
+
+    fn expr_uses_flag(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Identifier(name) => {
+                if self.flag_variables.contains(name) {
+                    Some(name.clone())
+                } else {
+                    None
+                }
+            }
+            Expr::FormatString { parts } => {
+                for part in parts {
+                    match part {
+                        FormatPart::Variable { name, .. } => {
+                            if self.flag_variables.contains(name) {
+                                return Some(name.clone());
+                            }
+                        }
+                        FormatPart::Expression { expr, .. } => {
+                            if let Some(name) = self.expr_uses_flag(expr) {
+                                return Some(name);
+                            }
+                        }
+                        FormatPart::Literal(_) => {}
+                    }
+                }
+                None
+            }
+            Expr::BinaryOp { left, right, .. } => self.expr_uses_flag(left).or_else(|| self.expr_uses_flag(right)),
+            Expr::UnaryOp { operand, .. } => self.expr_uses_flag(operand),
+            Expr::Range { start, end, step, .. } => self
+                .expr_uses_flag(start)
+                .or_else(|| self.expr_uses_flag(end))
+                .or_else(|| step.as_ref().and_then(|s| self.expr_uses_flag(s))),
+            Expr::PropertyCheck { value, .. } => self.expr_uses_flag(value),
+            Expr::FunctionCall { args, named, .. } => args
+                .iter()
+                .chain(named.iter().map(|(_, a)| a))
+                .find_map(|a| self.expr_uses_flag(a)),
+            Expr::ListLit { elements } => elements.iter().find_map(|e| self.expr_uses_flag(e)),
+            Expr::StructLit { fields, .. } => fields.iter().find_map(|(_, v)| self.expr_uses_flag(v)),
+            Expr::MapGet { key, .. } | Expr::MapHas { key, .. } => self.expr_uses_flag(key),
+            Expr::Quantifier { collection, condition, .. } => {
+                self.expr_uses_flag(collection).or_else(|| self.expr_uses_flag(condition))
+            }
+            Expr::TextSlice { text, start, end } => self
+                .expr_uses_flag(text)
+                .or_else(|| self.expr_uses_flag(start))
+                .or_else(|| end.as_ref().and_then(|e| self.expr_uses_flag(e))),
+            Expr::TextFind { part: left, text: right }
+            | Expr::TextCheck { text: left, part: right, .. }
+            | Expr::TextSplit { text: left, separator: right }
+            | Expr::TextJoin { list: left, separator: right } => {
+                self.expr_uses_flag(left).or_else(|| self.expr_uses_flag(right))
+            }
+            Expr::TextCase { text, .. } | Expr::TextTrim { text } => self.expr_uses_flag(text),
+            Expr::ListAccess { list, index } => self.expr_uses_flag(list).or_else(|| self.expr_uses_flag(index)),
+            Expr::ByteAccess { buffer, index } => self.expr_uses_flag(buffer).or_else(|| self.expr_uses_flag(index)),
+            Expr::ElementAccess { list, index } => self.expr_uses_flag(list).or_else(|| self.expr_uses_flag(index)),
+            Expr::RandomNumber { low, high } => self.expr_uses_flag(low).or_else(|| self.expr_uses_flag(high)),
+            Expr::RandomElement { list } => self.expr_uses_flag(list),
+            Expr::MathFunction { operand, .. } => self.expr_uses_flag(operand),
+            Expr::Power { base: left, exponent: right } | Expr::MinMax { left, right, .. } => {
+                self.expr_uses_flag(left).or_else(|| self.expr_uses_flag(right))
+            }
+            Expr::Rounded { value, places, .. } => self
+                .expr_uses_flag(value)
+                .or_else(|| places.as_ref().and_then(|p| self.expr_uses_flag(p))),
+            Expr::ListSlice { list, start, end } => self
+                .expr_uses_flag(list)
+                .or_else(|| self.expr_uses_flag(start))
+                .or_else(|| self.expr_uses_flag(end)),
+            Expr::ListConcat { left, right } => self.expr_uses_flag(left).or_else(|| self.expr_uses_flag(right)),
+            Expr::Cast { value, .. } => self.expr_uses_flag(value),
+            Expr::DurationCast { value, .. } => self.expr_uses_flag(value),
+            Expr::TreatingAs { value, match_value, replacement } => self
+                .expr_uses_flag(value)
+                .or_else(|| self.expr_uses_flag(match_value))
+                .or_else(|| self.expr_uses_flag(replacement)),
+            Expr::ArgumentAt { index } => self.expr_uses_flag(index),
+            Expr::EnvironmentVariable { name } => self.expr_uses_flag(name),
+            Expr::EnvironmentVariableAt { index } => self.expr_uses_flag(index),
+            Expr::EnvironmentVariableExists { name } => self.expr_uses_flag(name),
             _ => None,
         }
     }
@@ -517,9 +1629,26 @@ impl Analyzer {
                     .or_else(|| else_if_blocks.iter().find_map(|(c, b)| self.expr_uses_flag(c).or_else(|| b.iter().find_map(|s| self.statement_uses_flag(s)))))
                     .or_else(|| else_block.as_ref().and_then(|b| b.iter().find_map(|s| self.statement_uses_flag(s))))
             }
-            Statement::While { condition, body } => self
+            Statement::Depending { subject, arms, otherwise, .. } => self
+                .expr_uses_flag(subject)
+                .or_else(|| arms.iter().find_map(|(patterns, body)| {
+                    patterns
+                        .iter()
+                        .find_map(|p| match p {
+                            MatchPattern::Value(value) => self.expr_uses_flag(value),
+                            MatchPattern::Range { start, end } => {
+                                self.expr_uses_flag(start).or_else(|| self.expr_uses_flag(end))
+                            }
+                        })
+                        .or_else(|| body.iter().find_map(|s| self.statement_uses_flag(s)))
+                }))
+                .or_else(|| otherwise.as_ref().and_then(|b| b.iter().find_map(|s| self.statement_uses_flag(s)))),
+            Statement::While { condition, body }
+            | Statement::Until { condition, body }
+            | Statement::DoUntil { body, condition } => self
                 .expr_uses_flag(condition)
                 .or_else(|| body.iter().find_map(|s| self.statement_uses_flag(s))),
+            Statement::Loop { body } => body.iter().find_map(|s| self.statement_uses_flag(s)),
             Statement::ForRange { range, body, .. } => self
                 .expr_uses_flag(range)
                 .or_else(|| body.iter().find_map(|s| self.statement_uses_flag(s))),
@@ -529,18 +1658,37 @@ impl Analyzer {
             Statement::Repeat { count, body } => self
                 .expr_uses_flag(count)
                 .or_else(|| body.iter().find_map(|s| self.statement_uses_flag(s))),
-            Statement::Return { value } => value.as_ref().and_then(|v| self.expr_uses_flag(v)),
+            Statement::NamedLoop { inner, .. } => self.statement_uses_flag(inner),
+            Statement::Ensure { condition, message, .. } => self
+                .expr_uses_flag(condition)
+                .or_else(|| message.as_ref().and_then(|m| self.expr_uses_flag(m))),
+            Statement::Return { values } => values.iter().find_map(|v| self.expr_uses_flag(v)),
+            Statement::Destructure { value, .. } => self.expr_uses_flag(value),
+            Statement::ConstDecl { value, .. } => self.expr_uses_flag(value),
             Statement::Exit { code } => self.expr_uses_flag(code),
             Statement::Allocate { size, .. } => self.expr_uses_flag(size),
             Statement::ByteSet { index, value, .. } => self.expr_uses_flag(index).or_else(|| self.expr_uses_flag(value)),
             Statement::ElementSet { index, value, .. } => self.expr_uses_flag(index).or_else(|| self.expr_uses_flag(value)),
-            Statement::ListAppend { value, .. } => self.expr_uses_flag(value),
+            Statement::ListAppend { value, .. } | Statement::ListRemove { value, .. } => self.expr_uses_flag(value),
+            Statement::ListInsert { value, position, .. } => {
+                self.expr_uses_flag(value).or_else(|| self.expr_uses_flag(position))
+            }
+            Statement::ListRemoveAt { position, .. } => self.expr_uses_flag(position),
+            Statement::FieldSet { value, .. } => self.expr_uses_flag(value),
+            Statement::MapSet { key, value, .. } => self.expr_uses_flag(key).or_else(|| self.expr_uses_flag(value)),
+            Statement::MapRemove { key, .. } => self.expr_uses_flag(key),
+            Statement::ForEachEntry { body, .. } => body.iter().find_map(|s| self.statement_uses_flag(s)),
             Statement::FileOpen { path, .. } => self.expr_uses_flag(path),
             Statement::FileWrite { value, .. } => self.expr_uses_flag(value),
-            Statement::OnError { actions } => actions.iter().find_map(|a| self.statement_uses_flag(a)),
+            Statement::OnError { actions, .. } => actions.iter().find_map(|a| self.statement_uses_flag(a)),
             Statement::BufferResize { new_size, .. } => self.expr_uses_flag(new_size),
-            Statement::FunctionCall { args, .. } => args.iter().find_map(|a| self.expr_uses_flag(a)),
+            Statement::FunctionCall { args, named, .. } => args
+                .iter()
+                .chain(named.iter().map(|(_, a)| a))
+                .find_map(|a| self.expr_uses_flag(a)),
             Statement::Wait { duration, .. } => self.expr_uses_flag(duration),
+            Statement::SeedRandom { seed } => self.expr_uses_flag(seed),
+            Statement::RandomBytes { count: Some(count), .. } => self.expr_uses_flag(count),
             _ => None,
         }
     }
@@ -573,6 +1721,26 @@ impl Analyzer {
         None
     }
 
+    /// Like find_symbol_location, but ignoring case, for phrases that may
+    /// start a sentence
+    fn find_phrase_location(&self, phrase: &str, occurrence: usize) -> Option<SourceLocation> {
+        let source = self.source_file.as_ref()?;
+        let phrase = phrase.to_lowercase();
+        source
+            .content
+            .lines()
+            .enumerate()
+            .flat_map(|(idx, line)| {
+                let lower = line.to_lowercase();
+                lower.match_indices(&phrase).map(|(column, _)| (idx, column)).collect::<Vec<_>>()
+            })
+            .nth(occurrence)
+            .map(|(idx, column)| {
+                let line = source.content.lines().nth(idx).unwrap_or("");
+                SourceLocation::new(&source.filename, idx + 1, column + 1, line)
+            })
+    }
+
     fn push_error(&mut self, message: String, symbol: Option<&str>) {
         let mut err = CompileError::new(&message);
         if let Some(name) = symbol {
@@ -585,173 +1753,258 @@ impl Analyzer {
         self.errors.push(err);
     }
 
-    fn push_unknown_variable(&mut self, name: &str) {
-        self.push_error(format!("Unknown variable: {}", name), Some(name));
+    /// Records a warning pointing at the `skip`-th `needle` from `line` on
+    fn push_warning_at(&mut self, message: String, line: usize, needle: &str, skip: usize) {
+        let mut warning = CompileError::new(&message).into_warning();
+        if let Some(loc) = self.find_from_line(line, needle, skip) {
+            warning = warning.with_location(loc);
+        }
+        self.warnings.push(warning);
     }
 
-    fn current_env(&self) -> AnalysisEnv {
-        AnalysisEnv {
-            always: self.variables.clone(),
-            guarded: self.guarded_scopes.clone(),
+    fn find_from_line(&self, line: usize, needle: &str, skip: usize) -> Option<SourceLocation> {
+        let source = self.source_file.as_ref()?;
+        let mut seen = 0usize;
+        for (idx, text) in source.content.lines().enumerate().skip(line.saturating_sub(1)) {
+            for (column, _) in text.match_indices(needle) {
+                // `1` must not match inside `10` or `x1`
+                let before = text[..column].chars().next_back();
+                let after = text[column + needle.len()..].chars().next();
+                if before.into_iter().chain(after).any(char::is_alphanumeric) {
+                    continue;
+                }
+                if seen == skip {
+                    return Some(SourceLocation::new(&source.filename, idx + 1, column + 1, text));
+                }
+                seen += 1;
+            }
         }
+        None
     }
 
-    fn apply_env(&mut self, env: &AnalysisEnv) {
-        self.variables = env.always.clone();
-        self.guarded_scopes = env.guarded.clone();
-    }
+    /// Warns about `when` values an earlier arm already matches, and about
+    /// arms that never run because all their values are taken
+    fn check_depending_arms(&mut self, arms: &[(Vec<MatchPattern>, Vec<Statement>)], line: usize) {
+        let mut numbers: Vec<(i64, i64)> = Vec::new();
+        let mut texts: HashSet<String> = HashSet::new();
+        let mut written: HashMap<String, usize> = HashMap::new();
+
+        for (patterns, _) in arms {
+            let mut matches_new_values = false;
+            let mut repeats = Vec::new();
+            for pattern in patterns {
+                let (text, needle) = match pattern {
+                    MatchPattern::Value(value) => (describe_pattern_value(value), describe_pattern_value(value)),
+                    MatchPattern::Range { start, end } => (
+                        format!("{} to {}", describe_pattern_value(start), describe_pattern_value(end)),
+                        describe_pattern_value(start),
+                    ),
+                };
+                let count = written.entry(needle.clone()).or_insert(0);
+                let skip = *count;
+                *count += 1;
+
+                match (pattern.literal_numbers(), pattern) {
+                    (Some((start, end)), _) if start > end => {
+                        let message = format!(
+                            "The range {} matches nothing - write it as {} to {}",
+                            text, end, start
+                        );
+                        self.push_warning_at(message, line, &needle, skip);
+                    }
+                    (Some((start, end)), _) => {
+                        if numbers_covered(&numbers, start, end) {
+                            repeats.push((text, needle, skip));
+                        } else {
+                            matches_new_values = true;
+                        }
+                        numbers.push((start, end));
+                    }
+                    (None, MatchPattern::Value(Expr::StringLit(s))) => {
+                        if texts.insert(s.clone()) {
+                            matches_new_values = true;
+                        } else {
+                            repeats.push((text, needle, skip));
+                        }
+                    }
+                    _ => matches_new_values = true,
+                }
+            }
 
-    fn is_variable_available(&self, name: &str) -> bool {
-        if self.variables.contains(name) {
-            return true;
+            if !matches_new_values && !repeats.is_empty() {
+                let values: Vec<&str> = repeats.iter().map(|(text, _, _)| text.as_str()).collect();
+                let message = format!("This arm never runs - earlier arms already match {}", values.join(", "));
+                let (_, needle, skip) = &repeats[0];
+                self.push_warning_at(message, line, needle, *skip);
+            } else {
+                for (text, needle, skip) in repeats {
+                    let message = format!("{} is already matched by an earlier arm", text);
+                    self.push_warning_at(message, line, &needle, skip);
+                }
+            }
         }
+    }
 
-        self.active_guards.iter().any(|guard| {
-            self.guarded_scopes
-                .get(guard)
-                .map(|vars| vars.contains(name))
-                .unwrap_or(false)
-        })
+    fn push_unknown_variable(&mut self, name: &str) {
+        let message = self.unavailable_message("Unknown variable", name);
+        self.push_error(message, Some(name));
     }
 
-    fn declare_variable_in_current_scope(&mut self, name: &str) {
-        if self.active_guards.is_empty() {
-            self.variables.insert(name.to_string());
-        } else {
-            for guard in &self.active_guards {
-                self.guarded_scopes
-                    .entry(guard.clone())
-                    .or_default()
-                    .insert(name.to_string());
+    /// Builds the CFG of one scope, solves definite assignment over it, and
+    /// analyzes every node with the state that holds in front of it.
+    fn analyze_scope(&mut self, statements: &[Statement], entry: FlowState) {
+        let cfg = Cfg::build(statements);
+        let flow = Dataflow::solve(&cfg, entry.clone(), &self.function_writes);
+
+        // Unreachable code is checked against every name the scope assigns.
+        let mut declared = entry;
+        for block in &cfg.blocks {
+            for node in &block.nodes {
+                flow.transfer(node, &mut declared);
+            }
+        }
+        let saved_variables = std::mem::replace(&mut self.variables, declared.assigned_names());
+        let saved_state = self.flow_state.take();
+
+        for (id, block) in cfg.blocks.iter().enumerate() {
+            self.flow_state = flow.entry_state(id).cloned();
+            for node in &block.nodes {
+                match node {
+                    Node::Statement(stmt) => self.analyze_statement(stmt),
+                    Node::Eval(expr) => self.analyze_expr(expr),
+                    Node::Bind(_) => {}
+                }
+                if let Some(state) = self.flow_state.as_mut() {
+                    flow.transfer(node, state);
+                }
             }
         }
+
+        self.variables = saved_variables;
+        self.flow_state = saved_state;
     }
 
-    fn merge_continuing_envs(&self, envs: &[AnalysisEnv], fallback: &AnalysisEnv) -> AnalysisEnv {
-        if envs.is_empty() {
-            return fallback.clone();
+    fn is_variable_available(&self, name: &str) -> bool {
+        match &self.flow_state {
+            Some(state) => state.is_assigned(name),
+            None => self.variables.contains(name),
         }
+    }
+
+    /// True when the scope assigns `name` somewhere, but not on every path
+    /// reaching this point.
+    fn is_maybe_unassigned(&self, name: &str) -> bool {
+        self.variables.contains(name) && !self.is_variable_available(name)
+    }
 
-        let mut merged_always = envs[0].always.clone();
-        for env in envs.iter().skip(1) {
-            merged_always.retain(|name| env.always.contains(name));
+    /// Error text for a name that is not definitely assigned, naming a
+    /// path on which it is missing when the scope does assign it.
+    fn unavailable_message(&self, prefix: &str, name: &str) -> String {
+        let path = self
+            .flow_state
+            .as_ref()
+            .filter(|_| self.variables.contains(name))
+            .and_then(|state| state.unassigned_path(name));
+        match path {
+            Some(facts) => format!("{}: {} (may be unassigned {})", prefix, name, describe_path(facts)),
+            None => format!("{}: {}", prefix, name),
         }
+    }
 
-        let mut merged_guarded: HashMap<String, HashSet<String>> = HashMap::new();
-        for env in envs {
-            for (guard, vars) in &env.guarded {
-                merged_guarded
-                    .entry(guard.clone())
-                    .or_default()
-                    .extend(vars.iter().cloned());
+    fn check_function_call(&mut self, name: &str, args: &[Expr], named: &[(String, Expr)]) {
+        self.deps.uses_funcs = true; // Track that functions are used
+        if !self.functions.contains(name) {
+            let mut err = format!("Unknown function: {}", name);
+            if let Some(suggestion) = find_similar_keyword(name, ENGLISH_KEYWORDS) {
+                err.push_str(&format!(" (did you mean '{}'?)", suggestion));
             }
+            self.push_error(err, Some(name));
         }
-
-        AnalysisEnv {
-            always: merged_always,
-            guarded: merged_guarded,
+        for arg in args.iter().chain(named.iter().map(|(_, arg)| arg)) {
+            self.analyze_expr(arg);
+        }
+        if let Some(params) = self.function_params.get(name).cloned() {
+            self.resolve_arguments(name, &params, args.len(), named);
         }
     }
 
-    fn simple_guard_key(condition: &Expr) -> Option<String> {
-        match condition {
-            Expr::Identifier(name) => Some(name.clone()),
-            Expr::StringLit(name) => Some(name.clone()),
-            Expr::UnaryOp { op: UnaryOperator::Not, operand } => {
-                Self::simple_guard_key(operand).map(|k| format!("not ({})", k))
+    /// Matches a call's positional and named arguments to the parameters:
+    /// positional ones fill parameters in order, named ones by name, and
+    /// defaults fill the rest.
+    fn resolve_arguments(&mut self, function: &str, params: &[(String, bool)], positional: usize, named: &[(String, Expr)]) {
+        if positional > params.len() {
+            self.push_error(
+                format!(
+                    "'{}' takes {} argument{}, but this call gives {}",
+                    function,
+                    params.len(),
+                    if params.len() == 1 { "" } else { "s" },
+                    positional
+                ),
+                Some(function),
+            );
+        }
+        let mut given: Vec<&str> = params.iter().take(positional).map(|(param, _)| param.as_str()).collect();
+        for (param, _) in named {
+            if !params.iter().any(|(p, _)| p == param) {
+                let mut err = format!("'{}' has no parameter called '{}'", function, param);
+                let names: Vec<&str> = params.iter().map(|(p, _)| p.as_str()).collect();
+                if let Some(suggestion) = find_similar_keyword(param, &names) {
+                    err.push_str(&format!(" (did you mean '{}'?)", suggestion));
+                }
+                self.push_error(err, Some(param));
+            } else if given.contains(&param.as_str()) {
+                self.push_error(format!("'{}' is given twice in this call to '{}'", param, function), Some(param));
+            } else {
+                given.push(param);
             }
-            Expr::BinaryOp { left, op, right } => {
-                let connector = match op {
-                    BinaryOperator::And => "and",
-                    BinaryOperator::Or => "or",
-                    _ => return None,
-                };
-                let left_key = Self::simple_guard_key(left)?;
-                let right_key = Self::simple_guard_key(right)?;
-                Some(format!("({}) {} ({})", left_key, connector, right_key))
+        }
+        for (param, has_default) in params {
+            if !has_default && !given.contains(&param.as_str()) {
+                self.push_error(
+                    format!(
+                        "The call to '{}' is missing a value for '{}'\n  Tip: pass it in order, or by name: with {} as <value>",
+                        function, param, param
+                    ),
+                    Some(function),
+                );
             }
-            _ => None,
         }
     }
 
-    fn maybe_activate_true_guard(&mut self, name: &str, var_type: &Option<Type>, value: &Option<Expr>) {
-        if self.block_depth == 0 {
-            return;
-        }
-
-        let is_bool_typed = var_type
-            .as_ref()
-            .map(|t| matches!(t, Type::Boolean))
-            .unwrap_or(true);
-        let is_true = matches!(value, Some(Expr::BoolLit(true)));
-
-        if is_bool_typed && is_true {
-            if !self.active_guards.iter().any(|g| g == name) {
-                self.active_guards.push(name.to_string());
-            }
-            self.guarded_scopes
-                .entry(name.to_string())
-                .or_default()
-                .insert(name.to_string());
+    /// How many values a function returns, when its definition says:
+    /// "Return a number and a boolean, ..." returns two
+    fn return_count(&self, function: &str) -> Option<usize> {
+        match self.function_returns.get(function)? {
+            Type::Tuple(types) => Some(types.len()),
+            Type::Void => None,
+            _ => Some(1),
         }
     }
 
-    fn analyze_block_in_scope(&mut self, block: &[Statement], input_env: &AnalysisEnv, active_guard: Option<&str>) -> (AnalysisEnv, bool) {
-        let saved_env = self.current_env();
-        let saved_guards = self.active_guards.clone();
-        let saved_block_depth = self.block_depth;
-        self.apply_env(input_env);
-        self.block_depth += 1;
-        if let Some(guard) = active_guard {
-            self.active_guards.push(guard.to_string());
-        }
-
-        let mut terminates = false;
-        for stmt in block {
-            self.analyze_statement(stmt);
-            if self.statement_always_terminates(stmt) {
-                terminates = true;
-                break;
-            }
+    fn count_values(count: usize) -> String {
+        if count == 1 {
+            "1 value".to_string()
+        } else {
+            format!("{} values", count)
         }
-        let resulting_env = self.current_env();
-        self.block_depth = saved_block_depth;
-        self.active_guards = saved_guards;
-        self.apply_env(&saved_env);
-        (resulting_env, terminates)
     }
 
-    fn block_always_terminates(&self, block: &[Statement]) -> bool {
-        for stmt in block {
-            if self.statement_always_terminates(stmt) {
-                return true;
-            }
+    fn check_available(&mut self, prefix: &str, name: &str) {
+        if !self.is_variable_available(name) {
+            let message = self.unavailable_message(prefix, name);
+            self.push_error(message, Some(name));
         }
-        false
     }
 
-    fn statement_always_terminates(&self, stmt: &Statement) -> bool {
-        match stmt {
-            Statement::Return { .. } | Statement::Exit { .. } => true,
-            Statement::If { then_block, else_if_blocks, else_block, .. } => {
-                if !self.block_always_terminates(then_block) {
-                    return false;
-                }
-                for (_, block) in else_if_blocks {
-                    if !self.block_always_terminates(block) {
-                        return false;
-                    }
-                }
-                if let Some(block) = else_block {
-                    self.block_always_terminates(block)
-                } else {
-                    false
-                }
-            }
-            _ => false,
+    /// Like check_available, but the standard streams are always open
+    fn check_file_available(&mut self, file: &str) {
+        if STANDARD_STREAMS.contains(&file) && !self.variables.contains(file) {
+            return;
         }
+        self.check_available("Unknown file", file);
     }
-    
+
     fn analyze_statement(&mut self, stmt: &Statement) {
         match stmt {
             Statement::Print { value, .. } => {
@@ -763,179 +2016,306 @@ impl Analyzer {
                 }
             }
             
-            Statement::VarDecl { name, var_type, value } => {
-                self.declare_variable_in_current_scope(name);
-                self.maybe_activate_true_guard(name, var_type, value);
-                if let Some(v) = value {
-                    self.analyze_expr(v);
+            Statement::Ensure { condition, message, .. } => {
+                self.deps.uses_io = true;
+                self.analyze_expr(condition);
+                if let Some(message) = message {
+                    self.analyze_expr(message);
                 }
             }
-
-            Statement::FlagSchemaDecl { name, default, .. } => {
-                self.deps.uses_args = true;
-                self.declare_variable_in_current_scope(name);
-                if let Some(v) = default {
-                    self.analyze_expr(v);
+            
+            Statement::Example { name, body } => {
+                if let Some(function) = &self.current_function {
+                    let err = format!("Example \"{}\" is inside '{}' - examples go at the top level", name, function);
+                    self.push_error(err, Some(name));
                 }
+                // Like a function body, an example sees the globals but keeps its own variables
+                let entry = FlowState::entry(self.global_variables.iter().cloned());
+                self.analyze_scope(body, entry);
             }
-
-            Statement::ParseFlags => {
-                self.deps.uses_args = true;
+            
+            Statement::VarDecl { name, value, var_type } => {
+                self.check_not_constant(name);
+                if let Some(Type::Struct(struct_name)) = var_type {
+                    self.check_struct_exists(struct_name);
+                }
+                if let Some(v) = value {
+                    self.check_big_assignment(name, var_type.as_ref(), v);
+                    self.check_decimal_assignment(name, var_type.as_ref(), v);
+                }
+                // "a list of numbers called x is [...]" holds only numbers, so
+                // the declared type decides rather than the elements given
+                if let (Some(Type::List(_)), Some(Expr::ListLit { elements })) = (var_type, value) {
+                    self.deps.uses_heap = true;
+                    for element in elements {
+                        self.check_list_value(name, element);
+                        self.analyze_expr(element);
+                    }
+                } else if let Some(v) = value {
+                    self.analyze_expr(v);
+                }
             }
             
-            Statement::Assignment { name, value } => {
-                if !self.is_variable_available(name) {
-                    self.declare_variable_in_current_scope(name);
+            Statement::ConstDecl { name, value, .. } => {
+                self.declare_constant(name, value);
+                if value.as_number_literal().is_none() && !matches!(value, Expr::StringLit(_) | Expr::BoolLit(_) | Expr::BigLit(_)) {
+                    self.analyze_expr(value);
+                    self.push_error(
+                        format!(
+                            "The constant '{}' must be a fixed value like 10, 2.5, \"text\" or true\n  \
+                             Tip: Use a variable if the value is worked out while the program runs",
+                            name
+                        ),
+                        Some(name),
+                    );
                 }
-                self.analyze_expr(value);
             }
             
-            Statement::If { condition, then_block, else_if_blocks, else_block } => {
-                self.analyze_expr(condition);
-
-                // Branches are analyzed with the same incoming scope.
-                // Declarations inside one branch do not become visible in sibling
-                // branches. After the if-statement, only variables that are
-                // definitely available on all continuing paths remain visible.
-                let branch_env = self.current_env();
-                let mut continuing_envs: Vec<AnalysisEnv> = Vec::new();
-
-                let guard_key = Self::simple_guard_key(condition);
-                let (then_env, then_terminates) = self.analyze_block_in_scope(
-                    then_block,
-                    &branch_env,
-                    guard_key.as_deref(),
-                );
-                if !then_terminates {
-                    continuing_envs.push(then_env);
-                }
-
-                for (cond, block) in else_if_blocks {
-                    let saved_env = self.current_env();
-                    self.apply_env(&branch_env);
-                    self.analyze_expr(cond);
-                    self.apply_env(&saved_env);
-                    let (elif_env, elif_terminates) = self.analyze_block_in_scope(block, &branch_env, None);
-                    if !elif_terminates {
-                        continuing_envs.push(elif_env);
+            // Struct definitions are collected up front by collect_types
+            Statement::StructDef { .. } => {}
+            
+            Statement::MapSet { map, key, value } => {
+                self.check_map(map, "set a key in");
+                self.check_map_key(map, key);
+                let value_type = self.value_type(value);
+                if let (Some(Some(holds)), Some(given)) = (self.map_value_types.get(map), &value_type) {
+                    if !Self::stores_as(holds, given, value) {
+                        let err = format!(
+                            "Values in '{}' are {}, but this value is {}",
+                            map,
+                            describe_type(holds),
+                            describe_type(given)
+                        );
+                        self.push_error(err, Some(map));
                     }
                 }
-
-                if let Some(block) = else_block {
-                    let (else_env, else_terminates) = self.analyze_block_in_scope(block, &branch_env, None);
-                    if !else_terminates {
-                        continuing_envs.push(else_env);
+                // Buffer values are copied into the map, which hands out new texts
+                if let Expr::Identifier(name) | Expr::StringLit(name) = value {
+                    if self.declared_types.get(name) == Some(&Some(Type::Buffer)) {
+                        self.deps.uses_strings = true;
                     }
-                } else {
-                    // No else means the original incoming scope can continue unchanged.
-                    continuing_envs.push(branch_env.clone());
                 }
-
-                let merged_env = self.merge_continuing_envs(&continuing_envs, &branch_env);
-                self.apply_env(&merged_env);
+                self.analyze_expr(key);
+                self.analyze_expr(value);
             }
             
-            Statement::While { condition, body } => {
-                self.analyze_expr(condition);
-                for s in body {
-                    self.analyze_statement(s);
-                }
+            Statement::MapRemove { map, key } => {
+                self.check_map(map, "remove a key from");
+                self.check_map_key(map, key);
+                self.analyze_expr(key);
             }
             
-            Statement::ForRange { variable, range, body } => {
-                self.variables.insert(variable.clone());
-                self.analyze_expr(range);
-                for s in body {
-                    self.analyze_statement(s);
-                }
+            // The loop itself is laid out by the CFG; this node reads the map
+            Statement::ForEachEntry { map, .. } => {
+                self.check_map(map, "loop over the keys of");
             }
             
-            Statement::ForEach { variable, collection, body } => {
-                self.variables.insert(variable.clone());
-                self.analyze_expr(collection);
-                for s in body {
-                    self.analyze_statement(s);
+            Statement::FieldSet { object, field, value } => {
+                if !self.is_variable_available(object) {
+                    self.push_unknown_variable(object);
+                } else {
+                    self.check_struct_field(object, field);
                 }
+                self.analyze_expr(value);
             }
-            
-            Statement::Repeat { count, body } => {
-                self.analyze_expr(count);
-                for s in body {
-                    self.analyze_statement(s);
+
+            Statement::FlagSchemaDecl { default, .. } => {
+                self.deps.uses_args = true;
+                if let Some(v) = default {
+                    self.analyze_expr(v);
                 }
             }
+
+            Statement::ParseFlags => {
+                self.deps.uses_args = true;
+            }
             
-            Statement::Return { value } => {
-                if let Some(v) = value {
-                    self.analyze_expr(v);
+            Statement::Assignment { name, value } => {
+                self.check_not_constant(name);
+                let declared = self.declared_types.get(name).cloned().flatten();
+                self.check_big_assignment(name, declared.as_ref(), value);
+                self.check_decimal_assignment(name, declared.as_ref(), value);
+                self.analyze_expr(value);
+            }
+            
+            // The arms are laid out by the CFG; this node covers the subject and the values
+            Statement::Depending { subject, arms, line, .. } => {
+                self.analyze_expr(subject);
+                let text_values = arms
+                    .iter()
+                    .flat_map(|(patterns, _)| patterns)
+                    .any(|p| matches!(p, MatchPattern::Value(Expr::StringLit(_))));
+                if text_values || self.value_type(subject) == Some(Type::String) {
+                    self.deps.uses_strings = true; // text is compared with _str_eq
                 }
+                for pattern in arms.iter().flat_map(|(patterns, _)| patterns) {
+                    match pattern {
+                        MatchPattern::Value(value) => self.analyze_expr(value),
+                        MatchPattern::Range { start, end } => {
+                            self.analyze_expr(start);
+                            self.analyze_expr(end);
+                            let text_bound = [start, end]
+                                .into_iter()
+                                .find(|bound| self.value_type(bound) == Some(Type::String));
+                            if let Some(bound) = text_bound {
+                                let text = describe_pattern_value(bound);
+                                let mut err = CompileError::new(&format!(
+                                    "A range in 'when' needs numbers, but {} is text",
+                                    text
+                                ));
+                                if let Some(loc) = self.find_from_line(*line, &text, 0) {
+                                    err = err.with_location(loc);
+                                }
+                                self.errors.push(err);
+                            }
+                        }
+                    }
+                }
+                self.check_depending_arms(arms, *line);
             }
             
-            Statement::Allocate { name, size } => {
+            // Control flow is laid out by the CFG in analyze_scope; its
+            // conditions and bounds arrive here as separate nodes.
+            Statement::If { .. }
+            | Statement::While { .. }
+            | Statement::Until { .. }
+            | Statement::DoUntil { .. }
+            | Statement::Loop { .. }
+            | Statement::ForRange { .. }
+            | Statement::ForEach { .. }
+            | Statement::Repeat { .. }
+            | Statement::Exit { .. }
+            | Statement::OnError { .. }
+            | Statement::NamedLoop { .. }
+            | Statement::Break { .. }
+            | Statement::Continue { .. } => {}
+            
+            // Error catching is checked in source order by check_error_catching;
+            // the checks the parser inserts only matter to codegen.
+            Statement::ErrorCatching { .. } | Statement::ErrorCheck { .. } => {}
+            
+            Statement::Allocate { size, .. } => {
                 self.deps.uses_heap = true;
-                self.variables.insert(name.clone());
                 self.analyze_expr(size);
             }
             
             Statement::Free { name } => {
                 self.deps.uses_heap = true;
-                if !self.is_variable_available(name) {
-                    self.push_error(format!("Freeing unknown variable: {}", name), Some(name));
-                }
+                self.check_available("Freeing unknown variable", name);
             }
             
-            Statement::FunctionCall { name, args } => {
-                self.deps.uses_funcs = true; // Track that functions are used
-                if !self.functions.contains(name) {
-                    let mut err = format!("Unknown function: {}", name);
-                    if let Some(suggestion) = find_similar_keyword(name, ENGLISH_KEYWORDS) {
-                        err.push_str(&format!(" (did you mean '{}'?)", suggestion));
+            Statement::FunctionCall { name, args, named } => self.check_function_call(name, args, named),
+            
+            Statement::Return { values } => {
+                for value in values {
+                    self.analyze_expr(value);
+                }
+                let expected = self
+                    .current_function
+                    .as_ref()
+                    .and_then(|function| Some((function.clone(), self.return_count(function)?)));
+                if let Some((function, count)) = expected {
+                    if values.len() != count {
+                        self.push_error(
+                            format!(
+                                "'{}' returns {}, but this returns {}",
+                                function,
+                                Self::count_values(count),
+                                Self::count_values(values.len())
+                            ),
+                            None,
+                        );
                     }
-                    self.push_error(err, Some(name));
                 }
-                for arg in args {
-                    self.analyze_expr(arg);
+            }
+            
+            Statement::Destructure { names, value } => {
+                for name in names {
+                    self.check_not_constant(name);
+                }
+                let Expr::FunctionCall { name, args, named } = value else {
+                    self.analyze_expr(value);
+                    self.push_error(
+                        format!(
+                            "Only a function call can set several variables at once\n  \
+                             Example: Set {} to \"divmod\" of 17 and 5.",
+                            names.join(" and ")
+                        ),
+                        names.first().map(String::as_str),
+                    );
+                    return;
+                };
+                self.check_function_call(name, args, named);
+                if let Some(count) = self.return_count(name) {
+                    if count != names.len() {
+                        self.push_error(
+                            format!(
+                                "Cannot set {} variables from '{}' - it returns {}",
+                                names.len(),
+                                name,
+                                Self::count_values(count)
+                            ),
+                            Some(name),
+                        );
+                    }
                 }
             }
             
-            Statement::FunctionDef { name, params, body, .. } => {
+            Statement::FunctionDef { name, params, defaults, body, .. } => {
+                // Defaults are filled in where the function is called, so only
+                // fixed values mean the same thing at every call
+                for (param, default) in defaults {
+                    if default.as_number_literal().is_none() && !matches!(default, Expr::StringLit(_) | Expr::BoolLit(_) | Expr::BigLit(_)) {
+                        self.push_error(
+                            format!(
+                                "The default for '{}' must be a fixed value like 10, 2.5, \"text\" or true\n  \
+                                 Tip: leave the default out and pass the value in each call",
+                                param
+                            ),
+                            Some(param),
+                        );
+                    }
+                }
                 self.functions.insert(name.clone());
                 self.deps.uses_funcs = true; // Track that functions are used
-
-                // Functions can access top-level globals, but locals declared inside
-                // the function must not leak back into top-level scope.
-                let saved_env = self.current_env();
-                let saved_guards = self.active_guards.clone();
-                let saved_block_depth = self.block_depth;
-                self.variables = self.global_variables.clone();
-                self.guarded_scopes.clear();
-                self.active_guards.clear();
-                self.block_depth = 0;
-
-                // Add function parameters to function scope.
-                for (param_name, _) in params {
-                    self.variables.insert(param_name.clone());
+                if let Some(count) = self.return_count(name).filter(|count| *count > MAX_RETURN_VALUES) {
+                    self.push_error(
+                        format!(
+                            "'{}' returns {}, but a function can return at most {}",
+                            name,
+                            Self::count_values(count),
+                            MAX_RETURN_VALUES
+                        ),
+                        Some(name),
+                    );
                 }
-                for s in body {
-                    self.analyze_statement(s);
+                for (_, param_type) in params {
+                    if let Type::Struct(struct_name) = param_type {
+                        self.check_struct_exists(struct_name);
+                    }
                 }
 
-                self.block_depth = saved_block_depth;
-                self.active_guards = saved_guards;
-                self.apply_env(&saved_env);
+                // Functions can access top-level globals, but locals declared inside
+                // the function must not leak back into top-level scope.
+                let entry = self
+                    .global_variables
+                    .iter()
+                    .cloned()
+                    .chain(params.iter().map(|(param_name, _)| param_name.clone()));
+                let outer_function = self.current_function.replace(name.clone());
+                self.analyze_scope(body, FlowState::entry(entry));
+                self.current_function = outer_function;
             }
             
             Statement::Increment { name } | Statement::Decrement { name } => {
+                self.check_not_constant(name);
                 if !self.is_variable_available(name) {
                     self.push_unknown_variable(name);
                 }
             }
             
-            Statement::Break | Statement::Continue => {}
-            
             // File I/O statements
-            Statement::BufferDecl { name, size } => {
-                self.variables.insert(name.clone());
+            Statement::BufferDecl { size, .. } => {
                 self.analyze_expr(size);
                 self.deps.uses_heap = true;
             }
@@ -948,70 +2328,129 @@ impl Analyzer {
             
             Statement::ElementSet { list, index, value } => {
                 self.track_identifier(list);
+                self.check_mixed_elements(&Expr::Identifier(list.clone()), std::slice::from_ref(value));
                 self.analyze_expr(index);
                 self.analyze_expr(value);
             }
             
             Statement::ListAppend { list, value } => {
+                self.check_not_constant(list);
                 self.track_identifier(list);
+                self.check_list_value(list, value);
+                self.check_mixed_elements(&Expr::Identifier(list.clone()), std::slice::from_ref(value));
+                self.analyze_expr(value);
+            }
+            
+            Statement::ListInsert { list, value, position } => {
+                self.check_list(list, "insert into");
+                self.check_list_value(list, value);
+                self.check_mixed_elements(&Expr::Identifier(list.clone()), std::slice::from_ref(value));
+                self.check_element_position(position);
+                self.analyze_expr(value);
+                self.analyze_expr(position);
+            }
+            
+            Statement::ListRemoveAt { list, position } => {
+                self.check_list(list, "remove an element from");
+                self.check_element_position(position);
+                self.analyze_expr(position);
+            }
+            
+            Statement::ListRemove { list, value } => {
+                self.check_list(list, "remove a value from");
+                if self.list_element(&Expr::Identifier(list.clone())) == Some(Type::Any) {
+                    let err = format!("Cannot remove a value from '{}' - it holds a mix of types", list);
+                    self.push_error(err, Some(list));
+                }
+                self.check_list_value(list, value);
                 self.analyze_expr(value);
             }
             
-            Statement::FileOpen { name, path, .. } => {
-                self.variables.insert(name.clone());
+            Statement::ListSort { list, .. } => {
+                self.check_list(list, "sort");
+                match self.list_element(&Expr::Identifier(list.clone())) {
+                    Some(Type::String) => self.deps.uses_strings = true,
+                    Some(Type::Integer | Type::Float | Type::BigNumber | Type::Decimal(_)) | None => {}
+                    Some(other) => {
+                        let err = format!(
+                            "Cannot sort '{}' - it holds {}, and only numbers and text can be sorted",
+                            list,
+                            describe_type(&other)
+                        );
+                        self.push_error(err, Some(list));
+                    }
+                }
+            }
+            
+            Statement::ListReverse { list } => {
+                self.check_list(list, "reverse");
+            }
+            
+            Statement::ListShuffle { list } => {
+                self.check_list(list, "shuffle");
+            }
+            
+            Statement::RandomBytes { buffer, count } => {
+                self.check_available("Unknown buffer", buffer);
+                if let Some(other) = self.declared_types.get(buffer).cloned().flatten().filter(|t| *t != Type::Buffer) {
+                    let err = format!(
+                        "Cannot get random bytes into '{}' - it is {}, not a buffer",
+                        buffer,
+                        describe_type(&other)
+                    );
+                    self.push_error(err, Some(buffer));
+                }
+                if let Some(count) = count {
+                    self.check_whole_number("A byte count", count);
+                    self.analyze_expr(count);
+                }
+            }
+            
+            Statement::SeedRandom { seed } => {
+                self.check_whole_number("A seed", seed);
+                self.analyze_expr(seed);
+            }
+            
+            Statement::FileOpen { path, .. } => {
                 self.analyze_expr(path);
                 self.deps.uses_io = true;
             }
             
             Statement::FileRead { buffer, .. } => {
-                if !self.is_variable_available(buffer) {
-                    self.push_error(format!("Unknown buffer: {}", buffer), Some(buffer));
-                }
+                self.check_available("Unknown buffer", buffer);
                 self.deps.uses_io = true;
             }
 
             Statement::FileReadLine { buffer, .. } => {
-                if !self.is_variable_available(buffer) {
-                    self.push_error(format!("Unknown buffer: {}", buffer), Some(buffer));
-                }
+                self.check_available("Unknown buffer", buffer);
                 self.deps.uses_io = true;
             }
 
             Statement::FileSeekLine { file, line } => {
-                if !self.is_variable_available(file) {
-                    self.push_error(format!("Unknown file: {}", file), Some(file));
-                }
+                self.check_file_available(file);
                 self.analyze_expr(line);
                 self.deps.uses_io = true;
             }
 
             Statement::FileSeekByte { file, byte } => {
-                if !self.is_variable_available(file) {
-                    self.push_error(format!("Unknown file: {}", file), Some(file));
-                }
+                self.check_file_available(file);
                 self.analyze_expr(byte);
                 self.deps.uses_io = true;
             }
             
             Statement::FileWrite { file, value } => {
-                if !self.is_variable_available(file) {
-                    self.push_error(format!("Unknown file: {}", file), Some(file));
-                }
+                self.check_file_available(file);
                 self.analyze_expr(value);
                 self.deps.uses_io = true;
             }
             
             Statement::FileWriteNewline { file } => {
-                if !self.is_variable_available(file) {
-                    self.push_error(format!("Unknown file: {}", file), Some(file));
-                }
+                self.check_file_available(file);
                 self.deps.uses_io = true;
             }
             
             Statement::FileClose { file } => {
-                if !self.is_variable_available(file) {
-                    self.push_error(format!("Unknown file: {}", file), Some(file));
-                }
+                self.check_available("Unknown file", file);
                 self.deps.uses_io = true;
             }
             
@@ -1020,16 +2459,8 @@ impl Analyzer {
                 self.deps.uses_io = true;
             }
             
-            Statement::OnError { actions } => {
-                for action in actions {
-                    self.analyze_statement(action);
-                }
-            }
-            
             Statement::BufferResize { name, new_size } => {
-                if !self.is_variable_available(name) {
-                    self.push_error(format!("Unknown buffer: {}", name), Some(name));
-                }
+                self.check_available("Unknown buffer", name);
                 self.analyze_expr(new_size);
                 self.deps.uses_heap = true;
             }
@@ -1042,69 +2473,133 @@ impl Analyzer {
                 // See statements are handled at compile time
             }
             
-            Statement::Exit { code } => {
-                self.analyze_expr(code);
-            }
-            
             // Time and Timer statements
-            Statement::TimerDecl { name } => {
-                self.variables.insert(name.clone());
-            }
+            Statement::TimerDecl { .. } => {}
             
             Statement::TimerStart { name } => {
-                if !self.is_variable_available(name) {
-                    self.push_error(format!("Unknown timer: {}", name), Some(name));
-                }
+                self.check_available("Unknown timer", name);
             }
             
             Statement::TimerStop { name } => {
-                if !self.is_variable_available(name) {
-                    self.push_error(format!("Unknown timer: {}", name), Some(name));
-                }
+                self.check_available("Unknown timer", name);
             }
             
             Statement::Wait { duration, .. } => {
                 self.analyze_expr(duration);
             }
             
-            Statement::GetTime { into } => {
-                self.variables.insert(into.clone());
-            }
+            Statement::GetTime { .. } => {}
         }
     }
     
+    /// Evaluates an integer expression made only of literals. Returns `None`
+    /// for anything else, including folds that overflow.
+    fn fold_constant(expr: &Expr) -> Option<i64> {
+        match expr {
+            Expr::IntegerLit(n) => Some(*n),
+            Expr::UnaryOp { op: UnaryOperator::Negate, operand } => Self::fold_constant(operand)?.checked_neg(),
+            Expr::BinaryOp { left, op, right } => {
+                let l = Self::fold_constant(left)?;
+                let r = Self::fold_constant(right)?;
+                match op {
+                    BinaryOperator::Add => l.checked_add(r),
+                    BinaryOperator::Subtract => l.checked_sub(r),
+                    BinaryOperator::Multiply => l.checked_mul(r),
+                    BinaryOperator::Divide => l.checked_div(r),
+                    BinaryOperator::Modulo => l.checked_rem(r),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn check_constant_arithmetic(&mut self, expr: &Expr, left: &Expr, op: &BinaryOperator, right: &Expr) {
+        let (Some(l), Some(r)) = (Self::fold_constant(left), Self::fold_constant(right)) else {
+            return;
+        };
+        let word = match op {
+            BinaryOperator::Add => "plus",
+            BinaryOperator::Subtract => "minus",
+            BinaryOperator::Multiply => "times",
+            BinaryOperator::Divide => "divided by",
+            BinaryOperator::Modulo => "modulo",
+            _ => return,
+        };
+        if Self::fold_constant(expr).is_some() {
+            return;
+        }
+        let message = if r == 0 && matches!(op, BinaryOperator::Divide | BinaryOperator::Modulo) {
+            format!("Division by zero in constant expression: {} {} {}", l, word, r)
+        } else {
+            format!("Integer overflow in constant expression: {} {} {}", l, word, r)
+        };
+        self.push_error(message, Some(&l.to_string()));
+    }
+
     fn analyze_expr(&mut self, expr: &Expr) {
         match expr {
-            Expr::BinaryOp { left, op: _, right } => {
+            Expr::BinaryOp { left, op, right } => {
                 self.analyze_expr(left);
                 self.analyze_expr(right);
-                
+                self.check_big_operands(left, op, right);
+                self.check_decimal_operands(expr, left, op, right);
+                if self.checked_arithmetic {
+                    self.check_constant_arithmetic(expr, left, op, right);
+                }
             }
             
-            Expr::UnaryOp { operand, .. } => {
+            Expr::UnaryOp { op, operand } => {
                 self.analyze_expr(operand);
+                if self.checked_arithmetic && matches!(op, UnaryOperator::Negate) {
+                    if let Some(n) = Self::fold_constant(operand) {
+                        if n.checked_neg().is_none() {
+                            self.push_error(
+                                format!("Integer overflow in constant expression: -({})", n),
+                                None,
+                            );
+                        }
+                    }
+                }
             }
             
-            Expr::Range { start, end, .. } => {
+            Expr::Range { start, end, step, .. } => {
                 self.analyze_expr(start);
                 self.analyze_expr(end);
+                if let Some(step) = step {
+                    self.analyze_expr(step);
+                    if step.as_number_literal() == Some(0.0) {
+                        self.push_error(
+                            "Range step cannot be zero\n  \
+                             Tip: count with a positive step like 'by 2'; 'down to' counts backwards".to_string(),
+                            None,
+                        );
+                    }
+                }
             }
             
             Expr::PropertyCheck { value, .. } => {
                 self.analyze_expr(value);
             }
             
-            Expr::FunctionCall { name, args } => {
-                self.deps.uses_funcs = true; // Track that functions are used
-                if !self.functions.contains(name) {
-                    let mut err = format!("Unknown function: {}", name);
-                    if let Some(suggestion) = find_similar_keyword(name, ENGLISH_KEYWORDS) {
-                        err.push_str(&format!(" (did you mean '{}'?)", suggestion));
-                    }
-                    self.push_error(err, Some(name));
-                }
-                for arg in args {
-                    self.analyze_expr(arg);
+            Expr::Cast { value, target_type } => {
+                self.check_big_cast(value, target_type);
+                self.check_decimal_cast(value, target_type);
+                self.analyze_expr(value);
+            }
+            
+            Expr::FunctionCall { name, args, named } => {
+                self.check_function_call(name, args, named);
+                if let Some(count) = self.return_count(name).filter(|count| *count > 1) {
+                    self.push_error(
+                        format!(
+                            "'{}' returns {} - take them apart with: Set <names> to \"{}\" of ...",
+                            name,
+                            Self::count_values(count),
+                            name
+                        ),
+                        Some(name),
+                    );
                 }
             }
             
@@ -1115,72 +2610,328 @@ impl Analyzer {
             
             Expr::ListLit { elements } => {
                 self.deps.uses_heap = true;
+                self.check_mixed_elements(expr, elements);
                 for elem in elements {
                     self.analyze_expr(elem);
                 }
             }
             
-            Expr::StringLit(_) => {
-                self.deps.uses_strings = true;
+            Expr::ListSlice { list, start, end } => {
+                self.deps.uses_heap = true;
+                self.check_list_operand("elements ... of", list);
+                self.check_element_position(start);
+                self.check_element_position(end);
+                self.analyze_expr(list);
+                self.analyze_expr(start);
+                self.analyze_expr(end);
             }
             
-            Expr::FormatString { parts } => {
-                self.deps.uses_strings = true;
-                for part in parts {
-                    match part {
-                        FormatPart::Expression { expr, .. } => {
-                            self.analyze_expr(expr);
-                        }
-                        FormatPart::Variable { name, .. } => {
-                            self.track_identifier(name);
-                            if !self.is_variable_available(name) && name != "_iter" {
-                                if find_similar_keyword(name, ENGLISH_KEYWORDS).is_none() {
-                                    self.push_unknown_variable(name);
-                                } else {
-                                    self.track_typo_candidate(name);
-                                }
-                            }
-                        }
-                        FormatPart::Literal(_) => {}
-                    }
-                }
+            Expr::ListConcat { left, right } => {
+                self.deps.uses_heap = true;
+                self.check_list_operand("followed by", left);
+                self.check_list_operand("followed by", right);
+                self.analyze_expr(left);
+                self.analyze_expr(right);
             }
             
-            Expr::Identifier(name) => {
-                self.track_identifier(name);
-                if !self.is_variable_available(name) && name != "_iter" {
-                    // Don't report as unknown variable if it might be a keyword typo
-                    // (that will be caught by check_for_typos)
-                    if find_similar_keyword(name, ENGLISH_KEYWORDS).is_none() {
-                        self.push_unknown_variable(name);
-                    } else {
-                        self.track_typo_candidate(name);
-                    }
-                }
+            Expr::RandomNumber { low, high } => {
+                self.check_whole_number("A random number's bound", low);
+                self.check_whole_number("A random number's bound", high);
+                self.analyze_expr(low);
+                self.analyze_expr(high);
             }
             
-            // Argument and environment variable expressions
-            Expr::ArgumentCount | Expr::ArgumentName | Expr::ArgumentFirst | 
-            Expr::ArgumentSecond | Expr::ArgumentLast | Expr::ArgumentEmpty |
-            Expr::ArgumentAll | Expr::ArgumentRaw => {
-                self.deps.uses_args = true;
+            Expr::RandomElement { list } => {
+                self.check_list_operand("random element of", list);
+                self.analyze_expr(list);
             }
-
-            Expr::ArgumentHas { value } => {
-                self.deps.uses_args = true;
-                self.deps.uses_strings = true;
+            
+            Expr::MathFunction { function, operand } => {
+                self.check_math_operand(&format!("{} of", function.words()), operand);
+                self.analyze_expr(operand);
+            }
+            
+            Expr::Power { base, exponent } => {
+                self.check_math_operand("to the power of", base);
+                self.check_math_operand("to the power of", exponent);
+                self.analyze_expr(base);
+                self.analyze_expr(exponent);
+            }
+            
+            // A decimal's places are known when compiling, so they are written out
+            Expr::Rounded { value, places, .. } if self.is_decimal(value) => {
                 self.analyze_expr(value);
+                let written = match places.as_deref() {
+                    None => Some(0),
+                    Some(Expr::IntegerLit(places)) => u32::try_from(*places).ok(),
+                    Some(_) => None,
+                };
+                if written.is_none_or(|places| places > DECIMAL_MAX_PLACES) {
+                    let err = format!(
+                        "A decimal rounds to 0 to {} places, written as a number\n  \
+                         Tip: for example, price rounded down to 2 places",
+                        DECIMAL_MAX_PLACES
+                    );
+                    self.push_error(err, Self::operand_name(value));
+                }
             }
             
-            Expr::TreatingAs { value, match_value, replacement } => {
+            Expr::Rounded { value, places, .. } => {
+                self.check_math_operand("rounded", value);
                 self.analyze_expr(value);
-                self.analyze_expr(match_value);
-                self.analyze_expr(replacement);
+                if let Some(places) = places {
+                    self.check_whole_number("The number of places", places);
+                    self.analyze_expr(places);
+                }
             }
             
-            Expr::ArgumentAt { index } => {
-                self.deps.uses_args = true;
-                self.analyze_expr(index);
+            Expr::MinMax { left, right, maximum } => {
+                let operation = if *maximum { "maximum of" } else { "minimum of" };
+                self.check_math_operand(operation, left);
+                self.check_math_operand(operation, right);
+                self.analyze_expr(left);
+                self.analyze_expr(right);
+            }
+            
+            Expr::MapGet { map, key } | Expr::MapHas { map, key } => {
+                self.check_map(map, "look up a key in");
+                self.check_map_key(map, key);
+                self.analyze_expr(key);
+            }
+            
+            Expr::Quantifier { kind, variable, collection, condition } => {
+                self.analyze_expr(collection);
+                if let Some(other) = self.value_type(collection).filter(|t| !matches!(t, Type::List(_))) {
+                    let word = match kind {
+                        QuantifierKind::Any => "any",
+                        QuantifierKind::All => "all",
+                        QuantifierKind::None => "none",
+                    };
+                    let err = format!("'{}' needs a list or a range, but this is {}", word, describe_type(&other));
+                    self.push_error(err, None);
+                }
+                
+                // The element is assigned only while the condition is checked
+                let saved_state = self.flow_state.clone();
+                let newly_visible = self.variables.insert(variable.clone());
+                if let Some(state) = self.flow_state.as_mut() {
+                    state.assign(variable);
+                }
+                self.analyze_expr(condition);
+                self.flow_state = saved_state;
+                if newly_visible {
+                    self.variables.remove(variable);
+                }
+            }
+            
+            Expr::TextSlice { text, start, end } => {
+                self.deps.uses_strings = true;
+                self.check_text_operand("characters ... of", text);
+                for position in std::iter::once(start).chain(end) {
+                    if let Some(other) = self.value_type(position).filter(|t| *t != Type::Integer) {
+                        let err = format!("Character positions are numbers, but this is {}", describe_type(&other));
+                        self.push_error(err, Self::operand_name(position));
+                    }
+                }
+                self.analyze_expr(text);
+                self.analyze_expr(start);
+                if let Some(end) = end {
+                    self.analyze_expr(end);
+                }
+            }
+            
+            // "position of X in list" and "list contains X" search a list
+            Expr::TextFind { part: value, text: list }
+            | Expr::TextCheck { text: list, check: TextCheckKind::Contains, part: value }
+                if matches!(self.value_type(list), Some(Type::List(_))) =>
+            {
+                let word = if matches!(expr, Expr::TextFind { .. }) { "position of" } else { "contains" };
+                if self.list_element(list) == Some(Type::Any) {
+                    let err = format!("'{}' needs a list with one type of element, but this list holds a mix of types", word);
+                    self.push_error(err, Self::operand_name(list));
+                } else if let (Some(holds), Some(given)) = (self.list_element(list), self.value_type(value)) {
+                    if holds != given {
+                        let err = format!(
+                            "'{}' looks for {} in this list, but this is {}",
+                            word,
+                            describe_type(&holds),
+                            describe_type(&given)
+                        );
+                        self.push_error(err, Self::operand_name(value));
+                    }
+                    if holds == Type::String {
+                        self.deps.uses_strings = true;
+                    }
+                }
+                self.analyze_expr(list);
+                self.analyze_expr(value);
+            }
+            
+            Expr::TextFind { part, text } => {
+                self.deps.uses_strings = true;
+                self.check_text_operand("position of", part);
+                self.check_text_operand("position of", text);
+                self.analyze_expr(part);
+                self.analyze_expr(text);
+            }
+            
+            Expr::TextCheck { text, check, part } => {
+                self.deps.uses_strings = true;
+                let word = match check {
+                    TextCheckKind::StartsWith => "starts with",
+                    TextCheckKind::EndsWith => "ends with",
+                    TextCheckKind::Contains => "contains",
+                };
+                self.check_text_operand(word, text);
+                self.check_text_operand(word, part);
+                self.analyze_expr(text);
+                self.analyze_expr(part);
+            }
+            
+            Expr::TextSplit { text, separator } => {
+                self.deps.uses_strings = true;
+                self.check_text_operand("split by", text);
+                self.check_text_operand("split by", separator);
+                self.analyze_expr(text);
+                self.analyze_expr(separator);
+            }
+            
+            Expr::TextJoin { list, separator } => {
+                self.deps.uses_strings = true;
+                match self.value_type(list) {
+                    Some(Type::List(element)) if !matches!(*element, Type::String | Type::Unknown) => {
+                        let err = format!("'joined with' needs a list of text, but this list holds {}", describe_type(&element));
+                        self.push_error(err, Self::operand_name(list));
+                    }
+                    Some(Type::List(_)) | None => {}
+                    Some(other) => {
+                        let err = format!("'joined with' needs a list of text, but this is {}", describe_type(&other));
+                        self.push_error(err, Self::operand_name(list));
+                    }
+                }
+                self.check_text_operand("joined with", separator);
+                self.analyze_expr(list);
+                self.analyze_expr(separator);
+            }
+            
+            Expr::TextCase { text, upper } => {
+                self.deps.uses_strings = true;
+                self.check_text_operand(if *upper { "in uppercase" } else { "in lowercase" }, text);
+                self.analyze_expr(text);
+            }
+            
+            Expr::TextTrim { text } => {
+                self.deps.uses_strings = true;
+                self.check_text_operand("trimmed", text);
+                self.analyze_expr(text);
+            }
+            
+            Expr::FileLines { source } => {
+                self.check_file_available(source);
+                self.deps.uses_io = true;
+            }
+            
+            Expr::TextBytes { text } => self.analyze_expr(text),
+            
+            Expr::StructLit { name, fields } => {
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    if fields[..i].iter().any(|(earlier, _)| earlier == field) {
+                        self.push_error(format!("{}'s {} is given twice", name, field), Some(field));
+                    } else if let Some(known) = self.struct_types.get(name) {
+                        if !known.iter().any(|(f, _)| f == field) {
+                            let mut err = format!("{} has no property called '{}'", name, field);
+                            let names: Vec<&str> = known.iter().map(|(f, _)| f.as_str()).collect();
+                            if let Some(suggestion) = find_similar_keyword(field, &names) {
+                                err.push_str(&format!(" (did you mean '{}'?)", suggestion));
+                            }
+                            self.push_error(err, Some(field));
+                        }
+                    }
+                    self.analyze_expr(value);
+                }
+            }
+            
+            // A word that is not a built-in property must be a struct field;
+            // a built-in word is only checked when the object is a struct
+            Expr::PropertyAccess { object, property: ObjectProperty::Field(field) }
+                if self.struct_variables.contains_key(object) || ObjectProperty::from_word(field).is_none() =>
+            {
+                self.check_struct_field(object, field);
+            }
+            
+            Expr::PropertyAccess { object, property } => {
+                if let Some(struct_name) = self.struct_variables.get(object) {
+                    let word = format!("{:?}", property).to_lowercase();
+                    let err = format!("{} has no property called '{}'", struct_name, word);
+                    self.push_error(err, Some(object));
+                }
+            }
+            
+            Expr::StringLit(_) => {
+                self.deps.uses_strings = true;
+            }
+            
+            Expr::FormatString { parts } => {
+                self.deps.uses_strings = true;
+                for part in parts {
+                    match part {
+                        FormatPart::Expression { expr, .. } => {
+                            self.analyze_expr(expr);
+                        }
+                        FormatPart::Variable { name, .. } => {
+                            self.track_identifier(name);
+                            if !self.is_variable_available(name) && name != "_iter" && !self.is_math_constant(name) {
+                                if self.is_maybe_unassigned(name)
+                                    || find_similar_keyword(name, ENGLISH_KEYWORDS).is_none()
+                                {
+                                    self.push_unknown_variable(name);
+                                } else {
+                                    self.track_typo_candidate(name);
+                                }
+                            }
+                        }
+                        FormatPart::Literal(_) => {}
+                    }
+                }
+            }
+            
+            Expr::Identifier(name) => {
+                self.track_identifier(name);
+                if !self.is_variable_available(name) && name != "_iter" && !self.is_math_constant(name) {
+                    // Don't report as unknown variable if it might be a keyword typo
+                    // (that will be caught by check_for_typos)
+                    if self.is_maybe_unassigned(name)
+                        || find_similar_keyword(name, ENGLISH_KEYWORDS).is_none()
+                    {
+                        self.push_unknown_variable(name);
+                    } else {
+                        self.track_typo_candidate(name);
+                    }
+                }
+            }
+            
+            // Argument and environment variable expressions
+            Expr::ArgumentCount | Expr::ArgumentName | Expr::ArgumentFirst | 
+            Expr::ArgumentSecond | Expr::ArgumentLast | Expr::ArgumentEmpty |
+            Expr::ArgumentAll | Expr::ArgumentRaw => {
+                self.deps.uses_args = true;
+            }
+
+            Expr::ArgumentHas { value } => {
+                self.deps.uses_args = true;
+                self.deps.uses_strings = true;
+                self.analyze_expr(value);
+            }
+            
+            Expr::TreatingAs { value, match_value, replacement } => {
+                self.analyze_expr(value);
+                self.analyze_expr(match_value);
+                self.analyze_expr(replacement);
+            }
+            
+            Expr::ArgumentAt { index } => {
+                self.deps.uses_args = true;
+                self.analyze_expr(index);
             }
             
             Expr::EnvironmentVariable { name } => {
@@ -1208,3 +2959,1099 @@ impl Analyzer {
     }
 }
 
+/// How a `when` value is written in warnings
+fn describe_pattern_value(value: &Expr) -> String {
+    match value {
+        Expr::IntegerLit(n) => n.to_string(),
+        Expr::FloatLit(f) => f.to_string(),
+        Expr::StringLit(s) => format!("\"{}\"", s),
+        Expr::Identifier(name) => name.clone(),
+        _ => "this value".to_string(),
+    }
+}
+
+/// Whether the ranges in `covered` together include every number in `start..=end`
+fn numbers_covered(covered: &[(i64, i64)], start: i64, end: i64) -> bool {
+    let mut sorted = covered.to_vec();
+    sorted.sort();
+    let mut next = start as i128;
+    for (lo, hi) in sorted {
+        if lo as i128 <= next && hi as i128 >= next {
+            next = hi as i128 + 1;
+        }
+    }
+    next > end as i128
+}
+
+/// How a value of this type is described in error messages
+fn describe_type(t: &Type) -> &'static str {
+    match t {
+        Type::Integer => "a number",
+        Type::Float => "a float",
+        Type::String => "text",
+        Type::Boolean => "a boolean",
+        Type::List(_) => "a list",
+        Type::Map => "a map",
+        Type::BigNumber => "a big number",
+        Type::Decimal(_) => "a decimal",
+        Type::Struct(_) => "a struct",
+        Type::Any => "a mix of types",
+        _ => "a value",
+    }
+}
+
+
+#[cfg(test)]
+mod checked_arithmetic_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_checked(input: &str, checked: bool) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new()
+            .with_source("test.en", input)
+            .with_checked_arithmetic(checked);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn constant_overflow_is_a_compile_error_in_checked_mode() {
+        let input = r#"
+            Print 9223372036854775807 add 1.
+            Print 4611686018427387904 multiply 2.
+        "#;
+
+        let analyzer = analyze_checked(input, true);
+        let overflow_errors = analyzer
+            .errors
+            .iter()
+            .filter(|e| e.message.contains("Integer overflow in constant expression"))
+            .count();
+        assert_eq!(overflow_errors, 2, "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn constant_division_by_zero_is_a_compile_error_in_checked_mode() {
+        let analyzer = analyze_checked("Print 10 divide 0.", true);
+        assert!(
+            analyzer
+                .errors
+                .iter()
+                .any(|e| e.message.contains("Division by zero in constant expression")),
+            "got: {:?}",
+            analyzer.errors
+        );
+    }
+
+    #[test]
+    fn constant_overflow_is_allowed_without_checked_mode() {
+        let analyzer = analyze_checked("Print 9223372036854775807 add 1.", false);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn nested_constant_overflow_is_reported_once() {
+        let analyzer = analyze_checked("Print 9223372036854775807 add 1 add 2.", true);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+    }
+}
+
+#[cfg(test)]
+mod error_catching_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn disable_without_enable_is_an_error() {
+        let analyzer = analyze_input("Disable error catching.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("not enabled"));
+    }
+
+    #[test]
+    fn enabling_twice_points_at_the_second_enable() {
+        let input = "Enable error catching.\nPrint 1.\nEnable auto error catching.";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("already enabled"));
+        assert_eq!(analyzer.errors[0].location.as_ref().map(|l| l.line), Some(3));
+    }
+
+    #[test]
+    fn enable_and_disable_regions_in_blocks_are_accepted() {
+        let input = r#"
+            Enable error catching.
+            If 1 is equal to 1 then,
+                disable error catching,
+                print "unchecked".
+            Enable error catching.
+        "#;
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+}
+
+#[cfg(test)]
+mod struct_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    const DOG: &str = "Define a struct called \"Dog\" with a text called \"name\" and a number called \"age\".\n";
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn fields_of_declared_structs_are_accepted() {
+        let input = format!(
+            "{}a Dog called \"rex\" with name \"Rex\" and age 3.\nSet rex's age to rex's age add 1.\nPrint rex's name.",
+            DOG
+        );
+        let analyzer = analyze_input(&input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn misspelled_field_suggests_the_real_one() {
+        let input = format!("{}a Dog called \"rex\".\nPrint rex's nmae.", DOG);
+        let analyzer = analyze_input(&input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Dog has no property called 'nmae'"));
+        assert!(analyzer.errors[0].message.contains("did you mean 'name'"));
+    }
+
+    #[test]
+    fn unknown_struct_type_is_an_error() {
+        let analyzer = analyze_input(&format!("{}a Cat called \"tom\".", DOG));
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Unknown struct: Cat"));
+    }
+
+    #[test]
+    fn struct_fields_are_known_through_function_parameters_and_loops() {
+        let input = format!(
+            "{}To \"older\" with a Dog called \"d\".\n  Set d's age to d's age add 1.\n\
+             a Dog called \"rex\".\na list called \"dogs\" is [rex].\n\
+             For each dog in dogs, print dog's name.",
+            DOG
+        );
+        let analyzer = analyze_input(&input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+}
+
+#[cfg(test)]
+mod map_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn maps_used_consistently_are_accepted() {
+        let input = "a map called \"counts\".\na list called \"words\" is [\"a\", \"b\"].\n\
+                     For each w in words,\n    set w in counts to 1.\n\n\
+                     For each word and n in counts,\n    Print \"{word} {n}\".\n\n\
+                     remove \"a\" from counts.";
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn values_of_another_type_are_an_error() {
+        let input = "a map called \"ages\".\nset \"alice\" in ages to 30.\nset \"bob\" in ages to \"old\".";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Values in 'ages' are a number, but this value is text"));
+    }
+
+    #[test]
+    fn keys_must_be_text() {
+        let input = "a map called \"ages\".\na number called \"n\" is 3.\nset n in ages to 30.";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Keys of 'ages' are text, but this key is a number"));
+    }
+
+    #[test]
+    fn map_statements_on_other_variables_are_an_error() {
+        let input = "a number called \"ages\" is 3.\nremove \"alice\" from ages.";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Cannot remove a key from 'ages' - it is not a map"));
+    }
+}
+
+#[cfg(test)]
+mod depending_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn distinct_arms_are_accepted_quietly() {
+        let input = "a number called \"n\" is 4.\n\
+                     Depending on n, when 1 or 2 then print \"a\", when 3 to 9 then print \"b\", otherwise print \"c\".";
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+        assert!(analyzer.warnings.is_empty(), "got: {:?}", analyzer.warnings);
+    }
+
+    #[test]
+    fn repeated_value_is_a_warning() {
+        let input = "a text called \"cmd\" is \"go\".\n\
+                     Depending on cmd, when \"go\" then print \"a\", when \"stop\" or \"go\" then print \"b\".";
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+        assert_eq!(analyzer.warnings.len(), 1, "got: {:?}", analyzer.warnings);
+        assert!(analyzer.warnings[0].message.contains("\"go\" is already matched by an earlier arm"));
+        assert!(analyzer.warnings[0].is_warning);
+    }
+
+    #[test]
+    fn arm_covered_by_earlier_range_never_runs() {
+        let input = "a number called \"n\" is 4.\n\
+                     Depending on n, when 1 to 10 then print \"a\", when 5 or 7 then print \"b\".";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.warnings.len(), 1, "got: {:?}", analyzer.warnings);
+        assert!(analyzer.warnings[0].message.contains("This arm never runs - earlier arms already match 5, 7"));
+    }
+
+    #[test]
+    fn backwards_range_is_a_warning() {
+        let input = "a number called \"n\" is 4.\nDepending on n, when 9 to 2 then print \"a\".";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.warnings.len(), 1, "got: {:?}", analyzer.warnings);
+        assert!(analyzer.warnings[0].message.contains("The range 9 to 2 matches nothing - write it as 2 to 9"));
+    }
+
+    #[test]
+    fn text_in_a_range_is_an_error() {
+        let input = "a text called \"cmd\" is \"go\".\nDepending on cmd, when \"a\" to \"m\" then print \"early\".";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("A range in 'when' needs numbers"));
+    }
+}
+
+#[cfg(test)]
+mod quantifier_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn element_is_known_inside_the_condition() {
+        let input = "a list called \"scores\" is [1, 2].\n\
+                     If any score in scores is greater than 1 then, print \"yes\".";
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn element_is_unknown_after_the_quantifier() {
+        let input = "a list called \"scores\" is [1, 2].\n\
+                     If any score in scores is greater than 1 then, print score.";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Unknown variable: score"));
+    }
+
+    #[test]
+    fn quantifier_over_a_number_is_an_error() {
+        let input = "a number called \"total\" is 3.\nIf all n in total are even then, print \"even\".";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'all' needs a list or a range, but this is a number"));
+    }
+}
+
+#[cfg(test)]
+mod text_operation_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn text_operations_on_text_are_fine() {
+        let input = "a text called \"line\" is \" a,b \".\n\
+                     a list called \"parts\" is line trimmed split by \",\".\n\
+                     Print parts joined with \" \".\n\
+                     Print characters 1 to 2 of line in uppercase.";
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+        assert!(analyzer.deps.uses_strings);
+    }
+
+    #[test]
+    fn splitting_a_number_is_an_error() {
+        let input = "a number called \"total\" is 3.\nPrint total split by \",\".";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'split by' needs text, but this is a number"));
+    }
+
+    #[test]
+    fn joining_text_is_an_error() {
+        let input = "a text called \"word\" is \"hi\".\nPrint word joined with \" \".";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'joined with' needs a list of text, but this is text"));
+    }
+
+    #[test]
+    fn character_positions_must_be_numbers() {
+        let input = "a text called \"word\" is \"hi\".\nPrint character 1.5 of word.";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Character positions are numbers, but this is a float"));
+    }
+}
+
+#[cfg(test)]
+mod line_loop_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn lines_of_a_file_are_text() {
+        let input = "open a file for reading called source at \"notes.txt\".\n\
+                     For each line in source, print line trimmed.\n\
+                     Close source.";
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+        assert!(analyzer.deps.uses_io);
+    }
+
+    #[test]
+    fn bytes_of_text_are_numbers() {
+        let input = "a text called \"word\" is \"vox\".\n\
+                     For each byte in word, print byte in uppercase.";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'in uppercase' needs text, but this is a number"));
+    }
+}
+
+#[cfg(test)]
+mod list_operation_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn list_operations_on_a_list() {
+        let input = "a list called names is [\"pear\", \"fig\"].\n\
+                     Insert \"kiwi\" at position 1 of names.\n\
+                     Sort names.\n\
+                     Remove \"fig\" from names.\n\
+                     Print position of \"pear\" in names.\n\
+                     Print names joined with \", \".";
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+        assert!(analyzer.deps.uses_strings);
+    }
+
+    #[test]
+    fn only_lists_can_be_sorted() {
+        let analyzer = analyze_input("a number called n is 5.\nSort n.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Cannot sort 'n' - it is not a list"));
+    }
+
+    #[test]
+    fn values_must_match_the_elements() {
+        let analyzer = analyze_input("a list called scores is [1, 2].\nInsert \"x\" at position 1 of scores.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Elements of 'scores' are a number, but this value is text"));
+
+        let analyzer = analyze_input("a list called scores is [1, 2].\nIf scores contains \"x\" then, print 1.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'contains' looks for a number in this list, but this is text"));
+    }
+}
+
+#[cfg(test)]
+mod typed_list_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn typed_list_elements_must_match() {
+        let analyzer = analyze_input("a list of floats called prices is [1.5, \"two\"].");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Elements of 'prices' are a float, but this value is text"));
+
+        let analyzer = analyze_input("a list of numbers called scores.\nAppend \"x\" to scores.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Elements of 'scores' are a number"));
+    }
+
+    #[test]
+    fn nested_lists_and_mixed_lists_are_allowed() {
+        let input = "a list of lists of numbers called grid is [[1, 2], [3, 4]].\n\
+                     Print element 2 of element 1 of grid.\n\
+                     a list called mixed is [1, \"two\", 3.5].\n\
+                     Append 4 to mixed.\n\
+                     For each item in mixed, print item.";
+        let analyzer = analyze_input(input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn mixed_lists_cannot_be_sorted_or_searched() {
+        let analyzer = analyze_input("a list called mixed is [1, \"two\"].\nSort mixed.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("it holds a mix of types"));
+
+        let analyzer = analyze_input("a list called mixed is [1, \"two\"].\nPrint position of 1 in mixed.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("needs a list with one type of element"));
+    }
+}
+
+#[cfg(test)]
+mod multiple_return_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    const DIVMOD: &str = "To divmod with a number called x and a number called y. \
+                          Return a number and a number, x divide y and x modulo y.\n\n";
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn destructured_names_are_declared() {
+        let input = format!("{}Set q and r to \"divmod\" of 17 and 5.\nPrint q add r.", DIVMOD);
+        let analyzer = analyze_input(&input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn destructuring_count_must_match() {
+        let input = format!("{}Set q and r and s to \"divmod\" of 17 and 5.", DIVMOD);
+        let analyzer = analyze_input(&input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Cannot set 3 variables from 'divmod' - it returns 2 values"));
+    }
+
+    #[test]
+    fn several_values_need_destructuring() {
+        let input = format!("{}Print \"divmod\" of 17 and 5.", DIVMOD);
+        let analyzer = analyze_input(&input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'divmod' returns 2 values - take them apart"));
+
+        let analyzer = analyze_input("a number called n is 5.\nSet p and q to n.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Only a function call can set several variables at once"));
+    }
+
+    #[test]
+    fn every_return_gives_the_declared_count() {
+        let input = "To check with a number called n,\n\
+                     If n is 0 then, Return a number and a boolean, 0 and false.\n\
+                     Return a number, n.";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'check' returns 2 values, but this returns 1 value"));
+    }
+}
+
+#[cfg(test)]
+mod named_argument_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    const OPEN_LOG: &str = "To \"open log\" with a text called \"path\" and a number called \"retries\" \
+                            which defaults to 3,\nPrint path.\n\n";
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn defaults_and_names_fill_the_parameters() {
+        let input = format!(
+            "{}\"open log\" of \"a.log\".\n\"open log\" with retries as 1 and path as \"b.log\".",
+            OPEN_LOG
+        );
+        let analyzer = analyze_input(&input);
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn unknown_and_repeated_names_are_errors() {
+        let analyzer = analyze_input(&format!("{}\"open log\" of \"a.log\" with retrys as 5.", OPEN_LOG));
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'open log' has no parameter called 'retrys' (did you mean 'retries'?)"));
+
+        let analyzer = analyze_input(&format!("{}\"open log\" of \"a.log\" with path as \"b.log\".", OPEN_LOG));
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'path' is given twice in this call to 'open log'"));
+    }
+
+    #[test]
+    fn parameters_without_defaults_need_a_value() {
+        let analyzer = analyze_input(&format!("{}\"open log\" with retries as 5.", OPEN_LOG));
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("The call to 'open log' is missing a value for 'path'"));
+
+        let analyzer = analyze_input(&format!("{}\"open log\" of \"a.log\" and 1 and 2.", OPEN_LOG));
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'open log' takes 2 arguments, but this call gives 3"));
+    }
+
+    #[test]
+    fn defaults_are_fixed_values() {
+        let input = "a number called \"limit\" is 3.\n\
+                     To \"retry\" with a number called \"tries\" which defaults to limit,\nPrint tries.\n\n\
+                     To \"pad\" with a number called \"width\" and a number called \"fill\" which defaults to width add 1,\nPrint fill.\n\n";
+        let analyzer = analyze_input(input);
+        assert_eq!(analyzer.errors.len(), 2, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("The default for 'tries' must be a fixed value"));
+        assert!(analyzer.errors[1].message.contains("The default for 'fill' must be a fixed value"));
+
+        let analyzer = analyze_input("To \"scale\" with a number called \"factor\" which defaults to -2 and a text called \"unit\" which defaults to \"cm\",\nPrint unit.\n\n");
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+}
+
+#[cfg(test)]
+mod constant_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn constants_can_be_read_anywhere() {
+        let analyzer = analyze_input(
+            "a constant number called \"limit\" is 10.\n\
+             To \"show\",\n    Print limit.\n\n\
+             Set total to limit plus 1.\nPrint total.\n\"show\"."
+        );
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn constants_cannot_change() {
+        for change in ["Set limit to 5.", "The limit is 5.", "Increment limit.", "Decrement limit.", "Append 5 to limit."] {
+            let analyzer = analyze_input(&format!("a constant number called \"limit\" is 10.\n{}", change));
+            assert_eq!(analyzer.errors.len(), 1, "{}: got {:?}", change, analyzer.errors);
+            assert!(analyzer.errors[0].message.contains("Cannot change 'limit' - it is a constant"), "{}", change);
+        }
+    }
+
+    #[test]
+    fn constants_need_a_fixed_value() {
+        let analyzer = analyze_input("a number called \"x\" is 3.\na constant number called \"limit\" is x plus 1.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("The constant 'limit' must be a fixed value"));
+    }
+
+    #[test]
+    fn repeating_a_constant_needs_the_same_value() {
+        let analyzer = analyze_input("a constant text called \"app\" is \"vox\".\na constant text called \"app\" is \"vox\".");
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+
+        let analyzer = analyze_input("a constant number called \"limit\" is 10.\na constant number called \"limit\" is 11.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("The constant 'limit' is already declared with a different value"));
+    }
+}
+
+#[cfg(test)]
+mod named_loop_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn inner_loops_can_leave_named_outer_loops() {
+        let analyzer = analyze_input(
+            "For each row from 1 to 3, called \"rows\",\n\
+             \x20   For each col from 1 to 3, if col is equal to 2 then, continue rows, print col.\n\n\
+             For each n from 1 to 3, called \"outer\", break out of outer."
+        );
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn unknown_loop_names_are_errors() {
+        let analyzer = analyze_input("For each row from 1 to 3, called \"rows\", break out of cols.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0]
+            .message
+            .contains("There is no loop called 'cols' around this 'break out of cols' - the loops around it are 'rows'"));
+    }
+
+    #[test]
+    fn loop_names_are_only_seen_inside_their_loop() {
+        let analyzer = analyze_input(
+            "For each n from 1 to 3, called \"outer\", print n.\n\n\
+             Repeat 2 times, continue outer."
+        );
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("it is not inside a named loop"));
+
+        let analyzer = analyze_input(
+            "For each n from 1 to 3, called \"outer\",\n\
+             \x20   \"helper\".\n\n\
+             To \"helper\",\n    Loop forever, break out of outer."
+        );
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+    }
+}
+
+#[cfg(test)]
+mod ensure_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn examples_can_call_functions() {
+        let analyzer = analyze_input(
+            "To \"double\" with a number called \"x\". Return a number, x multiply 2.\n\n\
+             Example \"doubles\": ensure \"double\" of 4 is 8."
+        );
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn ensure_checks_its_condition() {
+        let analyzer = analyze_input("Ensure missing is 3.");
+        assert!(!analyzer.errors.is_empty());
+        assert!(analyzer.errors[0].message.contains("missing"), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn examples_go_at_the_top_level() {
+        let analyzer = analyze_input(
+            "To \"check\",\n\
+             \x20   Example \"inner\": ensure 1 is 1."
+        );
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Example \"inner\" is inside 'check'"));
+    }
+}
+
+#[cfg(test)]
+mod random_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn random_values_have_their_types() {
+        let analyzer = analyze_input(
+            "a list called \"names\" is [\"ann\", \"bob\"].\n\
+             a number called \"roll\" is a random number between 1 and 6.\n\
+             a float called \"chance\" is a random float.\n\
+             a text called \"pick\" is a random element of names.\n\
+             Shuffle names."
+        );
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn bounds_must_be_numbers() {
+        let analyzer = analyze_input("Print a random number between 1 and \"six\".");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("A random number's bound must be a number, but this is text"));
+    }
+
+    #[test]
+    fn shuffling_text_is_an_error() {
+        let analyzer = analyze_input("a text called \"word\" is \"hi\".\nShuffle word.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Cannot shuffle 'word' - it is not a list"));
+    }
+
+    #[test]
+    fn random_bytes_need_a_buffer() {
+        let analyzer = analyze_input("a number called \"key\" is 3.\nGet 4 random bytes into key.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Cannot get random bytes into 'key' - it is a number, not a buffer"));
+    }
+}
+
+#[cfg(test)]
+mod math_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn math_results_have_their_types() {
+        let analyzer = analyze_input(
+            "a float called \"root\" is the square root of 2.\n\
+             a number called \"whole\" is root rounded.\n\
+             a float called \"cents\" is root rounded to 2 places.\n\
+             a number called \"big\" is 2 to the power of 10.\n\
+             a float called \"circle\" is 2 multiply pi.\n\
+             Print \"{e} {maximum of whole and big}\"."
+        );
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn a_variable_called_e_replaces_the_constant() {
+        let analyzer = analyze_input("a text called \"e\" is \"x\".\nPrint the square root of e.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("but this is text"));
+    }
+
+    #[test]
+    fn operands_must_be_numbers() {
+        let analyzer = analyze_input("Print the square root of \"nine\".");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'square root of' needs a number or a float, but this is text"));
+    }
+
+    #[test]
+    fn places_must_be_a_whole_number() {
+        let analyzer = analyze_input("Print 3.14159 rounded to 1.5 places.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("The number of places must be a number, but this is a float"));
+    }
+}
+
+#[cfg(test)]
+mod big_number_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn big_numbers_mix_with_numbers() {
+        let analyzer = analyze_input(
+            "a big number called \"huge\" is 123456789012345678901234567890.\n\
+             a big number called \"tripled\" is huge multiply 3 add 1.\n\
+             a number called \"small\" is tripled modulo 10 as a number.\n\
+             a big number called \"parsed\" is \"42\" as a big number.\n\
+             If tripled is greater than huge then, print \"{tripled} {parsed as text}\"."
+        );
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn floats_and_bitwise_operations_are_rejected() {
+        let analyzer = analyze_input("a big number called \"huge\" is 5.\nPrint huge add 1.5.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("works with numbers and big numbers, but this is a float"));
+
+        let analyzer = analyze_input("a big number called \"huge\" is 5.\nPrint huge bit-and 1.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("do not work on big numbers"));
+    }
+
+    #[test]
+    fn variables_keep_their_kind() {
+        let analyzer = analyze_input("a number called \"small\" is 123456789012345678901234567890.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'small' is a number, but this value is a big number"));
+
+        let analyzer = analyze_input("a big number called \"huge\" is 2.5.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Tip: convert it with 'as a big number'"));
+    }
+
+    #[test]
+    fn casts_only_to_and_from_numbers_and_text() {
+        let analyzer = analyze_input("a big number called \"huge\" is 5.\na float called \"f\" is huge as a float.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("can only become a number or text, not a float"));
+
+        let analyzer = analyze_input("Print 2.5 as a big number.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Only numbers and text can become big numbers, but this is a float"));
+    }
+
+    #[test]
+    fn lists_of_big_numbers_but_not_mixed_lists() {
+        let analyzer = analyze_input(
+            "a big number called \"huge\" is 5.\n\
+             a list called \"values\" is [huge, huge add 1].\n\
+             sort values.\n\
+             Print element 1 of values add 1."
+        );
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+
+        let analyzer = analyze_input("a big number called \"huge\" is 5.\nPrint [huge, \"text\"].");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("A list of mixed types cannot hold a big number"));
+
+        let analyzer = analyze_input(
+            "a big number called \"huge\" is 5.\na list called \"mixed\" is [1, \"a\"].\nAppend huge to mixed."
+        );
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("A list of mixed types cannot hold a big number"));
+    }
+}
+
+#[cfg(test)]
+mod decimal_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze_input(input: &str) -> Analyzer {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let mut program = parser.parse().expect("input should parse");
+        let mut analyzer = Analyzer::new().with_source("test.en", input);
+        analyzer.analyze(&mut program);
+        analyzer
+    }
+
+    #[test]
+    fn decimals_mix_with_numbers_and_written_amounts() {
+        let analyzer = analyze_input(
+            "a decimal with 2 places called \"price\" is 19.99.\n\
+             a decimal with 2 places called \"total\" is price multiply 3 add 0.5.\n\
+             a decimal with 4 places called \"rate\" is price multiply 0.08.\n\
+             a number called \"whole\" is total rounded down.\n\
+             a float called \"f\" is total as a float.\n\
+             If total is greater than 10.5 then, print \"{total} {price divide 3 rounded up to 2 places}\"."
+        );
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn float_variables_and_modulo_are_rejected() {
+        let analyzer = analyze_input("a decimal with 2 places called \"price\" is 5.\na float called \"f\" is 1.5.\nPrint price add f.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Tip: convert it with 'as a decimal with 2 places'"));
+
+        let analyzer = analyze_input("a decimal with 2 places called \"price\" is 5.\nPrint price modulo 2.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("do not work on decimals"));
+    }
+
+    #[test]
+    fn variables_keep_their_kind() {
+        let analyzer = analyze_input("a decimal with 2 places called \"price\" is 5.\na number called \"n\" is price.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'n' is a number, but this value is a decimal"));
+
+        let analyzer = analyze_input("a float called \"f\" is 1.5.\na decimal with 2 places called \"price\" is f.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("'price' is a decimal, but this value is a float"));
+    }
+
+    #[test]
+    fn places_stay_within_eighteen() {
+        let analyzer = analyze_input("a decimal with 10 places called \"x\" is 1.5.\nPrint x multiply x.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("would have 20 places"));
+
+        let analyzer = analyze_input("a decimal with 2 places called \"x\" is 1.5.\na number called \"k\" is 1.\nPrint x rounded to k places.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("A decimal rounds to 0 to 18 places"));
+    }
+
+    #[test]
+    fn big_numbers_do_not_mix_with_decimals() {
+        let analyzer = analyze_input("a decimal with 2 places called \"price\" is 5.\na big number called \"b\" is 7.\nPrint price add b.");
+        assert!(analyzer.errors.iter().any(|e| e.message.contains("but this is a big number")), "got: {:?}", analyzer.errors);
+
+        let analyzer = analyze_input("a big number called \"b\" is 7.\nPrint b as a decimal with 2 places.");
+        assert!(analyzer.errors.iter().any(|e| e.message.contains("can become decimals, but this is a big number")), "got: {:?}", analyzer.errors);
+    }
+
+    #[test]
+    fn lists_and_maps_hold_decimals() {
+        let analyzer = analyze_input(
+            "a decimal with 2 places called \"price\" is 19.99.\n\
+             a list of decimals with 2 places called \"fees\" is [1.5, 2, price].\n\
+             Append 0.25 to fees.\n\
+             sort fees.\n\
+             a map called \"costs\".\n\
+             set \"tea\" in costs to price.\n\
+             set \"cake\" in costs to 3.5.\n\
+             Print element 1 of fees add costs's \"tea\"."
+        );
+        assert!(analyzer.errors.is_empty(), "got: {:?}", analyzer.errors);
+
+        let analyzer = analyze_input("a list of decimals with 2 places called \"fees\" is [1.5].\nAppend \"free\" to fees.");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("Elements of 'fees' are a decimal, but this value is text"));
+
+        let analyzer = analyze_input("a decimal with 2 places called \"price\" is 1.\nPrint [price, \"text\"].");
+        assert_eq!(analyzer.errors.len(), 1, "got: {:?}", analyzer.errors);
+        assert!(analyzer.errors[0].message.contains("A list of mixed types cannot hold a decimal"));
+    }
+}
@@ -1,9 +1,16 @@
 use crate::parser::ast::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Where a function leaves each of several returned values, in order
+const RETURN_REGS: [&str; 7] = ["rax", "rdx", "rcx", "rsi", "rdi", "r8", "r9"];
+
+/// Exit code of a program stopped by a failed `Ensure`
+const ENSURE_FAILED_EXIT: i64 = 3;
 
 pub struct CodeGenerator {
     output: String,
     data_section: String,
+    rodata_section: String,
     bss_section: String,
     functions_section: String,
     label_counter: usize,
@@ -11,11 +18,24 @@ pub struct CodeGenerator {
     float_counter: usize,
     variables: HashMap<String, i64>,
     variable_types: HashMap<String, VarType>,
+    big_slots: HashSet<i64>,  // slots holding a big number variable's own value (or 0)
     global_constants: HashMap<String, Expr>,
-    list_element_types: HashMap<String, VarType>,
+    /// Declared constants; numbers are inlined, text and floats live in .rodata
+    constants: HashMap<String, Expr>,
+    /// .rodata labels by content, so the same text is stored once
+    rodata_labels: HashMap<String, String>,
+    list_element_types: HashMap<String, Type>,
+    map_value_types: HashMap<String, VarType>,
+    struct_defs: HashMap<String, Vec<(String, Type)>>,
+    function_returns: HashMap<String, Type>,
+    function_params: HashMap<String, Vec<(String, Type, Option<Expr>)>>,  // with defaults
     file_writable: HashMap<String, bool>,
     stack_offset: i64,
     shared_lib_mode: bool,
+    checked_arithmetic: bool,  // --checked-arithmetic: overflow sets _last_error
+    random_seed: Option<i64>,  // --seed N: random values repeat from run to run
+    test_mode: bool,  // vox test: run the examples instead of the program
+    example_done: Option<String>,  // where a failed ensure in an example jumps
     exported_functions: Vec<String>,
     // Feature tracking for conditional includes
     uses_ints: bool,
@@ -27,7 +47,16 @@ pub struct CodeGenerator {
     uses_time: bool,
     uses_funcs: bool,
     uses_lists: bool,
+    uses_maps: bool,
+    uses_strings: bool, // text compared with _str_eq
+    uses_errors: bool,
+    uses_random: bool,
+    uses_math: bool,
+    uses_big: bool,
+    uses_decimals: bool,
+    function_return: Option<Type>,  // return type of the function being generated
     loop_stack: Vec<(String, String)>, // (continue_label, break_label)
+    loop_names: HashMap<String, usize>, // named loop -> its loop_stack entry
     flag_schemas: Vec<FlagSchemaRuntime>,
     target_arch: String,
 }
@@ -48,6 +77,11 @@ enum VarType {
     String,      // Raw string pointer (from lists, etc.)
     Buffer,      // Dynamic buffer struct (has header)
     List,        // List struct [length, elem0, elem1, ...]
+    Tagged,      // Box of an element of a mixed list: [tag][value]
+    Struct(String), // Pointer to 8-byte field slots, in declaration order
+    Map,         // Map handle (see map.asm)
+    BigNumber,   // Pointer to an immutable big number (see bignum.asm)
+    Decimal(u32), // Units of the last of this many places (see decimal.asm)
     Boolean,
     Unknown,
 }
@@ -74,6 +108,7 @@ impl CodeGenerator {
         CodeGenerator {
             output: String::new(),
             data_section: String::new(),
+            rodata_section: String::new(),
             bss_section: String::new(),
             functions_section: String::new(),
             label_counter: 0,
@@ -81,11 +116,22 @@ impl CodeGenerator {
             float_counter: 0,
             variables: HashMap::new(),
             variable_types: HashMap::new(),
+            big_slots: HashSet::new(),
             global_constants: HashMap::new(),
+            constants: HashMap::new(),
+            rodata_labels: HashMap::new(),
             list_element_types: HashMap::new(),
+            map_value_types: HashMap::new(),
+            struct_defs: HashMap::new(),
+            function_returns: HashMap::new(),
+            function_params: HashMap::new(),
             file_writable: HashMap::new(),
             stack_offset: 0,
             shared_lib_mode: false,
+            checked_arithmetic: false,
+            random_seed: None,
+            test_mode: false,
+            example_done: None,
             exported_functions: Vec::new(),
             uses_ints: false,
             uses_floats: false,
@@ -96,7 +142,16 @@ impl CodeGenerator {
             uses_time: false,
             uses_funcs: false,
             uses_lists: false,
+            uses_maps: false,
+            uses_strings: false,
+            uses_errors: false,
+            uses_random: false,
+            uses_math: false,
+            uses_big: false,
+            uses_decimals: false,
+            function_return: None,
             loop_stack: Vec::new(),
+            loop_names: HashMap::new(),
             flag_schemas: Vec::new(),
             target_arch: "x86_64".to_string(),
         }
@@ -106,6 +161,18 @@ impl CodeGenerator {
         self.shared_lib_mode = enabled;
     }
     
+    pub fn set_checked_arithmetic(&mut self, enabled: bool) {
+        self.checked_arithmetic = enabled;
+    }
+    
+    pub fn set_random_seed(&mut self, seed: Option<i64>) {
+        self.random_seed = seed;
+    }
+    
+    pub fn set_test_mode(&mut self, enabled: bool) {
+        self.test_mode = enabled;
+    }
+    
     pub fn set_target_arch(&mut self, arch: &str) {
         self.target_arch = arch.to_string();
     }
@@ -116,11 +183,9 @@ impl CodeGenerator {
         label
     }
     
-    fn add_string(&mut self, s: &str) -> String {
-        let label = format!("str_{}", self.string_counter);
-        self.string_counter += 1;
-        
-        let escaped: String = s.chars().map(|c| {
+    /// Text as the inside of a NASM quoted string
+    fn escape_text(s: &str) -> String {
+        s.chars().map(|c| {
             match c {
                 '\n' => "', 10, '".to_string(),
                 '\t' => "', 9, '".to_string(),
@@ -128,7 +193,14 @@ impl CodeGenerator {
                 '\'' => "', 39, '".to_string(),  // Escape apostrophe for NASM
                 _ => c.to_string(),
             }
-        }).collect();
+        }).collect()
+    }
+    
+    fn add_string(&mut self, s: &str) -> String {
+        let label = format!("str_{}", self.string_counter);
+        self.string_counter += 1;
+        
+        let escaped = Self::escape_text(s);
         
         self.data_section.push_str(&format!("    {}: db '{}', 0\n", label, escaped));
         self.data_section.push_str(&format!("    {}_len: equ $ - {} - 1\n", label, label));
@@ -145,6 +217,129 @@ impl CodeGenerator {
         label
     }
     
+    /// The .rodata label holding a constant's text; constants with the same
+    /// text, even from different included files, share one copy
+    fn add_constant_text(&mut self, s: &str) -> String {
+        let key = format!("text:{}", s);
+        if let Some(label) = self.rodata_labels.get(&key) {
+            return label.clone();
+        }
+        let label = format!("const_str_{}", self.rodata_labels.len());
+        let escaped = Self::escape_text(s);
+        self.rodata_section.push_str(&format!("    {}: db '{}', 0\n", label, escaped));
+        self.rodata_section.push_str(&format!("    {}_len: equ $ - {} - 1\n", label, label));
+        self.rodata_labels.insert(key, label.clone());
+        label
+    }
+    
+    fn add_constant_float(&mut self, f: f64) -> String {
+        let bits = f.to_bits();
+        let key = format!("float:{:016X}", bits);
+        if let Some(label) = self.rodata_labels.get(&key) {
+            return label.clone();
+        }
+        let label = format!("const_float_{}", self.rodata_labels.len());
+        self.rodata_section.push_str(&format!("    {}: dq 0x{:016X}  ; {}\n", label, bits, f));
+        self.rodata_labels.insert(key, label.clone());
+        label
+    }
+    
+    /// The .rodata label holding a big number literal, laid out like the
+    /// values bignum.asm builds, with capacity 0 so it is never released
+    fn add_constant_big(&mut self, digits: &str) -> String {
+        let key = format!("big:{}", digits);
+        if let Some(label) = self.rodata_labels.get(&key) {
+            return label.clone();
+        }
+        let label = format!("const_big_{}", self.rodata_labels.len());
+        let limbs = Self::big_limbs(digits);
+        let words: Vec<String> = limbs.iter().map(|limb| format!("0x{:016X}", limb)).collect();
+        self.rodata_section.push_str(&format!(
+            "    {}: dq 0, {}, 0, {}  ; {}\n",
+            label,
+            limbs.len(),
+            words.join(", "),
+            digits
+        ));
+        self.rodata_labels.insert(key, label.clone());
+        label
+    }
+    
+    /// Decimal digits as 64-bit limbs, least significant first
+    fn big_limbs(digits: &str) -> Vec<u64> {
+        let mut limbs: Vec<u64> = Vec::new();
+        for digit in digits.bytes().map(|b| u128::from(b - b'0')) {
+            let mut carry = digit;
+            for limb in limbs.iter_mut() {
+                let value = u128::from(*limb) * 10 + carry;
+                *limb = value as u64;
+                carry = value >> 64;
+            }
+            if carry != 0 {
+                limbs.push(carry as u64);
+            }
+        }
+        limbs
+    }
+    
+    /// Loads a constant's value into rax without touching the stack frame,
+    /// so constants also work inside functions
+    fn generate_constant(&mut self, value: &Expr) {
+        match value {
+            Expr::IntegerLit(n) => self.emit_indent(&format!("mov rax, {}", n)),
+            Expr::UnaryOp { op: UnaryOperator::Negate, operand } if matches!(**operand, Expr::IntegerLit(_)) => {
+                if let Expr::IntegerLit(n) = **operand {
+                    self.emit_indent(&format!("mov rax, {}", n.wrapping_neg()));
+                }
+            }
+            Expr::BoolLit(b) => self.emit_indent(&format!("mov rax, {}", u8::from(*b))),
+            Expr::StringLit(s) => {
+                let label = self.add_constant_text(s);
+                self.emit_indent(&format!("lea rax, [{}]", label));
+            }
+            _ => match value.as_number_literal() {
+                Some(f) => {
+                    self.uses_floats = true;
+                    let label = self.add_constant_float(f);
+                    self.emit_indent(&format!("FLOAT_LOAD {}", label));
+                    self.emit_indent("XMM0_TO_RAX");
+                }
+                // The analyzer rejects anything else
+                None => self.generate_expr(value),
+            },
+        }
+    }
+    
+    /// A constant's value as its uses inline it; a number declared as a
+    /// big number or a decimal becomes one
+    fn constant_value(var_type: &Option<Type>, value: &Expr) -> Expr {
+        match var_type {
+            Some(target_type @ (Type::BigNumber | Type::Decimal(_))) if !matches!(value, Expr::BigLit(_)) => {
+                Expr::Cast { value: Box::new(value.clone()), target_type: target_type.clone() }
+            }
+            _ => value.clone(),
+        }
+    }
+    
+    fn constant_type(var_type: &Option<Type>, value: &Expr) -> VarType {
+        match (var_type, value) {
+            (Some(t), _) => Self::var_type_for(t),
+            (None, Expr::StringLit(_)) => VarType::String,
+            (None, Expr::BoolLit(_)) => VarType::Boolean,
+            (None, Expr::FloatLit(_)) => VarType::Float,
+            (None, Expr::UnaryOp { operand, .. }) if matches!(**operand, Expr::FloatLit(_)) => VarType::Float,
+            _ => VarType::Integer,
+        }
+    }
+    
+    /// Labels of the innermost loop, or of the enclosing loop with the given name
+    fn target_loop(&self, loop_name: &Option<String>) -> Option<(String, String)> {
+        match loop_name {
+            Some(name) => self.loop_names.get(name).and_then(|&index| self.loop_stack.get(index)).cloned(),
+            None => self.loop_stack.last().cloned(),
+        }
+    }
+    
     fn alloc_var(&mut self, name: &str) -> i64 {
         self.stack_offset += 8;
         self.variables.insert(name.to_string(), self.stack_offset);
@@ -154,15 +349,107 @@ impl CodeGenerator {
     fn get_var(&self, name: &str) -> Option<i64> {
         self.variables.get(name).copied()
     }
+    
+    // The slot for a variable being declared: its old one, unless that
+    // changes between holding a big number variable's own value and not.
+    // Big number slots are zeroed when the frame starts and only ever hold
+    // such values, so the one a slot holds can always be released.
+    fn declare_slot(&mut self, name: &str, owns_big: bool) -> i64 {
+        match self.get_var(name) {
+            Some(existing) if self.big_slots.contains(&existing) == owns_big => existing,
+            _ => {
+                let offset = self.alloc_var(name);
+                if owns_big {
+                    self.big_slots.insert(offset);
+                }
+                offset
+            }
+        }
+    }
+    
+    fn owns_big(&self, name: &str) -> bool {
+        self.get_var(name).is_some_and(|offset| self.big_slots.contains(&offset))
+    }
+
+    // `pi` or `e`, unless a variable or constant of that name is in scope
+    fn builtin_constant(&self, name: &str) -> Option<f64> {
+        if self.get_var(name).is_some() || self.constants.contains_key(name) || self.global_constants.contains_key(name) {
+            return None;
+        }
+        math_constant(name)
+    }
 
     fn collect_global_constants(&mut self, program: &Program) {
         self.global_constants.clear();
+        self.constants.clear();
         for stmt in &program.statements {
             if let Statement::VarDecl { name, value: Some(expr), .. } = stmt {
                 if matches!(expr, Expr::StringLit(_) | Expr::IntegerLit(_) | Expr::BoolLit(_)) {
                     self.global_constants.insert(name.clone(), expr.clone());
                 }
             }
+            // Functions may come before the constants they use
+            if let Statement::ConstDecl { name, var_type, value } = stmt {
+                let value = Self::constant_value(var_type, value);
+                self.variable_types.insert(name.clone(), Self::constant_type(var_type, &value));
+                self.constants.insert(name.clone(), value);
+            }
+        }
+    }
+
+    fn collect_functions(&mut self, program: &Program) {
+        self.function_returns.clear();
+        self.function_params.clear();
+        for stmt in &program.statements {
+            if let Statement::FunctionDef { name, params, defaults, return_type, .. } = stmt {
+                self.function_returns.insert(name.clone(), return_type.clone());
+                let params = params
+                    .iter()
+                    .map(|(param, param_type)| {
+                        let default = defaults.iter().find(|(d, _)| d == param).map(|(_, e)| e.clone());
+                        (param.clone(), param_type.clone(), default)
+                    })
+                    .collect();
+                self.function_params.insert(name.clone(), params);
+            }
+        }
+    }
+
+    /// A call's arguments in parameter order: positional ones first, then
+    /// named ones in their parameter's place, and defaults for the rest.
+    /// Each comes with whether it is a default.
+    fn call_arguments(&self, name: &str, args: &[Expr], named: &[(String, Expr)]) -> Vec<(Expr, bool)> {
+        let given = |arg: &Expr| (arg.clone(), false);
+        let Some(params) = self.function_params.get(name) else {
+            return args.iter().chain(named.iter().map(|(_, arg)| arg)).map(given).collect();
+        };
+        let mut resolved: Vec<(Expr, bool)> = args.iter().map(given).collect();
+        for (param, _, default) in params.iter().skip(args.len()) {
+            let value = named
+                .iter()
+                .find(|(n, _)| n == param)
+                .map(|(_, arg)| given(arg))
+                .or_else(|| default.clone().map(|value| (value, true)))
+                .unwrap_or((Expr::IntegerLit(0), true));
+            resolved.push(value);
+        }
+        resolved
+    }
+
+    /// Evaluates a call argument; a big number or decimal parameter turns
+    /// a number into one. A default is a fixed value, so none of the
+    /// caller's variables can stand in for it.
+    fn generate_argument(&mut self, name: &str, index: usize, (arg, is_default): &(Expr, bool)) {
+        let param_type = self
+            .function_params
+            .get(name)
+            .and_then(|params| params.get(index))
+            .map(|(_, param_type, _)| param_type.clone());
+        match param_type {
+            Some(Type::BigNumber) => self.generate_owned_big(arg),
+            Some(Type::Decimal(places)) => self.generate_decimal_value(arg, places),
+            _ if *is_default => self.generate_constant(arg),
+            _ => self.generate_expr(arg),
         }
     }
 
@@ -323,7 +610,66 @@ impl CodeGenerator {
         let _ = next_check_label;
     }
 
+    /// Prints "<location>: ensure failed: <condition>", then " - <message>"
+    /// when there is one
+    fn emit_ensure_failure(&mut self, location: &str, text: &str, message: Option<&Expr>) {
+        let report = self.add_string(&format!("{}: ensure failed: {}", location, text));
+        self.emit_indent(&format!("PRINT_STR {}, {}_len", report, report));
+        if let Some(message) = message {
+            let dash = self.add_string(" - ");
+            self.emit_indent(&format!("PRINT_STR {}, {}_len", dash, dash));
+            self.generate_print(message, true);
+        }
+        self.emit_indent("PRINT_NEWLINE");
+    }
+    
+    /// Ends a test build: "3 examples: 2 passed, 1 failed", exiting with 1
+    /// when any failed
+    fn emit_example_summary(&mut self) {
+        self.uses_io = true;
+        self.data_section.push_str("    _example_name: dq 0\n");
+        self.data_section.push_str("    _ensure_failed: dq 0\n");
+        self.data_section.push_str("    _examples_passed: dq 0\n");
+        self.data_section.push_str("    _examples_failed: dq 0\n");
+        
+        self.emit_indent("; example summary");
+        self.emit_indent("mov rdi, [rel _examples_passed]");
+        self.emit_indent("add rdi, [rel _examples_failed]");
+        self.emit_indent("PRINT_INT rdi");
+        let examples = self.add_string(" examples: ");
+        self.emit_indent(&format!("PRINT_STR {}, {}_len", examples, examples));
+        self.emit_indent("mov rdi, [rel _examples_passed]");
+        self.emit_indent("PRINT_INT rdi");
+        let passed = self.add_string(" passed, ");
+        self.emit_indent(&format!("PRINT_STR {}, {}_len", passed, passed));
+        self.emit_indent("mov rdi, [rel _examples_failed]");
+        self.emit_indent("PRINT_INT rdi");
+        let failed = self.add_string(" failed");
+        self.emit_indent(&format!("PRINT_STR {}, {}_len", failed, failed));
+        self.emit_indent("PRINT_NEWLINE");
+        let all_passed = self.new_label("examples_passed");
+        self.emit_indent("cmp qword [rel _examples_failed], 0");
+        self.emit_indent(&format!("je {}", all_passed));
+        self.generate_statement(&Statement::Exit { code: Expr::IntegerLit(1) });
+        self.emit(&format!("{}:", all_passed));
+    }
+    
     fn emit_global_constant_format_fallback(&mut self, name: &str, format: Option<&String>) -> bool {
+        if let Some(value) = self.builtin_constant(name) {
+            self.generate_constant(&Expr::FloatLit(value));
+            self.emit_indent("mov rdi, rax");
+            let fmt_spec = self.parse_format_spec(format.map(|s| s.as_str()));
+            self.emit_formatted_value(Some(VarType::Float), fmt_spec);
+            return true;
+        }
+        if let Some(value) = self.constants.get(name).cloned() {
+            self.generate_constant(&value);
+            self.emit_indent("mov rdi, rax");
+            let var_type = self.variable_types.get(name).cloned();
+            let fmt_spec = self.parse_format_spec(format.map(|s| s.as_str()));
+            self.emit_formatted_value(var_type, fmt_spec);
+            return true;
+        }
         let Some(expr) = self.global_constants.get(name).cloned() else {
             return false;
         };
@@ -350,11 +696,47 @@ impl CodeGenerator {
         }
     }
     
+    // Slot index and declared type of a field of a struct variable
+    fn struct_field(&self, object: &str, field: &str) -> Option<(usize, Type)> {
+        let Some(VarType::Struct(struct_name)) = self.variable_types.get(object) else {
+            return None;
+        };
+        self.struct_defs
+            .get(struct_name)?
+            .iter()
+            .enumerate()
+            .find(|(_, (name, _))| name == field)
+            .map(|(i, (_, field_type))| (i, field_type.clone()))
+    }
+    
+    fn is_buffer_var(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::StringLit(s) | Expr::Identifier(s) => self.variable_types.get(s) == Some(&VarType::Buffer),
+            _ => false,
+        }
+    }
+    
+    // Evaluate a map key into rax. Quoted keys are always literal text;
+    // buffers pass their data, and map.asm copies the text either way.
+    fn generate_map_key(&mut self, key: &Expr) {
+        if let Expr::StringLit(s) = key {
+            let label = self.add_string(s);
+            self.emit_indent(&format!("lea rax, [{}]", label));
+            return;
+        }
+        self.generate_expr(key);
+        if self.is_buffer_var(key) {
+            self.emit_indent("mov rdi, rax  ; buffer struct pointer");
+            self.emit_indent("call _buffer_data  ; get data pointer");
+        }
+    }
+    
     fn is_float_expr(&self, expr: &Expr) -> bool {
         match expr {
+            _ if self.decimal_places(expr).is_some() => false,
             Expr::FloatLit(_) => true,
             Expr::Identifier(name) => {
-                self.variable_types.get(name) == Some(&VarType::Float)
+                self.variable_types.get(name) == Some(&VarType::Float) || self.builtin_constant(name).is_some()
             }
             Expr::Cast { target_type, .. } => {
                 // Cast to float produces a float
@@ -372,6 +754,46 @@ impl CodeGenerator {
                 }
             }
             Expr::UnaryOp { operand, .. } => self.is_float_expr(operand),
+            Expr::PropertyAccess { object, property: ObjectProperty::Field(field) } => {
+                matches!(self.struct_field(object, field), Some((_, Type::Float)))
+            }
+            Expr::MapGet { map, .. } => self.map_value_types.get(map) == Some(&VarType::Float),
+            Expr::ElementAccess { list, .. } | Expr::RandomElement { list } => self.list_element_type(list) == Type::Float,
+            Expr::RandomFloat | Expr::MathFunction { .. } | Expr::Rounded { places: Some(_), .. } => true,
+            Expr::Power { base: left, exponent: right } | Expr::MinMax { left, right, .. } => {
+                self.is_float_expr(left) || self.is_float_expr(right)
+            }
+            Expr::PropertyAccess { object, property: ObjectProperty::First | ObjectProperty::Last } => {
+                self.variable_types.get(object) == Some(&VarType::List)
+                    && self.list_element_types.get(object) == Some(&Type::Float)
+            }
+            _ => false,
+        }
+    }
+    
+    // Operand holding a file handle's fd. The standard streams are always
+    // open unless the program declares a handle with the same name.
+    fn file_fd(&self, file: &str, fallback: &str) -> String {
+        if let Some(offset) = self.get_var(file) {
+            return format!("[rbp-{}]", offset);
+        }
+        match file {
+            "stdin" => "0",
+            "stdout" => "1",
+            "stderr" => "2",
+            _ => fallback,
+        }
+        .to_string()
+    }
+    
+    // A range with any float bound or step counts in floats
+    fn is_float_range(&self, range: &Expr) -> bool {
+        match range {
+            Expr::Range { start, end, step, .. } => {
+                self.is_float_expr(start)
+                    || self.is_float_expr(end)
+                    || step.as_ref().is_some_and(|s| self.is_float_expr(s))
+            }
             _ => false,
         }
     }
@@ -379,18 +801,428 @@ impl CodeGenerator {
     // Check if operands involve floats (for choosing comparison instructions)
     fn has_float_operands(&self, expr: &Expr) -> bool {
         match expr {
+            _ if self.decimal_places(expr).is_some() => false,
             Expr::FloatLit(_) => true,
             Expr::Identifier(name) => {
-                self.variable_types.get(name) == Some(&VarType::Float)
+                self.variable_types.get(name) == Some(&VarType::Float) || self.builtin_constant(name).is_some()
             }
             Expr::BinaryOp { left, right, .. } => {
                 self.has_float_operands(left) || self.has_float_operands(right)
             }
             Expr::UnaryOp { operand, .. } => self.has_float_operands(operand),
+            Expr::MapGet { .. }
+            | Expr::ElementAccess { .. }
+            | Expr::RandomElement { .. }
+            | Expr::RandomFloat
+            | Expr::MathFunction { .. }
+            | Expr::Power { .. }
+            | Expr::Rounded { .. }
+            | Expr::MinMax { .. } => self.is_float_expr(expr),
+            Expr::PropertyAccess { property: ObjectProperty::First | ObjectProperty::Last, .. } => {
+                self.is_float_expr(expr)
+            }
             _ => false,
         }
     }
     
+    // Emits an int.asm arithmetic macro, using its _CHECKED variant
+    // when --checked-arithmetic is on.
+    fn emit_int_arith(&mut self, op: &str) {
+        if self.checked_arithmetic {
+            self.emit_indent(&format!("{}_CHECKED", op));
+        } else {
+            self.emit_indent(op);
+        }
+    }
+    
+    // Follows an inc/dec with an overflow check in checked mode.
+    fn emit_overflow_check(&mut self) {
+        if self.checked_arithmetic {
+            self.uses_ints = true;
+            self.emit_indent("CHECK_OVERFLOW");
+        }
+    }
+    
+    // Maps hand out copies of the text values they own through string.asm
+    fn use_maps(&mut self) {
+        self.uses_maps = true;
+        self.uses_strings = true;
+    }
+    
+    // Big numbers live in resource.asm buffers and print through string.asm
+    fn use_big_numbers(&mut self) {
+        self.uses_big = true;
+        self.uses_buffers = true;
+        self.uses_strings = true;
+    }
+    
+    fn is_big_expr(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::BigLit(_) => true,
+            Expr::Identifier(name) => self.variable_types.get(name) == Some(&VarType::BigNumber),
+            Expr::Cast { target_type, .. } => *target_type == Type::BigNumber,
+            Expr::BinaryOp { left, op, right } => {
+                matches!(
+                    op,
+                    BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply |
+                    BinaryOperator::Divide | BinaryOperator::Modulo
+                ) && (self.is_big_expr(left) || self.is_big_expr(right))
+            }
+            Expr::UnaryOp { op: UnaryOperator::Negate, operand } => self.is_big_expr(operand),
+            Expr::FunctionCall { name, .. } => self.function_returns.get(name) == Some(&Type::BigNumber),
+            _ => self.stored_value_type(expr) == Some(VarType::BigNumber),
+        }
+    }
+    
+    // The type of a value read back from a list, map or struct; None for
+    // other expressions
+    fn stored_value_type(&self, expr: &Expr) -> Option<VarType> {
+        match expr {
+            Expr::ElementAccess { list, .. } | Expr::RandomElement { list } => {
+                Some(Self::var_type_for(&self.list_element_type(list)))
+            }
+            Expr::PropertyAccess { object, property: ObjectProperty::First | ObjectProperty::Last }
+                if self.variable_types.get(object) == Some(&VarType::List) =>
+            {
+                self.list_element_types.get(object).map(Self::var_type_for)
+            }
+            Expr::PropertyAccess { object, property: ObjectProperty::Field(field) } => {
+                self.struct_field(object, field).map(|(_, field_type)| Self::var_type_for(&field_type))
+            }
+            Expr::MapGet { map, .. } => self.map_value_types.get(map).cloned(),
+            _ => None,
+        }
+    }
+    
+    // Evaluates a number or big number into rax as a big number
+    fn generate_big_value(&mut self, expr: &Expr) {
+        self.use_big_numbers();
+        self.generate_expr(expr);
+        if !self.is_big_expr(expr) {
+            self.emit_indent("mov rdi, rax");
+            self.emit_indent("call _big_from_int");
+        }
+    }
+    
+    // Whether a big number expression makes a new value rather than
+    // reading one a variable or constant holds
+    fn is_fresh_big(&self, expr: &Expr) -> bool {
+        match expr {
+            _ if !self.is_big_expr(expr) => true, // made from a number
+            Expr::Identifier(_) | Expr::BigLit(_) => false,
+            _ if self.stored_value_type(expr).is_some() => false, // the collection keeps it
+            Expr::Cast { value, .. } => self.is_fresh_big(value),
+            _ => true,
+        }
+    }
+    
+    // Copies the big number in rax if a variable holds it, so whatever
+    // stores it has a value of its own. Constants are never released.
+    fn emit_unshared_big(&mut self, expr: &Expr) {
+        if self.is_big_expr(expr) && !self.is_fresh_big(expr) && !matches!(expr, Expr::BigLit(_)) {
+            self.emit_indent("mov rdi, rax");
+            self.emit_indent("call _big_copy");
+        }
+    }
+    
+    // Evaluates a number or big number into rax as a value of its own
+    fn generate_owned_big(&mut self, expr: &Expr) {
+        self.generate_big_value(expr);
+        self.emit_unshared_big(expr);
+    }
+    
+    // Releases the value a big number slot holds, keeping rax
+    fn emit_release_big(&mut self, offset: i64) {
+        self.emit_indent("push rax");
+        self.emit_indent(&format!("mov rdi, [rbp-{}]  ; the value it replaces", offset));
+        self.emit_indent("call _big_release");
+        self.emit_indent("pop rax");
+    }
+    
+    // Before a function returns: releases its big number variables' values,
+    // except those leaving as its results
+    fn emit_release_owned_bigs(&mut self, results: &[Expr]) {
+        let leaving: Vec<i64> = results
+            .iter()
+            .filter_map(|result| match result {
+                Expr::Identifier(name) => self.get_var(name),
+                _ => None,
+            })
+            .collect();
+        let mut offsets: Vec<i64> = self.big_slots.iter().copied().filter(|offset| !leaving.contains(offset)).collect();
+        if offsets.is_empty() {
+            return;
+        }
+        offsets.sort_unstable();
+        self.emit_indent("push rax");
+        for offset in offsets {
+            self.emit_indent(&format!("mov rdi, [rbp-{}]", offset));
+            self.emit_indent("call _big_release");
+        }
+        self.emit_indent("pop rax");
+    }
+    
+    // Zeroes a function's big number slots other than its parameters'
+    fn emit_zero_big_slots(&mut self, params: &[i64]) {
+        let mut offsets: Vec<i64> = self.big_slots.iter().copied().filter(|offset| !params.contains(offset)).collect();
+        offsets.sort_unstable();
+        for offset in offsets {
+            self.emit_indent(&format!("mov qword [rbp-{}], 0  ; big number slot", offset));
+        }
+    }
+    
+    // Calls a bignum.asm routine with the value of `expr` in rax as rdi,
+    // releasing that value afterwards if it was made just for the call
+    fn emit_big_routine(&mut self, routine: &str, expr: &Expr) {
+        self.emit_indent("mov rdi, rax");
+        if !self.is_fresh_big(expr) {
+            self.emit_indent(&format!("call {}", routine));
+            return;
+        }
+        self.emit_indent("push rax");
+        self.emit_indent(&format!("call {}", routine));
+        self.emit_indent("pop rdi");
+        self.emit_indent("push rax");
+        self.emit_indent("call _big_release");
+        self.emit_indent("pop rax");
+    }
+    
+    // Calls a bignum.asm routine with `left` in rdi and `right` in rsi,
+    // then releases the operands made just for it
+    fn generate_big_call(&mut self, routine: &str, left: &Expr, right: &Expr) {
+        self.generate_big_value(right);
+        self.emit_indent("push rax");
+        self.generate_big_value(left);
+        self.emit_indent("mov rdi, rax");
+        self.emit_indent("pop rsi");
+        let made = [(self.is_fresh_big(left), "rdi"), (self.is_fresh_big(right), "rsi")];
+        if made.iter().all(|(fresh, _)| !fresh) {
+            self.emit_indent(&format!("call {}", routine));
+            return;
+        }
+        self.emit_indent("push rdi");
+        self.emit_indent("push rsi");
+        self.emit_indent(&format!("call {}", routine));
+        self.emit_indent("pop rsi");
+        self.emit_indent("pop rdi");
+        self.emit_indent("push rax");
+        for (fresh, register) in made {
+            if fresh {
+                self.emit_indent(&format!("mov rdi, {}", register));
+                self.emit_indent("call _big_release");
+            }
+        }
+        self.emit_indent("pop rax");
+    }
+    
+    // Big number arithmetic into rax; comparisons leave 1 or 0 like INT_EQ
+    fn generate_big_operation(&mut self, left: &Expr, op: BinaryOperator, right: &Expr) {
+        let routine = match op {
+            BinaryOperator::Add => "_big_add",
+            BinaryOperator::Subtract => "_big_subtract",
+            BinaryOperator::Multiply => "_big_multiply",
+            BinaryOperator::Divide => "_big_divide",
+            BinaryOperator::Modulo => "_big_modulo",
+            // The analyzer rejects the rest
+            _ => "_big_compare",
+        };
+        self.generate_big_call(routine, left, right);
+        let comparison = match op {
+            BinaryOperator::Equal => "INT_EQ",
+            BinaryOperator::NotEqual => "INT_NE",
+            BinaryOperator::Greater => "INT_GT",
+            BinaryOperator::Less => "INT_LT",
+            BinaryOperator::GreaterEqual => "INT_GE",
+            BinaryOperator::LessEqual => "INT_LE",
+            _ => return,
+        };
+        self.uses_ints = true;
+        self.emit_indent("xor ebx, ebx  ; compare the -1/0/1 result with 0");
+        self.emit_indent(comparison);
+    }
+    
+    // rdi = big number; prints its digits
+    fn emit_print_big(&mut self) {
+        self.emit_indent("call _big_to_text");
+        self.emit_indent("PRINT_CSTR rax");
+    }
+    
+    // Decimals are scaled 64-bit numbers (see decimal.asm) and print
+    // through string.asm
+    fn use_decimals(&mut self) {
+        self.uses_decimals = true;
+        self.uses_strings = true;
+    }
+    
+    // The places of a decimal expression; None when it is not a decimal
+    fn decimal_places(&self, expr: &Expr) -> Option<u32> {
+        match expr {
+            Expr::Identifier(name) => match self.variable_types.get(name) {
+                Some(VarType::Decimal(places)) => Some(*places),
+                _ => None,
+            },
+            Expr::Cast { target_type: Type::Decimal(places), .. } => Some(*places),
+            Expr::BinaryOp { left, op, right }
+                if matches!(
+                    op,
+                    BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply | BinaryOperator::Divide
+                ) =>
+            {
+                let (left_places, right_places) = (self.decimal_places(left), self.decimal_places(right));
+                left_places.or(right_places)?;
+                let left = left_places.unwrap_or_else(|| Self::amount_places(left));
+                let right = right_places.unwrap_or_else(|| Self::amount_places(right));
+                Some(if matches!(op, BinaryOperator::Multiply) { left + right } else { left.max(right) })
+            }
+            Expr::UnaryOp { op: UnaryOperator::Negate, operand } => self.decimal_places(operand),
+            // Rounding without places gives a number
+            Expr::Rounded { value, places: Some(places), .. } if self.decimal_places(value).is_some() => {
+                match **places {
+                    Expr::IntegerLit(places) => u32::try_from(places).ok(),
+                    _ => None,
+                }
+            }
+            Expr::FunctionCall { name, .. } => match self.function_returns.get(name) {
+                Some(Type::Decimal(places)) => Some(*places),
+                _ => None,
+            },
+            _ => match self.stored_value_type(expr) {
+                Some(VarType::Decimal(places)) => Some(places),
+                _ => None,
+            },
+        }
+    }
+    
+    // The places of a decimal, or of a number or written-out amount used with one
+    fn operand_places(&self, expr: &Expr) -> u32 {
+        self.decimal_places(expr).unwrap_or_else(|| Self::amount_places(expr))
+    }
+    
+    fn amount_places(expr: &Expr) -> u32 {
+        expr.as_number_literal().map_or(0, literal_places)
+    }
+    
+    fn decimal_rounding(mode: RoundingMode) -> &'static str {
+        match mode {
+            RoundingMode::Nearest => "DEC_ROUND_NEAREST",
+            RoundingMode::Up => "DEC_ROUND_UP",
+            RoundingMode::Down => "DEC_ROUND_DOWN",
+        }
+    }
+    
+    // Evaluates a number, float or decimal into rax as a decimal with
+    // `places`; written-out amounts are converted while compiling
+    fn generate_decimal_value(&mut self, expr: &Expr, places: u32) {
+        self.use_decimals();
+        let units = match expr {
+            Expr::IntegerLit(n) => n.checked_mul(10i64.pow(places)),
+            _ if self.decimal_places(expr).is_none() => {
+                expr.as_number_literal().and_then(|value| decimal_units(value, places))
+            }
+            _ => None,
+        };
+        if let Some(units) = units {
+            self.emit_indent(&format!("mov rax, {}", units));
+            return;
+        }
+        self.generate_expr(expr);
+        if self.is_float_expr(expr) {
+            self.emit_indent("mov rdi, rax");
+            self.emit_indent(&format!("mov esi, {}", places));
+            self.emit_indent("call _dec_from_float");
+        } else {
+            let from = self.decimal_places(expr).unwrap_or(0);
+            self.emit_decimal_rescale(from, places, "DEC_ROUND_NEAREST");
+        }
+    }
+    
+    // Moves the decimal in rax from `from` places to `to`
+    fn emit_decimal_rescale(&mut self, from: u32, to: u32, mode: &str) {
+        if from != to {
+            self.emit_indent("mov rdi, rax");
+            self.emit_indent(&format!("mov rsi, {}", i64::from(to) - i64::from(from)));
+            self.emit_indent(&format!("mov edx, {}", mode));
+            self.emit_indent("call _dec_rescale");
+        }
+    }
+    
+    // Evaluates `left` into rdi and `right` into rsi as decimals with the given places
+    fn generate_decimal_pair(&mut self, left: &Expr, left_places: u32, right: &Expr, right_places: u32) {
+        self.generate_decimal_value(right, right_places);
+        self.emit_indent("push rax");
+        self.generate_decimal_value(left, left_places);
+        self.emit_indent("mov rdi, rax");
+        self.emit_indent("pop rsi");
+    }
+    
+    // The quotient as a decimal with `places`, rounded once by `mode`
+    fn generate_decimal_division(&mut self, left: &Expr, right: &Expr, places: u32, mode: &str) {
+        // _dec_divide moves the dividend left by the quotient's places plus
+        // the divisor's, less its own: 0 to 18 of them. The operands take
+        // on places to keep it in that range.
+        let mut left_places = self.operand_places(left);
+        let right_places = self.operand_places(right).max(left_places.saturating_sub(places));
+        let shift = places + right_places - left_places;
+        left_places += shift.saturating_sub(DECIMAL_MAX_PLACES);
+        self.generate_decimal_pair(left, left_places, right, right_places);
+        self.emit_indent(&format!("mov rdx, {}", places + right_places - left_places));
+        self.emit_indent(&format!("mov ecx, {}", mode));
+        self.emit_indent("call _dec_divide");
+    }
+    
+    // Decimal arithmetic into rax; comparisons leave 1 or 0 like INT_EQ
+    fn generate_decimal_operation(&mut self, left: &Expr, op: BinaryOperator, right: &Expr) {
+        let (left_places, right_places) = (self.operand_places(left), self.operand_places(right));
+        let places = left_places.max(right_places);
+        let comparison = match op {
+            BinaryOperator::Multiply => {
+                self.generate_decimal_pair(left, left_places, right, right_places);
+                self.emit_indent("call _dec_multiply");
+                return;
+            }
+            BinaryOperator::Divide => {
+                self.generate_decimal_division(left, right, places, "DEC_ROUND_NEAREST");
+                return;
+            }
+            BinaryOperator::Equal => "INT_EQ",
+            BinaryOperator::NotEqual => "INT_NE",
+            BinaryOperator::Greater => "INT_GT",
+            BinaryOperator::Less => "INT_LT",
+            BinaryOperator::GreaterEqual => "INT_GE",
+            BinaryOperator::LessEqual => "INT_LE",
+            _ => {
+                self.generate_decimal_pair(left, places, right, places);
+                // The analyzer leaves only adding and subtracting
+                let routine = if matches!(op, BinaryOperator::Subtract) { "_dec_subtract" } else { "_dec_add" };
+                self.emit_indent(&format!("call {}", routine));
+                return;
+            }
+        };
+        self.generate_decimal_pair(left, places, right, places);
+        self.uses_ints = true;
+        self.emit_indent("mov rax, rdi");
+        self.emit_indent("mov rbx, rsi");
+        self.emit_indent(comparison);
+    }
+    
+    // rdi = decimal with `places`; prints it with all of its places
+    fn emit_print_decimal(&mut self, places: u32) {
+        self.emit_indent(&format!("mov esi, {}", places));
+        self.emit_indent("call _dec_to_text");
+        self.emit_indent("PRINT_CSTR rax");
+    }
+    
+    // Evaluates a number or float into xmm0 as a float
+    fn generate_float_operand(&mut self, expr: &Expr) {
+        self.uses_floats = true;
+        self.generate_expr(expr);
+        if self.is_float_expr(expr) {
+            self.emit_indent("RAX_TO_XMM0");
+        } else {
+            self.emit_indent("INT_TO_FLOAT");
+        }
+    }
+    
     fn emit(&mut self, code: &str) {
         self.output.push_str(code);
         self.output.push('\n');
@@ -404,6 +1236,7 @@ impl CodeGenerator {
     
     pub fn generate(&mut self, program: &Program) -> String {
         self.collect_global_constants(program);
+        self.collect_functions(program);
         self.collect_flag_schemas(program);
 
         let explicit_parse_idx = program
@@ -415,18 +1248,28 @@ impl CodeGenerator {
             .iter()
             .rposition(|s| matches!(s, Statement::FlagSchemaDecl { .. }))
             .map(|i| i + 1);
-        let parse_insert_idx = explicit_parse_idx.or(auto_parse_idx);
+        let parse_insert_idx = explicit_parse_idx.or(auto_parse_idx).filter(|_| !self.test_mode);
 
         for (idx, stmt) in program.statements.iter().enumerate() {
             if parse_insert_idx == Some(idx) {
                 self.emit_flag_parse_routine();
             }
+            // A test build keeps only what the examples can use
+            if self.test_mode && !matches!(
+                stmt,
+                Statement::FunctionDef { .. } | Statement::StructDef { .. } | Statement::ConstDecl { .. } | Statement::Example { .. }
+            ) {
+                continue;
+            }
             self.generate_statement(stmt);
         }
 
         if parse_insert_idx == Some(program.statements.len()) {
             self.emit_flag_parse_routine();
         }
+        if self.test_mode {
+            self.emit_example_summary();
+        }
         
         let mut result = String::new();
         
@@ -449,16 +1292,22 @@ impl CodeGenerator {
             if self.uses_buffers || self.uses_files {
                 result.push_str(&format!("%include \"coreasm/{}/resource.asm\"\n", self.target_arch));
             }
+            if self.uses_errors {
+                result.push_str(&format!("%include \"coreasm/{}/error.asm\"\n", self.target_arch));
+            }
             if self.uses_ints {
                 result.push_str(&format!("%include \"coreasm/{}/int.asm\"\n", self.target_arch));
             }
             if self.uses_floats {
                 result.push_str(&format!("%include \"coreasm/{}/float.asm\"\n", self.target_arch));
             }
+            if self.uses_math {
+                result.push_str(&format!("%include \"coreasm/{}/math.asm\"\n", self.target_arch));
+            }
             if program.uses_heap {
                 result.push_str(&format!("%include \"coreasm/{}/heap.asm\"\n", self.target_arch));
             }
-            if program.uses_strings {
+            if program.uses_strings || self.uses_strings {
                 result.push_str(&format!("%include \"coreasm/{}/string.asm\"\n", self.target_arch));
             }
             if program.uses_args {
@@ -476,6 +1325,18 @@ impl CodeGenerator {
             if self.uses_lists {
                 result.push_str(&format!("%include \"coreasm/{}/list.asm\"\n", self.target_arch));
             }
+            if self.uses_maps {
+                result.push_str(&format!("%include \"coreasm/{}/map.asm\"\n", self.target_arch));
+            }
+            if self.uses_random {
+                result.push_str(&format!("%include \"coreasm/{}/random.asm\"\n", self.target_arch));
+            }
+            if self.uses_big {
+                result.push_str(&format!("%include \"coreasm/{}/bignum.asm\"\n", self.target_arch));
+            }
+            if self.uses_decimals {
+                result.push_str(&format!("%include \"coreasm/{}/decimal.asm\"\n", self.target_arch));
+            }
         }
         result.push('\n');
         
@@ -483,6 +1344,12 @@ impl CodeGenerator {
         result.push_str(&self.data_section);
         result.push('\n');
         
+        if !self.rodata_section.is_empty() {
+            result.push_str("section .rodata\n");
+            result.push_str(&self.rodata_section);
+            result.push('\n');
+        }
+        
         if !self.bss_section.is_empty() {
             result.push_str("section .bss\n");
             result.push_str(&self.bss_section);
@@ -519,8 +1386,19 @@ impl CodeGenerator {
             if self.stack_offset > 0 {
                 result.push_str(&format!("    sub rsp, {}\n", (self.stack_offset + 15) & !15));
             }
+            let mut big_slots: Vec<i64> = self.big_slots.iter().copied().collect();
+            big_slots.sort_unstable();
+            for offset in big_slots {
+                result.push_str(&format!("    mov qword [rbp-{}], 0  ; big number slot\n", offset));
+            }
             result.push('\n');
             
+            if let (true, Some(seed)) = (self.uses_random, self.random_seed) {
+                result.push_str("    ; --seed: start the generator from a fixed seed\n");
+                result.push_str(&format!("    mov rdi, {}\n", seed));
+                result.push_str("    call _random_seed\n\n");
+            }
+            
             result.push_str(&self.output);
             
             // Only cleanup if we used resources
@@ -528,6 +1406,9 @@ impl CodeGenerator {
                 result.push_str("\n    ; Cleanup all resources before exit\n");
                 result.push_str("    call _cleanup_all\n");
             }
+            if self.uses_maps {
+                result.push_str("    call _cleanup_maps\n");
+            }
             result.push_str("\n    ; Exit program\n");
             result.push_str("    EXIT 0\n");
             
@@ -543,62 +1424,102 @@ impl CodeGenerator {
     
     fn generate_statement(&mut self, stmt: &Statement) {
         match stmt {
-            Statement::Print { value, without_newline } => {
+            Statement::Print { value, without_newline, to_stderr } => {
+                if *to_stderr {
+                    self.emit_indent("mov qword [rel _output_fd], 2  ; print to stderr");
+                }
                 self.generate_print(value, *without_newline);
+                if *to_stderr {
+                    self.emit_indent("mov qword [rel _output_fd], 1");
+                }
+            }
+            
+            Statement::ConstDecl { name, var_type, value } => {
+                let value = Self::constant_value(var_type, value);
+                self.constants.insert(name.clone(), value.clone());
+                self.variable_types.insert(name.clone(), Self::constant_type(var_type, &value));
+                // Uses are inlined, but the slot keeps the paths that read
+                // variables from the frame (printing, formatting) working
+                let offset = self.declare_slot(name, false);
+                self.generate_constant(&value);
+                self.emit_indent(&format!("mov [rbp-{}], rax  ; constant", offset));
             }
             
             Statement::VarDecl { name, var_type, value } => {
-                // Reuse existing slot for reassignment, otherwise allocate new
-                let offset = if let Some(&existing) = self.variables.get(name) {
-                    existing
-                } else {
-                    self.stack_offset += 8;
-                    self.variables.insert(name.clone(), self.stack_offset);
-                    self.stack_offset
+                // Reuse existing slot for reassignment, otherwise allocate new.
+                // "Set" keeps a big number variable a big number.
+                let owns_big = match var_type {
+                    Some(t) => *t == Type::BigNumber,
+                    None => self.owns_big(name) || value.as_ref().is_some_and(|v| self.is_big_expr(v)),
                 };
+                let offset = self.declare_slot(name, owns_big);
                 
                 // Track variable type from declaration
                 if let Some(ref t) = var_type {
-                    let vt = match t {
-                        Type::String => VarType::String,
-                        Type::Integer => VarType::Integer,
-                        Type::Float => VarType::Float,
-                        Type::Boolean => VarType::Boolean,
-                        Type::Buffer => VarType::Buffer,
-                        _ => VarType::Unknown,
-                    };
-                    self.variable_types.insert(name.clone(), vt);
+                    self.variable_types.insert(name.clone(), Self::var_type_for(t));
+                }
+                // "a list of floats" fixes the element type; otherwise the value decides
+                let declared_element = match var_type {
+                    Some(Type::List(element)) if **element != Type::Unknown => Some((**element).clone()),
+                    _ => None,
+                };
+                if let Some(element) = &declared_element {
+                    self.list_element_types.insert(name.clone(), element.clone());
                 }
                 
                 if let Some(val) = value {
                     // Track list type and element type for lists
-                    if let Expr::ListLit { elements } = val {
+                    if let Expr::ListLit { .. } = val {
                         self.variable_types.insert(name.clone(), VarType::List);
-                        // Track element type separately
-                        if let Some(first) = elements.first() {
-                            let elem_type = match first {
-                                Expr::StringLit(_) => VarType::String,
-                                Expr::IntegerLit(_) => VarType::Integer,
-                                Expr::FloatLit(_) => VarType::Float,
-                                Expr::BoolLit(_) => VarType::Boolean,
-                                _ => VarType::Unknown,
-                            };
-                            self.list_element_types.insert(name.clone(), elem_type);
+                        if declared_element.is_none() {
+                            let elem_type = self.list_element_type(val);
+                            if elem_type != Type::Unknown {
+                                self.list_element_types.insert(name.clone(), elem_type);
+                            }
                         }
                     }
                     // Float literals set float type
-                    else if self.is_float_expr(val) {
+                    else if self.is_float_expr(val) && !matches!(var_type, Some(Type::Decimal(_))) {
                         self.variable_types.insert(name.clone(), VarType::Float);
                     }
+                    else if var_type.is_none() && self.is_big_expr(val) {
+                        self.variable_types.insert(name.clone(), VarType::BigNumber);
+                    }
+                    else if let (None, Some(places)) = (var_type, self.decimal_places(val)) {
+                        self.variable_types.insert(name.clone(), VarType::Decimal(places));
+                    }
                     // Argument/environment expressions return string pointers
                     else if matches!(val, 
                         Expr::ArgumentAt { .. } | Expr::ArgumentName | Expr::ArgumentFirst | 
                         Expr::ArgumentSecond | Expr::ArgumentLast |
                         Expr::EnvironmentVariable { .. } | Expr::EnvironmentVariableAt { .. } |
-                        Expr::EnvironmentVariableFirst | Expr::EnvironmentVariableLast
+                        Expr::EnvironmentVariableFirst | Expr::EnvironmentVariableLast |
+                        Expr::LastErrorMessage |
+                        Expr::TextSlice { .. } | Expr::TextJoin { .. } | Expr::TextCase { .. } | Expr::TextTrim { .. }
                     ) {
                         self.variable_types.insert(name.clone(), VarType::String);
                     }
+                    // Splitting text, slicing and joining lists, and elements of
+                    // nested lists give lists
+                    else if self.infer_expr_type(val) == Some(VarType::List) {
+                        self.variable_types.insert(name.clone(), VarType::List);
+                        let elem_type = self.list_element_type(val);
+                        if declared_element.is_none() && elem_type != Type::Unknown {
+                            self.list_element_types.insert(name.clone(), elem_type);
+                        }
+                    }
+                    // Map lookups take the type of the map's values
+                    else if let Expr::MapGet { map, .. } = val {
+                        if let Some(value_type) = self.map_value_types.get(map).cloned() {
+                            self.variable_types.insert(name.clone(), value_type);
+                        }
+                    }
+                    // A random element has the type of the list's elements
+                    else if let Expr::RandomElement { .. } = val {
+                        if let Some(element_type) = self.infer_expr_type(val) {
+                            self.variable_types.insert(name.clone(), element_type);
+                        }
+                    }
                     
                     // Special handling for buffer initialization with string literal
                     if matches!(var_type, Some(Type::Buffer)) {
@@ -627,6 +1548,18 @@ impl CodeGenerator {
                             self.generate_expr(val);
                             self.emit_indent(&format!("mov [rbp-{}], rax", offset));
                         }
+                    } else if owns_big {
+                        self.generate_owned_big(val);
+                        self.emit_release_big(offset);
+                        self.emit_indent(&format!("mov [rbp-{}], rax", offset));
+                    } else if let Some(Type::Decimal(places)) = var_type {
+                        self.generate_decimal_value(val, *places);
+                        self.emit_indent(&format!("mov [rbp-{}], rax", offset));
+                    } else if let Expr::ListLit { elements } = val {
+                        // The declared element type decides whether elements are boxed
+                        let elem_type = self.list_element_types.get(name).cloned().unwrap_or(Type::Unknown);
+                        self.generate_list_literal(elements, &elem_type);
+                        self.emit_indent(&format!("mov [rbp-{}], rax", offset));
                     } else {
                         self.generate_expr(val);
                         self.emit_indent(&format!("mov [rbp-{}], rax", offset));
@@ -642,6 +1575,23 @@ impl CodeGenerator {
                                 self.emit_indent(&format!("mov [rbp-{}], rax", offset));
                                 self.uses_buffers = true;
                             }
+                            Type::Map => {
+                                self.emit_indent("call _map_new");
+                                self.emit_indent(&format!("mov [rbp-{}], rax  ; map handle", offset));
+                                self.use_maps();
+                            }
+                            Type::List(_) => {
+                                self.emit_indent("xor rdi, rdi  ; default capacity");
+                                self.emit_indent("call _list_new");
+                                self.emit_indent(&format!("mov [rbp-{}], rax  ; empty list", offset));
+                                self.uses_lists = true;
+                            }
+                            Type::BigNumber => {
+                                self.use_big_numbers();
+                                self.emit_indent("lea rax, [rel _big_zero]");
+                                self.emit_release_big(offset);
+                                self.emit_indent(&format!("mov [rbp-{}], rax  ; zero", offset));
+                            }
                             _ => {
                                 // Initialize to 0/null
                                 self.emit_indent(&format!("mov qword [rbp-{}], 0", offset));
@@ -654,16 +1604,24 @@ impl CodeGenerator {
                 }
             }
 
+            Statement::StructDef { name, fields } => {
+                // Layout only; instances are allocated by struct literals
+                self.struct_defs.insert(name.clone(), fields.clone());
+            }
+            
+            Statement::FieldSet { object, field, value } => {
+                self.generate_expr(value);
+                self.emit_unshared_big(value);
+                if let (Some(offset), Some((index, _))) = (self.get_var(object), self.struct_field(object, field)) {
+                    self.emit_indent(&format!("mov rcx, [rbp-{}]", offset));
+                    self.emit_indent(&format!("mov [rcx+{}], rax  ; {}'s {}", index * 8, object, field));
+                }
+            }
+            
             Statement::FlagSchemaDecl { name, value_type, default, .. } => {
                 // Current bootstrap behavior: represent parsed flag value as a normal variable slot.
                 // Runtime schema parsing/assignment is emitted in a later iteration.
-                let offset = if let Some(&existing) = self.variables.get(name) {
-                    existing
-                } else {
-                    self.stack_offset += 8;
-                    self.variables.insert(name.clone(), self.stack_offset);
-                    self.stack_offset
-                };
+                let offset = self.declare_slot(name, false);
 
                 let vt = match value_type {
                     FlagValueType::Boolean => VarType::Boolean,
@@ -685,8 +1643,20 @@ impl CodeGenerator {
                 // planned to be emitted around this marker in a subsequent iteration.
             }
             
+            Statement::Assignment { name, value } if self.owns_big(name) => {
+                self.generate_owned_big(value);
+                if let Some(offset) = self.get_var(name) {
+                    self.emit_release_big(offset);
+                    self.emit_indent(&format!("mov [rbp-{}], rax", offset));
+                }
+            }
+            
             Statement::Assignment { name, value } => {
-                self.generate_expr(value);
+                match self.variable_types.get(name).cloned() {
+                    Some(VarType::BigNumber) => self.generate_owned_big(value),
+                    Some(VarType::Decimal(places)) => self.generate_decimal_value(value, places),
+                    _ => self.generate_expr(value),
+                }
                 if let Some(offset) = self.get_var(name) {
                     self.emit_indent(&format!("mov [rbp-{}], rax", offset));
                 } else {
@@ -735,6 +1705,99 @@ impl CodeGenerator {
                 self.emit(&format!("{}:", end_label));
             }
             
+            Statement::Depending { subject, arms, otherwise, .. } => {
+                let otherwise_label = self.new_label("depending_otherwise");
+                let end_label = self.new_label("depending_end");
+                let arm_labels: Vec<String> = arms.iter().map(|_| self.new_label("depending_arm")).collect();
+                let patterns = || arms.iter().enumerate().flat_map(|(arm, (patterns, _))| patterns.iter().map(move |p| (arm, p)));
+                
+                let is_text = self.is_buffer_var(subject)
+                    || self.infer_expr_type(subject) == Some(VarType::String)
+                    || patterns().any(|(_, p)| matches!(p, MatchPattern::Value(Expr::StringLit(_))));
+                let is_float = self.is_float_expr(subject)
+                    || patterns().any(|(_, p)| match p {
+                        MatchPattern::Value(value) => self.is_float_expr(value),
+                        MatchPattern::Range { start, end } => self.is_float_expr(start) || self.is_float_expr(end),
+                    });
+                
+                // The subject is evaluated once into its own slot
+                self.emit_indent("; depending on");
+                self.generate_expr(subject);
+                if self.is_buffer_var(subject) {
+                    self.emit_indent("mov rdi, rax  ; buffer struct pointer");
+                    self.emit_indent("call _buffer_data  ; get data pointer");
+                }
+                let slot_name = format!("_depending_{}", self.label_counter);
+                let slot = self.alloc_var(&slot_name);
+                self.emit_indent(&format!("mov [rbp-{}], rax  ; subject", slot));
+                
+                if is_text {
+                    for (arm, pattern) in patterns() {
+                        // Ranges of text are rejected by the analyzer
+                        let MatchPattern::Value(value) = pattern else { continue };
+                        if let Expr::StringLit(text) = value {
+                            let label = self.add_string(text);
+                            self.emit_indent(&format!("lea rsi, [rel {}]", label));
+                        } else {
+                            self.generate_map_key(value);
+                            self.emit_indent("mov rsi, rax");
+                        }
+                        self.emit_indent(&format!("mov rdi, [rbp-{}]  ; subject", slot));
+                        self.emit_indent("call _str_eq");
+                        self.emit_indent("test rax, rax");
+                        self.emit_indent(&format!("jnz {}", arm_labels[arm]));
+                    }
+                    self.emit_indent(&format!("jmp {}", otherwise_label));
+                } else if let Some(cases) = Self::integer_cases(arms).filter(|_| !is_float) {
+                    self.emit_integer_dispatch(&cases, &arm_labels, &otherwise_label);
+                } else {
+                    // Compare one value at a time, the way an If chain would
+                    let slot_type = if is_float { VarType::Float } else { VarType::Integer };
+                    self.variable_types.insert(slot_name.clone(), slot_type);
+                    let slot_expr = || Box::new(Expr::Identifier(slot_name.clone()));
+                    for (arm, pattern) in patterns() {
+                        let condition = match pattern {
+                            MatchPattern::Value(value) => Expr::BinaryOp {
+                                left: slot_expr(),
+                                op: BinaryOperator::Equal,
+                                right: Box::new(value.clone()),
+                            },
+                            MatchPattern::Range { start, end } => Expr::BinaryOp {
+                                left: Box::new(Expr::BinaryOp {
+                                    left: slot_expr(),
+                                    op: BinaryOperator::GreaterEqual,
+                                    right: Box::new(start.clone()),
+                                }),
+                                op: BinaryOperator::And,
+                                right: Box::new(Expr::BinaryOp {
+                                    left: slot_expr(),
+                                    op: BinaryOperator::LessEqual,
+                                    right: Box::new(end.clone()),
+                                }),
+                            },
+                        };
+                        let next_label = self.new_label("depending_next");
+                        self.generate_condition(&condition, &next_label);
+                        self.emit_indent(&format!("jmp {}", arm_labels[arm]));
+                        self.emit(&format!("{}:", next_label));
+                    }
+                    self.emit_indent(&format!("jmp {}", otherwise_label));
+                }
+                
+                for ((_, body), label) in arms.iter().zip(&arm_labels) {
+                    self.emit(&format!("{}:", label));
+                    for s in body {
+                        self.generate_statement(s);
+                    }
+                    self.emit_indent(&format!("jmp {}", end_label));
+                }
+                self.emit(&format!("{}:", otherwise_label));
+                for s in otherwise.iter().flatten() {
+                    self.generate_statement(s);
+                }
+                self.emit(&format!("{}:", end_label));
+            }
+            
             Statement::While { condition, body } => {
                 let start_label = self.new_label("while_start");
                 let end_label = self.new_label("while_end");
@@ -753,29 +1816,124 @@ impl CodeGenerator {
                 self.emit(&format!("{}:", end_label));
             }
             
+            Statement::Until { condition, body } => {
+                let start_label = self.new_label("until_start");
+                let body_label = self.new_label("until_body");
+                let end_label = self.new_label("until_end");
+                
+                // Enter the body only while the condition is still false
+                self.emit(&format!("{}:", start_label));
+                self.generate_condition(condition, &body_label);
+                self.emit_indent(&format!("jmp {}", end_label));
+                self.emit(&format!("{}:", body_label));
+
+                self.loop_stack.push((start_label.clone(), end_label.clone()));
+                
+                for s in body {
+                    self.generate_statement(s);
+                }
+                self.loop_stack.pop();
+                
+                self.emit_indent(&format!("jmp {}", start_label));
+                self.emit(&format!("{}:", end_label));
+            }
+            
+            Statement::DoUntil { body, condition } => {
+                let start_label = self.new_label("keep_start");
+                let continue_label = self.new_label("keep_continue");
+                let end_label = self.new_label("keep_end");
+                
+                self.emit(&format!("{}:", start_label));
+
+                self.loop_stack.push((continue_label.clone(), end_label.clone()));
+                
+                for s in body {
+                    self.generate_statement(s);
+                }
+                self.loop_stack.pop();
+
+                // Test after each pass; go around again while the condition is false
+                self.emit(&format!("{}:", continue_label));
+                self.generate_condition(condition, &start_label);
+                self.emit(&format!("{}:", end_label));
+            }
+            
+            Statement::Loop { body } => {
+                let start_label = self.new_label("loop_start");
+                let end_label = self.new_label("loop_end");
+                
+                self.emit(&format!("{}:", start_label));
+
+                self.loop_stack.push((start_label.clone(), end_label.clone()));
+                
+                for s in body {
+                    self.generate_statement(s);
+                }
+                self.loop_stack.pop();
+                
+                self.emit_indent(&format!("jmp {}", start_label));
+                self.emit(&format!("{}:", end_label));
+            }
+            
             Statement::ForRange { variable, range, body } => {
+                if self.is_float_range(range) {
+                    self.generate_float_range(variable, range, body);
+                    return;
+                }
+                
                 let start_label = self.new_label("for_start");
                 let continue_label = self.new_label("for_continue");
                 let end_label = self.new_label("for_end");
                 
-                if let Expr::Range { start, end, inclusive } = range {
+                if let Expr::Range { start, end, step, inclusive, descending } = range {
                     self.generate_expr(start);
                     let var_offset = self.alloc_var(variable);
                     self.variables.insert("_iter".to_string(), var_offset);
                     self.emit_indent(&format!("mov [rbp-{}], rax", var_offset));
                     
+                    // The end is kept as given, so a range ending at the
+                    // largest or smallest number never wraps around
                     self.generate_expr(end);
                     let end_offset = self.alloc_var(&format!("{}_end", variable));
-                    if *inclusive {
-                        self.emit_indent("inc rax");
-                    }
                     self.emit_indent(&format!("mov [rbp-{}], rax", end_offset));
                     
+                    // A literal step is folded into the increment; any other step
+                    // is checked for zero once, before the first iteration
+                    let mut step_offset = None;
+                    let mut step_size = 1;
+                    if let Some(step) = step {
+                        match step.as_number_literal() {
+                            Some(n) => step_size = (n as i64).abs(),
+                            None => {
+                                let ok_label = self.new_label("for_step_ok");
+                                self.generate_expr(step);
+                                self.emit_indent("test rax, rax");
+                                self.emit_indent(&format!("jnz {}", ok_label));
+                                self.emit_indent("mov qword [rel _last_error], ERR_ZERO_STEP");
+                                self.emit_indent(&format!("jmp {}", end_label));
+                                self.emit(&format!("{}:", ok_label));
+                                // The step is a size; the range's direction decides the sign
+                                self.emit_indent("mov rdx, rax");
+                                self.emit_indent("neg rax");
+                                self.emit_indent("cmovl rax, rdx");
+                                let offset = self.alloc_var(&format!("{}_step", variable));
+                                self.emit_indent(&format!("mov [rbp-{}], rax", offset));
+                                step_offset = Some(offset);
+                            }
+                        }
+                    }
+                    
                     self.emit(&format!("{}:", start_label));
                     
                     self.emit_indent(&format!("mov rax, [rbp-{}]", var_offset));
                     self.emit_indent(&format!("cmp rax, [rbp-{}]", end_offset));
-                    self.emit_indent(&format!("jge {}", end_label));
+                    let past_end = match (*descending, *inclusive) {
+                        (false, true) => "jg",
+                        (false, false) => "jge",
+                        (true, true) => "jl",
+                        (true, false) => "jle",
+                    };
+                    self.emit_indent(&format!("{} {}", past_end, end_label));
 
                     self.loop_stack.push((continue_label.clone(), end_label.clone()));
                     
@@ -786,7 +1944,46 @@ impl CodeGenerator {
 
                     self.emit(&format!("{}:", continue_label));
                     
-                    self.emit_indent(&format!("inc qword [rbp-{}]", var_offset));
+                    // Stop unless a whole step is left before the end; the
+                    // distance is unsigned, so it fits even from one limit to
+                    // the other and the step below can never overflow
+                    if *descending {
+                        self.emit_indent(&format!("mov rax, [rbp-{}]", var_offset));
+                        self.emit_indent(&format!("sub rax, [rbp-{}]", end_offset));
+                    } else {
+                        self.emit_indent(&format!("mov rax, [rbp-{}]", end_offset));
+                        self.emit_indent(&format!("sub rax, [rbp-{}]", var_offset));
+                    }
+                    match step_offset {
+                        Some(offset) => self.emit_indent(&format!("cmp rax, [rbp-{}]", offset)),
+                        None if step_size <= i32::MAX as i64 => {
+                            self.emit_indent(&format!("cmp rax, {}", step_size));
+                        }
+                        None => {
+                            self.emit_indent(&format!("mov rdx, {}", step_size));
+                            self.emit_indent("cmp rax, rdx");
+                        }
+                    }
+                    self.emit_indent(&format!("{} {}", if *inclusive { "jb" } else { "jbe" }, end_label));
+                    
+                    let op = if *descending { "sub" } else { "add" };
+                    match step_offset {
+                        Some(offset) => {
+                            self.emit_indent(&format!("mov rax, [rbp-{}]", offset));
+                            self.emit_indent(&format!("{} [rbp-{}], rax", op, var_offset));
+                        }
+                        None if step_size == 1 => {
+                            let op = if *descending { "dec" } else { "inc" };
+                            self.emit_indent(&format!("{} qword [rbp-{}]", op, var_offset));
+                        }
+                        None if step_size <= i32::MAX as i64 => {
+                            self.emit_indent(&format!("{} qword [rbp-{}], {}", op, var_offset, step_size));
+                        }
+                        None => {
+                            self.emit_indent(&format!("mov rax, {}", step_size));
+                            self.emit_indent(&format!("{} [rbp-{}], rax", op, var_offset));
+                        }
+                    }
                     self.emit_indent(&format!("jmp {}", start_label));
                     
                     self.emit(&format!("{}:", end_label));
@@ -836,57 +2033,224 @@ impl CodeGenerator {
                 }
             }
             
+            Statement::Increment { name } | Statement::Decrement { name }
+                if self.variable_types.get(name) == Some(&VarType::BigNumber) =>
+            {
+                let op = if matches!(stmt, Statement::Increment { .. }) {
+                    BinaryOperator::Add
+                } else {
+                    BinaryOperator::Subtract
+                };
+                self.generate_big_operation(&Expr::Identifier(name.clone()), op, &Expr::IntegerLit(1));
+                if let Some(offset) = self.get_var(name) {
+                    if self.owns_big(name) {
+                        self.emit_release_big(offset);
+                    }
+                    self.emit_indent(&format!("mov [rbp-{}], rax", offset));
+                }
+            }
+            
+            Statement::Increment { name } | Statement::Decrement { name }
+                if matches!(self.variable_types.get(name), Some(VarType::Decimal(_))) =>
+            {
+                let op = if matches!(stmt, Statement::Increment { .. }) {
+                    BinaryOperator::Add
+                } else {
+                    BinaryOperator::Subtract
+                };
+                self.generate_decimal_operation(&Expr::Identifier(name.clone()), op, &Expr::IntegerLit(1));
+                if let Some(offset) = self.get_var(name) {
+                    self.emit_indent(&format!("mov [rbp-{}], rax", offset));
+                }
+            }
+            
             Statement::Increment { name } => {
                 if let Some(offset) = self.get_var(name) {
                     self.emit_indent(&format!("inc qword [rbp-{}]", offset));
+                    self.emit_overflow_check();
                 }
             }
             
             Statement::Decrement { name } => {
                 if let Some(offset) = self.get_var(name) {
                     self.emit_indent(&format!("dec qword [rbp-{}]", offset));
+                    self.emit_overflow_check();
                 }
             }
             
-            Statement::Break => {
-                self.emit_indent("; break");
-                if let Some((_, break_label)) = self.loop_stack.last() {
+            Statement::NamedLoop { name, inner } => {
+                // The loop pushes its labels next; a name used again in a
+                // later loop points there instead
+                let outer = self.loop_names.insert(name.clone(), self.loop_stack.len());
+                self.generate_statement(inner);
+                match outer {
+                    Some(index) => self.loop_names.insert(name.clone(), index),
+                    None => self.loop_names.remove(name),
+                };
+            }
+            
+            Statement::Break { loop_name } => {
+                match loop_name {
+                    Some(name) => self.emit_indent(&format!("; break out of {}", name)),
+                    None => self.emit_indent("; break"),
+                }
+                if let Some((_, break_label)) = self.target_loop(loop_name) {
                     self.emit_indent(&format!("jmp {}", break_label));
                 }
             }
             
+            Statement::Ensure { condition, message, location, text } => {
+                self.uses_io = true;
+                let ok_label = self.new_label("ensure_ok");
+                let failed_label = self.new_label("ensure_failed");
+                self.emit_indent(&format!("; ensure {}", text));
+                self.generate_condition(condition, &failed_label);
+                self.emit_indent(&format!("jmp {}", ok_label));
+                self.emit(&format!("{}:", failed_label));
+                if self.test_mode {
+                    // The first failure in an example names it
+                    let reported_label = self.new_label("ensure_reported");
+                    self.emit_indent("cmp qword [rel _ensure_failed], 0");
+                    self.emit_indent(&format!("jne {}", reported_label));
+                    let fail = self.add_string("FAIL ");
+                    self.emit_indent(&format!("PRINT_STR {}, {}_len", fail, fail));
+                    self.emit_indent("mov rdi, [rel _example_name]");
+                    self.emit_indent("PRINT_CSTR rdi");
+                    self.emit_indent("PRINT_NEWLINE");
+                    self.emit_indent("mov qword [rel _ensure_failed], 1");
+                    self.emit(&format!("{}:", reported_label));
+                    self.emit_ensure_failure(&format!("  {}", location), text, message.as_ref());
+                    // Ensures in functions let the example carry on
+                    if let Some(done) = self.example_done.clone() {
+                        self.emit_indent(&format!("jmp {}", done));
+                    }
+                } else {
+                    self.emit_indent("mov qword [rel _output_fd], 2  ; report to stderr");
+                    self.emit_ensure_failure(location, text, message.as_ref());
+                    self.generate_statement(&Statement::Exit { code: Expr::IntegerLit(ENSURE_FAILED_EXIT) });
+                }
+                self.emit(&format!("{}:", ok_label));
+            }
+            
+            // Examples only run in a test build; see emit_example_summary
+            Statement::Example { name, body } => {
+                if !self.test_mode {
+                    return;
+                }
+                self.uses_io = true;
+                self.emit_indent(&format!("; example \"{}\"", name));
+                let name_label = self.add_string(name);
+                self.emit_indent(&format!("lea rax, [{}]", name_label));
+                self.emit_indent("mov [rel _example_name], rax");
+                
+                let done_label = self.new_label("example_done");
+                let failed_label = self.new_label("example_failed");
+                let end_label = self.new_label("example_end");
+                let outer = self.example_done.replace(done_label.clone());
+                for s in body {
+                    self.generate_statement(s);
+                }
+                self.example_done = outer;
+                
+                self.emit(&format!("{}:", done_label));
+                self.emit_indent("cmp qword [rel _ensure_failed], 0");
+                self.emit_indent(&format!("jne {}", failed_label));
+                let pass = self.add_string("PASS ");
+                self.emit_indent(&format!("PRINT_STR {}, {}_len", pass, pass));
+                self.emit_indent(&format!("PRINT_STR {}, {}_len", name_label, name_label));
+                self.emit_indent("PRINT_NEWLINE");
+                self.emit_indent("inc qword [rel _examples_passed]");
+                self.emit_indent(&format!("jmp {}", end_label));
+                self.emit(&format!("{}:", failed_label));
+                self.emit_indent("inc qword [rel _examples_failed]");
+                self.emit_indent("mov qword [rel _ensure_failed], 0");
+                self.emit(&format!("{}:", end_label));
+            }
+            
             Statement::Exit { code } => {
                 self.emit_indent("; exit program");
                 self.generate_expr(code);
                 self.emit_indent("mov rdi, rax  ; exit code");
-                if self.uses_files || self.uses_buffers {
+                if self.uses_files || self.uses_buffers || self.uses_maps {
                     self.emit_indent("push rdi      ; save exit code");
-                    self.emit_indent("call _cleanup_all");
+                    if self.uses_files || self.uses_buffers {
+                        self.emit_indent("call _cleanup_all");
+                    }
+                    if self.uses_maps {
+                        self.emit_indent("call _cleanup_maps");
+                    }
                     self.emit_indent("pop rdi       ; restore exit code");
                 }
                 self.emit_indent("EXIT rdi");
             }
             
-            Statement::Continue => {
-                self.emit_indent("; continue");
-                if let Some((continue_label, _)) = self.loop_stack.last() {
+            Statement::Continue { loop_name } => {
+                match loop_name {
+                    Some(name) => self.emit_indent(&format!("; continue {}", name)),
+                    None => self.emit_indent("; continue"),
+                }
+                if let Some((continue_label, _)) = self.target_loop(loop_name) {
                     self.emit_indent(&format!("jmp {}", continue_label));
                 }
             }
             
-            Statement::Return { value } => {
-                if let Some(v) = value {
-                    self.generate_expr(v); // should leave return value in RAX
+            Statement::Return { values } => {
+                if let [v] = values.as_slice() {
+                    match self.function_return {
+                        // A variable's own value leaves with it; others are copied
+                        Some(Type::BigNumber) if matches!(v, Expr::Identifier(name) if self.owns_big(name)) => {
+                            self.generate_big_value(v)
+                        }
+                        Some(Type::BigNumber) => self.generate_owned_big(v),
+                        Some(Type::Decimal(places)) => self.generate_decimal_value(v, places),
+                        _ => self.generate_expr(v), // should leave return value in RAX
+                    }
+                } else if values.len() > 1 {
+                    // Several values leave in rax, rdx, rcx, ... (see funcs.asm)
+                    for v in values {
+                        self.generate_expr(v);
+                        self.emit_indent("push rax");
+                    }
+                    self.emit_release_owned_bigs(values);
+                    self.emit_indent(&format!("FUNC_RETURN_VALUES {}", values.len()));
+                    return;
                 }
+                self.emit_release_owned_bigs(values);
                 self.emit_indent("FUNC_EPILOGUE");
             }
             
-            Statement::FunctionCall { name, args } => {
+            Statement::Destructure { names, value } => {
+                self.generate_expr(value);
+                let returned = match value {
+                    Expr::FunctionCall { name, .. } => self.function_returns.get(name).cloned(),
+                    _ => None,
+                };
+                let types = match returned {
+                    Some(Type::Tuple(types)) => types,
+                    _ => Vec::new(),
+                };
+                for (i, name) in names.iter().enumerate().take(RETURN_REGS.len()) {
+                    let offset = self.declare_slot(name, false);
+                    self.emit_indent(&format!("mov [rbp-{}], {}  ; {}", offset, RETURN_REGS[i], name));
+                    let value_type = types.get(i).cloned().unwrap_or(Type::Unknown);
+                    if value_type != Type::Unknown {
+                        self.variable_types.insert(name.clone(), Self::var_type_for(&value_type));
+                    }
+                    if let Type::List(element) = value_type {
+                        if *element != Type::Unknown {
+                            self.list_element_types.insert(name.clone(), *element);
+                        }
+                    }
+                }
+            }
+            
+            Statement::FunctionCall { name, args, named } => {
                 // Mark that we're using functions so funcs.asm gets included
                 self.uses_funcs = true;
                 
+                let args = self.call_arguments(name, args, named);
                 for (i, arg) in args.iter().enumerate() {
-                    self.generate_expr(arg);
+                    self.generate_argument(name, i, arg);
                     let reg = match i {
                         0 => "rdi",
                         1 => "rsi",
@@ -905,7 +2269,7 @@ impl CodeGenerator {
                 self.emit_indent(&format!("call {}", func_label));
             }
                         
-            Statement::FunctionDef { name, params, body, .. } => {
+            Statement::FunctionDef { name, params, body, return_type, .. } => {
                 // Mark that we're using functions so funcs.asm gets included
                 self.uses_funcs = true;
                 
@@ -919,8 +2283,12 @@ impl CodeGenerator {
                 // Save outer codegen state
                 let saved_output = std::mem::take(&mut self.output);
                 let saved_vars = std::mem::take(&mut self.variables);
+                let saved_big_slots = std::mem::take(&mut self.big_slots);
                 let saved_stack = self.stack_offset;
                 let saved_loop_stack = std::mem::take(&mut self.loop_stack);
+                let saved_loop_names = std::mem::take(&mut self.loop_names);
+                let saved_example_done = self.example_done.take();
+                let saved_return = self.function_return.replace(return_type.clone());
 
                 // Fresh function-local state
                 self.output = String::new();
@@ -935,18 +2303,18 @@ impl CodeGenerator {
 
                 // Allocate param stack slots FIRST so offsets are stable.
                 // Also register param types so they're known in function body.
+                // A big number argument arrives as a value of the function's own
                 for (param_name, param_type) in params.iter() {
-                    self.alloc_var(param_name);
-                    let var_type = match param_type {
-                        Type::Integer => VarType::Integer,
-                        Type::Float => VarType::Float,
-                        Type::String => VarType::String,
-                        Type::Boolean => VarType::Boolean,
-                        Type::List(_) => VarType::List,
-                        Type::Buffer => VarType::Buffer,
-                        _ => VarType::Unknown,
-                    };
-                    self.variable_types.insert(param_name.clone(), var_type);
+                    let offset = self.alloc_var(param_name);
+                    if *param_type == Type::BigNumber {
+                        self.big_slots.insert(offset);
+                    }
+                    self.variable_types.insert(param_name.clone(), Self::var_type_for(param_type));
+                    if let Type::List(element) = param_type {
+                        if **element != Type::Unknown {
+                            self.list_element_types.insert(param_name.clone(), (**element).clone());
+                        }
+                    }
                 }
 
                 // Generate body into a temp buffer (this will call alloc_var for locals too)
@@ -964,6 +2332,7 @@ impl CodeGenerator {
 
                 // If no explicit return, add a default epilogue
                 if !has_return {
+                    self.emit_release_owned_bigs(&[]);
                     self.emit_indent("FUNC_EPILOGUE");
                 }
 
@@ -998,6 +2367,8 @@ impl CodeGenerator {
                         }
                     }
                 }
+                let param_slots: Vec<i64> = params.iter().filter_map(|(name, _)| self.get_var(name)).collect();
+                self.emit_zero_big_slots(&param_slots);
 
                 // Append the already-generated body
                 self.output.push_str(&body_code);
@@ -1009,8 +2380,12 @@ impl CodeGenerator {
                 // Restore outer codegen state
                 self.output = saved_output;
                 self.variables = saved_vars;
+                self.big_slots = saved_big_slots;
                 self.stack_offset = saved_stack;
                 self.loop_stack = saved_loop_stack;
+                self.loop_names = saved_loop_names;
+                self.example_done = saved_example_done;
+                self.function_return = saved_return;
 
                 // Append to functions section
                 self.functions_section.push_str(&format!("; Function: {}\n", name));
@@ -1018,6 +2393,45 @@ impl CodeGenerator {
             }
 
             
+            Statement::ForEachEntry { key, value, map, body } => {
+                self.use_maps();
+                let start_label = self.new_label("foreach_entry_start");
+                let continue_label = self.new_label("foreach_entry_continue");
+                let end_label = self.new_label("foreach_entry_end");
+                
+                // Slot index to resume the scan from
+                let index_var = self.alloc_var(&format!("{}_idx", key));
+                self.emit_indent(&format!("mov qword [rbp-{}], 0", index_var));
+                let key_var = self.alloc_var(key);
+                self.variable_types.insert(key.clone(), VarType::String);
+                let value_var = self.alloc_var(value);
+                let value_type = self.map_value_types.get(map).cloned().unwrap_or(VarType::Integer);
+                self.variable_types.insert(value.clone(), value_type);
+                
+                self.emit(&format!("{}:", start_label));
+                if let Some(offset) = self.get_var(map) {
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; map handle", offset));
+                }
+                self.emit_indent(&format!("mov rsi, [rbp-{}]  ; slot index", index_var));
+                self.emit_indent("call _map_next");
+                self.emit_indent("cmp rax, -1");
+                self.emit_indent(&format!("je {}", end_label));
+                self.emit_indent("inc rax");
+                self.emit_indent(&format!("mov [rbp-{}], rax  ; resume after this slot", index_var));
+                self.emit_indent(&format!("mov [rbp-{}], rdx  ; store in {}", key_var, key));
+                self.emit_indent(&format!("mov [rbp-{}], rcx  ; store in {}", value_var, value));
+                
+                self.loop_stack.push((continue_label.clone(), end_label.clone()));
+                for s in body {
+                    self.generate_statement(s);
+                }
+                self.loop_stack.pop();
+                
+                self.emit(&format!("{}:", continue_label));
+                self.emit_indent(&format!("jmp {}", start_label));
+                self.emit(&format!("{}:", end_label));
+            }
+            
             Statement::ForEach { variable, collection, body } => {
                 let start_label = self.new_label("foreach_start");
                 let continue_label = self.new_label("foreach_continue");
@@ -1075,24 +2489,121 @@ impl CodeGenerator {
                     return;
                 }
                 
-                // Determine element type from list
-                let elem_type = if let Expr::Identifier(list_name) = collection {
-                    // Get element type from list_element_types, not variable_types
-                    self.list_element_types.get(list_name).cloned().unwrap_or(VarType::Unknown)
-                } else if let Expr::ListLit { elements } = collection {
-                    if let Some(first) = elements.first() {
-                        match first {
-                            Expr::StringLit(_) => VarType::String,
-                            Expr::IntegerLit(_) => VarType::Integer,
-                            Expr::BoolLit(_) => VarType::Boolean,
-                            _ => VarType::Unknown,
-                        }
-                    } else {
-                        VarType::Unknown
+                // Lines of a file or standard stream, read one at a time
+                if let Expr::FileLines { source } = collection {
+                    self.uses_files = true;
+                    self.uses_buffers = true;
+                    let source_fd = self.file_fd(source, "0");
+                    
+                    // The line lives in its own growing buffer, reused every iteration
+                    self.emit_indent("call _alloc_buffer");
+                    let line_var = self.alloc_var(variable);
+                    self.emit_indent(&format!("mov [rbp-{}], rax  ; line buffer", line_var));
+                    self.variables.insert(variable.clone(), line_var);
+                    self.variable_types.insert(variable.clone(), VarType::Buffer);
+                    
+                    self.emit(&format!("{}:", start_label));
+                    self.emit_indent(&format!("mov rdi, {}", source_fd));
+                    self.emit_indent("test rdi, rdi");
+                    self.emit_indent(&format!("js {}  ; no lines from an invalid fd", end_label));
+                    self.emit_indent(&format!("mov rsi, [rbp-{}]", line_var));
+                    self.emit_indent("mov qword [rsi + 8], 0  ; reset buffer length");
+                    self.emit_indent("call _read_line_into_buffer");
+                    self.emit_indent(&format!("mov [rbp-{}], rsi  ; updated buffer ptr", line_var));
+                    // An empty line still reads its newline; only the end of input stops
+                    self.emit_indent("cmp qword [rel _line_at_end], 0");
+                    self.emit_indent(&format!("jne {}", end_label));
+                    
+                    // Drop the newline, which the last line may not have
+                    let line_ready = self.new_label("line_ready");
+                    self.emit_indent("mov rax, [rsi + 8]  ; line length");
+                    self.emit_indent("test rax, rax");
+                    self.emit_indent(&format!("jz {}", line_ready));
+                    self.emit_indent("cmp byte [rsi + 24 + rax - 1], 10");
+                    self.emit_indent(&format!("jne {}", line_ready));
+                    self.emit_indent("dec rax");
+                    self.emit_indent("mov [rsi + 8], rax");
+                    self.emit_indent("mov byte [rsi + 24 + rax], 0");
+                    self.emit(&format!("{}:", line_ready));
+                    
+                    self.loop_stack.push((continue_label.clone(), end_label.clone()));
+                    for s in body {
+                        self.generate_statement(s);
                     }
-                } else {
-                    VarType::Unknown
+                    self.loop_stack.pop();
+                    
+                    self.emit(&format!("{}:", continue_label));
+                    self.emit_indent(&format!("jmp {}", start_label));
+                    self.emit(&format!("{}:", end_label));
+                    return;
+                }
+                
+                // Characters of a text or buffer as one-character texts, or
+                // with "the bytes of" their byte values; a list is walked as usual
+                let (collection, bytes) = match collection {
+                    Expr::TextBytes { text } => (&**text, true),
+                    other => (other, false),
                 };
+                let over_buffer = self.is_buffer_var(collection);
+                if over_buffer || self.infer_expr_type(collection) == Some(VarType::String) {
+                    self.uses_strings = true;
+                    let text_var = self.alloc_var(&format!("{}_text", variable));
+                    let len_var = self.alloc_var(&format!("{}_len", variable));
+                    if !over_buffer {
+                        self.generate_text(collection);
+                        self.emit_indent(&format!("mov [rbp-{}], rax  ; text", text_var));
+                        self.emit_indent("mov rdi, rax");
+                        self.emit_indent("call _str_length");
+                        self.emit_indent(&format!("mov [rbp-{}], rax  ; text length", len_var));
+                    }
+                    
+                    let index_var = self.alloc_var(&format!("{}_idx", variable));
+                    self.emit_indent(&format!("mov qword [rbp-{}], 0  ; index", index_var));
+                    
+                    let elem_var = self.alloc_var(variable);
+                    self.variables.insert(variable.clone(), elem_var);
+                    let elem_type = if bytes { VarType::Integer } else { VarType::String };
+                    self.variable_types.insert(variable.clone(), elem_type);
+                    
+                    self.emit(&format!("{}:", start_label));
+                    if over_buffer {
+                        // The body may grow the buffer, so its data and length are read every time
+                        self.generate_expr(collection);
+                        self.emit_indent("mov rdi, rax");
+                        self.emit_indent("mov rax, [rdi + 8]");
+                        self.emit_indent(&format!("mov [rbp-{}], rax  ; buffer length", len_var));
+                        self.emit_indent("call _buffer_data");
+                        self.emit_indent(&format!("mov [rbp-{}], rax  ; buffer data", text_var));
+                    }
+                    self.emit_indent(&format!("mov rax, [rbp-{}]  ; index", index_var));
+                    self.emit_indent(&format!("cmp rax, [rbp-{}]  ; compare with length", len_var));
+                    self.emit_indent(&format!("jge {}", end_label));
+                    
+                    self.emit_indent(&format!("mov rsi, [rbp-{}]", text_var));
+                    self.emit_indent("add rsi, rax");
+                    if bytes {
+                        self.emit_indent("movzx rax, byte [rsi]");
+                    } else {
+                        self.emit_indent("mov rdx, 1");
+                        self.emit_indent("call _str_new  ; one-character text");
+                    }
+                    self.emit_indent(&format!("mov [rbp-{}], rax  ; store in {}", elem_var, variable));
+                    
+                    self.loop_stack.push((continue_label.clone(), end_label.clone()));
+                    for s in body {
+                        self.generate_statement(s);
+                    }
+                    self.loop_stack.pop();
+                    
+                    self.emit(&format!("{}:", continue_label));
+                    self.emit_indent(&format!("inc qword [rbp-{}]", index_var));
+                    self.emit_indent(&format!("jmp {}", start_label));
+                    self.emit(&format!("{}:", end_label));
+                    return;
+                }
+                
+                // Determine element type from list
+                let elem_type = self.list_element_type(collection);
                 
                 // Get list pointer
                 // List structure: [capacity:8][length:8][elem_size:8][data...]
@@ -1112,7 +2623,11 @@ impl CodeGenerator {
                 // Allocate variable for current element and track its type
                 let elem_var = self.alloc_var(variable);
                 self.variables.insert(variable.clone(), elem_var);
-                self.variable_types.insert(variable.clone(), elem_type);
+                self.variable_types.insert(variable.clone(), Self::var_type_for(&elem_type));
+                if let Type::List(inner) = elem_type {
+                    // Rows of a list of lists are lists themselves
+                    self.list_element_types.insert(variable.clone(), *inner);
+                }
                 
                 self.emit(&format!("{}:", start_label));
                 
@@ -1207,7 +2722,20 @@ impl CodeGenerator {
                 self.emit_indent("imul rcx, rdx  ; index * element_size");
                 self.emit_indent("add rcx, 24  ; data starts at offset 24");
                 // Get value
-                self.generate_expr(value);
+                if let Type::Decimal(places) = self.list_element_type(&Expr::Identifier(list.clone())) {
+                    self.emit_indent("push rbx");
+                    self.emit_indent("push rcx");
+                    self.generate_decimal_value(value, places);
+                    self.emit_indent("pop rcx");
+                    self.emit_indent("pop rbx");
+                } else {
+                    self.generate_expr(value);
+                }
+                if self.is_big_expr(value) && !self.is_fresh_big(value) {
+                    self.emit_indent("push rcx");
+                    self.emit_unshared_big(value);
+                    self.emit_indent("pop rcx");
+                }
                 // Write element
                 self.emit_indent("mov [rbx + rcx], rax  ; write element");
             }
@@ -1215,69 +2743,141 @@ impl CodeGenerator {
             Statement::ListAppend { list, value } => {
                 self.uses_lists = true;
                 self.emit_indent("; Append value to list");
+                self.track_list_element_type(list, value);
+                let elem_type = self.list_element_type(&Expr::Identifier(list.clone()));
                 
-                // Track element type from appended value if not already set
-                if !self.list_element_types.contains_key(list) {
-                    let elem_type = match value {
-                        Expr::StringLit(s) => {
-                            // If this string literal is a buffer variable, the list gets strings
-                            if self.variable_types.get(s) == Some(&VarType::Buffer) {
-                                VarType::String
-                            } else {
-                                VarType::String
-                            }
-                        }
-                        Expr::IntegerLit(_) => VarType::Integer,
-                        Expr::FloatLit(_) => VarType::Float,
-                        Expr::BoolLit(_) => VarType::Boolean,
-                        Expr::Identifier(name) => {
-                            // Buffer variables produce string elements when appended
-                            match self.variable_types.get(name) {
-                                Some(VarType::Buffer) => VarType::String,
-                                Some(t) => t.clone(),
-                                None => VarType::Unknown,
-                            }
-                        }
-                        _ => VarType::Unknown,
-                    };
-                    if elem_type != VarType::Unknown {
-                        self.list_element_types.insert(list.clone(), elem_type);
-                    }
+                // Get list pointer
+                if let Some(offset) = self.get_var(list) {
+                    self.generate_list_element(value, &elem_type);
+                    self.emit_indent("push rax  ; save value to append");
+                    
+                    // Get list pointer
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; list ptr", offset));
+                    
+                    // Call list_append helper (rdi = list ptr, rsi = value)
+                    self.emit_indent("pop rsi  ; value to append");
+                    self.emit_indent("call _list_append");
+                    
+                    // Store potentially new list pointer back
+                    self.emit_indent(&format!("mov [rbp-{}], rax  ; store new list ptr", offset));
                 }
+            }
+            
+            Statement::ListInsert { list, value, position } => {
+                self.uses_lists = true;
+                self.emit_indent("; Insert value into list");
+                self.track_list_element_type(list, value);
+                let elem_type = self.list_element_type(&Expr::Identifier(list.clone()));
                 
-                // Get list pointer
                 if let Some(offset) = self.get_var(list) {
-                    // Check if the value is a buffer variable
-                    let is_buffer_value = match value {
-                        Expr::StringLit(s) | Expr::Identifier(s) => {
-                            self.variable_types.get(s) == Some(&VarType::Buffer)
-                        }
-                        _ => false,
+                    self.generate_list_element(value, &elem_type);
+                    self.emit_indent("push rax  ; save value to insert");
+                    self.generate_expr(position);
+                    self.emit_indent("mov rsi, rax  ; position");
+                    self.emit_indent("pop rdx  ; value");
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; list ptr", offset));
+                    self.emit_indent("call _list_insert");
+                    self.emit_indent(&format!("mov [rbp-{}], rax  ; store new list ptr", offset));
+                }
+            }
+            
+            Statement::ListRemoveAt { list, position } => {
+                self.uses_lists = true;
+                if let Some(offset) = self.get_var(list) {
+                    self.generate_expr(position);
+                    self.emit_indent("mov rsi, rax  ; position");
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; list ptr", offset));
+                    self.emit_indent("call _list_remove_at");
+                }
+            }
+            
+            Statement::ListRemove { list, value } => {
+                self.uses_lists = true;
+                let elem_type = self.list_element_type(&Expr::Identifier(list.clone()));
+                if let Some(offset) = self.get_var(list) {
+                    self.generate_list_search_value(value, &elem_type);
+                    self.emit_indent("mov rsi, rax  ; value to remove");
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; list ptr", offset));
+                    self.emit_list_compare(&elem_type, "rdx");
+                    self.emit_indent("call _list_remove_value");
+                }
+            }
+            
+            Statement::ListSort { list, descending } => {
+                self.uses_lists = true;
+                let elem_type = self.list_element_type(&Expr::Identifier(list.clone()));
+                if let Some(offset) = self.get_var(list) {
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; list ptr", offset));
+                    self.emit_list_compare(&elem_type, "rsi");
+                    self.emit_indent(&format!("mov rdx, {}  ; descending", *descending as i32));
+                    self.emit_indent("call _list_sort");
+                }
+            }
+            
+            Statement::ListReverse { list } => {
+                self.uses_lists = true;
+                if let Some(offset) = self.get_var(list) {
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; list ptr", offset));
+                    self.emit_indent("call _list_reverse");
+                }
+            }
+            
+            Statement::ListShuffle { list } => {
+                self.uses_lists = true;
+                self.uses_random = true;
+                if let Some(offset) = self.get_var(list) {
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; list ptr", offset));
+                    self.emit_indent("call _list_shuffle");
+                }
+            }
+            
+            Statement::MapSet { map, key, value } => {
+                self.use_maps();
+                if !self.map_value_types.contains_key(map) {
+                    let value_type = if self.is_buffer_var(value) {
+                        Some(VarType::String)
+                    } else {
+                        self.infer_expr_type(value)
                     };
-                    
-                    // Evaluate value first and save it
+                    if let Some(value_type) = value_type.filter(|t| *t != VarType::Unknown) {
+                        self.map_value_types.insert(map.clone(), value_type);
+                    }
+                }
+                
+                if let Some(VarType::Decimal(places)) = self.map_value_types.get(map).cloned() {
+                    self.generate_decimal_value(value, places);
+                } else {
                     self.generate_expr(value);
-                    
-                    if is_buffer_value {
-                        // For buffer values, extract string data and duplicate it
-                        self.emit_indent("mov rdi, rax  ; buffer struct pointer");
-                        self.emit_indent("call _buffer_data  ; get data pointer");
-                        self.emit_indent("mov rdi, rax  ; source string");
-                        self.emit_indent("call _strdup  ; duplicate string");
-                    }
-                    
-                    self.emit_indent("push rax  ; save value to append");
-                    
-                    // Get list pointer
-                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; list ptr", offset));
-                    
-                    // Call list_append helper (rdi = list ptr, rsi = value)
-                    self.emit_indent("pop rsi  ; value to append");
-                    self.emit_indent("call _list_append");
-                    
-                    // Store potentially new list pointer back
-                    self.emit_indent(&format!("mov [rbp-{}], rax  ; store new list ptr", offset));
+                    self.emit_unshared_big(value);
+                }
+                let copies_text = self.is_buffer_var(value);
+                if copies_text {
+                    self.emit_indent("mov rdi, rax  ; buffer struct pointer");
+                    self.emit_indent("call _buffer_data  ; get data pointer");
                 }
+                self.emit_indent("push rax  ; save value");
+                self.generate_map_key(key);
+                self.emit_indent("mov rsi, rax  ; key");
+                self.emit_indent("pop rdx  ; value");
+                if let Some(offset) = self.get_var(map) {
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; map handle", offset));
+                }
+                if copies_text {
+                    // Buffers change later, so the map keeps its own copy of the text
+                    self.emit_indent("call _map_set_text");
+                } else {
+                    self.emit_indent("call _map_set");
+                }
+            }
+            
+            Statement::MapRemove { map, key } => {
+                self.use_maps();
+                self.generate_map_key(key);
+                self.emit_indent("mov rsi, rax  ; key");
+                if let Some(offset) = self.get_var(map) {
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; map handle", offset));
+                }
+                self.emit_indent("call _map_remove");
             }
             
             Statement::FileOpen { name, path, mode } => {
@@ -1339,14 +2939,8 @@ impl CodeGenerator {
             }
             
             Statement::FileRead { source, buffer } => {
-                // Get source fd
-                let source_fd = if source == "stdin" {
-                    "0".to_string()  // STDIN
-                } else if let Some(offset) = self.get_var(source) {
-                    format!("[rbp-{}]", offset)
-                } else {
-                    "0".to_string()
-                };
+                self.uses_files = true;
+                let source_fd = self.file_fd(source, "0");
                 
                 // Use dynamic read that auto-grows buffer (only if fd is valid)
                 if let Some(buf_offset) = self.get_var(buffer) {
@@ -1366,14 +2960,8 @@ impl CodeGenerator {
             }
 
             Statement::FileReadLine { source, buffer } => {
-                // Get source fd
-                let source_fd = if source == "stdin" {
-                    "0".to_string()  // STDIN
-                } else if let Some(offset) = self.get_var(source) {
-                    format!("[rbp-{}]", offset)
-                } else {
-                    "0".to_string()
-                };
+                self.uses_files = true;
+                let source_fd = self.file_fd(source, "0");
 
                 if let Some(buf_offset) = self.get_var(buffer) {
                     let skip_label = self.new_label("skip_fd");
@@ -1394,11 +2982,7 @@ impl CodeGenerator {
             Statement::FileSeekLine { file, line } => {
                 self.uses_files = true;
 
-                let file_fd = if let Some(offset) = self.get_var(file) {
-                    format!("[rbp-{}]", offset)
-                } else {
-                    "0".to_string()
-                };
+                let file_fd = self.file_fd(file, "0");
 
                 self.generate_expr(line);
                 self.emit_indent("mov rsi, rax  ; target line (1-indexed)");
@@ -1409,11 +2993,7 @@ impl CodeGenerator {
             Statement::FileSeekByte { file, byte } => {
                 self.uses_files = true;
 
-                let file_fd = if let Some(offset) = self.get_var(file) {
-                    format!("[rbp-{}]", offset)
-                } else {
-                    "0".to_string()
-                };
+                let file_fd = self.file_fd(file, "0");
 
                 self.generate_expr(byte);
                 self.emit_indent("mov rsi, rax  ; target byte (1-indexed)");
@@ -1422,12 +3002,8 @@ impl CodeGenerator {
             }
             
             Statement::FileWrite { file, value } => {
-                // Get file fd
-                let file_fd = if let Some(offset) = self.get_var(file) {
-                    format!("[rbp-{}]", offset)
-                } else {
-                    "1".to_string()  // STDOUT as fallback
-                };
+                self.uses_files = true;
+                let file_fd = self.file_fd(file, "1");
                 
                 let skip_label = self.new_label("skip_fd");
                 self.emit_indent(&format!("mov rdi, {}", file_fd));
@@ -1514,11 +3090,8 @@ impl CodeGenerator {
             }
             
             Statement::FileWriteNewline { file } => {
-                let file_fd = if let Some(offset) = self.get_var(file) {
-                    format!("[rbp-{}]", offset)
-                } else {
-                    "1".to_string()
-                };
+                self.uses_files = true;
+                let file_fd = self.file_fd(file, "1");
                 let skip_label = self.new_label("skip_fd");
                 self.emit_indent(&format!("mov rdi, {}", file_fd));
                 // Skip write if fd is invalid (negative)
@@ -1555,12 +3128,21 @@ impl CodeGenerator {
                 }
             }
             
-            Statement::OnError { actions } => {
+            Statement::OnError { error, actions } => {
                 // Check if last operation had an error
                 let skip_label = self.new_label("skip_error");
                 self.emit_indent("mov rax, [rel _last_error]");
-                self.emit_indent("test rax, rax");
-                self.emit_indent(&format!("jz {}  ; skip if no error", skip_label));
+                match error {
+                    // A kind-specific handler leaves other errors set for later handlers
+                    Some(kind) => {
+                        self.emit_indent(&format!("cmp rax, {}  ; {}", kind.code(), kind.name()));
+                        self.emit_indent(&format!("jne {}  ; skip unless this kind", skip_label));
+                    }
+                    None => {
+                        self.emit_indent("test rax, rax");
+                        self.emit_indent(&format!("jz {}  ; skip if no error", skip_label));
+                    }
+                }
                 
                 // Execute all error actions
                 for action in actions {
@@ -1573,6 +3155,38 @@ impl CodeGenerator {
                 self.emit(&format!("{}:", skip_label));
             }
             
+            Statement::ErrorCatching { enabled } => {
+                if *enabled {
+                    // Errors left over from before catching was enabled are dropped
+                    self.emit_indent("mov qword [rel _last_error], 0  ; enable error catching");
+                } else {
+                    self.emit_indent("; disable error catching");
+                }
+            }
+            
+            Statement::ErrorCheck { line, source } => {
+                // Unhandled error: report it with its source line and exit 1
+                self.uses_errors = true;
+                let ok_label = self.new_label("no_error");
+                self.emit_indent("cmp qword [rel _last_error], 0");
+                self.emit_indent(&format!("je {}", ok_label));
+                let location = match source {
+                    Some(text) => format!("  --> line {}: {}", line, text),
+                    None => format!("  --> line {}", line),
+                };
+                let label = self.add_string(&location);
+                self.emit_indent(&format!("lea rdi, [rel {}]", label));
+                self.emit_indent("call _print_error_at");
+                if self.uses_files || self.uses_buffers {
+                    self.emit_indent("call _cleanup_all");
+                }
+                if self.uses_maps {
+                    self.emit_indent("call _cleanup_maps");
+                }
+                self.emit_indent("EXIT 1");
+                self.emit(&format!("{}:", ok_label));
+            }
+            
             Statement::BufferResize { name, new_size } => {
                 if let Some(offset) = self.get_var(name) {
                     self.generate_expr(new_size);
@@ -1644,6 +3258,31 @@ impl CodeGenerator {
                 }
             }
             
+            Statement::RandomBytes { buffer, count } => {
+                self.uses_buffers = true;
+                self.uses_random = true;
+                self.emit_indent(&format!("; Get random bytes into: {}", buffer));
+                match count {
+                    Some(count) => {
+                        self.generate_expr(count);
+                        self.emit_indent("mov rsi, rax  ; byte count");
+                    }
+                    None => self.emit_indent("mov rsi, -1  ; fill to capacity"),
+                }
+                if let Some(offset) = self.get_var(buffer) {
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; buffer struct pointer", offset));
+                    self.emit_indent("call _random_bytes");
+                }
+            }
+            
+            Statement::SeedRandom { seed } => {
+                self.uses_random = true;
+                self.emit_indent("; Seed the random generator");
+                self.generate_expr(seed);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("call _random_seed");
+            }
+            
             Statement::GetTime { into } => {
                 self.uses_time = true;
                 // Get current unix time and store in variable
@@ -1729,6 +3368,22 @@ impl CodeGenerator {
     }
     
     fn emit_formatted_value(&mut self, value_type: Option<VarType>, fmt: FormatSpec) {
+        if value_type == Some(VarType::BigNumber) {
+            self.emit_print_big();
+            return;
+        }
+        if let Some(VarType::Decimal(places)) = value_type {
+            // {x:.2} shows the decimal rounded to that many places
+            let shown = fmt.precision.map_or(places, |precision| (precision.max(0) as u32).min(DECIMAL_MAX_PLACES));
+            if shown != places {
+                self.emit_indent("mov rax, rdi");
+                self.emit_decimal_rescale(places, shown, "DEC_ROUND_NEAREST");
+                self.emit_indent("mov rdi, rax");
+            }
+            self.emit_print_decimal(shown);
+            return;
+        }
+        
         // Handle precision format for floats
         if let Some(precision) = fmt.precision {
             self.emit_indent("movq xmm0, rdi");
@@ -1750,6 +3405,7 @@ impl CodeGenerator {
                 Some(VarType::String) | Some(VarType::Buffer) => {
                     self.emit_indent("PRINT_CSTR rdi");
                 }
+                Some(VarType::Tagged) => self.emit_print_tagged(),
                 _ => {
                     self.emit_indent("PRINT_INT rdi");
                 }
@@ -1848,6 +3504,132 @@ impl CodeGenerator {
         }
     }
     
+    /// Sorted, non-overlapping `(low, high, arm)` ranges when every `when`
+    /// value is a number literal. Where values repeat, the earlier arm keeps them.
+    fn integer_cases(arms: &[(Vec<MatchPattern>, Vec<Statement>)]) -> Option<Vec<(i64, i64, usize)>> {
+        let mut cases: Vec<(i64, i64, usize)> = Vec::new();
+        for (arm, (patterns, _)) in arms.iter().enumerate() {
+            for pattern in patterns {
+                let (start, end) = pattern.literal_numbers()?;
+                let mut pieces = vec![(start as i128, end as i128)];
+                for &(low, high, _) in &cases {
+                    let (low, high) = (low as i128, high as i128);
+                    pieces = pieces
+                        .into_iter()
+                        .flat_map(|(a, b)| {
+                            if high < a || low > b {
+                                vec![(a, b)]
+                            } else {
+                                let mut rest = Vec::new();
+                                if a < low {
+                                    rest.push((a, low - 1));
+                                }
+                                if high < b {
+                                    rest.push((high + 1, b));
+                                }
+                                rest
+                            }
+                        })
+                        .collect();
+                }
+                cases.extend(pieces.into_iter().filter(|(a, b)| a <= b).map(|(a, b)| (a as i64, b as i64, arm)));
+            }
+        }
+        cases.sort();
+        Some(cases)
+    }
+    
+    // cmp against a 64-bit constant; cmp only takes 32-bit immediates
+    fn emit_cmp_rax(&mut self, value: i64) {
+        if i32::try_from(value).is_ok() {
+            self.emit_indent(&format!("cmp rax, {}", value));
+        } else {
+            self.emit_indent(&format!("mov rcx, {}", value));
+            self.emit_indent("cmp rax, rcx");
+        }
+    }
+    
+    /// Jumps from the number in rax to its arm: through a jump table when the
+    /// values are dense, otherwise by binary search over the ranges
+    fn emit_integer_dispatch(&mut self, cases: &[(i64, i64, usize)], arm_labels: &[String], otherwise: &str) {
+        const MIN_TABLE_CASES: usize = 4;
+        const MAX_TABLE_SPAN: i128 = 1024;
+        
+        let (Some(first), Some(last)) = (cases.first(), cases.last()) else {
+            self.emit_indent(&format!("jmp {}", otherwise));
+            return;
+        };
+        let (min, max) = (first.0, last.1);
+        let span = max as i128 - min as i128 + 1;
+        let covered: i128 = cases.iter().map(|&(low, high, _)| high as i128 - low as i128 + 1).sum();
+        
+        if cases.len() < MIN_TABLE_CASES || span > MAX_TABLE_SPAN || covered * 2 < span {
+            self.emit_case_search(cases, arm_labels, otherwise);
+            return;
+        }
+        
+        let table_label = self.new_label("depending_table");
+        if min != 0 {
+            if i32::try_from(min).is_ok() {
+                self.emit_indent(&format!("sub rax, {}  ; index from the smallest value", min));
+            } else {
+                self.emit_indent(&format!("mov rcx, {}", min));
+                self.emit_indent("sub rax, rcx  ; index from the smallest value");
+            }
+        }
+        // Unsigned compare also sends values below the smallest to otherwise
+        self.emit_indent(&format!("cmp rax, {}", span - 1));
+        self.emit_indent(&format!("ja {}", otherwise));
+        // Entries are offsets from the table, so the code stays position independent
+        self.emit_indent(&format!("lea rcx, [rel {}]", table_label));
+        self.emit_indent("movsxd rax, dword [rcx + rax*4]");
+        self.emit_indent("add rax, rcx");
+        self.emit_indent("jmp rax");
+        self.emit_indent("align 4");
+        self.emit(&format!("{}:", table_label));
+        let targets: Vec<String> = (0..span)
+            .map(|offset| {
+                let value = (min as i128 + offset) as i64;
+                cases
+                    .iter()
+                    .find(|&&(low, high, _)| low <= value && value <= high)
+                    .map_or(otherwise, |&(_, _, arm)| arm_labels[arm].as_str())
+            })
+            .map(|target| format!("{} - {}", target, table_label))
+            .collect();
+        for row in targets.chunks(4) {
+            self.emit_indent(&format!("dd {}", row.join(", ")));
+        }
+    }
+    
+    fn emit_case_search(&mut self, cases: &[(i64, i64, usize)], arm_labels: &[String], otherwise: &str) {
+        if cases.len() <= 3 {
+            for &(low, high, arm) in cases {
+                if low == high {
+                    self.emit_cmp_rax(low);
+                    self.emit_indent(&format!("je {}", arm_labels[arm]));
+                } else {
+                    let next_label = self.new_label("depending_next");
+                    self.emit_cmp_rax(low);
+                    self.emit_indent(&format!("jl {}", next_label));
+                    self.emit_cmp_rax(high);
+                    self.emit_indent(&format!("jle {}", arm_labels[arm]));
+                    self.emit(&format!("{}:", next_label));
+                }
+            }
+            self.emit_indent(&format!("jmp {}", otherwise));
+            return;
+        }
+        
+        let mid = cases.len() / 2;
+        let upper_label = self.new_label("depending_upper");
+        self.emit_cmp_rax(cases[mid].0);
+        self.emit_indent(&format!("jge {}", upper_label));
+        self.emit_case_search(&cases[..mid], arm_labels, otherwise);
+        self.emit(&format!("{}:", upper_label));
+        self.emit_case_search(&cases[mid..], arm_labels, otherwise);
+    }
+    
     fn generate_print(&mut self, value: &Expr, without_newline: bool) {
         self.uses_io = true;
         match value {
@@ -1956,6 +3738,9 @@ impl CodeGenerator {
                             self.emit_indent("PRINT_FLOAT");
                             self.uses_floats = true;
                         }
+                        Some(VarType::Tagged) => self.emit_print_tagged(),
+                        Some(VarType::BigNumber) => self.emit_print_big(),
+                        Some(VarType::Decimal(places)) => self.emit_print_decimal(places),
                         _ => {
                             self.emit_indent("PRINT_INT rdi");
                         }
@@ -1980,7 +3765,7 @@ impl CodeGenerator {
                 self.uses_floats = true;
             }
             
-            Expr::Identifier(name) => {
+            Expr::Identifier(name) if self.builtin_constant(name).is_none() => {
                 if let Some(offset) = self.get_var(name) {
                     self.emit_indent(&format!("mov rdi, [rbp-{}]", offset));
                     let var_type = self.variable_types.get(name).cloned();
@@ -2000,6 +3785,9 @@ impl CodeGenerator {
                             self.emit_indent("PRINT_FLOAT");
                             self.uses_floats = true;
                         }
+                        Some(VarType::Tagged) => self.emit_print_tagged(),
+                        Some(VarType::BigNumber) => self.emit_print_big(),
+                        Some(VarType::Decimal(places)) => self.emit_print_decimal(places),
                         _ => {
                             self.emit_indent("PRINT_INT rdi");
                         }
@@ -2007,85 +3795,403 @@ impl CodeGenerator {
                 } else if name == "_iter" {
                     self.emit_indent("mov rdi, rax");
                     self.emit_indent("PRINT_INT rdi");
+                } else if self.constants.contains_key(name) {
+                    self.emit_global_constant_format_fallback(name, None);
+                }
+            }
+            
+            Expr::LastErrorMessage => {
+                self.generate_expr(value);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("PRINT_CSTR rdi");
+            }
+            
+            Expr::PropertyAccess { object, property: ObjectProperty::Field(field) }
+                if matches!(self.struct_field(object, field), Some((_, Type::String))) =>
+            {
+                self.generate_expr(value);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("PRINT_CSTR rdi");
+            }
+            
+            Expr::MapGet { map, .. } if self.map_value_types.get(map) == Some(&VarType::String) => {
+                self.generate_expr(value);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("PRINT_CSTR rdi");
+            }
+            
+            Expr::ElementAccess { list, .. } | Expr::RandomElement { list } => {
+                // Get the list's element type for proper printing
+                let elem_type = self.list_element_type(list);
+                
+                self.generate_expr(value);
+                self.emit_indent("mov rdi, rax");
+                
+                match elem_type {
+                    Type::String => {
+                        self.emit_indent("PRINT_CSTR rdi");
+                    }
+                    Type::Float => {
+                        self.emit_indent("movq xmm0, rdi");
+                        self.emit_indent("PRINT_FLOAT");
+                        self.uses_floats = true;
+                    }
+                    Type::Any => self.emit_print_tagged(),
+                    Type::BigNumber => self.emit_print_big(),
+                    Type::Decimal(places) => self.emit_print_decimal(places),
+                    _ => {
+                        self.emit_indent("PRINT_INT rdi");
+                    }
+                }
+            }
+            
+            _ if self.infer_expr_type(value) == Some(VarType::String) => {
+                self.generate_expr(value);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("PRINT_CSTR rdi");
+            }
+            
+            _ if self.is_big_expr(value) => {
+                self.generate_expr(value);
+                self.emit_big_routine("_big_to_text", value);
+                self.emit_indent("PRINT_CSTR rax");
+            }
+            
+            _ if self.decimal_places(value).is_some() => {
+                let places = self.decimal_places(value).unwrap_or(0);
+                self.generate_expr(value);
+                self.emit_indent("mov rdi, rax");
+                self.emit_print_decimal(places);
+            }
+            
+            _ if self.infer_expr_type(value) == Some(VarType::Tagged) => {
+                self.generate_expr(value);
+                self.emit_indent("mov rdi, rax");
+                self.emit_print_tagged();
+            }
+            
+            _ => {
+                let is_float = self.is_float_expr(value);
+                self.generate_expr(value);
+                if is_float {
+                    self.emit_indent("movq xmm0, rax");
+                    self.emit_indent("PRINT_FLOAT");
+                    self.uses_floats = true;
+                } else {
+                    self.emit_indent("mov rdi, rax");
+                    self.emit_indent("PRINT_INT rdi");
+                }
+            }
+        }
+        if !without_newline {
+            self.emit_indent("PRINT_NEWLINE");
+        }
+    }
+    
+    fn generate_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::IntegerLit(n) => {
+                self.emit_indent(&format!("mov rax, {}", n));
+            }
+            
+            Expr::BigLit(digits) => {
+                self.use_big_numbers();
+                let label = self.add_constant_big(digits);
+                self.emit_indent(&format!("lea rax, [{}]", label));
+            }
+            
+            Expr::FloatLit(n) => {
+                self.uses_floats = true;
+                // Store float as 64-bit IEEE 754 in data section
+                let label = self.add_float(*n);
+                self.emit_indent(&format!("FLOAT_LOAD {}", label));
+                // Store float bits in rax for stack operations
+                self.emit_indent("XMM0_TO_RAX");
+            }
+            
+            Expr::BoolLit(b) => {
+                self.emit_indent(&format!("mov rax, {}", if *b { 1 } else { 0 }));
+            }
+            
+            Expr::StringLit(name) | Expr::Identifier(name) if self.constants.contains_key(name) => {
+                let value = self.constants[name].clone();
+                self.generate_constant(&value);
+            }
+            
+            Expr::StringLit(s) => {
+                // Check if this string literal is actually a variable reference
+                if let Some(offset) = self.get_var(s) {
+                    self.emit_indent(&format!("mov rax, [rbp-{}]", offset));
+                } else {
+                    let label = self.add_string(s);
+                    self.emit_indent(&format!("lea rax, [{}]", label));
+                }
+            }
+            
+            Expr::Identifier(name) => {
+                if let Some(offset) = self.get_var(name) {
+                    self.emit_indent(&format!("mov rax, [rbp-{}]", offset));
+                } else if let Some(value) = self.builtin_constant(name) {
+                    self.generate_constant(&Expr::FloatLit(value));
+                }
+            }
+            
+            Expr::MapGet { map, key } | Expr::MapHas { map, key } => {
+                self.use_maps();
+                self.generate_map_key(key);
+                self.emit_indent("mov rsi, rax  ; key");
+                if let Some(offset) = self.get_var(map) {
+                    self.emit_indent(&format!("mov rdi, [rbp-{}]  ; map handle", offset));
+                }
+                if matches!(expr, Expr::MapGet { .. }) {
+                    self.emit_indent("call _map_get");
+                } else {
+                    self.emit_indent("call _map_has");
+                }
+            }
+            
+            Expr::Quantifier { kind, variable, collection, condition } => {
+                // Loop over the elements and break at the first one that decides
+                // the answer: a match for any/none, a mismatch for all
+                let result_name = format!("_quantifier_{}", self.label_counter);
+                let result = self.alloc_var(&result_name);
+                self.variable_types.insert(result_name.clone(), VarType::Boolean);
+                let decided = *kind == QuantifierKind::Any;
+                self.emit_indent(&format!("mov qword [rbp-{}], {}  ; answer if nothing decides", result, u8::from(!decided)));
+                
+                let decides = if *kind == QuantifierKind::All {
+                    Expr::UnaryOp { op: UnaryOperator::Not, operand: condition.clone() }
+                } else {
+                    (**condition).clone()
+                };
+                let body = vec![Statement::If {
+                    condition: decides,
+                    then_block: vec![
+                        Statement::Assignment { name: result_name, value: Expr::BoolLit(decided) },
+                        Statement::Break { loop_name: None },
+                    ],
+                    else_if_blocks: Vec::new(),
+                    else_block: None,
+                }];
+                let loop_stmt = match collection.as_ref() {
+                    Expr::Range { .. } => Statement::ForRange {
+                        variable: variable.clone(),
+                        range: (**collection).clone(),
+                        body,
+                    },
+                    _ => Statement::ForEach {
+                        variable: variable.clone(),
+                        collection: (**collection).clone(),
+                        body,
+                    },
+                };
+                
+                // The element only exists inside the quantifier
+                let outer_slot = self.variables.get(variable).copied();
+                let outer_type = self.variable_types.get(variable).cloned();
+                self.generate_statement(&loop_stmt);
+                match outer_slot {
+                    Some(slot) => self.variables.insert(variable.clone(), slot),
+                    None => self.variables.remove(variable),
+                };
+                match outer_type {
+                    Some(var_type) => self.variable_types.insert(variable.clone(), var_type),
+                    None => self.variable_types.remove(variable),
+                };
+                
+                self.emit_indent(&format!("mov rax, [rbp-{}]", result));
+            }
+            
+            Expr::TextSlice { text, start, end } => {
+                self.uses_strings = true;
+                if let Some(end) = end {
+                    self.generate_expr(end);
+                    self.emit_indent("push rax");
+                    self.generate_expr(start);
+                } else {
+                    // A single character ends where it starts
+                    self.generate_expr(start);
+                    self.emit_indent("push rax");
                 }
+                self.emit_indent("push rax");
+                self.generate_text(text);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("pop rsi  ; first character");
+                self.emit_indent("pop rdx  ; last character");
+                self.emit_indent("call _str_slice");
+            }
+            
+            Expr::TextFind { part, text } if self.infer_expr_type(text) == Some(VarType::List) => {
+                self.generate_list_find(text, part);
+            }
+            
+            Expr::TextFind { part, text } => self.generate_text_call("_str_find", text, part),
+            
+            Expr::TextCheck { text, check: TextCheckKind::Contains, part }
+                if self.infer_expr_type(text) == Some(VarType::List) =>
+            {
+                self.generate_list_find(text, part);
+                self.emit_indent("test rax, rax");
+                self.emit_indent("setnz al");
+                self.emit_indent("movzx rax, al");
+            }
+            
+            Expr::TextCheck { text, check, part } => {
+                let routine = match check {
+                    TextCheckKind::StartsWith => "_str_starts_with",
+                    TextCheckKind::EndsWith => "_str_ends_with",
+                    TextCheckKind::Contains => "_str_contains",
+                };
+                self.generate_text_call(routine, text, part);
+            }
+            
+            Expr::TextSplit { text, separator } => self.generate_text_call("_str_split", text, separator),
+            
+            Expr::TextJoin { list, separator } => {
+                self.uses_strings = true;
+                self.generate_text(separator);
+                self.emit_indent("push rax");
+                self.generate_expr(list);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("pop rsi");
+                self.emit_indent("call _str_join");
             }
             
-            Expr::ElementAccess { list, .. } => {
-                // Get the list's element type for proper printing
-                let elem_type = if let Expr::Identifier(name) = list.as_ref() {
-                    self.list_element_types.get(name).cloned()
-                } else {
-                    None
-                };
-                
-                self.generate_expr(value);
+            Expr::TextCase { text, upper } => {
+                self.uses_strings = true;
+                self.generate_text(text);
                 self.emit_indent("mov rdi, rax");
-                
-                match elem_type {
-                    Some(VarType::String) => {
-                        self.emit_indent("PRINT_CSTR rdi");
-                    }
-                    Some(VarType::Float) => {
-                        self.emit_indent("movq xmm0, rdi");
-                        self.emit_indent("PRINT_FLOAT");
-                        self.uses_floats = true;
-                    }
-                    _ => {
-                        self.emit_indent("PRINT_INT rdi");
-                    }
-                }
+                self.emit_indent(if *upper { "call _str_upper" } else { "call _str_lower" });
             }
             
-            _ => {
-                let is_float = self.is_float_expr(value);
-                self.generate_expr(value);
-                if is_float {
-                    self.emit_indent("movq xmm0, rax");
-                    self.emit_indent("PRINT_FLOAT");
-                    self.uses_floats = true;
-                } else {
-                    self.emit_indent("mov rdi, rax");
-                    self.emit_indent("PRINT_INT rdi");
-                }
+            Expr::TextTrim { text } => {
+                self.uses_strings = true;
+                self.generate_text(text);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("call _str_trim");
             }
-        }
-        if !without_newline {
-            self.emit_indent("PRINT_NEWLINE");
-        }
-    }
-    
-    fn generate_expr(&mut self, expr: &Expr) {
-        match expr {
-            Expr::IntegerLit(n) => {
-                self.emit_indent(&format!("mov rax, {}", n));
+            
+            Expr::RandomNumber { low, high } => {
+                self.uses_random = true;
+                self.generate_expr(high);
+                self.emit_indent("push rax");
+                self.generate_expr(low);
+                self.emit_indent("mov rdi, rax  ; low");
+                self.emit_indent("pop rsi  ; high");
+                self.emit_indent("call _random_between");
             }
             
-            Expr::FloatLit(n) => {
-                self.uses_floats = true;
-                // Store float as 64-bit IEEE 754 in data section
-                let label = self.add_float(*n);
-                self.emit_indent(&format!("FLOAT_LOAD {}", label));
-                // Store float bits in rax for stack operations
-                self.emit_indent("XMM0_TO_RAX");
+            Expr::RandomFloat => {
+                self.uses_random = true;
+                self.emit_indent("call _random_float");
             }
             
-            Expr::BoolLit(b) => {
-                self.emit_indent(&format!("mov rax, {}", if *b { 1 } else { 0 }));
+            Expr::RandomElement { list } => {
+                self.uses_random = true;
+                self.generate_expr(list);
+                self.emit_indent("mov rdi, rax  ; list ptr");
+                self.emit_indent("call _random_element");
             }
             
-            Expr::StringLit(s) => {
-                // Check if this string literal is actually a variable reference
-                if let Some(offset) = self.get_var(s) {
-                    self.emit_indent(&format!("mov rax, [rbp-{}]", offset));
+            Expr::MathFunction { function, operand } => {
+                self.uses_math = true;
+                self.generate_float_operand(operand);
+                let routine = match function {
+                    MathFunction::SquareRoot => "_math_sqrt",
+                    MathFunction::Sine => "_math_sin",
+                    MathFunction::Cosine => "_math_cos",
+                    MathFunction::Tangent => "_math_tan",
+                    MathFunction::Logarithm => "_math_log",
+                };
+                self.emit_indent(&format!("call {}", routine));
+                self.emit_indent("XMM0_TO_RAX");
+            }
+            
+            Expr::Power { base, exponent } => {
+                self.uses_math = true;
+                if self.is_float_expr(expr) {
+                    self.generate_float_operand(exponent);
+                    self.emit_indent("XMM0_TO_RAX");
+                    self.emit_indent("push rax  ; exponent");
+                    self.generate_float_operand(base);
+                    self.emit_indent("pop rax");
+                    self.emit_indent("RAX_TO_XMM1");
+                    self.emit_indent("call _math_power");
+                    self.emit_indent("XMM0_TO_RAX");
                 } else {
-                    let label = self.add_string(s);
-                    self.emit_indent(&format!("lea rax, [{}]", label));
+                    self.generate_expr(exponent);
+                    self.emit_indent("push rax  ; exponent");
+                    self.generate_expr(base);
+                    self.emit_indent("mov rdi, rax  ; base");
+                    self.emit_indent("pop rsi");
+                    self.emit_indent("call _math_power_int");
                 }
             }
             
-            Expr::Identifier(name) => {
-                if let Some(offset) = self.get_var(name) {
-                    self.emit_indent(&format!("mov rax, [rbp-{}]", offset));
+            // A decimal divided and rounded is rounded once
+            Expr::Rounded { value, mode, places } if self.decimal_places(value).is_some() => {
+                let to = match places.as_deref() {
+                    Some(Expr::IntegerLit(places)) => *places as u32,
+                    _ => 0,
+                };
+                let mode = Self::decimal_rounding(*mode);
+                match value.as_ref() {
+                    Expr::BinaryOp { left, op: BinaryOperator::Divide, right } => {
+                        self.generate_decimal_division(left, right, to, mode);
+                    }
+                    _ => {
+                        let from = self.decimal_places(value).unwrap_or(0);
+                        self.generate_expr(value);
+                        self.emit_decimal_rescale(from, to, mode);
+                    }
+                }
+            }
+            
+            // Whole numbers are already rounded
+            Expr::Rounded { value, places: None, .. } if !self.is_float_expr(value) => self.generate_expr(value),
+            
+            Expr::Rounded { value, mode, places } => {
+                self.uses_math = true;
+                let mode = match mode {
+                    RoundingMode::Nearest => 0,
+                    RoundingMode::Up => 1,
+                    RoundingMode::Down => 2,
+                };
+                match places {
+                    Some(places) => {
+                        self.generate_expr(places);
+                        self.emit_indent("push rax  ; places");
+                        self.generate_float_operand(value);
+                        self.emit_indent("pop rsi");
+                        self.emit_indent(&format!("mov rdi, {}  ; rounding mode", mode));
+                        self.emit_indent("call _math_round_places");
+                        self.emit_indent("XMM0_TO_RAX");
+                    }
+                    None => {
+                        self.generate_float_operand(value);
+                        self.emit_indent(&format!("mov rdi, {}  ; rounding mode", mode));
+                        self.emit_indent("call _math_round_whole");
+                    }
+                }
+            }
+            
+            Expr::MinMax { left, right, maximum } => {
+                if self.is_float_expr(expr) {
+                    self.generate_float_operand(right);
+                    self.emit_indent("XMM0_TO_RAX");
+                    self.emit_indent("push rax");
+                    self.generate_float_operand(left);
+                    self.emit_indent("pop rax");
+                    self.emit_indent("RAX_TO_XMM1");
+                    self.emit_indent(if *maximum { "maxsd xmm0, xmm1" } else { "minsd xmm0, xmm1" });
+                    self.emit_indent("XMM0_TO_RAX");
+                } else {
+                    self.generate_expr(right);
+                    self.emit_indent("push rax");
+                    self.generate_expr(left);
+                    self.emit_indent("pop rcx");
+                    self.emit_indent("cmp rax, rcx");
+                    self.emit_indent(if *maximum { "cmovl rax, rcx" } else { "cmovg rax, rcx" });
                 }
             }
             
@@ -2093,7 +4199,16 @@ impl CodeGenerator {
                 // Use has_float_operands for instruction selection (includes comparisons)
                 let has_floats = self.has_float_operands(left) || self.has_float_operands(right);
                 
-                if has_floats {
+                if self.is_big_expr(left) || self.is_big_expr(right) {
+                    self.generate_big_operation(left, op.clone(), right);
+                } else if self.decimal_places(left).is_some() || self.decimal_places(right).is_some() {
+                    self.generate_decimal_operation(left, op.clone(), right);
+                } else if matches!(op, BinaryOperator::Equal | BinaryOperator::NotEqual) && self.is_text_comparison(left, right) {
+                    self.generate_text_equality(left, right);
+                    if matches!(op, BinaryOperator::NotEqual) {
+                        self.emit_indent("xor rax, 1");
+                    }
+                } else if has_floats {
                     self.uses_floats = true;
                     // Float operations using coreasm macros
                     // Convert int operands to float if needed
@@ -2204,19 +4319,19 @@ impl CodeGenerator {
                     
                     match op {
                         BinaryOperator::Add => {
-                            self.emit_indent("INT_ADD");
+                            self.emit_int_arith("INT_ADD");
                         }
                         BinaryOperator::Subtract => {
-                            self.emit_indent("INT_SUB");
+                            self.emit_int_arith("INT_SUB");
                         }
                         BinaryOperator::Multiply => {
-                            self.emit_indent("INT_MUL");
+                            self.emit_int_arith("INT_MUL");
                         }
                         BinaryOperator::Divide => {
-                            self.emit_indent("INT_DIV");
+                            self.emit_int_arith("INT_DIV");
                         }
                         BinaryOperator::Modulo => {
-                            self.emit_indent("INT_MOD");
+                            self.emit_int_arith("INT_MOD");
                         }
                         BinaryOperator::Equal => {
                             self.emit_indent("INT_EQ");
@@ -2279,10 +4394,14 @@ impl CodeGenerator {
                                 // Move result back to rax for consistency
                                 self.emit_indent("XMM0_TO_RAX");
                             }
+                            Some(VarType::BigNumber) => {
+                                self.generate_expr(operand);
+                                self.emit_big_routine("_big_negate", operand);
+                            }
                             _ => {
                                 self.uses_ints = true;
                                 self.generate_expr(operand);
-                                self.emit_indent("INT_NEG");
+                                self.emit_int_arith("INT_NEG");
                             }
                         }
                     }
@@ -2296,6 +4415,10 @@ impl CodeGenerator {
             
             Expr::PropertyCheck { value, property } => {
                 self.generate_expr(value);
+                if self.is_big_expr(value) {
+                    // A small number with the same sign and parity
+                    self.emit_big_routine("_big_sign_parity", value);
+                }
                 match property {
                     Property::Even => {
                         self.emit_indent("test rax, 1");
@@ -2323,14 +4446,15 @@ impl CodeGenerator {
                         self.emit_indent("movzx rax, al");
                     }
                     Property::Empty => {
-                        // For buffer/list variables, check the size field at offset 8
-                        let is_buffer_or_list = match value.as_ref() {
-                            Expr::StringLit(s) | Expr::Identifier(s) => {
-                                matches!(self.variable_types.get(s), Some(VarType::Buffer) | Some(VarType::List))
-                            }
+                        // Buffers, lists and maps keep their size or entry count at offset 8
+                        let is_collection = match value.as_ref() {
+                            Expr::StringLit(s) | Expr::Identifier(s) => matches!(
+                                self.variable_types.get(s),
+                                Some(VarType::Buffer) | Some(VarType::List) | Some(VarType::Map)
+                            ),
                             _ => false,
                         };
-                        if is_buffer_or_list {
+                        if is_collection {
                             self.emit_indent("mov rax, [rax + 8]  ; get size/length");
                         }
                         self.emit_indent("test rax, rax");
@@ -2340,14 +4464,16 @@ impl CodeGenerator {
                 }
             }
             
-            Expr::Range { .. } => {}
+            // Only loops take these; ForEach reads them itself
+            Expr::Range { .. } | Expr::FileLines { .. } | Expr::TextBytes { .. } => {}
 
-            Expr::FunctionCall { name, args } => {
+            Expr::FunctionCall { name, args, named } => {
                 let param_regs = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+                let args = self.call_arguments(name, args, named);
 
                 // 1) Evaluate/push all args right-to-left (so arg0 ends up deepest)
-                for arg in args.iter().rev() {
-                    self.generate_expr(arg);
+                for (i, arg) in args.iter().enumerate().rev() {
+                    self.generate_argument(name, i, arg);
                     self.emit_indent("push rax");
                 }
 
@@ -2393,45 +4519,44 @@ impl CodeGenerator {
             }
 
             Expr::ListLit { elements } => {
-                // List structure: [capacity:8][length:8][elem_size:8][data...]
-                // Each element is 8 bytes, header is 24 bytes
-                let capacity = std::cmp::max(elements.len(), 8); // minimum capacity 8
-                let header_size = 24;
-                let data_size = capacity * 8;
-                let total_size = header_size + data_size;
+                let elem_type = self.list_element_type(expr);
+                self.generate_list_literal(elements, &elem_type);
+            }
+            
+            Expr::StructLit { name, fields } => {
+                let Some(layout) = self.struct_defs.get(name).cloned() else {
+                    self.emit_indent(&format!("; Unknown struct {}", name));
+                    self.emit_indent("xor rax, rax");
+                    return;
+                };
+                self.emit_indent(&format!("; {} with {} fields", name, layout.len()));
                 
-                self.uses_lists = true;
-                self.emit_indent(&format!("; List literal with {} elements (capacity {})", elements.len(), capacity));
-                
-                // Allocate memory using mmap (heap allocation)
-                self.emit_indent(&format!("mov rdi, 0  ; addr = NULL"));
-                self.emit_indent(&format!("mov rsi, {}  ; size", total_size));
-                self.emit_indent("mov rdx, 3  ; PROT_READ | PROT_WRITE");
-                self.emit_indent("mov r10, 0x22  ; MAP_PRIVATE | MAP_ANONYMOUS");
-                self.emit_indent("mov r8, -1  ; fd = -1");
-                self.emit_indent("mov r9, 0  ; offset = 0");
-                self.emit_indent("mov rax, 9  ; sys_mmap");
-                self.emit_indent("syscall");
-                self.emit_indent("push rax  ; save list pointer");
+                // Zeroed field slots from the tracked record chunks
+                self.uses_buffers = true;
+                self.emit_indent(&format!("mov rdi, {}  ; size", std::cmp::max(layout.len(), 1) * 8));
+                self.emit_indent("call _alloc_record");
+                self.emit_indent("push rax  ; save struct pointer");
                 
-                // Store capacity
-                self.emit_indent(&format!("mov qword [rax], {}  ; capacity", capacity));
-                // Store length
-                self.emit_indent(&format!("mov qword [rax + 8], {}  ; length", elements.len()));
-                // Store element size
-                self.emit_indent("mov qword [rax + 16], 8  ; element size");
-                
-                // Store elements (data starts at offset 24)
-                for (i, elem) in elements.iter().enumerate() {
-                    self.emit_indent("pop rbx  ; get list pointer");
-                    self.emit_indent("push rbx ; save it back");
-                    self.generate_expr(elem);
-                    self.emit_indent("pop rbx  ; get list pointer");
-                    self.emit_indent(&format!("mov [rbx+{}], rax", header_size + i * 8));
-                    self.emit_indent("push rbx ; save list pointer");
+                // Fields not given keep zero, except text which starts empty
+                for (i, (field, field_type)) in layout.iter().enumerate() {
+                    let given = fields.iter().find(|(f, _)| f == field).map(|(_, v)| v);
+                    match given {
+                        Some(value) => {
+                            self.generate_expr(value);
+                            self.emit_unshared_big(value);
+                        }
+                        None if *field_type == Type::String => {
+                            let label = self.add_string("");
+                            self.emit_indent(&format!("lea rax, [rel {}]", label));
+                        }
+                        None => continue,
+                    }
+                    self.emit_indent("pop rbx  ; get struct pointer");
+                    self.emit_indent(&format!("mov [rbx+{}], rax  ; {}", i * 8, field));
+                    self.emit_indent("push rbx ; save struct pointer");
                 }
                 
-                self.emit_indent("pop rax  ; list pointer in rax");
+                self.emit_indent("pop rax  ; struct pointer in rax");
             }
             
             // ListAccess: 0-indexed access (internal use)
@@ -2461,7 +4586,7 @@ impl CodeGenerator {
                 
                 // Error path: out of bounds
                 self.emit(&format!("{}:", error_label));
-                self.emit_indent("mov qword [rel _last_error], 1  ; set error flag");
+                self.emit_indent("mov qword [rel _last_error], ERR_OUT_OF_BOUNDS");
                 self.emit_indent("xor rax, rax  ; return 0 on error");
                 self.emit_indent(&format!("jmp {}", done_label));
                 
@@ -2478,6 +4603,21 @@ impl CodeGenerator {
                 self.emit(&format!("{}:", done_label));
             }
             
+            Expr::PropertyAccess { object, property: ObjectProperty::Field(field) } => {
+                if let Some((index, _)) = self.struct_field(object, field) {
+                    let Some(offset) = self.get_var(object) else {
+                        unreachable!("struct variable {} has no stack slot", object);
+                    };
+                    self.emit_indent(&format!("mov rax, [rbp-{}]", offset));
+                    self.emit_indent(&format!("mov rax, [rax+{}]  ; {}'s {}", index * 8, object, field));
+                } else if let Some(property) = ObjectProperty::from_word(field) {
+                    // A field name that shadows a built-in property, used on a non-struct
+                    self.generate_expr(&Expr::PropertyAccess { object: object.clone(), property });
+                } else {
+                    self.emit_indent(&format!("xor rax, rax  ; unknown property {}'s {}", object, field));
+                }
+            }
+            
             Expr::PropertyAccess { object, property } => {
                 if let Some(offset) = self.get_var(object) {
                     let var_type = self.variable_types.get(object).cloned().unwrap_or(VarType::Unknown);
@@ -2490,6 +4630,12 @@ impl CodeGenerator {
                                 self.emit_indent("mov rax, [rax + 8]  ; buffer length/size");
                             } else if var_type == VarType::List {
                                 self.emit_indent("mov rax, [rax + 8]  ; list length at offset 8");
+                            } else if var_type == VarType::Map {
+                                self.emit_indent("mov rax, [rax + 8]  ; map entry count at offset 8");
+                            } else if var_type == VarType::String {
+                                self.uses_strings = true;
+                                self.emit_indent("mov rdi, rax");
+                                self.emit_indent("call _str_length");
                             } else {
                                 // For files, call _file_size
                                 self.emit_indent("mov rdi, rax");
@@ -2504,6 +4650,8 @@ impl CodeGenerator {
                             self.emit_indent(&format!("mov rax, [rbp-{}]", offset));
                             if var_type == VarType::List {
                                 self.emit_indent("mov rax, [rax + 8]  ; get list length (offset 8)");
+                            } else if var_type == VarType::String {
+                                self.emit_indent("movzx rax, byte [rax]  ; first character");
                             } else {
                                 self.emit_indent("mov rax, [rax + 8]  ; get buffer size");
                             }
@@ -2689,6 +4837,7 @@ impl CodeGenerator {
                             self.emit_indent(&format!("lea rax, [rbp - {}]", offset + 48));
                             self.emit_indent("mov rax, [rax + TIMER_RUNNING]");
                         }
+                        ObjectProperty::Field(_) => unreachable!("fields are generated above"),
                     }
                 } else if object == "_current_time" {
                     // Special handling for current time's properties
@@ -2712,6 +4861,12 @@ impl CodeGenerator {
                 self.emit_indent("mov rax, [rel _last_error]");
             }
             
+            Expr::LastErrorMessage => {
+                self.uses_errors = true;
+                self.emit_indent("mov rdi, [rel _last_error]");
+                self.emit_indent("call _error_message");
+            }
+            
             // Command-line arguments
             Expr::ArgumentCount => {
                 self.emit_indent("call _get_argc");
@@ -2899,6 +5054,58 @@ impl CodeGenerator {
             }
             
             // Type casting
+            Expr::Cast { value, target_type } if matches!(target_type, Type::Decimal(_)) || self.decimal_places(value).is_some() => {
+                self.use_decimals();
+                let value_type = self.infer_expr_type(value);
+                match (self.decimal_places(value), target_type) {
+                    (_, Type::Decimal(places)) if matches!(value_type, Some(VarType::String | VarType::Buffer)) => {
+                        self.generate_text(value);
+                        self.emit_indent("mov rdi, rax");
+                        self.emit_indent(&format!("mov esi, {}", places));
+                        self.emit_indent("call _dec_parse");
+                    }
+                    (_, Type::Decimal(places)) => self.generate_decimal_value(value, *places),
+                    (Some(places), Type::Float | Type::String) => {
+                        self.generate_expr(value);
+                        self.emit_indent("mov rdi, rax");
+                        self.emit_indent(&format!("mov esi, {}", places));
+                        let routine = if *target_type == Type::Float { "_dec_to_float" } else { "_dec_to_text" };
+                        self.emit_indent(&format!("call {}", routine));
+                    }
+                    // A number keeps the whole part, like a float's
+                    (places, _) => {
+                        self.generate_expr(value);
+                        self.emit_decimal_rescale(places.unwrap_or(0), 0, "DEC_ROUND_TRUNCATE");
+                    }
+                }
+            }
+            
+            Expr::Cast { value, target_type } if *target_type == Type::BigNumber || self.is_big_expr(value) => {
+                self.use_big_numbers();
+                let value_type = self.infer_expr_type(value);
+                if matches!(value_type, Some(VarType::String | VarType::Buffer)) {
+                    self.generate_text(value);
+                } else {
+                    self.generate_expr(value);
+                }
+                let routine = match (value_type, target_type) {
+                    (Some(VarType::BigNumber), Type::BigNumber) => None,
+                    (Some(VarType::String | VarType::Buffer), _) => Some("_big_parse"),
+                    (_, Type::BigNumber) => Some("_big_from_int"),
+                    (_, Type::String) => Some("_big_to_text"),
+                    // The analyzer only lets big numbers become numbers or text
+                    _ => Some("_big_to_int"),
+                };
+                match routine {
+                    Some(routine @ ("_big_to_text" | "_big_to_int")) => self.emit_big_routine(routine, value),
+                    Some(routine) => {
+                        self.emit_indent("mov rdi, rax");
+                        self.emit_indent(&format!("call {}", routine));
+                    }
+                    None => {}
+                }
+            }
+            
             Expr::Cast { value, target_type } => {
                 self.generate_expr(value);
                 match target_type {
@@ -2976,6 +5183,29 @@ impl CodeGenerator {
             // Element access: element N of list (1-indexed)
             // List structure: [capacity:8][length:8][elem_size:8][data...] 
             // MEMORY SAFETY: Always bounds-check before access
+            Expr::ListSlice { list, start, end } => {
+                self.uses_lists = true;
+                self.generate_expr(end);
+                self.emit_indent("push rax  ; last position");
+                self.generate_expr(start);
+                self.emit_indent("push rax  ; first position");
+                self.generate_expr(list);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("pop rsi");
+                self.emit_indent("pop rdx");
+                self.emit_indent("call _list_slice");
+            }
+            
+            Expr::ListConcat { left, right } => {
+                self.uses_lists = true;
+                self.generate_expr(right);
+                self.emit_indent("push rax");
+                self.generate_expr(left);
+                self.emit_indent("mov rdi, rax");
+                self.emit_indent("pop rsi");
+                self.emit_indent("call _list_concat");
+            }
+            
             Expr::ElementAccess { list, index } => {
                 let ok_label = self.new_label("elem_ok");
                 let error_label = self.new_label("elem_err");
@@ -2999,7 +5229,7 @@ impl CodeGenerator {
                 
                 // Error path: out of bounds
                 self.emit(&format!("{}:", error_label));
-                self.emit_indent("mov qword [rel _last_error], 1  ; set error flag");
+                self.emit_indent("mov qword [rel _last_error], ERR_OUT_OF_BOUNDS");
                 self.emit_indent("xor rax, rax  ; return 0 on error");
                 self.emit_indent(&format!("jmp {}", done_label));
                 
@@ -3025,10 +5255,119 @@ impl CodeGenerator {
         }
     }
     
+    // Evaluate an expression as a float, leaving its bits in rax
+    fn generate_float_expr(&mut self, expr: &Expr) {
+        self.generate_expr(expr);
+        if !self.is_float_expr(expr) {
+            self.emit_indent("INT_TO_FLOAT");
+            self.emit_indent("XMM0_TO_RAX");
+        }
+    }
+    
+    // Float ranges count iterations up front and compute each value as
+    // start + i * step, so rounding never adds or drops the last value
+    fn generate_float_range(&mut self, variable: &str, range: &Expr, body: &[Statement]) {
+        let Expr::Range { start, end, step, inclusive, descending } = range else {
+            return;
+        };
+        self.uses_floats = true;
+        
+        let start_label = self.new_label("for_start");
+        let continue_label = self.new_label("for_continue");
+        let end_label = self.new_label("for_end");
+        
+        let var_offset = self.alloc_var(variable);
+        self.variables.insert("_iter".to_string(), var_offset);
+        self.variable_types.insert(variable.to_string(), VarType::Float);
+        self.variable_types.insert("_iter".to_string(), VarType::Float);
+        
+        self.generate_float_expr(start);
+        let start_offset = self.alloc_var(&format!("{}_start", variable));
+        self.emit_indent(&format!("mov [rbp-{}], rax", start_offset));
+        
+        // Step size, always positive
+        match step {
+            Some(step) => {
+                self.generate_float_expr(step);
+                self.emit_indent("RAX_TO_XMM0");
+                self.emit_indent("FLOAT_ABS");
+                if step.as_number_literal().is_none() {
+                    let ok_label = self.new_label("for_step_ok");
+                    self.emit_indent("FLOAT_IS_ZERO");
+                    self.emit_indent("test rax, rax");
+                    self.emit_indent(&format!("jz {}", ok_label));
+                    self.emit_indent("mov qword [rel _last_error], ERR_ZERO_STEP");
+                    self.emit_indent(&format!("jmp {}", end_label));
+                    self.emit(&format!("{}:", ok_label));
+                }
+                self.emit_indent("XMM0_TO_RAX");
+            }
+            None => {
+                let label = self.add_float(1.0);
+                self.emit_indent(&format!("FLOAT_LOAD {}", label));
+                self.emit_indent("XMM0_TO_RAX");
+            }
+        }
+        let step_offset = self.alloc_var(&format!("{}_step", variable));
+        self.emit_indent(&format!("mov [rbp-{}], rax", step_offset));
+        
+        // Distance in the direction of travel, then the number of values
+        self.generate_float_expr(end);
+        if *descending {
+            self.emit_indent("RAX_TO_XMM1");
+            self.emit_indent(&format!("mov rax, [rbp-{}]", start_offset));
+            self.emit_indent("RAX_TO_XMM0");
+        } else {
+            self.emit_indent("RAX_TO_XMM0");
+            self.emit_indent(&format!("mov rax, [rbp-{}]", start_offset));
+            self.emit_indent("RAX_TO_XMM1");
+        }
+        self.emit_indent("FLOAT_SUB");
+        self.emit_indent(&format!("mov rax, [rbp-{}]", step_offset));
+        self.emit_indent("RAX_TO_XMM1");
+        self.emit_indent(&format!("mov rdi, {}", if *inclusive { 1 } else { 0 }));
+        self.emit_indent("call _float_range_count");
+        let count_offset = self.alloc_var(&format!("{}_count", variable));
+        self.emit_indent(&format!("mov [rbp-{}], rax", count_offset));
+        let index_offset = self.alloc_var(&format!("{}_index", variable));
+        self.emit_indent(&format!("mov qword [rbp-{}], 0", index_offset));
+        
+        self.emit(&format!("{}:", start_label));
+        self.emit_indent(&format!("mov rax, [rbp-{}]", index_offset));
+        self.emit_indent(&format!("cmp rax, [rbp-{}]", count_offset));
+        self.emit_indent(&format!("jge {}", end_label));
+        
+        self.emit_indent("INT_TO_FLOAT");
+        self.emit_indent(&format!("mov rax, [rbp-{}]", step_offset));
+        self.emit_indent("RAX_TO_XMM1");
+        self.emit_indent("FLOAT_MUL");
+        self.emit_indent("movsd xmm1, xmm0");
+        self.emit_indent(&format!("mov rax, [rbp-{}]", start_offset));
+        self.emit_indent("RAX_TO_XMM0");
+        self.emit_indent(if *descending { "FLOAT_SUB" } else { "FLOAT_ADD" });
+        self.emit_indent("XMM0_TO_RAX");
+        self.emit_indent(&format!("mov [rbp-{}], rax", var_offset));
+        
+        self.loop_stack.push((continue_label.clone(), end_label.clone()));
+        for s in body {
+            self.generate_statement(s);
+        }
+        self.loop_stack.pop();
+        
+        self.emit(&format!("{}:", continue_label));
+        self.emit_indent(&format!("inc qword [rbp-{}]", index_offset));
+        self.emit_indent(&format!("jmp {}", start_label));
+        self.emit(&format!("{}:", end_label));
+    }
+    
     fn generate_condition(&mut self, condition: &Expr, false_label: &str) {
         match condition {
             Expr::PropertyCheck { value, property } => {
                 self.generate_expr(value);
+                if self.is_big_expr(value) {
+                    // A small number with the same sign and parity
+                    self.emit_big_routine("_big_sign_parity", value);
+                }
                 match property {
                     Property::Even => {
                         self.emit_indent("test rax, 1");
@@ -3051,14 +5390,15 @@ impl CodeGenerator {
                         self.emit_indent(&format!("jge {}", false_label));
                     }
                     Property::Empty => {
-                        // For buffer/list variables, check the size field at offset 8
-                        let is_buffer_or_list = match value.as_ref() {
-                            Expr::StringLit(s) | Expr::Identifier(s) => {
-                                matches!(self.variable_types.get(s), Some(VarType::Buffer) | Some(VarType::List))
-                            }
+                        // Buffers, lists and maps keep their size or entry count at offset 8
+                        let is_collection = match value.as_ref() {
+                            Expr::StringLit(s) | Expr::Identifier(s) => matches!(
+                                self.variable_types.get(s),
+                                Some(VarType::Buffer) | Some(VarType::List) | Some(VarType::Map)
+                            ),
                             _ => false,
                         };
-                        if is_buffer_or_list {
+                        if is_collection {
                             self.emit_indent("mov rax, [rax + 8]  ; get size/length");
                         }
                         self.emit_indent("test rax, rax");
@@ -3081,10 +5421,19 @@ impl CodeGenerator {
                         self.generate_condition(right, false_label);
                         self.emit(&format!("{}:", true_label));
                     }
+                    BinaryOperator::Equal | BinaryOperator::NotEqual
+                        if self.is_text_comparison(left, right) =>
+                    {
+                        self.generate_text_equality(left, right);
+                        self.emit_indent("test rax, rax");
+                        let jmp = if matches!(op, BinaryOperator::Equal) { "jz" } else { "jnz" };
+                        self.emit_indent(&format!("{} {}", jmp, false_label));
+                    }
                     BinaryOperator::Equal | BinaryOperator::NotEqual |
                     BinaryOperator::Greater | BinaryOperator::Less |
                     BinaryOperator::GreaterEqual | BinaryOperator::LessEqual => {
-                        let is_float = self.is_float_expr(left) || self.is_float_expr(right);
+                        let is_decimal = self.decimal_places(left).is_some() || self.decimal_places(right).is_some();
+                        let is_float = !is_decimal && (self.is_float_expr(left) || self.is_float_expr(right));
                         
                         if is_float {
                             // Float comparison using SSE2
@@ -3107,12 +5456,22 @@ impl CodeGenerator {
                             };
                             self.emit_indent(&format!("{} {}", jmp, false_label));
                         } else {
-                            // Integer comparison
-                            self.generate_expr(right);
-                            self.emit_indent("push rax");
-                            self.generate_expr(left);
-                            self.emit_indent("pop rbx");
-                            self.emit_indent("cmp rax, rbx");
+                            if self.is_big_expr(left) || self.is_big_expr(right) {
+                                // _big_compare gives -1, 0 or 1 to compare with 0
+                                self.generate_big_call("_big_compare", left, right);
+                                self.emit_indent("cmp rax, 0");
+                            } else if is_decimal {
+                                let places = self.operand_places(left).max(self.operand_places(right));
+                                self.generate_decimal_pair(left, places, right, places);
+                                self.emit_indent("cmp rdi, rsi");
+                            } else {
+                                // Integer comparison
+                                self.generate_expr(right);
+                                self.emit_indent("push rax");
+                                self.generate_expr(left);
+                                self.emit_indent("pop rbx");
+                                self.emit_indent("cmp rax, rbx");
+                            }
                             
                             let jmp = match op {
                                 BinaryOperator::Equal => "jne",
@@ -3149,19 +5508,340 @@ impl CodeGenerator {
         }
     }
     
+    /// Both sides are text, so `is` compares the characters, not the pointers
+    fn is_text_comparison(&self, left: &Expr, right: &Expr) -> bool {
+        let is_text = |expr: &Expr| match expr {
+            // A buffer compares by its content
+            _ if self.is_buffer_var(expr) => true,
+            // A quoted name reads the variable
+            Expr::StringLit(name) if self.get_var(name).is_some() => {
+                self.variable_types.get(name) == Some(&VarType::String)
+            }
+            _ => self.infer_expr_type(expr) == Some(VarType::String),
+        };
+        is_text(left) && is_text(right)
+    }
+    
+    /// 1 in rax when the two texts are equal, 0 otherwise
+    fn generate_text_equality(&mut self, left: &Expr, right: &Expr) {
+        self.generate_text_call("_str_eq", left, right);
+    }
+    
+    /// Evaluates text into rax; a buffer gives its data
+    fn generate_text(&mut self, expr: &Expr) {
+        self.generate_expr(expr);
+        if self.is_buffer_var(expr) {
+            self.emit_indent("mov rdi, rax");
+            self.emit_indent("call _buffer_data");
+        }
+    }
+    
+    /// Calls a string.asm routine with `text` in rdi and `other` in rsi
+    fn generate_text_call(&mut self, routine: &str, text: &Expr, other: &Expr) {
+        self.uses_strings = true;
+        self.generate_text(other);
+        self.emit_indent("push rax");
+        self.generate_text(text);
+        self.emit_indent("mov rdi, rax");
+        self.emit_indent("pop rsi");
+        self.emit_indent(&format!("call {}", routine));
+    }
+    
+    /// The variable type a declared type gives
+    fn var_type_for(t: &Type) -> VarType {
+        match t {
+            Type::Integer => VarType::Integer,
+            Type::Float => VarType::Float,
+            Type::String => VarType::String,
+            Type::Boolean => VarType::Boolean,
+            Type::List(_) => VarType::List,
+            Type::Buffer => VarType::Buffer,
+            Type::Struct(struct_name) => VarType::Struct(struct_name.clone()),
+            Type::Map => VarType::Map,
+            Type::BigNumber => VarType::BigNumber,
+            Type::Decimal(places) => VarType::Decimal(*places),
+            Type::Any => VarType::Tagged,
+            _ => VarType::Unknown,
+        }
+    }
+    
+    /// The type of a list's elements, as far as it is known
+    fn list_element_type(&self, list: &Expr) -> Type {
+        match list {
+            Expr::Identifier(name) => self.list_element_types.get(name).cloned().unwrap_or(Type::Unknown),
+            Expr::ListLit { elements } => {
+                // Elements of different types are boxed with tags
+                let mut element = Type::Unknown;
+                for given in elements.iter().map(|e| self.value_element_type(e)) {
+                    if element == Type::Unknown {
+                        element = given;
+                    } else if given != Type::Unknown && given != element {
+                        element = Type::Any;
+                    }
+                }
+                element
+            }
+            Expr::TextSplit { .. } => Type::String,
+            Expr::ListSlice { list, .. } => self.list_element_type(list),
+            Expr::ListConcat { left, right } => match self.list_element_type(left) {
+                Type::Unknown => self.list_element_type(right),
+                known => known,
+            },
+            // An element of a list of lists is itself a list
+            Expr::ElementAccess { list, .. } | Expr::RandomElement { list } => match self.list_element_type(list) {
+                Type::List(inner) => *inner,
+                _ => Type::Unknown,
+            },
+            Expr::PropertyAccess { object, property: ObjectProperty::First | ObjectProperty::Last } => {
+                match self.list_element_types.get(object) {
+                    Some(Type::List(inner)) => (**inner).clone(),
+                    _ => Type::Unknown,
+                }
+            }
+            _ => Type::Unknown,
+        }
+    }
+    
+    /// The type a value has as a list element (Unknown when it can't be told)
+    fn value_element_type(&self, value: &Expr) -> Type {
+        match value {
+            Expr::StringLit(s) if self.get_var(s).is_some() => self.value_element_type(&Expr::Identifier(s.clone())),
+            Expr::StringLit(_) => Type::String,
+            Expr::IntegerLit(_) => Type::Integer,
+            Expr::FloatLit(_) => Type::Float,
+            Expr::BoolLit(_) => Type::Boolean,
+            Expr::Identifier(name) if self.builtin_constant(name).is_some() => Type::Float,
+            Expr::Identifier(name) => match self.variable_types.get(name) {
+                // Buffer variables produce string elements when appended
+                Some(VarType::Buffer) => Type::String,
+                Some(VarType::List) => Type::List(Box::new(self.list_element_type(value))),
+                Some(var_type) => match var_type {
+                    VarType::Integer => Type::Integer,
+                    VarType::Float => Type::Float,
+                    VarType::String => Type::String,
+                    VarType::Boolean => Type::Boolean,
+                    VarType::Struct(struct_name) => Type::Struct(struct_name.clone()),
+                    VarType::Map => Type::Map,
+                    VarType::Tagged => Type::Any,
+                    VarType::BigNumber => Type::BigNumber,
+                    VarType::Decimal(places) => Type::Decimal(*places),
+                    _ => Type::Unknown,
+                },
+                None => Type::Unknown,
+            },
+            Expr::ListLit { .. } => Type::List(Box::new(self.list_element_type(value))),
+            _ if self.is_big_expr(value) => Type::BigNumber,
+            _ if self.decimal_places(value).is_some() => self.decimal_places(value).map_or(Type::Unknown, Type::Decimal),
+            _ if self.is_float_expr(value) => Type::Float,
+            _ => match self.infer_expr_type(value) {
+                Some(VarType::List) => Type::List(Box::new(self.list_element_type(value))),
+                Some(VarType::String) => Type::String,
+                Some(VarType::Tagged) => Type::Any,
+                _ => Type::Unknown,
+            },
+        }
+    }
+    
+    /// Records what a list holds from the first value put into it
+    fn track_list_element_type(&mut self, list: &str, value: &Expr) {
+        if self.list_element_types.contains_key(list) {
+            return;
+        }
+        let elem_type = self.value_element_type(value);
+        if elem_type != Type::Unknown {
+            self.list_element_types.insert(list.to_string(), elem_type);
+        }
+    }
+    
+    /// Builds a list from a literal. Elements of a mixed list are boxed.
+    fn generate_list_literal(&mut self, elements: &[Expr], elem_type: &Type) {
+        // List structure: [capacity:8][length:8][elem_size:8][data...]
+        // Each element is 8 bytes, header is 24 bytes
+        let capacity = std::cmp::max(elements.len(), 8); // minimum capacity 8
+        let header_size = 24;
+        let data_size = capacity * 8;
+        let total_size = header_size + data_size;
+        
+        self.uses_lists = true;
+        self.emit_indent(&format!("; List literal with {} elements (capacity {})", elements.len(), capacity));
+        
+        // Allocate memory using mmap (heap allocation)
+        self.emit_indent("mov rdi, 0  ; addr = NULL");
+        self.emit_indent(&format!("mov rsi, {}  ; size", total_size));
+        self.emit_indent("mov rdx, 3  ; PROT_READ | PROT_WRITE");
+        self.emit_indent("mov r10, 0x22  ; MAP_PRIVATE | MAP_ANONYMOUS");
+        self.emit_indent("mov r8, -1  ; fd = -1");
+        self.emit_indent("mov r9, 0  ; offset = 0");
+        self.emit_indent("mov rax, 9  ; sys_mmap");
+        self.emit_indent("syscall");
+        self.emit_indent("push rax  ; save list pointer");
+        
+        // Store capacity
+        self.emit_indent(&format!("mov qword [rax], {}  ; capacity", capacity));
+        // Store length
+        self.emit_indent(&format!("mov qword [rax + 8], {}  ; length", elements.len()));
+        // Store element size
+        self.emit_indent("mov qword [rax + 16], 8  ; element size");
+        
+        // Store elements (data starts at offset 24)
+        for (i, elem) in elements.iter().enumerate() {
+            self.emit_indent("pop rbx  ; get list pointer");
+            self.emit_indent("push rbx ; save it back");
+            match (elem, elem_type) {
+                // "a list of lists of values" boxes the inner elements too
+                (Expr::ListLit { elements: inner }, Type::List(inner_type)) => {
+                    self.generate_list_literal(inner, inner_type);
+                }
+                (_, Type::Decimal(places)) => self.generate_decimal_value(elem, *places),
+                _ => {
+                    self.generate_expr(elem);
+                    self.emit_unshared_big(elem);
+                }
+            }
+            if *elem_type == Type::Any {
+                let value_type = self.value_element_type(elem);
+                self.emit_box(&value_type);
+            }
+            self.emit_indent("pop rbx  ; get list pointer");
+            self.emit_indent(&format!("mov [rbx+{}], rax", header_size + i * 8));
+            self.emit_indent("push rbx ; save list pointer");
+        }
+        
+        self.emit_indent("pop rax  ; list pointer in rax");
+    }
+    
+    /// Evaluates a value to store in a list. Buffers change later, so the
+    /// list keeps its own copy of their text; mixed lists get a tagged box.
+    fn generate_list_element(&mut self, value: &Expr, elem_type: &Type) {
+        if let Type::Decimal(places) = elem_type {
+            self.generate_decimal_value(value, *places);
+            return;
+        }
+        self.generate_expr(value);
+        self.emit_unshared_big(value);
+        if self.is_buffer_var(value) {
+            self.emit_indent("mov rdi, rax  ; buffer struct pointer");
+            self.emit_indent("call _buffer_data  ; get data pointer");
+            self.emit_indent("mov rdi, rax  ; source string");
+            self.emit_indent("call _strdup  ; duplicate string");
+        }
+        if *elem_type == Type::Any {
+            let value_type = self.value_element_type(value);
+            self.emit_box(&value_type);
+        }
+    }
+    
+    /// Boxes the value in rax with the tag for its type (see list.asm).
+    /// Values that are already boxes are left as they are.
+    fn emit_box(&mut self, value_type: &Type) {
+        let tag = match value_type {
+            Type::Any => return,
+            Type::Float => "LIST_TAG_FLOAT",
+            Type::String => "LIST_TAG_TEXT",
+            _ => "LIST_TAG_NUMBER",
+        };
+        self.emit_indent("mov rsi, rax  ; value");
+        self.emit_indent(&format!("mov rdi, {}", tag));
+        self.emit_indent("call _list_box");
+    }
+    
+    /// Prints the boxed element in rdi according to its tag
+    fn emit_print_tagged(&mut self) {
+        let text_label = self.new_label("tagged_text");
+        let float_label = self.new_label("tagged_float");
+        let done_label = self.new_label("tagged_done");
+        self.uses_floats = true;
+        self.emit_indent("mov rax, [rdi]  ; tag");
+        self.emit_indent("mov rdi, [rdi + 8]  ; value");
+        self.emit_indent("cmp rax, LIST_TAG_TEXT");
+        self.emit_indent(&format!("je {}", text_label));
+        self.emit_indent("cmp rax, LIST_TAG_FLOAT");
+        self.emit_indent(&format!("je {}", float_label));
+        self.emit_indent("PRINT_INT rdi");
+        self.emit_indent(&format!("jmp {}", done_label));
+        self.emit(&format!("{}:", text_label));
+        self.emit_indent("PRINT_CSTR rdi");
+        self.emit_indent(&format!("jmp {}", done_label));
+        self.emit(&format!("{}:", float_label));
+        self.emit_indent("movq xmm0, rdi");
+        self.emit_indent("PRINT_FLOAT");
+        self.emit(&format!("{}:", done_label));
+    }
+    
+    /// Evaluates a value to look for among a list's elements
+    fn generate_list_search_value(&mut self, value: &Expr, elem_type: &Type) {
+        match elem_type {
+            Type::String => self.generate_text(value),
+            Type::BigNumber => self.generate_big_value(value),
+            Type::Decimal(places) => self.generate_decimal_value(value, *places),
+            _ => self.generate_expr(value),
+        }
+    }
+    
+    /// Loads the compare routine for a list's elements (see list.asm)
+    fn emit_list_compare(&mut self, elem_type: &Type, reg: &str) {
+        let routine = match elem_type {
+            Type::String => {
+                self.uses_strings = true;
+                "_str_compare"
+            }
+            Type::Float => "_list_compare_floats",
+            Type::BigNumber => {
+                self.use_big_numbers();
+                "_big_compare"
+            }
+            _ => "_list_compare_integers",
+        };
+        self.emit_indent(&format!("lea {}, [rel {}]  ; compare routine", reg, routine));
+    }
+    
+    /// Calls _list_find with `list` in rdi and `value` in rsi
+    fn generate_list_find(&mut self, list: &Expr, value: &Expr) {
+        self.uses_lists = true;
+        let elem_type = self.list_element_type(list);
+        self.generate_list_search_value(value, &elem_type);
+        self.emit_indent("push rax");
+        self.generate_expr(list);
+        self.emit_indent("mov rdi, rax");
+        self.emit_indent("pop rsi");
+        self.emit_list_compare(&elem_type, "rdx");
+        self.emit_indent("call _list_find");
+    }
+    
     fn infer_expr_type(&self, expr: &Expr) -> Option<VarType> {
         match expr {
             Expr::IntegerLit(_) => Some(VarType::Integer),
-            Expr::FloatLit(_) => Some(VarType::Float),
+            _ if self.is_big_expr(expr) => Some(VarType::BigNumber),
+            Expr::Cast { value, target_type: Type::String } if self.is_big_expr(value) => Some(VarType::String),
+            _ if self.decimal_places(expr).is_some() => self.decimal_places(expr).map(VarType::Decimal),
+            Expr::Cast { value, target_type: Type::String } if self.decimal_places(value).is_some() => {
+                Some(VarType::String)
+            }
+            Expr::FloatLit(_) | Expr::RandomFloat => Some(VarType::Float),
+            Expr::Identifier(name) if self.builtin_constant(name).is_some() => Some(VarType::Float),
+            Expr::MathFunction { .. } | Expr::Power { .. } | Expr::Rounded { .. } | Expr::MinMax { .. }
+                if self.is_float_expr(expr) =>
+            {
+                Some(VarType::Float)
+            }
             Expr::StringLit(_) => Some(VarType::String),
+            Expr::LastErrorMessage => Some(VarType::String),
             Expr::BoolLit(_) => Some(VarType::Integer), // Booleans are integers (0/1)
             Expr::Identifier(name) => self.variable_types.get(name).cloned(),
             Expr::PropertyAccess { object, property } => {
                 // For First/Last on lists, return the list's element type
                 match property {
+                    ObjectProperty::Field(field) => match self.struct_field(object, field) {
+                        Some((_, Type::String)) => Some(VarType::String),
+                        Some((_, Type::Float)) => Some(VarType::Float),
+                        Some(_) => Some(VarType::Integer),
+                        None => ObjectProperty::from_word(field).and_then(|property| {
+                            self.infer_expr_type(&Expr::PropertyAccess { object: object.clone(), property })
+                        }),
+                    },
                     ObjectProperty::First | ObjectProperty::Last => {
                         if self.variable_types.get(object) == Some(&VarType::List) {
-                            self.list_element_types.get(object).cloned()
+                            self.list_element_types.get(object).map(Self::var_type_for)
                         } else {
                             Some(VarType::Integer)
                         }
@@ -3170,12 +5850,12 @@ impl CodeGenerator {
                     _ => Some(VarType::Integer),
                 }
             }
-            Expr::ElementAccess { list, .. } => {
+            Expr::MapGet { map, .. } => self.map_value_types.get(map).cloned().or(Some(VarType::Integer)),
+            Expr::ElementAccess { list, .. } | Expr::RandomElement { list } => {
                 // For element access, return the list's element type
-                if let Expr::Identifier(name) = list.as_ref() {
-                    self.list_element_types.get(name).cloned().or(Some(VarType::Integer))
-                } else {
-                    Some(VarType::Integer)
+                match self.list_element_type(list) {
+                    Type::Unknown => Some(VarType::Integer),
+                    element => Some(Self::var_type_for(&element)),
                 }
             }
             Expr::BinaryOp { left, op, right } => {
@@ -3196,6 +5876,10 @@ impl CodeGenerator {
             }
             Expr::UnaryOp { operand, .. } => self.infer_expr_type(operand),
             Expr::TreatingAs { value, .. } => self.infer_expr_type(value),
+            Expr::TextSlice { .. } | Expr::TextJoin { .. } | Expr::TextCase { .. } | Expr::TextTrim { .. } => {
+                Some(VarType::String)
+            }
+            Expr::TextSplit { .. } | Expr::ListSlice { .. } | Expr::ListConcat { .. } => Some(VarType::List),
             _ => Some(VarType::Integer), // Default to integer for complex expressions
         }
     }
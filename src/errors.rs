@@ -19,14 +19,18 @@ impl SourceLocation {
     }
 }
 
+// The rarely set parts are boxed so that every Result<_, CompileError>
+// stays small on the happy path
 #[derive(Debug, Clone)]
 pub struct CompileError {
     pub message: String,
-    pub location: Option<SourceLocation>,
-    pub hint: Option<String>,
+    pub location: Option<Box<SourceLocation>>,
+    pub hint: Option<Box<str>>,
     pub hint_location: Option<(usize, usize)>,  // (column, length) for visual connector
-    pub suggestion: Option<String>,
-    pub error_code: Option<String>,
+    pub suggestion: Option<Box<str>>,
+    pub error_code: Option<Box<str>>,
+    /// Reported without stopping compilation
+    pub is_warning: bool,
 }
 
 impl CompileError {
@@ -38,17 +42,18 @@ impl CompileError {
             hint_location: None,
             suggestion: None,
             error_code: None,
+            is_warning: false,
         }
     }
 
     pub fn with_location(mut self, loc: SourceLocation) -> Self {
-        self.location = Some(loc);
+        self.location = Some(Box::new(loc));
         self
     }
 
     #[allow(dead_code)]
     pub fn with_hint(mut self, hint: &str) -> Self {
-        self.hint = Some(hint.to_string());
+        self.hint = Some(hint.into());
         self
     }
     
@@ -59,13 +64,18 @@ impl CompileError {
     }
 
     pub fn with_suggestion(mut self, suggestion: &str) -> Self {
-        self.suggestion = Some(suggestion.to_string());
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub fn into_warning(mut self) -> Self {
+        self.is_warning = true;
         self
     }
 
     #[allow(dead_code)]
     pub fn with_code(mut self, code: &str) -> Self {
-        self.error_code = Some(code.to_string());
+        self.error_code = Some(code.into());
         self
     }
 }
@@ -94,7 +104,9 @@ impl fmt::Display for CompileError {
         const BOLD: &str = "\x1b[1m";
 
         // Error header
-        if let Some(ref code) = self.error_code {
+        if self.is_warning {
+            writeln!(f, "{}warning{}: {}{}", YELLOW, RESET, BOLD, self.message)?;
+        } else if let Some(ref code) = self.error_code {
             write!(f, "{}error[{}]{}: {}{}\n", RED, code, RESET, BOLD, self.message)?;
         } else {
             write!(f, "{}error{}: {}{}\n", RED, RESET, BOLD, self.message)?;
@@ -121,8 +133,9 @@ impl fmt::Display for CompileError {
             // Pointer line
             let pointer_offset = if loc.column > 0 { loc.column - 1 } else { 0 };
             let spaces = " ".repeat(pointer_offset);
-            write!(f, "  {:width$} {}{} {}{}^--- here{}\n", 
-                "", BLUE, "|", spaces, RED, RESET, width = line_num_width)?;
+            let marker = if self.is_warning { YELLOW } else { RED };
+            writeln!(f, "  {:width$} {}| {}{}^--- here{}",
+                "", BLUE, spaces, marker, RESET, width = line_num_width)?;
             
             // Draw connector to hint if we have a hint_location
             if let (Some(ref hint), Some((hint_col, hint_len))) = (&self.hint, self.hint_location) {
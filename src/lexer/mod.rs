@@ -27,7 +27,7 @@ pub enum Token {
     From, To, Between, Through, In, Of, On, The, A, An, All, By, Treating,
     
     // Types
-    Number, Float, Int, Text, Boolean, List, True, False,
+    Number, Float, Decimal, Int, Text, Boolean, List, True, False,
     
     // File I/O Types and Keywords
     Buffer, File, Bytes, Size, Into, Reading, Writing, Appending, Standard, Input,
@@ -62,6 +62,7 @@ pub enum Token {
     
     // Literals
     IntegerLiteral(i64),
+    BigIntegerLiteral(String), // digits past the 64-bit range
     FloatLiteral(f64),
     StringLiteral(String),
     
@@ -145,7 +146,8 @@ impl Token {
             "treating" | "treat" => Some("treating"),
             // Types
             "number" | "numbers" => Some("number"),
-            "float" | "decimal" | "real" => Some("float"),
+            "float" | "real" => Some("float"),
+            "decimal" => Some("decimal"),
             "int" | "integer" => Some("int"),
             "text" | "string" | "message" => Some("text"),
             "boolean" | "bool" => Some("boolean"),
@@ -318,6 +320,7 @@ impl Token {
             // Types
             Token::Number => Some("number"),
             Token::Float => Some("float"),
+            Token::Decimal => Some("decimal"),
             Token::Int => Some("int"),
             Token::Text => Some("text"),
             Token::Boolean => Some("boolean"),
@@ -411,6 +414,7 @@ impl Token {
             Token::Without => Some("without"),
             // Not keywords - these are identifiers, literals, punctuation, or special
             Token::IntegerLiteral(_) => None,
+            Token::BigIntegerLiteral(_) => None,
             Token::FloatLiteral(_) => None,
             Token::StringLiteral(_) => None,
             Token::Identifier(_) => None,
@@ -646,7 +650,10 @@ impl<'a> Lexer<'a> {
         if is_float {
             Token::FloatLiteral(num.parse().unwrap_or(0.0))
         } else {
-            Token::IntegerLiteral(num.parse().unwrap_or(0))
+            match num.parse() {
+                Ok(n) => Token::IntegerLiteral(n),
+                Err(_) => Token::BigIntegerLiteral(num),
+            }
         }
     }
     
@@ -712,7 +719,7 @@ impl<'a> Lexer<'a> {
         Token::IntegerLiteral(ch as i64)
     }
     
-    fn read_word(&mut self, first: char) -> Token {
+    fn read_word_text(&mut self, first: char) -> String {
         let mut word = String::from(first);
         while let Some(&ch) = self.peek() {
             if ch.is_alphanumeric() || ch == '_' || ch == '-' {
@@ -722,6 +729,23 @@ impl<'a> Lexer<'a> {
                 break;
             }
         }
+        word
+    }
+    
+    /// True right after a possessive `'s`, where the next word names a
+    /// property and is read as a plain identifier, never as a keyword
+    fn follows_possessive(tokens: &[TokenInfo]) -> bool {
+        match tokens {
+            [.., apostrophe, s] => {
+                apostrophe.token == Token::Apostrophe
+                    && matches!(&s.token, Token::Identifier(s) if s.eq_ignore_ascii_case("s"))
+            }
+            _ => false,
+        }
+    }
+    
+    fn read_word(&mut self, first: char) -> Token {
+        let word = self.read_word_text(first);
         
         match word.to_lowercase().as_str() {
             "print" | "prints" | "display" | "show" => Token::Print,
@@ -788,7 +812,8 @@ impl<'a> Lexer<'a> {
             "all" => Token::All,
             "by" => Token::By,
             "number" | "numbers" => Token::Number,
-            "float" | "decimal" | "real" => Token::Float,
+            "float" | "real" => Token::Float,
+            "decimal" => Token::Decimal,
             "int" | "integer" => Token::Int,
             "text" | "string" | "message" => Token::Text,
             "boolean" | "bool" => Token::Boolean,
@@ -943,6 +968,9 @@ impl<'a> Lexer<'a> {
                     }
                     '"' => Token::StringLiteral(self.read_string()),
                     c if c.is_ascii_digit() => self.read_number(c),
+                    c if c.is_alphabetic() && Self::follows_possessive(&tokens) => {
+                        Token::Identifier(self.read_word_text(c))
+                    }
                     c if c.is_alphabetic() => self.read_word(c),
                     _ => continue,
                 }
@@ -161,7 +161,8 @@ fn process_includes(
                     
                     let mut lexer = Lexer::new(&source);
                     let tokens = lexer.tokenize();
-                    let mut parser = Parser::new(tokens);
+                    let mut parser = Parser::new(tokens)
+                        .with_source(&include_path.to_string_lossy(), &source);
                     
                     if let Ok(mut included_program) = parser.parse() {
                         // Recursively process includes in the included file
@@ -194,6 +195,7 @@ fn show_version() {
 
 fn show_help() {
     eprintln!("Usage: vox <source.en> [options]");
+    eprintln!("       vox test <source.en> [options]   Run the file's examples");
     eprintln!();
     eprintln!("Options:");
     eprintln!("  --emit-asm       Output assembly only (don't assemble/link)");
@@ -203,6 +205,8 @@ fn show_help() {
     eprintln!("  --link <libs>    Link against shared libraries (comma-separated)");
     eprintln!("  --lib-path <paths>  Additional library search paths (comma-separated)");
     eprintln!("  --target <arch>   Target architecture (default: x86_64)");
+    eprintln!("  --checked-arithmetic  Catch integer overflow with 'on error'");
+    eprintln!("  --seed <n>       Make random values repeat from run to run");
     eprintln!("  -o <file>        Output file name");
     eprintln!("  -v | --verbose   Verbose output");
     eprintln!("  -h | --help           Show help");
@@ -212,7 +216,13 @@ fn show_help() {
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    
+    // `vox test file.en` builds the examples instead of the program and runs them
+    let test_mode = args.len() > 2 && args[1] == "test";
+    if test_mode {
+        args.remove(1);
+    }
     
     if args.len() < 2 {
         show_help();
@@ -237,8 +247,10 @@ fn main() {
     let source_path = &args[1];
     let mut emit_asm_only = false;
     let mut keep_asm = false;
-    let mut run_after = false;
+    let mut run_after = test_mode;
     let mut build_shared = false;
+    let mut checked_arithmetic = false;
+    let mut random_seed = None;
     let mut output_name = None;
     let mut verbose = false;
     let mut link_libs: Vec<String> = Vec::new();
@@ -260,6 +272,17 @@ fn main() {
             "--keep-asm" => keep_asm = true,
             "--run" => run_after = true,
             "--shared" => build_shared = true,
+            "--checked-arithmetic" => checked_arithmetic = true,
+            "--seed" => {
+                i += 1;
+                match args.get(i).map(|n| n.parse::<i64>()) {
+                    Some(Ok(seed)) => random_seed = Some(seed),
+                    _ => {
+                        eprintln!("--seed needs a whole number, like --seed 42");
+                        std::process::exit(1);
+                    }
+                }
+            }
             "--verbose" | "-v" => verbose = true,
             "-o" => {
                 i += 1;
@@ -320,9 +343,15 @@ fn main() {
     included_files.insert(source_path_buf.canonicalize().unwrap_or(source_path_buf.clone()));
     process_includes(&mut program, &source_path_buf, &mut included_files, verbose);
     
-    let mut analyzer = Analyzer::new().with_source(source_path, &source);
+    let mut analyzer = Analyzer::new()
+        .with_source(source_path, &source)
+        .with_checked_arithmetic(checked_arithmetic);
     analyzer.analyze(&mut program);
     
+    for warning in &analyzer.warnings {
+        eprintln!("{}", warning);
+    }
+    
     if !analyzer.errors.is_empty() {
         for err in &analyzer.errors {
             eprintln!("{}", err);
@@ -330,8 +359,16 @@ fn main() {
         std::process::exit(1);
     }
     
+    if test_mode && !program.statements.iter().any(|s| matches!(s, Statement::Example { .. })) {
+        eprintln!("No examples found in {}", source_path);
+        std::process::exit(1);
+    }
+    
     let mut codegen = CodeGenerator::new();
     codegen.set_shared_lib_mode(build_shared);
+    codegen.set_checked_arithmetic(checked_arithmetic);
+    codegen.set_random_seed(random_seed);
+    codegen.set_test_mode(test_mode);
     codegen.set_target_arch(&target_arch);
     let assembly = codegen.generate(&program);
     
@@ -345,6 +382,8 @@ fn main() {
     let output_path = output_name.unwrap_or_else(|| {
         if build_shared {
             format!("lib{}.so", base_name)
+        } else if test_mode {
+            format!("{}_test", base_name)
         } else {
             base_name.to_string()
         }
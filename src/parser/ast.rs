@@ -10,6 +10,12 @@ pub enum Type {
     File,
     Time,
     Timer,
+    Struct(String),  // a user-defined struct, by name
+    Map,             // text keys to values of one type
+    Any,             // list elements of several types, tagged at runtime
+    Tuple(Vec<Type>), // several values returned together
+    BigNumber,       // a whole number of any size (see bignum.asm)
+    Decimal(u32),    // fixed point with this many places (see decimal.asm)
     Void,
     Unknown,
 }
@@ -28,9 +34,127 @@ pub enum FileMode {
     Appending,
 }
 
+/// Runtime error categories that `On error "<name>"` can select.
+/// Errno values keep their Linux numbers; Vox's own codes start at 256
+/// (see core.asm).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    NotPermitted,
+    NotFound,
+    InputOutput,
+    BadDescriptor,
+    OutOfMemory,
+    PermissionDenied,
+    AlreadyExists,
+    NotADirectory,
+    IsADirectory,
+    InvalidArgument,
+    TooManyOpenFiles,
+    NoSpaceLeft,
+    ReadOnly,
+    NameTooLong,
+    Overflow,
+    DivisionByZero,
+    OutOfBounds,
+    BufferFull,
+    ParseFailure,
+    ZeroStep,
+    MissingKey,
+    Domain,
+}
+
+impl ErrorKind {
+    pub const ALL: [ErrorKind; 22] = [
+        ErrorKind::NotPermitted,
+        ErrorKind::NotFound,
+        ErrorKind::InputOutput,
+        ErrorKind::BadDescriptor,
+        ErrorKind::OutOfMemory,
+        ErrorKind::PermissionDenied,
+        ErrorKind::AlreadyExists,
+        ErrorKind::NotADirectory,
+        ErrorKind::IsADirectory,
+        ErrorKind::InvalidArgument,
+        ErrorKind::TooManyOpenFiles,
+        ErrorKind::NoSpaceLeft,
+        ErrorKind::ReadOnly,
+        ErrorKind::NameTooLong,
+        ErrorKind::Overflow,
+        ErrorKind::DivisionByZero,
+        ErrorKind::OutOfBounds,
+        ErrorKind::BufferFull,
+        ErrorKind::ParseFailure,
+        ErrorKind::ZeroStep,
+        ErrorKind::MissingKey,
+        ErrorKind::Domain,
+    ];
+
+    /// Value stored in `_last_error` for this kind
+    pub fn code(self) -> i64 {
+        match self {
+            ErrorKind::NotPermitted => 1,      // EPERM
+            ErrorKind::NotFound => 2,          // ENOENT
+            ErrorKind::InputOutput => 5,       // EIO
+            ErrorKind::BadDescriptor => 9,     // EBADF
+            ErrorKind::OutOfMemory => 12,      // ENOMEM
+            ErrorKind::PermissionDenied => 13, // EACCES
+            ErrorKind::AlreadyExists => 17,    // EEXIST
+            ErrorKind::NotADirectory => 20,    // ENOTDIR
+            ErrorKind::IsADirectory => 21,     // EISDIR
+            ErrorKind::InvalidArgument => 22,  // EINVAL
+            ErrorKind::TooManyOpenFiles => 24, // EMFILE
+            ErrorKind::NoSpaceLeft => 28,      // ENOSPC
+            ErrorKind::ReadOnly => 30,         // EROFS
+            ErrorKind::NameTooLong => 36,      // ENAMETOOLONG
+            ErrorKind::Overflow => 256,        // ERR_OVERFLOW
+            ErrorKind::DivisionByZero => 257,  // ERR_DIVIDE_BY_ZERO
+            ErrorKind::OutOfBounds => 258,     // ERR_OUT_OF_BOUNDS
+            ErrorKind::BufferFull => 259,      // ERR_BUFFER_FULL
+            ErrorKind::ParseFailure => 260,    // ERR_PARSE
+            ErrorKind::ZeroStep => 261,        // ERR_ZERO_STEP
+            ErrorKind::MissingKey => 262,      // ERR_MISSING_KEY
+            ErrorKind::Domain => 263,          // ERR_DOMAIN
+        }
+    }
+
+    /// The name used in `On error "<name>"`, also what `the error's message` returns
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::NotPermitted => "not permitted",
+            ErrorKind::NotFound => "not found",
+            ErrorKind::InputOutput => "input/output error",
+            ErrorKind::BadDescriptor => "bad file descriptor",
+            ErrorKind::OutOfMemory => "out of memory",
+            ErrorKind::PermissionDenied => "permission denied",
+            ErrorKind::AlreadyExists => "already exists",
+            ErrorKind::NotADirectory => "not a directory",
+            ErrorKind::IsADirectory => "is a directory",
+            ErrorKind::InvalidArgument => "invalid argument",
+            ErrorKind::TooManyOpenFiles => "too many open files",
+            ErrorKind::NoSpaceLeft => "no space left",
+            ErrorKind::ReadOnly => "read-only file system",
+            ErrorKind::NameTooLong => "name too long",
+            ErrorKind::Overflow => "overflow",
+            ErrorKind::DivisionByZero => "division by zero",
+            ErrorKind::OutOfBounds => "out of bounds",
+            ErrorKind::BufferFull => "buffer full",
+            ErrorKind::ParseFailure => "parse failure",
+            ErrorKind::ZeroStep => "zero step",
+            ErrorKind::MissingKey => "missing key",
+            ErrorKind::Domain => "domain error",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Expr {
     IntegerLit(i64),
+    BigLit(String),  // digits of a whole number too large for 64 bits
     FloatLit(f64),
     StringLit(String),
     BoolLit(bool),
@@ -47,10 +171,14 @@ pub enum Expr {
         operand: Box<Expr>,
     },
     
+    // from <start> [down] to <end> [by <step>]; the step is a size, the
+    // direction comes from `descending`
     Range {
         start: Box<Expr>,
         end: Box<Expr>,
+        step: Option<Box<Expr>>,
         inclusive: bool,
+        descending: bool,
     },
     
     PropertyCheck {
@@ -61,12 +189,20 @@ pub enum Expr {
     FunctionCall {
         name: String,
         args: Vec<Expr>,
+        named: Vec<(String, Expr)>,  // "with retries as 5", by parameter name
     },
     
     ListLit {
         elements: Vec<Expr>,
     },
     
+    // A new struct: Create a Dog called "buddy" with name "Buddy", age 3.
+    // Fields left out start as zero, false or empty text.
+    StructLit {
+        name: String,
+        fields: Vec<(String, Expr)>,
+    },
+    
     #[allow(dead_code)]
     ListAccess {
         list: Box<Expr>,
@@ -79,9 +215,9 @@ pub enum Expr {
         property: ObjectProperty,
     },
     
-    // The last error value
-    #[allow(dead_code)]
+    // The last error: the error's code, the error's message
     LastError,
+    LastErrorMessage,
     
     // Command-line arguments
     ArgumentCount,
@@ -148,6 +284,136 @@ pub enum Expr {
         index: Box<Expr>,
     },
     
+    // elements 2 to 4 of list (a new list)
+    ListSlice {
+        list: Box<Expr>,
+        start: Box<Expr>,
+        end: Box<Expr>,
+    },
+    
+    // first followed by second (a new list)
+    ListConcat {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    
+    // Map lookup: ages's "Alice" (sets "missing key" when absent)
+    MapGet {
+        map: String,
+        key: Box<Expr>,
+    },
+    
+    // Map membership: ages has "Bob"
+    MapHas {
+        map: String,
+        key: Box<Expr>,
+    },
+    
+    // Quantifier: any n in scores is greater than 90 (a list or a range;
+    // stops at the first element that decides the answer)
+    Quantifier {
+        kind: QuantifierKind,
+        variable: String,
+        collection: Box<Expr>,
+        condition: Box<Expr>,
+    },
+    
+    // Text slice: characters 2 to 5 of name, or character 1 of name without
+    // an end (1-based, both ends included; sets "out of bounds" outside the text)
+    TextSlice {
+        text: Box<Expr>,
+        start: Box<Expr>,
+        end: Option<Box<Expr>>,
+    },
+    
+    // Text search: position of "," in line (1-based, 0 when absent)
+    TextFind {
+        part: Box<Expr>,
+        text: Box<Expr>,
+    },
+    
+    // Text checks: line starts with "#", line ends with ".", line contains "error"
+    TextCheck {
+        text: Box<Expr>,
+        check: TextCheckKind,
+        part: Box<Expr>,
+    },
+    
+    // line split by "," (a list of text)
+    TextSplit {
+        text: Box<Expr>,
+        separator: Box<Expr>,
+    },
+    
+    // words joined with " "
+    TextJoin {
+        list: Box<Expr>,
+        separator: Box<Expr>,
+    },
+    
+    // name in uppercase, name in lowercase
+    TextCase {
+        text: Box<Expr>,
+        upper: bool,
+    },
+    
+    // line trimmed (spaces, tabs and line breaks removed at both ends)
+    TextTrim {
+        text: Box<Expr>,
+    },
+    
+    // each line in source, each line from standard input (only as a loop collection)
+    FileLines {
+        source: String,
+    },
+    
+    // the bytes of data, each byte in data - a text's or buffer's bytes as
+    // numbers (only as a loop collection)
+    TextBytes {
+        text: Box<Expr>,
+    },
+    
+    // a random number between 1 and 6 (both ends included, in either order)
+    RandomNumber {
+        low: Box<Expr>,
+        high: Box<Expr>,
+    },
+    
+    // a random float, from 0 up to but not including 1
+    RandomFloat,
+    
+    // a random element of names (sets "out of bounds" when the list is empty)
+    RandomElement {
+        list: Box<Expr>,
+    },
+    
+    // square root of x, sine of x, ... (always a float; "domain error"
+    // outside the function's domain)
+    MathFunction {
+        function: MathFunction,
+        operand: Box<Expr>,
+    },
+    
+    // x to the power of y (a number when both are numbers, else a float)
+    Power {
+        base: Box<Expr>,
+        exponent: Box<Expr>,
+    },
+    
+    // x rounded [up|down] - a whole number; with "to N places" a float
+    Rounded {
+        value: Box<Expr>,
+        mode: RoundingMode,
+        places: Option<Box<Expr>>,
+    },
+    
+    // minimum of a and b, maximum of a and b
+    MinMax {
+        left: Box<Expr>,
+        right: Box<Expr>,
+        maximum: bool,
+    },
+    
     // Format string: "Hello {name}, you are {age} years old"
     FormatString {
         parts: Vec<FormatPart>,
@@ -182,6 +448,84 @@ pub enum UnaryOperator {
     Not,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuantifierKind {
+    Any,
+    All,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextCheckKind {
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MathFunction {
+    SquareRoot,
+    Sine,
+    Cosine,
+    Tangent,
+    Logarithm,
+}
+
+impl MathFunction {
+    /// The words before "of": "square root of x"
+    pub fn words(self) -> &'static str {
+        match self {
+            MathFunction::SquareRoot => "square root",
+            MathFunction::Sine => "sine",
+            MathFunction::Cosine => "cosine",
+            MathFunction::Tangent => "tangent",
+            MathFunction::Logarithm => "logarithm",
+        }
+    }
+}
+
+/// How "rounded" settles halfway and in-between values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundingMode {
+    Nearest, // halves away from zero
+    Up,
+    Down,
+}
+
+/// The built-in constants `pi` and `e`. A variable or constant with the
+/// same name takes their place.
+pub fn math_constant(name: &str) -> Option<f64> {
+    match name {
+        "pi" => Some(std::f64::consts::PI),
+        "e" => Some(std::f64::consts::E),
+        _ => None,
+    }
+}
+
+/// The most places a decimal keeps; 10^18 still fits in 64 bits
+pub const DECIMAL_MAX_PLACES: u32 = 18;
+
+/// The places a number literal is written with: 2 for 19.99
+pub fn literal_places(value: f64) -> u32 {
+    let text = value.abs().to_string();
+    text.split_once('.').map_or(0, |(_, fraction)| fraction.len() as u32)
+}
+
+/// A number literal counted in units of its last place, rounding halves
+/// away from zero; None when that does not fit in 64 bits. Works on the
+/// literal's shortest digits, so 19.99 is exactly 1999 with 2 places.
+pub fn decimal_units(value: f64, places: u32) -> Option<i64> {
+    let text = value.abs().to_string();
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let mut digits = whole.to_string();
+    digits.extend(fraction.chars().chain(std::iter::repeat('0')).take(places as usize));
+    let mut units: i128 = digits.parse().ok()?;
+    if fraction.as_bytes().get(places as usize).is_some_and(|digit| *digit >= b'5') {
+        units += 1;
+    }
+    i64::try_from(if value < 0.0 { -units } else { units }).ok()
+}
+
 #[derive(Debug, Clone)]
 pub enum Property {
     Even,
@@ -236,6 +580,58 @@ pub enum ObjectProperty {
     StartTime,  // timer's start time
     EndTime,    // timer's end time
     Running,    // timer's running status
+    
+    // Struct fields
+    Field(String),  // dog's name
+}
+
+impl ObjectProperty {
+    /// The built-in property a word after `'s` names, if any
+    pub fn from_word(word: &str) -> Option<ObjectProperty> {
+        let property = match word.to_lowercase().as_str() {
+            "size" | "length" => ObjectProperty::Size,
+            "capacity" => ObjectProperty::Capacity,
+            "empty" | "nothing" | "null" | "nil" => ObjectProperty::Empty,
+            "full" => ObjectProperty::Full,
+            "descriptor" | "fd" => ObjectProperty::Descriptor,
+            "modified" => ObjectProperty::Modified,
+            "accessed" => ObjectProperty::Accessed,
+            "permissions" | "perms" => ObjectProperty::Permissions,
+            "readable" => ObjectProperty::Readable,
+            "writable" => ObjectProperty::Writable,
+            "first" => ObjectProperty::First,
+            "last" => ObjectProperty::Last,
+            "absolute" | "abs" => ObjectProperty::Absolute,
+            "sign" => ObjectProperty::Sign,
+            "even" => ObjectProperty::Even,
+            "odd" => ObjectProperty::Odd,
+            "positive" => ObjectProperty::Positive,
+            "negative" => ObjectProperty::Negative,
+            "zero" => ObjectProperty::Zero,
+            "hour" | "hours" => ObjectProperty::Hour,
+            "minute" | "minutes" => ObjectProperty::Minute,
+            "second" => ObjectProperty::Second,
+            "day" | "days" => ObjectProperty::Day,
+            "month" | "months" => ObjectProperty::Month,
+            "year" | "years" => ObjectProperty::Year,
+            "unix" | "unixtime" | "timestamp" => ObjectProperty::Unix,
+            "duration" => ObjectProperty::Duration,
+            "elapsed" => ObjectProperty::Elapsed,
+            "start" | "start time" => ObjectProperty::StartTime,
+            "end" | "end time" => ObjectProperty::EndTime,
+            "running" => ObjectProperty::Running,
+            _ => return None,
+        };
+        Some(property)
+    }
+}
+
+/// A value matched by a `when` arm of `Depending on`
+#[derive(Debug, Clone)]
+pub enum MatchPattern {
+    Value(Expr),
+    /// `when 1 to 9` - both ends included
+    Range { start: Expr, end: Expr },
 }
 
 #[derive(Debug, Clone)]
@@ -243,6 +639,7 @@ pub enum Statement {
     Print {
         value: Expr,
         without_newline: bool,
+        to_stderr: bool,
     },
     
     VarDecl {
@@ -251,6 +648,13 @@ pub enum Statement {
         value: Option<Expr>,
     },
 
+    /// `a constant number called "limit" is 10.` - never changes after this
+    ConstDecl {
+        name: String,
+        var_type: Option<Type>,
+        value: Expr,
+    },
+
     FlagSchemaDecl {
         name: String,
         short: String,
@@ -274,11 +678,37 @@ pub enum Statement {
         else_block: Option<Vec<Statement>>,
     },
     
+    // Depending on <subject>, when <values> then <actions>, ..., otherwise <actions>.
+    // The subject is evaluated once; the first arm with a matching value runs.
+    Depending {
+        subject: Expr,
+        arms: Vec<(Vec<MatchPattern>, Vec<Statement>)>,
+        otherwise: Option<Vec<Statement>>,
+        line: usize,
+    },
+    
     While {
         condition: Expr,
         body: Vec<Statement>,
     },
     
+    // Until <condition>, <actions>. - runs while the condition is false
+    Until {
+        condition: Expr,
+        body: Vec<Statement>,
+    },
+    
+    // Keep doing <actions> until <condition>. - tests after each pass
+    DoUntil {
+        body: Vec<Statement>,
+        condition: Expr,
+    },
+    
+    // Loop forever, <actions>. - left through break, exit or return
+    Loop {
+        body: Vec<Statement>,
+    },
+    
     ForRange {
         variable: String,
         range: Expr,
@@ -291,33 +721,71 @@ pub enum Statement {
         body: Vec<Statement>,
     },
     
+    // For each key and value in ages, ...
+    ForEachEntry {
+        key: String,
+        value: String,
+        map: String,
+        body: Vec<Statement>,
+    },
+    
     Repeat {
         count: Expr,
         body: Vec<Statement>,
     },
     
-    Break,
-    Continue,
+    // Without a loop name these leave or restart the innermost loop
+    Break { loop_name: Option<String> },
+    Continue { loop_name: Option<String> },
+    
+    // For each row from 1 to 10, called "rows", ... - a loop that
+    // `Break out of rows.` and `Continue rows.` can refer to
+    NamedLoop {
+        name: String,
+        inner: Box<Statement>,
+    },
     
     Exit {
         code: Expr,
     },
     
+    // Ensure <condition>, otherwise "<message>". - stops the program when false
+    Ensure {
+        condition: Expr,
+        message: Option<Expr>,
+        /// "file:line" and the condition as written, reported when it fails
+        location: String,
+        text: String,
+    },
+    
+    // Example "adds numbers": ensure ... - only compiled by `vox test`
+    Example {
+        name: String,
+        body: Vec<Statement>,
+    },
+    
     Return {
-        value: Option<Expr>,
+        values: Vec<Expr>,  // empty for a bare 'Return.'
     },
     
     FunctionDef {
         name: String,
         params: Vec<(String, Type)>,
-        #[allow(dead_code)]
+        defaults: Vec<(String, Expr)>,  // "which defaults to 3", by parameter name
         return_type: Type,
         body: Vec<Statement>,
     },
     
+    // Set q and r to "divmod" of 17 and 5.
+    Destructure {
+        names: Vec<String>,
+        value: Expr,
+    },
+    
     FunctionCall {
         name: String,
         args: Vec<Expr>,
+        named: Vec<(String, Expr)>,
     },
     
     Allocate {
@@ -350,6 +818,19 @@ pub enum Statement {
         value: Expr,
     },
     
+    // Define a struct called "Dog" with a text called "name" and a number called "age".
+    StructDef {
+        name: String,
+        fields: Vec<(String, Type)>,
+    },
+    
+    // Set dog's age to value
+    FieldSet {
+        object: String,
+        field: String,
+        value: Expr,
+    },
+    
     // Set element N of list to value (1-indexed)
     ElementSet {
         list: String,
@@ -363,6 +844,54 @@ pub enum Statement {
         value: Expr,
     },
     
+    // Insert value at position N of list (N may be one past the end)
+    ListInsert {
+        list: String,
+        value: Expr,
+        position: Expr,
+    },
+    
+    // Remove element N from list
+    ListRemoveAt {
+        list: String,
+        position: Expr,
+    },
+    
+    // Remove value from list (its first occurrence; no error if it is absent)
+    ListRemove {
+        list: String,
+        value: Expr,
+    },
+    
+    // Sort list [in descending order]
+    ListSort {
+        list: String,
+        descending: bool,
+    },
+    
+    // Reverse list
+    ListReverse {
+        list: String,
+    },
+    
+    // Shuffle list (every order equally likely)
+    ListShuffle {
+        list: String,
+    },
+    
+    // Set "Alice" in ages to value
+    MapSet {
+        map: String,
+        key: Expr,
+        value: Expr,
+    },
+    
+    // Remove "Bob" from ages (no error if it is absent)
+    MapRemove {
+        map: String,
+        key: Expr,
+    },
+    
     FileOpen {
         name: String,
         path: Expr,
@@ -406,8 +935,10 @@ pub enum Statement {
         path: Expr,
     },
     
-    // Error handling - actions are comma-separated within the sentence
+    // Error handling - actions are comma-separated within the sentence.
+    // `error` restricts the handler to one kind: On error "not found" ...
     OnError {
+        error: Option<ErrorKind>,
         actions: Vec<Statement>,
     },
     
@@ -451,6 +982,186 @@ pub enum Statement {
     GetTime {
         into: String,
     },
+    
+    // Get [N] random bytes into buffer - without N the buffer is filled to
+    // its capacity; more than fits sets "buffer full"
+    RandomBytes {
+        buffer: String,
+        count: Option<Expr>,
+    },
+    
+    // Seed the random generator with N. - later random values repeat from run to run
+    SeedRandom {
+        seed: Expr,
+    },
+    
+    // Enable/disable (auto) error catching: while enabled, an error that no
+    // `On error` handles stops the program with a message
+    ErrorCatching {
+        enabled: bool,
+    },
+    
+    // Inserted by the parser after each statement that can fail while
+    // error catching is enabled. `source` is the statement's source line.
+    ErrorCheck {
+        line: usize,
+        source: Option<String>,
+    },
+}
+
+impl MatchPattern {
+    /// The numbers this pattern matches, when they are written out as literals
+    pub fn literal_numbers(&self) -> Option<(i64, i64)> {
+        match self {
+            MatchPattern::Value(Expr::IntegerLit(n)) => Some((*n, *n)),
+            MatchPattern::Range { start: Expr::IntegerLit(start), end: Expr::IntegerLit(end) } => Some((*start, *end)),
+            _ => None,
+        }
+    }
+    
+    pub fn can_set_error(&self) -> bool {
+        match self {
+            MatchPattern::Value(value) => value.can_set_error(),
+            MatchPattern::Range { start, end } => start.can_set_error() || end.can_set_error(),
+        }
+    }
+}
+
+impl Statement {
+    /// Whether running this statement (not its nested blocks, which get their
+    /// own checks) may leave an error in `_last_error`. Errs on the side of yes.
+    pub fn can_set_error(&self) -> bool {
+        match self {
+            Statement::Print { value, .. }
+            | Statement::Assignment { value, .. }
+            | Statement::FieldSet { value, .. }
+            | Statement::ListRemove { value, .. } => value.can_set_error(),
+            Statement::VarDecl { value, .. } => value.as_ref().is_some_and(Expr::can_set_error),
+            Statement::If { condition, else_if_blocks, .. } => {
+                condition.can_set_error() || else_if_blocks.iter().any(|(c, _)| c.can_set_error())
+            }
+            Statement::Depending { subject, arms, .. } => {
+                subject.can_set_error()
+                    || arms.iter().flat_map(|(patterns, _)| patterns).any(MatchPattern::can_set_error)
+            }
+            Statement::While { condition, .. }
+            | Statement::Until { condition, .. }
+            | Statement::DoUntil { condition, .. } => condition.can_set_error(),
+            Statement::ForRange { range, .. } => range.can_set_error(),
+            Statement::ForEach { collection, .. } => collection.can_set_error(),
+            Statement::Repeat { count, .. } => count.can_set_error(),
+            Statement::NamedLoop { inner, .. } => inner.can_set_error(),
+            Statement::SeedRandom { seed } => seed.can_set_error(),
+            Statement::Ensure { condition, message, .. } => {
+                condition.can_set_error() || message.as_ref().is_some_and(Expr::can_set_error)
+            }
+            Statement::Break { .. }
+            | Statement::Continue { .. }
+            | Statement::Exit { .. }
+            | Statement::Return { .. }
+            | Statement::Loop { .. }
+            | Statement::ForEachEntry { .. }
+            | Statement::FunctionDef { .. }
+            | Statement::Example { .. }
+            | Statement::StructDef { .. }
+            | Statement::Free { .. }
+            | Statement::ListReverse { .. }
+            | Statement::ListShuffle { .. }
+            | Statement::FlagSchemaDecl { .. }
+            | Statement::ConstDecl { .. }
+            | Statement::FileWriteNewline { .. }
+            | Statement::FileClose { .. }
+            | Statement::OnError { .. }
+            | Statement::LibraryDecl { .. }
+            | Statement::See { .. }
+            | Statement::TimerDecl { .. }
+            | Statement::TimerStart { .. }
+            | Statement::TimerStop { .. }
+            | Statement::GetTime { .. }
+            | Statement::ErrorCatching { .. }
+            | Statement::ErrorCheck { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+impl Expr {
+    /// The value of a number literal, including a negated one
+    pub fn as_number_literal(&self) -> Option<f64> {
+        match self {
+            Expr::IntegerLit(n) => Some(*n as f64),
+            Expr::FloatLit(f) => Some(*f),
+            Expr::UnaryOp { op: UnaryOperator::Negate, operand } => operand.as_number_literal().map(|n| -n),
+            _ => None,
+        }
+    }
+
+    /// Whether evaluating this expression may set `_last_error` (division by
+    /// zero, bounds, checked overflow, or anything a called function does)
+    pub fn can_set_error(&self) -> bool {
+        match self {
+            Expr::IntegerLit(_)
+            | Expr::BigLit(_)
+            | Expr::FloatLit(_)
+            | Expr::StringLit(_)
+            | Expr::BoolLit(_)
+            | Expr::Identifier(_)
+            | Expr::PropertyAccess { .. }
+            | Expr::LastError
+            | Expr::LastErrorMessage
+            | Expr::ArgumentCount
+            | Expr::ArgumentName
+            | Expr::ArgumentFirst
+            | Expr::ArgumentSecond
+            | Expr::ArgumentLast
+            | Expr::ArgumentEmpty
+            | Expr::EnvironmentVariableCount
+            | Expr::EnvironmentVariableFirst
+            | Expr::EnvironmentVariableLast
+            | Expr::EnvironmentVariableEmpty
+            | Expr::CurrentTime
+            | Expr::RandomFloat => false,
+            Expr::BinaryOp { left, op, right } => {
+                matches!(
+                    op,
+                    BinaryOperator::Add
+                        | BinaryOperator::Subtract
+                        | BinaryOperator::Multiply
+                        | BinaryOperator::Divide
+                        | BinaryOperator::Modulo
+                ) || left.can_set_error()
+                    || right.can_set_error()
+            }
+            Expr::UnaryOp { op, operand } => {
+                matches!(op, UnaryOperator::Negate) || operand.can_set_error()
+            }
+            Expr::ListLit { elements } => elements.iter().any(Expr::can_set_error),
+            Expr::StructLit { fields, .. } => fields.iter().any(|(_, value)| value.can_set_error()),
+            Expr::MapHas { key, .. } => key.can_set_error(),
+            Expr::Quantifier { collection, condition, .. } => {
+                collection.can_set_error() || condition.can_set_error()
+            }
+            // Only a slice checks bounds; the other text operations can't fail
+            Expr::TextFind { part: left, text: right }
+            | Expr::TextCheck { text: left, part: right, .. }
+            | Expr::TextSplit { text: left, separator: right }
+            | Expr::TextJoin { list: left, separator: right } => left.can_set_error() || right.can_set_error(),
+            Expr::TextCase { text, .. } | Expr::TextTrim { text } => text.can_set_error(),
+            Expr::RandomNumber { low, high } => low.can_set_error() || high.can_set_error(),
+            Expr::MinMax { left, right, .. } => left.can_set_error() || right.can_set_error(),
+            // A step only known at run time may turn out to be zero
+            Expr::Range { start, end, step, .. } => {
+                start.can_set_error()
+                    || end.can_set_error()
+                    || step.as_ref().is_some_and(|s| s.as_number_literal().is_none())
+            }
+            Expr::FormatString { parts } => parts.iter().any(|part| match part {
+                FormatPart::Expression { expr, .. } => expr.can_set_error(),
+                _ => false,
+            }),
+            _ => true,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
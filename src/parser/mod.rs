@@ -3,11 +3,22 @@ pub mod ast;
 use crate::lexer::{Token, TokenInfo, Lexer};
 use crate::errors::{CompileError, SourceLocation, SourceFile, find_similar_keyword, ENGLISH_KEYWORDS};
 use ast::*;
+use std::collections::{HashMap, HashSet};
+
+/// The element of `none of <list> ...`, which has no name of its own
+const QUANTIFIED_ELEMENT: &str = "_element";
 
 pub struct Parser {
     tokens: Vec<TokenInfo>,
     pos: usize,
     source_file: Option<SourceFile>,
+    auto_error_catching: bool,  // between 'enable' and 'disable error catching'
+    structs: HashMap<String, Vec<String>>,  // field names of each defined struct
+    maps: HashSet<String>,                  // variables declared as maps
+    files: HashSet<String>,                 // file handles opened or taken as parameters
+    lists: HashSet<String>,                 // variables declared as lists
+    body_return_type: Option<Type>,         // first type a 'Return a <type>,' in a function body gives
+    in_call_arguments: bool,                // "with x as 1" after a quoted argument names the call's parameter
 }
 
 #[cfg(test)]
@@ -138,6 +149,152 @@ mod file_line_read_and_seek_tests {
         }
     }
 
+    #[test]
+    fn test_parse_stepped_and_descending_ranges() {
+        let input = r#"
+            For each i from 0 to 100 by 5, Print i.
+            For each i from 10 to 1, Print i.
+            a number called n is 3.
+            For each i from n down to 1, Print i.
+            For each i from 1 to n, Print i.
+        "#;
+
+        let result = parse_input(input).expect("ranges should parse");
+        let ranges: Vec<(bool, bool)> = result
+            .statements
+            .iter()
+            .filter_map(|s| match s {
+                Statement::ForRange { range: Expr::Range { step, descending, .. }, .. } => {
+                    Some((step.is_some(), *descending))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ranges, vec![(true, false), (false, true), (false, true), (false, false)]);
+    }
+
+    #[test]
+    fn test_print_each_range_with_step() {
+        let input = r#"print each number from 0.0 to 1.0 by 0.25."#;
+
+        let result = parse_input(input).expect("stepped loop expansion should parse");
+        match &result.statements[0] {
+            Statement::ForRange { range: Expr::Range { step: Some(step), .. }, .. } => {
+                assert!(matches!(**step, Expr::FloatLit(f) if f == 0.25));
+            }
+            other => panic!("Expected stepped ForRange, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_print_and_warn_to_standard_error() {
+        let input = r#"
+            Print "a" to standard error.
+            Print "b" without newline to standard error.
+            Print "c" to standard output.
+            Warn "d".
+        "#;
+
+        let result = parse_input(input).expect("stderr prints should parse");
+        let targets: Vec<(bool, bool)> = result
+            .statements
+            .iter()
+            .map(|s| match s {
+                Statement::Print { without_newline, to_stderr, .. } => (*without_newline, *to_stderr),
+                other => panic!("Expected Print, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(targets, vec![(false, true), (true, true), (false, false), (false, true)]);
+    }
+
+    #[test]
+    fn test_standard_streams_as_file_handles() {
+        let input = r#"
+            Write "x" to standard error.
+            Write a newline to standard output.
+        "#;
+
+        let result = parse_input(input).expect("standard streams should parse as files");
+        assert!(matches!(&result.statements[0], Statement::FileWrite { file, .. } if file == "stderr"));
+        assert!(matches!(&result.statements[1], Statement::FileWriteNewline { file } if file == "stdout"));
+    }
+
+    #[test]
+    fn test_variables_named_warn_and_keep() {
+        let input = r#"
+            a number called "warn" is 1.
+            a number called "keep" is 2.
+            warn is 3.
+            keep is warn.
+            Warn keep.
+        "#;
+
+        let result = parse_input(input).expect("warn and keep should parse as variables");
+        assert!(matches!(&result.statements[2], Statement::Assignment { name, .. } if name == "warn"));
+        assert!(matches!(&result.statements[3], Statement::Assignment { name, .. } if name == "keep"));
+        assert!(matches!(&result.statements[4], Statement::Print { to_stderr: true, .. }));
+    }
+
+    #[test]
+    fn test_parse_until_loop() {
+        let input = r#"
+            a number called n is 0.
+            Until n is 3,
+                Print n,
+                Increment n.
+        "#;
+
+        let result = parse_input(input).expect("until loop should parse");
+        match &result.statements[1] {
+            Statement::Until { body, .. } => assert_eq!(body.len(), 2),
+            other => panic!("Expected Until, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_keep_doing_until() {
+        let input = r#"
+            a number called n is 0.
+            Keep doing increment n, Print n until n is 3.
+            Print "done".
+        "#;
+
+        let result = parse_input(input).expect("keep doing should parse");
+        assert_eq!(result.statements.len(), 3);
+        match &result.statements[1] {
+            Statement::DoUntil { body, condition } => {
+                assert_eq!(body.len(), 2);
+                assert!(matches!(condition, Expr::BinaryOp { .. }));
+            }
+            other => panic!("Expected DoUntil, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_keep_doing_requires_until() {
+        let input = r#"Keep doing Print "x"."#;
+        let err = parse_input(input).expect_err("missing 'until' should fail");
+        assert!(err.to_string().contains("Missing 'until <condition>'"));
+    }
+
+    #[test]
+    fn test_parse_loop_forever() {
+        let input = r#"
+            Loop forever,
+                Print "tick",
+                break.
+        "#;
+
+        let result = parse_input(input).expect("loop forever should parse");
+        match &result.statements[0] {
+            Statement::Loop { body } => {
+                assert_eq!(body.len(), 2);
+                assert!(matches!(body[1], Statement::Break { loop_name: None }));
+            }
+            other => panic!("Expected Loop, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_on_error_sentence_can_return_to_parent_if_block() {
         let input = r#"
@@ -164,6 +321,86 @@ mod file_line_read_and_seek_tests {
         }
     }
 
+    #[test]
+    fn test_on_error_with_error_kind() {
+        let input = r#"On error "not found" print "missing", exit 1."#;
+        let result = parse_input(input).expect("on error with a kind should parse");
+        assert_eq!(result.statements.len(), 1);
+
+        match &result.statements[0] {
+            Statement::OnError { error, actions } => {
+                assert_eq!(*error, Some(ErrorKind::NotFound));
+                assert_eq!(actions.len(), 2);
+            }
+            other => panic!("Expected OnError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_on_error_unknown_kind_suggests_name() {
+        let input = r#"On error "not fund" exit 1."#;
+        let err = parse_input(input).expect_err("unknown error kind should be rejected");
+        assert!(err.message.contains("Unknown error kind"), "got: {}", err.message);
+        assert_eq!(err.suggestion.as_deref(), Some("not found"));
+    }
+
+    #[test]
+    fn test_parse_error_code_and_message() {
+        let input = r#"
+            On error print the error's message, exit the error's code.
+        "#;
+        let result = parse_input(input).expect("error properties should parse");
+
+        match &result.statements[0] {
+            Statement::OnError { error: None, actions } => {
+                assert!(matches!(actions[0], Statement::Print { value: Expr::LastErrorMessage, .. }));
+                assert!(matches!(actions[1], Statement::Exit { code: Expr::LastError }));
+            }
+            other => panic!("Expected catch-all OnError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_error_catching_inserts_checks_after_fallible_statements() {
+        let input = r#"
+            Enable error catching.
+            a number called divisor is 0.
+            Print 10 divide divisor.
+            Disable error catching.
+            Print 10 divide divisor.
+        "#;
+        let result = parse_input(input).expect("error catching should parse");
+        let kinds: Vec<&str> = result.statements.iter().map(|s| match s {
+            Statement::ErrorCatching { enabled: true } => "enable",
+            Statement::ErrorCatching { enabled: false } => "disable",
+            Statement::ErrorCheck { .. } => "check",
+            Statement::VarDecl { .. } => "decl",
+            Statement::Print { .. } => "print",
+            other => panic!("unexpected statement {:?}", other),
+        }).collect();
+        assert_eq!(kinds, ["enable", "decl", "print", "check", "disable", "print"]);
+        assert!(matches!(result.statements[3], Statement::ErrorCheck { line: 4, .. }));
+    }
+
+    #[test]
+    fn test_on_error_replaces_inserted_check() {
+        let input = r#"
+            Auto error catching.
+            Open a file for reading called "source" at "missing.txt".
+            On error print "missing".
+            Open a file for reading called "other" at "missing.txt".
+            On error "not found" print "missing".
+        "#;
+        let result = parse_input(input).expect("error catching should parse");
+        assert!(matches!(result.statements[2], Statement::OnError { error: None, .. }));
+        assert!(matches!(result.statements[4], Statement::OnError { error: Some(_), .. }));
+        assert!(
+            matches!(result.statements[5], Statement::ErrorCheck { line: 5, .. }),
+            "other kinds stay unhandled after a kind-specific handler"
+        );
+        assert_eq!(result.statements.len(), 6);
+    }
+
     #[test]
     fn test_parse_zero_param_function_def_with_comma_signature() {
         let input = r#"
@@ -197,7 +434,7 @@ mod file_line_read_and_seek_tests {
         assert_eq!(result.statements.len(), 2);
 
         match &result.statements[1] {
-            Statement::FunctionCall { name, args } => {
+            Statement::FunctionCall { name, args, .. } => {
                 assert_eq!(name, "show version");
                 assert!(args.is_empty(), "expected no call arguments");
             }
@@ -311,7 +548,7 @@ mod file_line_read_and_seek_tests {
 
 impl Parser {
     pub fn new(tokens: Vec<TokenInfo>) -> Self {
-        Parser { tokens, pos: 0, source_file: None }
+        Parser { tokens, pos: 0, source_file: None, auto_error_catching: false, structs: HashMap::new(), maps: HashSet::new(), files: HashSet::new(), lists: HashSet::new(), body_return_type: None, in_call_arguments: false }
     }
     
     pub fn with_source(mut self, filename: &str, content: &str) -> Self {
@@ -474,18 +711,12 @@ impl Parser {
                 break;
             }
             
-            match self.parse_statement() {
-                Ok(stmt) => {
-                    // Function definitions handle their own period and paragraph break
-                    let is_func_def = matches!(stmt, Statement::FunctionDef { .. });
-                    statements.push(stmt);
-                    
-                    if !is_func_def {
-                        self.skip_noise();
-                        self.expect(&Token::Period);
-                    }
-                }
-                Err(e) => return Err(e),
+            self.parse_statement_into(&mut statements)?;
+            
+            // Function definitions handle their own period and paragraph break
+            if !matches!(statements.last(), Some(Statement::FunctionDef { .. })) {
+                self.skip_noise();
+                self.expect(&Token::Period);
             }
             
             self.skip_all_whitespace();
@@ -494,6 +725,45 @@ impl Parser {
         Ok(Program::new(statements))
     }
     
+    /// Parses one statement and appends it to `statements`. While error
+    /// catching is enabled, a statement that can fail is followed by an
+    /// ErrorCheck, which a catch-all `On error` right after it replaces.
+    fn parse_statement_into(&mut self, statements: &mut Vec<Statement>) -> Result<(), CompileError> {
+        self.skip_all_whitespace();
+        let line = self.current_info().map(|info| info.line).unwrap_or(0);
+        let stmt = self.parse_statement()?;
+        
+        if let Statement::OnError { error, .. } = &stmt {
+            if matches!(statements.last(), Some(Statement::ErrorCheck { .. })) {
+                // Errors of other kinds are still unhandled after a kind-specific handler
+                let keep_check = error.is_some();
+                let check = statements.pop();
+                statements.push(stmt);
+                if keep_check {
+                    statements.extend(check);
+                }
+                return Ok(());
+            }
+        }
+        
+        let needs_check = self.auto_error_catching && stmt.can_set_error();
+        statements.push(stmt);
+        if needs_check {
+            let source = self.source_file.as_ref()
+                .and_then(|src| src.get_line(line))
+                .map(|text| text.trim().to_string());
+            statements.push(Statement::ErrorCheck { line, source });
+        }
+        Ok(())
+    }
+    
+    fn ends_with_on_error(statements: &[Statement]) -> bool {
+        matches!(
+            statements.iter().rev().find(|s| !matches!(s, Statement::ErrorCheck { .. })),
+            Some(Statement::OnError { .. })
+        )
+    }
+    
     fn parse_statement(&mut self) -> Result<Statement, CompileError> {
         self.skip_all_whitespace();
         
@@ -505,11 +775,40 @@ impl Parser {
             Token::The => self.parse_the_statement(),
             Token::If | Token::When => self.parse_if(),
             Token::While => self.parse_while(),
+            Token::Until => self.parse_until(),
+            Token::Loop => self.parse_loop_forever(),
+            Token::Identifier(ref s) if s.eq_ignore_ascii_case("keep")
+                && matches!(self.peek(1), Token::Identifier(d) if d.eq_ignore_ascii_case("doing")) =>
+            {
+                self.parse_keep_doing()
+            }
+            Token::Identifier(ref s) if s.eq_ignore_ascii_case("depending") => self.parse_depending(),
+            // "warn is 3." and "warn's ..." are a variable called warn
+            Token::Identifier(ref s) if s.eq_ignore_ascii_case("warn")
+                && !matches!(self.peek(1), Token::Is | Token::Equals | Token::Apostrophe | Token::Period) =>
+            {
+                self.parse_warn()
+            }
+            Token::Identifier(ref s) if s.eq_ignore_ascii_case("ensure") => self.parse_ensure(),
+            Token::Identifier(ref s) if s.eq_ignore_ascii_case("example")
+                && matches!(self.peek(1), Token::StringLiteral(_)) =>
+            {
+                self.parse_example()
+            }
+            Token::Identifier(ref s) if s.eq_ignore_ascii_case("insert") => self.parse_list_insert(),
+            Token::Identifier(ref s) if (s.eq_ignore_ascii_case("sort")
+                || s.eq_ignore_ascii_case("reverse")
+                || s.eq_ignore_ascii_case("shuffle"))
+                && matches!(self.peek(1), Token::Identifier(_) | Token::StringLiteral(_) | Token::The) =>
+            {
+                self.parse_list_reorder()
+            }
+            Token::Identifier(ref s) if s.eq_ignore_ascii_case("seed") => self.parse_seed_random(),
             Token::For => self.parse_for(),
             Token::Repeat => self.parse_repeat(),
             Token::Return => self.parse_return(),
-            Token::Break => { self.advance(); Ok(Statement::Break) }
-            Token::Continue => { self.advance(); Ok(Statement::Continue) }
+            Token::Break => self.parse_break(),
+            Token::Continue => self.parse_continue(),
             Token::Exit => self.parse_exit(),
             Token::Allocate => self.parse_allocate(),
             Token::Free => self.parse_free(),
@@ -563,11 +862,101 @@ impl Parser {
     }
     
     fn parse_print(&mut self) -> Result<Statement, CompileError> {
+        self.parse_print_to(false)
+    }
+    
+    fn parse_warn(&mut self) -> Result<Statement, CompileError> {
+        // "Warn <value>." is "Print <value> to standard error."
+        self.parse_print_to(true)
+    }
+    
+    /// "Ensure <condition>." or "Ensure <condition>, otherwise "<message>"."
+    fn parse_ensure(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'ensure'
+        self.skip_noise();
+        
+        let start = self.pos;
+        let condition = self.parse_condition()?;
+        let text = self.source_text(start, self.pos);
+        let line = self.tokens.get(start).map(|info| info.line).unwrap_or(0);
+        let location = match &self.source_file {
+            Some(src) => format!("{}:{}", src.filename, line),
+            None => format!("line {}", line),
+        };
+        self.skip_noise();
+        
+        let message = if *self.current() == Token::Comma && *self.peek(1) == Token::Otherwise {
+            self.advance();
+            self.advance();
+            self.skip_noise();
+            Some(self.parse_primary()?)
+        } else {
+            None
+        };
+        
+        Ok(Statement::Ensure { condition, message, location, text })
+    }
+    
+    /// The source text of tokens start..end, or a placeholder when the
+    /// parser has no source
+    fn source_text(&self, start: usize, end: usize) -> String {
+        let (Some(src), Some(first)) = (&self.source_file, self.tokens.get(start)) else {
+            return "the condition".to_string();
+        };
+        let last = self.tokens.get(end).map(|info| (info.line, info.column));
+        let mut text = Vec::new();
+        for (idx, line) in src.content.lines().enumerate().skip(first.line.saturating_sub(1)) {
+            let number = idx + 1;
+            let from = if number == first.line { first.column - 1 } else { 0 };
+            let part: String = match last {
+                Some((end_line, end_column)) if end_line == number => {
+                    line.chars().take(end_column - 1).skip(from).collect()
+                }
+                _ => line.chars().skip(from).collect(),
+            };
+            text.push(part.trim().to_string());
+            if last.is_none_or(|(end_line, _)| end_line <= number) {
+                break;
+            }
+        }
+        text.join(" ")
+    }
+    
+    /// "Example "<name>": <actions>." - a check of the program's functions
+    /// that only `vox test` compiles
+    fn parse_example(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'example'
+        let name = match self.advance() {
+            Token::StringLiteral(name) => name,
+            _ => unreachable!(),
+        };
+        self.skip_noise();
+        if !matches!(self.current(), Token::Colon | Token::Comma) {
+            return Err(self.err(&format!(
+                "Expected ':' after the example name\n  \
+                 Syntax: Example \"<name>\": <actions>.\n  \
+                 Example: Example \"{}\": ensure \"add\" of 2 and 3 is 5.",
+                name
+            )));
+        }
+        self.advance();
+        self.skip_noise();
+        while *self.current() == Token::ParagraphBreak {
+            self.advance();
+            self.skip_noise();
+        }
+        
+        let body = self.parse_loop_body()?;
+        Ok(Statement::Example { name, body })
+    }
+    
+    fn parse_print_to(&mut self, mut to_stderr: bool) -> Result<Statement, CompileError> {
         self.advance();
         self.skip_noise();
         
         // Check for loop expansion: "print each X from Y [treating X as Y]"
         if let Some((variable, collection, treating)) = self.try_parse_each_from()? {
+            to_stderr = self.parse_output_stream().unwrap_or(to_stderr);
             // Create the variable expression, with optional treating substitution
             let var_expr = if let Some((match_val, replacement)) = treating {
                 Expr::TreatingAs {
@@ -578,7 +967,7 @@ impl Parser {
             } else {
                 Expr::Identifier(variable.clone())
             };
-            let print_stmt = Statement::Print { value: var_expr, without_newline: false };
+            let print_stmt = Statement::Print { value: var_expr, without_newline: false, to_stderr };
             return self.wrap_in_loop_expansion(variable, collection, print_stmt);
         }
         
@@ -606,9 +995,10 @@ impl Parser {
                     };
                     let func_call = Expr::FunctionCall { 
                         name: func_name, 
-                        args: vec![arg_expr] 
+                        args: vec![arg_expr],
+                        named: Vec::new(),
                     };
-                    let print_stmt = Statement::Print { value: func_call, without_newline: false };
+                    let print_stmt = Statement::Print { value: func_call, without_newline: false, to_stderr };
                     return self.wrap_in_loop_expansion(variable, collection, print_stmt);
                 } else {
                     // Not a loop expansion, restore position and parse normally
@@ -622,6 +1012,10 @@ impl Parser {
         
         let value = self.parse_expression()?;
         
+        // "to standard error" may come before or after "without newline"
+        self.skip_noise();
+        to_stderr = self.parse_output_stream().unwrap_or(to_stderr);
+        
         // Check for "without newline" modifier
         self.skip_noise();
         let without_newline = if *self.current() == Token::Without {
@@ -638,6 +1032,8 @@ impl Parser {
         } else {
             false
         };
+        self.skip_noise();
+        to_stderr = self.parse_output_stream().unwrap_or(to_stderr);
         
         // Check for conditional print patterns: "print X, but if Y" or "print X but if Y"
         // IMPORTANT: do not consume a plain trailing comma here, because it may belong
@@ -654,7 +1050,7 @@ impl Parser {
             }
             
             if *self.current() == Token::If {
-                return self.parse_conditional_print(value);
+                return self.parse_conditional_print(value, to_stderr);
             }
 
             // Not a conditional-print continuation; restore parser position so
@@ -662,10 +1058,47 @@ impl Parser {
             self.pos = conditional_start_pos;
         }
         
-        Ok(Statement::Print { value, without_newline })
+        Ok(Statement::Print { value, without_newline, to_stderr })
+    }
+    
+    /// Parses "to standard output" or "to standard error" after a print.
+    /// Returns whether the output goes to standard error.
+    fn parse_output_stream(&mut self) -> Option<bool> {
+        if *self.current() != Token::To {
+            return None;
+        }
+        let saved_pos = self.pos;
+        self.advance();
+        self.skip_noise();
+        match self.try_parse_standard_stream().as_deref() {
+            Some("stdout") => Some(false),
+            Some("stderr") => Some(true),
+            _ => {
+                self.pos = saved_pos;
+                None
+            }
+        }
+    }
+    
+    /// Parses "standard input", "standard output" or "standard error" (or a
+    /// bare "stderr") as the name of an always-open file handle.
+    fn try_parse_standard_stream(&mut self) -> Option<String> {
+        if *self.current() == Token::Stderr {
+            self.advance();
+            return Some("stderr".to_string());
+        }
+        let name = match (self.current(), self.peek(1)) {
+            (Token::Standard, Token::Input) => "stdin",
+            (Token::Standard, Token::Identifier(s)) if s.eq_ignore_ascii_case("output") => "stdout",
+            (Token::Standard, Token::Error) => "stderr",
+            _ => return None,
+        };
+        self.advance();
+        self.advance();
+        Some(name.to_string())
     }
     
-    fn parse_conditional_print(&mut self, default_value: Expr) -> Result<Statement, CompileError> {
+    fn parse_conditional_print(&mut self, default_value: Expr, to_stderr: bool) -> Result<Statement, CompileError> {
         self.advance();
         self.skip_noise();
         
@@ -719,12 +1152,12 @@ impl Parser {
             }
         }
         
-        let mut result = Statement::Print { value: default_value, without_newline: false };
+        let mut result = Statement::Print { value: default_value, without_newline: false, to_stderr };
         
         for (cond, val) in conditions.into_iter().rev() {
             result = Statement::If {
                 condition: cond,
-                then_block: vec![Statement::Print { value: val, without_newline: false }],
+                then_block: vec![Statement::Print { value: val, without_newline: false, to_stderr }],
                 else_if_blocks: vec![],
                 else_block: Some(vec![result]),
             };
@@ -737,6 +1170,20 @@ impl Parser {
         self.advance(); // consume Set/Create
         self.skip_noise();
         
+        // Handle "Set dog's age to value"
+        if matches!(self.current(), Token::Identifier(_) | Token::StringLiteral(_))
+            && *self.peek(1) == Token::Apostrophe
+        {
+            return self.parse_field_set();
+        }
+        
+        // Handle "Set "Alice" in ages to value"
+        if matches!(self.current(), Token::Identifier(_) | Token::StringLiteral(_))
+            && *self.peek(1) == Token::In
+        {
+            return self.parse_map_set();
+        }
+        
         // Handle "Set byte N of buffer to value"
         if *self.current() == Token::Byte {
             self.advance();
@@ -795,6 +1242,23 @@ impl Parser {
             self.skip_noise();
         }
         
+        if self.at_constant() {
+            return self.parse_constant_decl();
+        }
+        
+        // "Define a struct called ...", "Create a map called ..." and "Create a Dog called ..."
+        if let Token::Identifier(type_name) = self.current().clone() {
+            if *self.peek(1) == Token::Called {
+                if type_name.eq_ignore_ascii_case("struct") {
+                    return self.parse_struct_def();
+                }
+                if Self::is_map_word(&type_name) {
+                    return self.parse_map_decl();
+                }
+                return self.parse_struct_var_decl(type_name);
+            }
+        }
+        
         // Check for typed declaration: "<type> called <name>"
         // Handle Timer specially - it has its own statement type
         if *self.current() == Token::Timer {
@@ -875,7 +1339,25 @@ impl Parser {
         };
         
         self.skip_noise();
-        
+
+        // "Set q and r to "divmod" of 17 and 5." takes apart several returned values
+        if var_type.is_none() && *self.current() == Token::And {
+            let mut names = vec![name];
+            while *self.current() == Token::And {
+                self.advance();
+                self.skip_noise();
+                names.push(self.parse_declared_name()?);
+                self.skip_noise();
+            }
+            if !matches!(self.current(), Token::To | Token::Equals | Token::Is) {
+                return Err(self.err_expected("'to' after the names to set", self.current()));
+            }
+            self.advance();
+            self.skip_noise();
+            let value = self.parse_expression()?;
+            return Ok(Statement::Destructure { names, value });
+        }
+
         // Handle buffer creation with size: "Create a buffer called X with/of size N"
         if var_type == Some(Type::Buffer) {
             // Check for "with size N" or "of size N" syntax
@@ -911,51 +1393,451 @@ impl Parser {
         })
     }
 
-    fn parse_flag_schema_decl(&mut self) -> Result<Statement, CompileError> {
-        self.expect(&Token::Flag);
-        self.skip_noise();
-
-        self.expect(&Token::Called);
+    /// Parses "Define a struct called "Dog" with a text called "name" and a
+    /// number called "age"." The fields may also follow "with:" one per line.
+    fn parse_struct_def(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'struct'
+        self.advance(); // consume 'called'
         self.skip_noise();
-
+        
         let name = match self.current().clone() {
-            Token::StringLiteral(n) => {
-                self.advance();
-                n
-            }
-            Token::Identifier(n) => {
-                self.advance();
-                n
-            }
-            _ => return Err(self.err("Expected flag variable name after 'called'")),
-        };
-
-        self.skip_noise();
-        self.expect(&Token::Is);
-        self.skip_noise();
-
-        let short = match self.current().clone() {
-            Token::StringLiteral(v) => {
-                self.advance();
-                v
-            }
-            _ => return Err(self.err("Expected short flag alias string like '-n'")),
+            Token::StringLiteral(n) | Token::Identifier(n) => { self.advance(); n }
+            _ => return Err(self.err(
+                "Missing struct name after 'called'\n  \
+                 Syntax: Define a struct called \"<Name>\" with a <type> called \"<field>\" and ...\n  \
+                 Example: Define a struct called \"Dog\" with a text called \"name\" and a number called \"age\"."
+            )),
         };
-
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(self.err(&format!("Struct names are single words: '{}'", name)));
+        }
+        if let Some(kw) = Token::string_is_keyword(&name) {
+            return Err(self.make_error(&format!(
+                "Cannot use '{}' as a struct name - it's a reserved keyword.\n  \
+                 Tip: Try a more descriptive name like 'My{}'",
+                name, kw
+            )));
+        }
+        if Self::is_map_word(&name) {
+            return Err(self.make_error(&format!(
+                "Cannot use '{}' as a struct name - it's the built-in map type.",
+                name
+            )));
+        }
+        
         self.skip_noise();
-        self.expect(&Token::Or);
+        if !self.expect(&Token::With) {
+            return Err(self.err(&format!(
+                "Missing 'with' and the fields of {}\n  \
+                 Example: Define a struct called \"{}\" with a number called \"x\" and a number called \"y\".",
+                name, name
+            )));
+        }
         self.skip_noise();
-
-        let long = match self.current().clone() {
-            Token::StringLiteral(v) => {
+        if *self.current() == Token::Colon {
+            self.advance();
+        }
+        
+        let mut fields: Vec<(String, Type)> = Vec::new();
+        loop {
+            self.skip_noise();
+            if matches!(self.current(), Token::A | Token::An) {
                 self.advance();
-                v
+                self.skip_noise();
             }
-            _ => return Err(self.err("Expected long flag alias string like '--number'")),
-        };
-
-        self.skip_noise();
-        self.expect(&Token::Comma);
+            
+            let field_type = match self.current() {
+                Token::Number | Token::Int => Type::Integer,
+                Token::Float => Type::Float,
+                Token::Text => Type::String,
+                Token::Boolean => Type::Boolean,
+                _ => return Err(self.err_expected("a field type (number, float, text or boolean)", self.current())),
+            };
+            self.advance();
+            self.skip_noise();
+            
+            if !self.expect(&Token::Called) {
+                return Err(self.err("Missing 'called' after the field type\n  Example: a number called \"age\""));
+            }
+            self.skip_noise();
+            
+            let field = match self.current().clone() {
+                Token::StringLiteral(n) | Token::Identifier(n) => n.to_lowercase(),
+                _ => return Err(self.err("Missing field name after 'called'")),
+            };
+            if field.is_empty() || field.contains(char::is_whitespace) {
+                return Err(self.err(&format!("Field names are single words: '{}'", field)));
+            }
+            if fields.iter().any(|(f, _)| *f == field) {
+                return Err(self.err(&format!("{} already has a property called '{}'", name, field)));
+            }
+            self.advance();
+            fields.push((field, field_type));
+            
+            // Fields are separated by ",", "and" or ", and"
+            self.skip_noise();
+            if !matches!(self.current(), Token::Comma | Token::And) {
+                break;
+            }
+            self.advance();
+            self.skip_noise();
+            if *self.current() == Token::And {
+                self.advance();
+            }
+        }
+        
+        self.structs.insert(name.clone(), fields.iter().map(|(f, _)| f.clone()).collect());
+        Ok(Statement::StructDef { name, fields })
+    }
+    
+    /// Parses the rest of "Create a Dog called "buddy" with name "Buddy", age 3."
+    /// from the struct name on. "a Dog called "rex" is <value>" takes its Dog
+    /// from any expression, such as a function call.
+    fn parse_struct_var_decl(&mut self, struct_name: String) -> Result<Statement, CompileError> {
+        self.advance(); // consume the struct name
+        self.advance(); // consume 'called'
+        self.skip_noise();
+        
+        let name = self.parse_declared_name()?;
+        self.skip_noise();
+        
+        let value = if matches!(self.current(), Token::Is | Token::To | Token::Equals) {
+            self.advance();
+            self.skip_noise();
+            self.parse_expression()?
+        } else {
+            let fields = if self.expect(&Token::With) {
+                self.parse_struct_fields(&struct_name)?
+            } else {
+                Vec::new()
+            };
+            Expr::StructLit { name: struct_name.clone(), fields }
+        };
+        
+        Ok(Statement::VarDecl {
+            name,
+            var_type: Some(Type::Struct(struct_name)),
+            value: Some(value),
+        })
+    }
+    
+    /// The variable name after 'called', which must not be a keyword
+    fn parse_declared_name(&mut self) -> Result<String, CompileError> {
+        self.check_not_keyword(self.current())?;
+        let name = match self.current().clone() {
+            Token::StringLiteral(n) => {
+                if let Some(kw) = Token::string_is_keyword(&n) {
+                    return Err(self.make_error(&format!(
+                        "Cannot use '{}' as a variable name - it's a reserved keyword.\n  \
+                         Tip: Try a more descriptive name like '{}_value' or 'my_{}'",
+                        n, kw, kw
+                    )));
+                }
+                self.advance();
+                n
+            }
+            Token::Identifier(n) => { self.advance(); n }
+            _ => return Err(self.err("Expected variable name after 'called'")),
+        };
+        Ok(name)
+    }
+    
+    /// The rest of a list type after "list": "of numbers", "of floats",
+    /// "of decimals with N places", "of texts", "of booleans", "of values"
+    /// (any mix) or "of lists of ..."
+    fn parse_list_type(&mut self) -> Result<Type, CompileError> {
+        self.skip_noise();
+        if *self.current() != Token::Of {
+            return Ok(Type::List(Box::new(Type::Unknown)));
+        }
+        self.advance();
+        self.skip_noise();
+        let element = match self.current().clone() {
+            Token::Number | Token::Int => Type::Integer,
+            Token::Float => Type::Float,
+            Token::Text => Type::String,
+            Token::Boolean => Type::Boolean,
+            Token::List => {
+                self.advance();
+                return Ok(Type::List(Box::new(self.parse_list_type()?)));
+            }
+            Token::Identifier(word) => match word.to_lowercase().as_str() {
+                "integers" | "ints" => Type::Integer,
+                "floats" => Type::Float,
+                "decimals" => return Ok(Type::List(Box::new(self.parse_decimal_type()?))),
+                "texts" | "strings" | "messages" => Type::String,
+                "booleans" | "bools" => Type::Boolean,
+                "values" | "things" => Type::Any,
+                "lists" | "arrays" => {
+                    self.advance();
+                    return Ok(Type::List(Box::new(self.parse_list_type()?)));
+                }
+                _ => return Err(self.err_expected("element type after 'list of'", &Token::Identifier(word))),
+            },
+            other => return Err(self.err_expected("element type after 'list of'", &other)),
+        };
+        self.advance();
+        Ok(Type::List(Box::new(element)))
+    }
+    
+    /// "constant" starting a declaration, not a variable that happens to be called that
+    fn at_constant(&self) -> bool {
+        matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("constant"))
+            && !matches!(self.peek(1), Token::To | Token::Is | Token::Equals)
+    }
+    
+    /// Parses "constant number called "limit" is 10" after the article.
+    /// The type may be left out: "a constant called "greeting" is "hello""
+    fn parse_constant_decl(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'constant'
+        self.skip_noise();
+        
+        let var_type = match self.current() {
+            _ if self.at_decimal_places() => Some(self.parse_decimal_type()?),
+            Token::Number | Token::Int => { self.advance(); Some(Type::Integer) }
+            Token::Float => { self.advance(); Some(Type::Float) }
+            Token::Text => { self.advance(); Some(Type::String) }
+            Token::Boolean => { self.advance(); Some(Type::Boolean) }
+            _ => None,
+        };
+        self.skip_noise();
+        
+        if *self.current() != Token::Called {
+            return Err(self.err(
+                "Missing 'called' after 'constant'\n  \
+                 Syntax: a constant <type> called \"<name>\" is <value>.\n  \
+                 Example: a constant number called \"limit\" is 10."
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        
+        let name = self.parse_declared_name()?;
+        self.skip_noise();
+        
+        if !matches!(self.current(), Token::Is | Token::Equals) {
+            return Err(self.err(&format!(
+                "The constant '{}' needs a value\n  \
+                 Syntax: a constant <type> called \"{}\" is <value>.",
+                name, name
+            )));
+        }
+        self.advance();
+        self.skip_noise();
+        let value = self.parse_expression()?;
+        
+        Ok(Statement::ConstDecl { name, var_type, value })
+    }
+    
+    /// "big number" as a type
+    fn at_big_number(&self) -> bool {
+        matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("big"))
+            && *self.peek(1) == Token::Number
+    }
+    
+    /// "decimal with N places" as a type
+    fn at_decimal_places(&self) -> bool {
+        *self.current() == Token::Decimal
+    }
+    
+    /// Consumes "decimal with N places" (or "decimals with N places" in a
+    /// list type); a decimal always says how many places it keeps
+    fn parse_decimal_type(&mut self) -> Result<Type, CompileError> {
+        let places = match (self.peek(1), self.peek(2), self.peek(3)) {
+            (Token::With, Token::IntegerLiteral(n), Token::Identifier(word))
+                if word.eq_ignore_ascii_case("places") || word.eq_ignore_ascii_case("place") =>
+            {
+                *n
+            }
+            _ => return Err(self.err(
+                "A decimal needs the number of places it keeps\n  \
+                 Syntax: a decimal with <places> places called \"<name>\" is <value>.\n  \
+                 Example: a decimal with 2 places called \"price\" is 19.99.\n  \
+                 Tip: use 'float' for a binary floating-point number"
+            )),
+        };
+        if places > i64::from(DECIMAL_MAX_PLACES) {
+            return Err(self.err(&format!(
+                "A decimal keeps at most {} places, but this one has {}",
+                DECIMAL_MAX_PLACES, places
+            )));
+        }
+        for _ in 0..4 {
+            self.advance();
+        }
+        Ok(Type::Decimal(places as u32))
+    }
+    
+    fn is_map_word(word: &str) -> bool {
+        word.eq_ignore_ascii_case("map") || word.eq_ignore_ascii_case("dictionary")
+    }
+    
+    /// The type named by a word before 'called' that is not a built-in type keyword
+    fn named_type(word: String) -> Type {
+        if Self::is_map_word(&word) {
+            Type::Map
+        } else {
+            Type::Struct(word)
+        }
+    }
+    
+    /// Parses "a map called "ages"" (or "dictionary"); maps start empty
+    fn parse_map_decl(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'map'
+        self.advance(); // consume 'called'
+        self.skip_noise();
+        
+        let name = self.parse_declared_name()?;
+        self.maps.insert(name.clone());
+        
+        Ok(Statement::VarDecl { name, var_type: Some(Type::Map), value: None })
+    }
+    
+    /// Parses the rest of "Set <key> in <map> to <value>"
+    fn parse_map_set(&mut self) -> Result<Statement, CompileError> {
+        let key = self.parse_primary()?;
+        self.skip_noise();
+        self.advance(); // consume 'in'
+        self.skip_noise();
+        
+        let map = match self.current().clone() {
+            Token::Identifier(n) | Token::StringLiteral(n) => { self.advance(); n }
+            other => return Err(self.err_expected("map name after 'in'", &other)),
+        };
+        self.skip_noise();
+        
+        if !matches!(self.current(), Token::To | Token::Equals) {
+            return Err(self.err(&format!(
+                "Missing 'to' after 'in {}'\n  \
+                 Syntax: Set <key> in {} to <value>.\n  \
+                 Example: Set \"Alice\" in {} to 30.",
+                map, map, map
+            )));
+        }
+        self.advance();
+        self.skip_noise();
+        let value = self.parse_expression()?;
+        
+        Ok(Statement::MapSet { map, key, value })
+    }
+    
+    /// Parses the field values after "with": name "Buddy", age 3 and ...
+    fn parse_struct_fields(&mut self, struct_name: &str) -> Result<Vec<(String, Expr)>, CompileError> {
+        let mut fields = Vec::new();
+        loop {
+            self.skip_noise();
+            // Unknown field names are reported by the analyzer
+            let field = match (self.struct_field_at(0, struct_name), self.current()) {
+                (Some(field), _) => field,
+                (None, Token::Identifier(word)) => word.to_lowercase(),
+                (None, other) => return Err(self.err_expected(&format!("a property of {}", struct_name), other)),
+            };
+            self.advance();
+            self.skip_noise();
+            let value = self.parse_expression()?;
+            fields.push((field, value));
+            
+            // A comma followed by anything but a field ends the statement
+            self.skip_noise();
+            let more = matches!(self.current(), Token::Comma | Token::And)
+                && (self.struct_field_at(1, struct_name).is_some()
+                    || matches!(self.peek(1), Token::Identifier(_)));
+            if !more {
+                break;
+            }
+            self.advance();
+        }
+        Ok(fields)
+    }
+    
+    /// The field of `struct_name` spelled by the token at `offset`. Field
+    /// names may be keywords, so they are compared token by token.
+    fn struct_field_at(&self, offset: usize, struct_name: &str) -> Option<String> {
+        let token = self.peek(offset);
+        self.structs.get(struct_name)?.iter().find(|field| match token {
+            Token::Identifier(word) => word.eq_ignore_ascii_case(field),
+            _ => Lexer::new(field).tokenize().first().is_some_and(|t| t.token == *token),
+        }).cloned()
+    }
+    
+    /// Parses "Set dog's age to 3." from the object name on
+    fn parse_field_set(&mut self) -> Result<Statement, CompileError> {
+        let object = match self.advance() {
+            Token::Identifier(n) | Token::StringLiteral(n) => n,
+            _ => return Err(self.err("Expected a name before 's")),
+        };
+        if !self.at_possessive() {
+            return Err(self.err("Expected 's after apostrophe for property access"));
+        }
+        self.advance();
+        self.advance();
+        self.skip_noise();
+        
+        let field = match self.current().clone() {
+            Token::Identifier(word) => { self.advance(); word.to_lowercase() }
+            other => return Err(self.err_expected("property name", &other)),
+        };
+        self.skip_noise();
+        
+        if !matches!(self.current(), Token::To | Token::Is | Token::Equals) {
+            return Err(self.err(&format!(
+                "Missing 'to' after {}'s {}\n  \
+                 Syntax: Set {}'s {} to <value>.",
+                object, field, object, field
+            )));
+        }
+        self.advance();
+        self.skip_noise();
+        let value = self.parse_expression()?;
+        
+        Ok(Statement::FieldSet { object, field, value })
+    }
+
+    fn parse_flag_schema_decl(&mut self) -> Result<Statement, CompileError> {
+        self.expect(&Token::Flag);
+        self.skip_noise();
+
+        self.expect(&Token::Called);
+        self.skip_noise();
+
+        let name = match self.current().clone() {
+            Token::StringLiteral(n) => {
+                self.advance();
+                n
+            }
+            Token::Identifier(n) => {
+                self.advance();
+                n
+            }
+            _ => return Err(self.err("Expected flag variable name after 'called'")),
+        };
+
+        self.skip_noise();
+        self.expect(&Token::Is);
+        self.skip_noise();
+
+        let short = match self.current().clone() {
+            Token::StringLiteral(v) => {
+                self.advance();
+                v
+            }
+            _ => return Err(self.err("Expected short flag alias string like '-n'")),
+        };
+
+        self.skip_noise();
+        self.expect(&Token::Or);
+        self.skip_noise();
+
+        let long = match self.current().clone() {
+            Token::StringLiteral(v) => {
+                self.advance();
+                v
+            }
+            _ => return Err(self.err("Expected long flag alias string like '--number'")),
+        };
+
+        self.skip_noise();
+        self.expect(&Token::Comma);
         self.skip_noise();
 
         // optional "it"
@@ -1041,13 +1923,32 @@ impl Parser {
             return self.parse_flag_schema_decl();
         }
         
+        if self.at_constant() {
+            return self.parse_constant_decl();
+        }
+        
+        // "a struct called ..." defines a struct; "a Dog called ..." makes one
+        if let Token::Identifier(type_name) = self.current().clone() {
+            if *self.peek(1) == Token::Called {
+                if type_name.eq_ignore_ascii_case("struct") {
+                    return self.parse_struct_def();
+                }
+                if Self::is_map_word(&type_name) {
+                    return self.parse_map_decl();
+                }
+                return self.parse_struct_var_decl(type_name);
+            }
+        }
+        
         // Parse type: number, int, float, text, boolean, list, buffer, file
         let var_type = match self.current() {
+            _ if self.at_big_number() => { self.advance(); self.advance(); Some(Type::BigNumber) }
+            _ if self.at_decimal_places() => Some(self.parse_decimal_type()?),
             Token::Number | Token::Int => { self.advance(); Some(Type::Integer) }
             Token::Float => { self.advance(); Some(Type::Float) }
             Token::Text => { self.advance(); Some(Type::String) }
             Token::Boolean => { self.advance(); Some(Type::Boolean) }
-            Token::List => { self.advance(); Some(Type::List(Box::new(Type::Unknown))) }
+            Token::List => { self.advance(); Some(self.parse_list_type()?) }
             Token::Buffer => { 
                 self.advance();
                 self.skip_noise();
@@ -1256,15 +2157,25 @@ impl Parser {
         
         self.skip_noise();
         
-        // Parse value if present: "is <value>"
+        // Parse value if present: "is <value>", or "contains a, b and c" for lists
         let value = if matches!(self.current(), Token::Is | Token::Equals) {
             self.advance();
             self.skip_noise();
             Some(self.parse_expression()?)
+        } else if matches!(var_type, Some(Type::List(_)))
+            && matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("contains"))
+        {
+            self.advance();
+            self.skip_noise();
+            Some(self.parse_list_contents()?)
         } else {
             None
         };
         
+        if matches!(var_type, Some(Type::List(_))) {
+            self.lists.insert(name.clone());
+        }
+        
         Ok(Statement::VarDecl {
             name,
             var_type,
@@ -1376,37 +2287,287 @@ impl Parser {
         })
     }
     
-    fn parse_while(&mut self) -> Result<Statement, CompileError> {
-        self.advance();
+    fn parse_depending(&mut self) -> Result<Statement, CompileError> {
+        // "Depending on <value>, when <values> then <actions>, ..., otherwise <actions>."
+        let line = self.current_info().map(|info| info.line).unwrap_or(0);
+        self.advance(); // consume 'depending'
         self.skip_noise();
         
-        let condition = self.parse_condition()?;
+        if !self.expect(&Token::On) {
+            return Err(self.err(
+                "Expected 'on' after 'depending'\n  \
+                 Syntax: Depending on <value>, when <value> then <action>, otherwise <action>.\n  \
+                 Example: Depending on the command, when \"start\" then print \"Starting\", otherwise print \"Unknown\"."
+            ));
+        }
+        self.skip_noise();
+        let subject = self.parse_expression()?;
         self.skip_noise();
         self.expect(&Token::Comma);
         self.skip_noise();
         
-        // Parse body: comma continues actions, period ends this while statement.
-        // Paragraph breaks are visual spacing and may appear after commas.
-        let mut body = Vec::new();
+        let mut arms = Vec::new();
+        let mut otherwise = None;
         loop {
-            if *self.current() == Token::EOF {
-                break;
-            }
-            if !body.is_empty() && self.is_block_terminator() {
-                break;
-            }
-            
-            let stmt = self.parse_statement()?;
-            body.push(stmt);
-            self.skip_noise();
-            
-            // Consume separator and decide whether to continue
-            if *self.current() == Token::Comma {
-                // Comma continues to next action in same sentence
-                self.advance();
-                self.skip_noise();
-                // Skip paragraph breaks after comma (visual spacing within sentence)
-                while *self.current() == Token::ParagraphBreak {
+            match self.current() {
+                Token::When => {
+                    self.advance();
+                    self.skip_noise();
+                    let patterns = self.parse_match_patterns()?;
+                    if !self.expect(&Token::Then) {
+                        return Err(self.err_expected("'then' after the values of a 'when' arm", self.current()));
+                    }
+                    self.skip_noise();
+                    self.expect(&Token::Comma);
+                    self.skip_noise();
+                    let body = self.parse_arm_body()?;
+                    arms.push((patterns, body));
+                }
+                Token::Otherwise | Token::Else if !arms.is_empty() => {
+                    self.advance();
+                    self.skip_noise();
+                    self.expect(&Token::Comma);
+                    self.skip_noise();
+                    otherwise = Some(self.parse_sentence_body()?);
+                    break;
+                }
+                Token::Period if !arms.is_empty() => {
+                    self.advance();
+                    self.skip_noise();
+                    break;
+                }
+                Token::EOF | Token::ParagraphBreak if !arms.is_empty() => break,
+                _ => return Err(self.err_expected("'when <value> then' in 'depending on'", self.current())),
+            }
+        }
+        
+        Ok(Statement::Depending { subject, arms, otherwise, line })
+    }
+    
+    /// Values of a `when` arm: `"a"`, `1 to 9`, separated by `or` or commas
+    fn parse_match_patterns(&mut self) -> Result<Vec<MatchPattern>, CompileError> {
+        let mut patterns = Vec::new();
+        loop {
+            // `"a" to ...` would otherwise read as a call to the function "a"
+            let start = match (self.current().clone(), self.peek(1)) {
+                (Token::StringLiteral(text), Token::To | Token::Through) => {
+                    self.advance();
+                    Expr::StringLit(text)
+                }
+                _ => self.parse_expression()?,
+            };
+            self.skip_noise();
+            if matches!(self.current(), Token::To | Token::Through) {
+                self.advance();
+                self.skip_noise();
+                let end = self.parse_expression()?;
+                patterns.push(MatchPattern::Range { start, end });
+            } else {
+                patterns.push(MatchPattern::Value(start));
+            }
+            self.skip_noise();
+            
+            if matches!(self.current(), Token::Or | Token::Comma) {
+                self.advance();
+                self.skip_noise();
+            } else {
+                return Ok(patterns);
+            }
+        }
+    }
+    
+    /// Comma-separated actions of a `when` arm. A comma followed by `when` or
+    /// `otherwise` starts the next arm.
+    fn parse_arm_body(&mut self) -> Result<Vec<Statement>, CompileError> {
+        let mut body = Vec::new();
+        loop {
+            self.parse_statement_into(&mut body)?;
+            self.skip_noise();
+            if *self.current() != Token::Comma {
+                return Ok(body);
+            }
+            self.advance();
+            self.skip_noise();
+            if matches!(self.current(), Token::When | Token::Otherwise | Token::Else) {
+                return Ok(body);
+            }
+        }
+    }
+    
+    fn parse_while(&mut self) -> Result<Statement, CompileError> {
+        self.advance();
+        self.skip_noise();
+        
+        let condition = self.parse_condition()?;
+        self.skip_noise();
+        self.expect(&Token::Comma);
+        self.skip_noise();
+        
+        let name = self.parse_loop_name()?;
+        let body = self.parse_loop_body()?;
+        Ok(Self::named_loop(name, Statement::While { condition, body }))
+    }
+    
+    fn parse_until(&mut self) -> Result<Statement, CompileError> {
+        // "Until <condition>, <actions>." - runs while the condition is false
+        self.advance();
+        self.skip_noise();
+        
+        let condition = self.parse_condition()?;
+        self.skip_noise();
+        self.expect(&Token::Comma);
+        self.skip_noise();
+        
+        let name = self.parse_loop_name()?;
+        let body = self.parse_loop_body()?;
+        Ok(Self::named_loop(name, Statement::Until { condition, body }))
+    }
+    
+    fn parse_loop_forever(&mut self) -> Result<Statement, CompileError> {
+        // "Loop forever, <actions>." - left only through break, exit or return
+        self.advance();
+        self.skip_noise();
+        
+        match self.current() {
+            Token::Identifier(id) if id.eq_ignore_ascii_case("forever") => { self.advance(); }
+            _ => return Err(self.err(
+                "Expected 'forever' after 'loop'\n  \
+                 Syntax: Loop forever, <action>.\n  \
+                 Example: Loop forever, increment the counter, if the counter is 10 then break."
+            )),
+        }
+        self.skip_noise();
+        self.expect(&Token::Comma);
+        self.skip_noise();
+        
+        let name = self.parse_loop_name()?;
+        let body = self.parse_loop_body()?;
+        Ok(Self::named_loop(name, Statement::Loop { body }))
+    }
+    
+    fn parse_keep_doing(&mut self) -> Result<Statement, CompileError> {
+        // "Keep doing <actions> until <condition>." - the actions run at least once
+        self.advance(); // consume 'keep'
+        self.advance(); // consume 'doing'
+        self.skip_noise();
+        self.expect(&Token::Comma);
+        self.skip_noise();
+        let name = self.parse_loop_name()?;
+        
+        let mut body = Vec::new();
+        while *self.current() != Token::Until {
+            if matches!(self.current(), Token::Period | Token::EOF) {
+                return Err(self.err(
+                    "Missing 'until <condition>' at the end of 'keep doing'\n  \
+                     Syntax: Keep doing <action>, <action> until <condition>."
+                ));
+            }
+            
+            self.parse_statement_into(&mut body)?;
+            self.skip_noise();
+            
+            if *self.current() == Token::Comma {
+                self.advance();
+                self.skip_noise();
+                while *self.current() == Token::ParagraphBreak {
+                    self.advance();
+                    self.skip_noise();
+                }
+            }
+        }
+        self.advance(); // consume 'until'
+        self.skip_noise();
+        
+        let condition = self.parse_condition()?;
+        Ok(Self::named_loop(name, Statement::DoUntil { body, condition }))
+    }
+    
+    /// The optional `called "rows",` after a loop's header comma
+    fn parse_loop_name(&mut self) -> Result<Option<String>, CompileError> {
+        if *self.current() != Token::Called {
+            return Ok(None);
+        }
+        self.advance();
+        self.skip_noise();
+        let name = match self.current().clone() {
+            Token::Identifier(n) | Token::StringLiteral(n) => { self.advance(); n }
+            _ => return Err(self.err(
+                "Missing loop name after 'called'\n  \
+                 Syntax: For each <variable> from <start> to <end>, called \"<name>\", <action>.\n  \
+                 Example: For each row from 1 to 10, called \"rows\", print the row."
+            )),
+        };
+        self.skip_noise();
+        if *self.current() != Token::Comma {
+            return Err(self.err(&format!("Expected ',' after the loop name '{}'", name)));
+        }
+        self.advance();
+        self.skip_noise();
+        Ok(Some(name))
+    }
+    
+    fn named_loop(name: Option<String>, loop_stmt: Statement) -> Statement {
+        match name {
+            Some(name) => Statement::NamedLoop { name, inner: Box::new(loop_stmt) },
+            None => loop_stmt,
+        }
+    }
+    
+    /// "break", or "break out of rows" to leave the loop called "rows"
+    fn parse_break(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'break'
+        let out_of = matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("out"))
+            && *self.peek(1) == Token::Of;
+        if !out_of {
+            return Ok(Statement::Break { loop_name: None });
+        }
+        self.advance();
+        self.advance();
+        self.skip_noise();
+        match self.current().clone() {
+            Token::Identifier(n) | Token::StringLiteral(n) => {
+                self.advance();
+                Ok(Statement::Break { loop_name: Some(n) })
+            }
+            other => Err(self.err_expected("loop name after 'break out of'", &other)),
+        }
+    }
+    
+    /// "continue", or "continue rows" to start the next pass of the loop called "rows"
+    fn parse_continue(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'continue'
+        match self.current().clone() {
+            Token::Identifier(n) | Token::StringLiteral(n) => {
+                self.advance();
+                Ok(Statement::Continue { loop_name: Some(n) })
+            }
+            _ => Ok(Statement::Continue { loop_name: None }),
+        }
+    }
+    
+    /// Parses a loop body up to and including the period that ends it.
+    /// Commas continue the body; paragraph breaks are visual spacing and may
+    /// appear after commas.
+    fn parse_loop_body(&mut self) -> Result<Vec<Statement>, CompileError> {
+        let mut body = Vec::new();
+        loop {
+            if *self.current() == Token::EOF {
+                break;
+            }
+            if !body.is_empty() && self.is_block_terminator() {
+                break;
+            }
+            
+            self.parse_statement_into(&mut body)?;
+            self.skip_noise();
+            
+            // Consume separator and decide whether to continue
+            if *self.current() == Token::Comma {
+                // Comma continues to next action in same sentence
+                self.advance();
+                self.skip_noise();
+                // Skip paragraph breaks after comma (visual spacing within sentence)
+                while *self.current() == Token::ParagraphBreak {
                     self.advance();
                     self.skip_noise();
                 }
@@ -1422,7 +2583,7 @@ impl Parser {
             }
         }
         
-        Ok(Statement::While { condition, body })
+        Ok(body)
     }
     
     /// Check if current token indicates end of a loop body inside a function
@@ -1445,9 +2606,12 @@ impl Parser {
         self.advance();
         self.skip_noise();
         
+        // "each byte in <text>" walks its bytes rather than its characters
+        let by_byte = *self.current() == Token::Byte;
         let variable = match self.current().clone() {
             Token::Identifier(n) => { self.advance(); n }
             Token::Number => { self.advance(); "number".to_string() }
+            Token::Byte => { self.advance(); "byte".to_string() }
             _ => return Err(self.err(
                 "Missing loop variable after 'for each'\n  \
                  Syntax: For each <variable> from <start> to <end>, <action>.\n  \
@@ -1462,19 +2626,20 @@ impl Parser {
             self.advance();
             self.skip_noise();
             
-            let start = self.parse_primary()?;
+            let start = match self.try_parse_file_lines() {
+                Some(lines) => lines,
+                None => self.parse_loop_primary()?,
+            };
             self.skip_noise();
             
             // Check if this is a range (has "to") or a collection iteration
-            if *self.current() == Token::To || matches!(self.current(), Token::Identifier(s) if s == "to") {
-                // Range: from X to Y
-                self.advance(); // consume "to"
-                self.skip_noise();
-                
-                let end = self.parse_primary()?;
+            if self.at_range_to() {
+                // Range: from X [down] to Y [by Z]
+                let range = self.parse_range_to(start, inclusive)?;
                 self.skip_noise();
                 self.expect(&Token::Comma);
                 self.skip_noise();
+                let name = self.parse_loop_name()?;
                 
                 // Parse body - terminated by period (single sentence loop body)
                 let mut body = Vec::new();
@@ -1486,8 +2651,7 @@ impl Parser {
                         break;
                     }
                     
-                    let stmt = self.parse_statement()?;
-                    body.push(stmt);
+                    self.parse_statement_into(&mut body)?;
                     self.skip_noise();
                     
                     if *self.current() == Token::Comma {
@@ -1504,15 +2668,11 @@ impl Parser {
                     }
                 }
                 
-                Ok(Statement::ForRange {
+                Ok(Self::named_loop(name, Statement::ForRange {
                     variable,
-                    range: Expr::Range {
-                        start: Box::new(start),
-                        end: Box::new(end),
-                        inclusive,
-                    },
+                    range,
                     body,
-                })
+                }))
             } else {
                 // Collection iteration: from <collection>
                 // start is actually the collection
@@ -1520,12 +2680,14 @@ impl Parser {
                     Expr::StringLit(s) => Expr::Identifier(s),
                     other => other,
                 };
+                let collection = Self::bytes_when(by_byte, collection);
                 
                 // Check for optional "treating X as Y" clause before the comma
                 let treating = self.try_parse_treating()?;
                 
                 self.expect(&Token::Comma);
                 self.skip_noise();
+                let name = self.parse_loop_name()?;
                 
                 // Parse body - terminated by period
                 let mut body = Vec::new();
@@ -1537,8 +2699,7 @@ impl Parser {
                         break;
                     }
                     
-                    let stmt = self.parse_statement()?;
-                    body.push(stmt);
+                    self.parse_statement_into(&mut body)?;
                     self.skip_noise();
                     
                     if *self.current() == Token::Comma {
@@ -1560,63 +2721,105 @@ impl Parser {
                     body
                 };
                 
-                Ok(Statement::ForEach {
+                Ok(Self::named_loop(name, Statement::ForEach {
                     variable,
                     collection,
                     body,
-                })
+                }))
             }
         } else if *self.current() == Token::In {
             self.advance();
             self.skip_noise();
             
             // Parse collection - convert StringLit to Identifier (quoted var names)
-            let collection = match self.parse_expression()? {
-                Expr::StringLit(s) => Expr::Identifier(s),
-                other => other,
+            let collection = match self.try_parse_file_lines() {
+                Some(lines) => lines,
+                None => match self.try_parse_bytes_of()? {
+                    Some(bytes) => bytes,
+                    None => match self.parse_expression()? {
+                        Expr::StringLit(s) => Expr::Identifier(s),
+                        other => other,
+                    },
+                },
             };
+            let collection = Self::bytes_when(by_byte, collection);
             self.skip_noise();
             self.expect(&Token::Comma);
             self.skip_noise();
+            let name = self.parse_loop_name()?;
             
-            // Parse body - terminated by period (single sentence loop body)
-            let mut body = Vec::new();
-            loop {
-                if matches!(self.current(), Token::EOF) {
-                    break;
-                }
-                if !body.is_empty() && matches!(self.current(), Token::ParagraphBreak) {
-                    break;
-                }
-                
-                let stmt = self.parse_statement()?;
-                body.push(stmt);
-                self.skip_noise();
-                
-                if *self.current() == Token::Comma {
-                    // Comma continues to next action in same for loop
-                    self.advance();
-                    self.skip_noise();
-                } else if *self.current() == Token::Period {
-                    // Period ends this for loop's body
-                    self.advance();
-                    self.skip_noise();
-                    break;
-                } else if *self.current() == Token::ParagraphBreak {
-                    break;
-                }
-            }
+            let body = self.parse_sentence_loop_body()?;
             
-            Ok(Statement::ForEach {
+            Ok(Self::named_loop(name, Statement::ForEach {
                 variable,
                 collection,
                 body,
-            })
+            }))
+        } else if *self.current() == Token::And
+            && matches!(self.peek(1), Token::Identifier(_))
+            && *self.peek(2) == Token::In
+        {
+            // For each key and value in <map>, <action>.
+            self.advance();
+            let value = match self.advance() {
+                Token::Identifier(n) => n,
+                _ => unreachable!(),
+            };
+            self.advance(); // consume 'in'
+            self.skip_noise();
+            
+            let map = match self.current().clone() {
+                Token::Identifier(n) | Token::StringLiteral(n) => { self.advance(); n }
+                other => return Err(self.err_expected("map name after 'in'", &other)),
+            };
+            self.skip_noise();
+            self.expect(&Token::Comma);
+            self.skip_noise();
+            let name = self.parse_loop_name()?;
+            
+            let body = self.parse_sentence_loop_body()?;
+            
+            Ok(Self::named_loop(name, Statement::ForEachEntry {
+                key: variable,
+                value,
+                map,
+                body,
+            }))
         } else {
             Err(self.err("Expected 'from', 'between', or 'in' after for each"))
         }
     }
     
+    /// Parses the actions of a single-sentence loop body, up to its period
+    fn parse_sentence_loop_body(&mut self) -> Result<Vec<Statement>, CompileError> {
+        let mut body = Vec::new();
+        loop {
+            if matches!(self.current(), Token::EOF) {
+                break;
+            }
+            if !body.is_empty() && matches!(self.current(), Token::ParagraphBreak) {
+                break;
+            }
+            
+            self.parse_statement_into(&mut body)?;
+            self.skip_noise();
+            
+            if *self.current() == Token::Comma {
+                // Comma continues to next action in same for loop
+                self.advance();
+                self.skip_noise();
+            } else if *self.current() == Token::Period {
+                // Period ends this for loop's body
+                self.advance();
+                self.skip_noise();
+                break;
+            } else if *self.current() == Token::ParagraphBreak {
+                break;
+            }
+        }
+        Ok(body)
+    }
+    
     fn parse_repeat(&mut self) -> Result<Statement, CompileError> {
         self.advance();
         self.skip_noise();
@@ -1627,6 +2830,7 @@ impl Parser {
         self.skip_noise();
         self.expect(&Token::Comma);
         self.skip_noise();
+        let name = self.parse_loop_name()?;
         
         // Parse body - terminated by period followed by major keyword or paragraph break
         let mut body = Vec::new();
@@ -1638,8 +2842,7 @@ impl Parser {
                 break;
             }
             
-            let stmt = self.parse_statement()?;
-            body.push(stmt);
+            self.parse_statement_into(&mut body)?;
             self.skip_noise();
             
             if matches!(self.current(), Token::Period) {
@@ -1651,7 +2854,7 @@ impl Parser {
             }
         }
         
-        Ok(Statement::Repeat { count, body })
+        Ok(Self::named_loop(name, Statement::Repeat { count, body }))
     }
     
     fn parse_return(&mut self) -> Result<Statement, CompileError> {
@@ -1659,42 +2862,100 @@ impl Parser {
         self.skip_noise();
         
         if matches!(self.current(), Token::Period | Token::EOF | Token::Newline) {
-            Ok(Statement::Return { value: None })
-        } else {
-            // Handle "Return a type, expr." syntax (type declaration is optional)
-            if matches!(self.current(), Token::A | Token::An) {
+            return Ok(Statement::Return { values: Vec::new() });
+        }
+        
+        // Handle "Return a type, expr." syntax (type declaration is optional)
+        let types = self.parse_return_types()?;
+        let values = match types.len() {
+            0 | 1 => vec![self.parse_expression()?],
+            count => self.parse_return_values(count)?,
+        };
+        if !types.is_empty() && self.body_return_type.is_none() {
+            self.body_return_type = Some(Self::return_type_from(types));
+        }
+        Ok(Statement::Return { values })
+    }
+    
+    fn return_type_from(mut types: Vec<Type>) -> Type {
+        match types.len() {
+            0 => Type::Void,
+            1 => types.remove(0),
+            _ => Type::Tuple(types),
+        }
+    }
+    
+    /// The declared types in "Return a number and a boolean, ...", through
+    /// the (optional) comma. Empty when the values follow 'Return' directly.
+    fn parse_return_types(&mut self) -> Result<Vec<Type>, CompileError> {
+        let mut types = Vec::new();
+        loop {
+            let has_article = matches!(self.current(), Token::A | Token::An);
+            if has_article {
                 self.advance();
                 self.skip_noise();
-                
-                // Check if this is a type keyword followed by comma
-                if matches!(self.current(), Token::Number | Token::Text | Token::Boolean) {
+            }
+            let return_type = match self.current().clone() {
+                _ if self.at_big_number() => { self.advance(); self.advance(); Type::BigNumber }
+                _ if self.at_decimal_places() => self.parse_decimal_type()?,
+                Token::Number | Token::Int => { self.advance(); Type::Integer }
+                Token::Float => { self.advance(); Type::Float }
+                Token::Text => { self.advance(); Type::String }
+                Token::Boolean => { self.advance(); Type::Boolean }
+                Token::File => { self.advance(); Type::File }
+                Token::List if has_article => { self.advance(); self.parse_list_type()? }
+                // "Return a Dog, <value>" or "Return a map, <value>"
+                Token::Identifier(type_name)
+                    if has_article && matches!(self.peek(1), Token::Comma | Token::And) =>
+                {
                     self.advance();
-                    self.skip_noise();
-                    
-                    if *self.current() == Token::Comma {
-                        self.advance();
-                        self.skip_noise();
-                        // Now parse the actual return expression
-                        let value = self.parse_expression()?;
-                        return Ok(Statement::Return { value: Some(value) });
-                    }
+                    Self::named_type(type_name)
                 }
-                // If not "a type,", backtrack isn't possible, so error
-                return Err(self.err("Expected type after 'a' in return statement"));
-            }
+                _ if has_article => return Err(self.err("Expected type after 'a' in return statement")),
+                _ => return Ok(types),
+            };
+            types.push(return_type);
             
-            let value = self.parse_expression()?;
-            Ok(Statement::Return { value: Some(value) })
+            self.skip_noise();
+            if *self.current() == Token::And {
+                self.advance();
+                self.skip_noise();
+                continue;
+            }
+            self.expect(&Token::Comma);
+            self.skip_noise();
+            return Ok(types);
         }
     }
     
-    fn parse_exit(&mut self) -> Result<Statement, CompileError> {
-        self.advance(); // consume 'exit'
-        self.skip_noise();
-        
-        // Parse exit code (default to 0 if not provided)
-        let code = if matches!(self.current(), Token::Period | Token::EOF | Token::Newline) {
-            Expr::IntegerLit(0)
+    /// "q and ok" after "Return a number and a boolean,": one value per
+    /// declared type
+    fn parse_return_values(&mut self, count: usize) -> Result<Vec<Expr>, CompileError> {
+        let mut values = Vec::new();
+        for i in 0..count {
+            if i > 0 {
+                self.skip_noise();
+                if !self.expect(&Token::And) {
+                    return Err(self.err(&format!(
+                        "This return declares {} values, but only {} follow\n  \
+                         Example: Return a number and a boolean, q and ok.",
+                        count, i
+                    )));
+                }
+                self.skip_noise();
+            }
+            values.push(self.parse_expression()?);
+        }
+        Ok(values)
+    }
+    
+    fn parse_exit(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'exit'
+        self.skip_noise();
+        
+        // Parse exit code (default to 0 if not provided)
+        let code = if matches!(self.current(), Token::Period | Token::EOF | Token::Newline) {
+            Expr::IntegerLit(0)
         } else {
             self.parse_expression()?
         };
@@ -1884,6 +3145,7 @@ impl Parser {
         let path_info = path_info.ok_or_else(|| self.err(
             "Missing file path - add 'at <path>' to specify which file to open"
         ))?;
+        self.files.insert(name.clone());
         
         // Build the statement
         match path_info {
@@ -1980,10 +3242,11 @@ impl Parser {
     
     fn apply_treating_to_statement(&self, stmt: Statement, variable: &str, match_val: &Expr, replacement: &Expr) -> Statement {
         match stmt {
-            Statement::Print { value, without_newline } => {
+            Statement::Print { value, without_newline, to_stderr } => {
                 Statement::Print {
                     value: self.apply_treating_to_expr(value, variable, match_val, replacement),
                     without_newline,
+                    to_stderr,
                 }
             }
             Statement::If { condition, then_block, else_if_blocks, else_block } => {
@@ -2003,10 +3266,14 @@ impl Parser {
                     value: self.apply_treating_to_expr(value, variable, match_val, replacement),
                 }
             }
-            Statement::FunctionCall { name, args } => {
+            Statement::FunctionCall { name, args, named } => {
                 Statement::FunctionCall {
                     name,
                     args: args.into_iter().map(|a| self.apply_treating_to_expr(a, variable, match_val, replacement)).collect(),
+                    named: named
+                        .into_iter()
+                        .map(|(param, a)| (param, self.apply_treating_to_expr(a, variable, match_val, replacement)))
+                        .collect(),
                 }
             }
             Statement::FileWrite { file, value } => {
@@ -2076,9 +3343,11 @@ impl Parser {
         self.skip_noise();
         
         // Get loop variable name
+        let by_byte = *self.current() == Token::Byte;
         let variable = match self.current().clone() {
             Token::Identifier(n) => { self.advance(); n }
             Token::Number => { self.advance(); "number".to_string() }
+            Token::Byte => { self.advance(); "byte".to_string() }
             _ => return Err(self.err(
                 "Missing loop variable after 'each'\n  \
                  Syntax: each <variable> from <collection>\n  \
@@ -2088,8 +3357,8 @@ impl Parser {
         
         self.skip_noise();
         
-        // Expect "from"
-        if *self.current() != Token::From {
+        // Expect "from" or "in"
+        if *self.current() != Token::From && *self.current() != Token::In {
             return Err(self.err(&format!(
                 "Missing 'from' after 'each {}'\n  \
                  Syntax: each {} from <collection>\n  \
@@ -2102,22 +3371,19 @@ impl Parser {
         
         // Get collection to iterate over - could be a range (1 to 15) or a collection expression
         // First parse a primary/simple expression
-        let first = self.parse_primary()?;
+        let first = match self.try_parse_file_lines() {
+            Some(lines) => lines,
+            None => self.parse_loop_primary()?,
+        };
         self.skip_noise();
         
         // Check if this is a range: <start> to <end>
         // But only if first is a simple value (number/identifier), not a list or other collection
-        let is_list_or_collection = matches!(first, Expr::ListLit { .. } | Expr::PropertyAccess { .. });
-        let collection = if *self.current() == Token::To && !is_list_or_collection {
-            self.advance();
-            self.skip_noise();
-            let end = self.parse_primary()?;
+        let is_list_or_collection = matches!(first, Expr::ListLit { .. } | Expr::PropertyAccess { .. } | Expr::FileLines { .. });
+        let collection = if self.at_range_to() && !is_list_or_collection {
+            let range = self.parse_range_to(first, true)?;
             self.skip_noise();
-            Expr::Range {
-                start: Box::new(first),
-                end: Box::new(end),
-                inclusive: true,
-            }
+            range
         } else {
             // Not a range - could be a more complex expression, but we already have first
             // Check if there are binary operators to continue parsing
@@ -2128,7 +3394,104 @@ impl Parser {
         // Check for optional "treating X as Y" clause
         let treating = self.try_parse_treating()?;
         
-        Ok(Some((variable, collection, treating)))
+        Ok(Some((variable, Self::bytes_when(by_byte, collection), treating)))
+    }
+    
+    /// The lines of an open file or a standard stream as a loop collection:
+    /// "each line in source", "each line from standard input"
+    fn try_parse_file_lines(&mut self) -> Option<Expr> {
+        if let Some(source) = self.try_parse_standard_stream() {
+            return Some(Expr::FileLines { source });
+        }
+        match self.current().clone() {
+            Token::Identifier(name) | Token::StringLiteral(name) if self.files.contains(&name) => {
+                self.advance();
+                Some(Expr::FileLines { source: name })
+            }
+            _ => None,
+        }
+    }
+    
+    /// "the bytes of <text or buffer>" as a loop collection
+    fn try_parse_bytes_of(&mut self) -> Result<Option<Expr>, CompileError> {
+        let the = usize::from(*self.current() == Token::The);
+        if !(*self.peek(the) == Token::Bytes && *self.peek(the + 1) == Token::Of) {
+            return Ok(None);
+        }
+        self.pos += the + 2;
+        self.skip_noise();
+        let text = self.parse_primary()?;
+        Ok(Some(Expr::TextBytes { text: Box::new(text) }))
+    }
+    
+    /// The start of a loop collection that may be a range
+    fn parse_loop_primary(&mut self) -> Result<Expr, CompileError> {
+        match self.try_parse_bytes_of()? {
+            Some(bytes) => Ok(bytes),
+            None => self.parse_primary(),
+        }
+    }
+    
+    /// "each byte in <text>" reads like "each byte in the bytes of <text>";
+    /// ranges, lines and lists are walked as they are
+    fn bytes_when(by_byte: bool, collection: Expr) -> Expr {
+        match collection {
+            Expr::Range { .. } | Expr::FileLines { .. } | Expr::TextBytes { .. } => collection,
+            _ if by_byte => Expr::TextBytes { text: Box::new(collection) },
+            _ => collection,
+        }
+    }
+    
+    /// Whether the next words continue a range: "to" or "down to"
+    fn at_range_to(&self) -> bool {
+        match self.current() {
+            Token::To => !matches!(self.peek(1), Token::Standard | Token::Stderr),
+            Token::Identifier(s) => s.eq_ignore_ascii_case("to") || s.eq_ignore_ascii_case("down"),
+            _ => false,
+        }
+    }
+    
+    /// Parses "[down] to <end> [by <step>]" after the start of a range.
+    fn parse_range_to(&mut self, start: Expr, inclusive: bool) -> Result<Expr, CompileError> {
+        let descending = matches!(self.current(), Token::Identifier(s) if s.eq_ignore_ascii_case("down"));
+        if descending {
+            self.advance();
+            self.skip_noise();
+        }
+        self.advance(); // consume "to"
+        self.skip_noise();
+        
+        let end = self.parse_primary()?;
+        self.skip_noise();
+        self.finish_range(start, end, inclusive, descending)
+    }
+    
+    /// Parses an optional "by <step>" and builds the range. Ranges between two
+    /// number literals count down on their own when the start is larger; with
+    /// variable bounds only "down to" does, so "from 1 to n" stays empty for n = 0.
+    fn finish_range(&mut self, start: Expr, end: Expr, inclusive: bool, descending: bool) -> Result<Expr, CompileError> {
+        let step = if *self.current() == Token::By {
+            self.advance();
+            self.skip_noise();
+            let step = self.parse_primary()?;
+            self.skip_noise();
+            Some(Box::new(step))
+        } else {
+            None
+        };
+        
+        let descending = descending || matches!(
+            (start.as_number_literal(), end.as_number_literal()),
+            (Some(s), Some(e)) if s > e
+        );
+        
+        Ok(Expr::Range {
+            start: Box::new(start),
+            end: Box::new(end),
+            step,
+            inclusive,
+            descending,
+        })
     }
     
     /// Wrap a statement in a ForEach loop with the given variable and collection.
@@ -2149,8 +3512,8 @@ impl Parser {
                 
                 if *self.current() == Token::If {
                     // Extract the default value from the base print statement
-                    if let Statement::Print { value: default_value, .. } = body.pop().unwrap() {
-                        let conditional_print = self.parse_conditional_print(default_value)?;
+                    if let Statement::Print { value: default_value, to_stderr, .. } = body.pop().unwrap() {
+                        let conditional_print = self.parse_conditional_print(default_value, to_stderr)?;
                         body.push(conditional_print);
                     } else {
                         return Err(self.err("'but if' conditional branching only works with print statements"));
@@ -2173,8 +3536,7 @@ impl Parser {
                         break;
                     }
                     
-                    let stmt = self.parse_statement()?;
-                    body.push(stmt);
+                    self.parse_statement_into(&mut body)?;
                     self.skip_noise();
                     
                     if *self.current() == Token::Comma {
@@ -2248,11 +3610,8 @@ impl Parser {
         }
         
         // Parse source: "standard input" or file name
-        let source = if *self.current() == Token::Standard {
-            self.advance();
-            self.skip_noise();
-            self.expect(&Token::Input);
-            "stdin".to_string()
+        let source = if let Some(stream) = self.try_parse_standard_stream() {
+            stream
         } else {
             match self.current().clone() {
                 Token::Identifier(n) => { self.advance(); n }
@@ -2380,11 +3739,15 @@ impl Parser {
                     self.skip_noise();
                     
                     // Get file name
-                    let file = match self.current().clone() {
-                        Token::Identifier(n) => { self.advance(); n }
-                        Token::StringLiteral(n) => { self.advance(); n }
-                        Token::File => { self.advance(); "File".to_string() }
-                        _ => return Err(self.err("Expected file name after 'to'")),
+                    let file = if let Some(stream) = self.try_parse_standard_stream() {
+                        stream
+                    } else {
+                        match self.current().clone() {
+                            Token::Identifier(n) => { self.advance(); n }
+                            Token::StringLiteral(n) => { self.advance(); n }
+                            Token::File => { self.advance(); "File".to_string() }
+                            _ => return Err(self.err("Expected file name after 'to'")),
+                        }
                     };
                     
                     return Ok(Statement::FileWriteNewline { file });
@@ -2454,11 +3817,15 @@ impl Parser {
         self.skip_noise();
         
         // Get file name
-        let file = match self.current().clone() {
-            Token::Identifier(n) => { self.advance(); n }
-            Token::StringLiteral(n) => { self.advance(); n }
-            Token::File => { self.advance(); "File".to_string() }
-            _ => return Err(self.err_expected("file name after 'to'", self.current())),
+        let file = if let Some(stream) = self.try_parse_standard_stream() {
+            stream
+        } else {
+            match self.current().clone() {
+                Token::Identifier(n) => { self.advance(); n }
+                Token::StringLiteral(n) => { self.advance(); n }
+                Token::File => { self.advance(); "File".to_string() }
+                _ => return Err(self.err_expected("file name after 'to'", self.current())),
+            }
         };
         
         Ok(Statement::FileWrite { file, value })
@@ -2492,6 +3859,48 @@ impl Parser {
         self.advance(); // consume 'delete'
         self.skip_noise();
         
+        // "Remove element <N> from <list>"
+        if *self.current() == Token::Element {
+            self.advance();
+            self.skip_noise();
+            let position = self.parse_primary()?;
+            self.skip_noise();
+            if *self.current() != Token::From {
+                return Err(self.err(
+                    "Expected 'from' after the element to remove\n  \
+                     Syntax: Remove element <N> from <list>.\n  \
+                     Example: Remove element 2 from scores."
+                ));
+            }
+            self.advance();
+            self.skip_noise();
+            let list = self.parse_list_name("'from'")?;
+            return Ok(Statement::ListRemoveAt { list, position });
+        }
+        
+        // "Remove <key> from <map>", or "Remove <value> from <list>"
+        if !matches!(self.current(), Token::The | Token::File) {
+            let key = self.parse_primary()?;
+            self.skip_noise();
+            if *self.current() != Token::From {
+                return Err(self.err(
+                    "Expected 'from' after the key to remove\n  \
+                     Syntax: Remove <key> from <map>.\n  \
+                     Example: Remove \"Bob\" from ages."
+                ));
+            }
+            self.advance();
+            self.skip_noise();
+            let map = match self.current().clone() {
+                Token::Identifier(n) | Token::StringLiteral(n) => { self.advance(); n }
+                other => return Err(self.err_expected("map name after 'from'", &other)),
+            };
+            if self.lists.contains(&map) {
+                return Ok(Statement::ListRemove { list: map, value: key });
+            }
+            return Ok(Statement::MapRemove { map, key });
+        }
+        
         // Skip optional "the"
         if *self.current() == Token::The {
             self.advance();
@@ -2523,6 +3932,24 @@ impl Parser {
         self.advance();
         self.skip_noise();
         
+        // Optional error kind: On error "not found" <action>.
+        let error = if let Token::StringLiteral(name) = self.current().clone() {
+            let Some(kind) = ErrorKind::from_name(&name) else {
+                let names: Vec<&str> = ErrorKind::ALL.iter().map(|k| k.name()).collect();
+                let mut err = self.err(&format!("Unknown error kind \"{}\" after 'on error'", name))
+                    .with_hint(&format!("Known kinds: {}", names.join(", ")));
+                if let Some(suggestion) = find_similar_keyword(&name, &names) {
+                    err = err.with_suggestion(&suggestion);
+                }
+                return Err(err);
+            };
+            self.advance();
+            self.skip_noise();
+            Some(kind)
+        } else {
+            None
+        };
+        
         // Parse comma-separated actions until end of sentence
         let actions = self.parse_sentence_body()?;
         
@@ -2534,31 +3961,68 @@ impl Parser {
             ));
         }
         
-        Ok(Statement::OnError { actions })
+        Ok(Statement::OnError { error, actions })
+    }
+    
+    /// Parses the property after "error": 's code or 's message
+    fn parse_error_property(&mut self) -> Option<Expr> {
+        if !self.at_possessive() {
+            return None;
+        }
+        self.advance();
+        self.advance();
+        self.skip_noise();
+        
+        let expr = match self.current() {
+            Token::Identifier(id) if id.eq_ignore_ascii_case("message") => Expr::LastErrorMessage,
+            Token::Identifier(id) if id.eq_ignore_ascii_case("code") => Expr::LastError,
+            _ => return None,
+        };
+        self.advance();
+        Some(expr)
     }
     
     fn parse_auto_error(&mut self) -> Result<Statement, CompileError> {
-        // Feature deferred - auto error catching not yet implemented
-        Err(self.err(
-            "'auto error catching' is not yet implemented.\n  \
-             Use 'on error <action>.' for manual error handling instead."
-        ))
+        // "Auto error catching."
+        self.advance(); // consume 'auto'
+        self.parse_error_catching(true)
     }
     
     fn parse_enable(&mut self) -> Result<Statement, CompileError> {
-        // Feature deferred - enable error catching not yet implemented
-        Err(self.err(
-            "'enable error catching' is not yet implemented.\n  \
-             Use 'on error <action>.' for manual error handling instead."
-        ))
+        // "Enable error catching." or "Enable auto error catching."
+        self.advance(); // consume 'enable'
+        self.skip_noise();
+        if *self.current() == Token::Auto {
+            self.advance();
+        }
+        self.parse_error_catching(true)
     }
     
     fn parse_disable(&mut self) -> Result<Statement, CompileError> {
-        // Feature deferred - disable error catching not yet implemented
-        Err(self.err(
-            "'disable error catching' is not yet implemented.\n  \
-             Use 'on error <action>.' for manual error handling instead."
-        ))
+        // "Disable error catching." or "Disable auto error catching."
+        self.advance(); // consume 'disable'
+        self.skip_noise();
+        if *self.current() == Token::Auto {
+            self.advance();
+        }
+        self.parse_error_catching(false)
+    }
+    
+    fn parse_error_catching(&mut self, enabled: bool) -> Result<Statement, CompileError> {
+        self.skip_noise();
+        if *self.current() != Token::Error || *self.peek(1) != Token::Catching {
+            let verb = if enabled { "Enable" } else { "Disable" };
+            return Err(self.err(&format!(
+                "Expected 'error catching'\n  \
+                 Syntax: {} error catching.",
+                verb
+            )));
+        }
+        self.advance();
+        self.advance();
+        
+        self.auto_error_catching = enabled;
+        Ok(Statement::ErrorCatching { enabled })
     }
     
     fn parse_resize(&mut self) -> Result<Statement, CompileError> {
@@ -2709,6 +4173,119 @@ impl Parser {
         Ok(Statement::ListAppend { list, value })
     }
     
+    /// Parses "Insert <value> at position <N> of <list>"
+    fn parse_list_insert(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'insert'
+        self.skip_noise();
+        // Quoted text before "at" would otherwise read as a call: "greet" at ...
+        let value = match (self.current().clone(), self.peek(1)) {
+            (Token::StringLiteral(text), Token::On) if !text.contains('{') => {
+                self.advance();
+                Expr::StringLit(text)
+            }
+            _ => self.parse_additive()?,
+        };
+        self.skip_noise();
+        
+        if *self.current() != Token::On {  // "at" is tokenized as On
+            return Err(self.err(
+                "Expected 'at' after the value to insert\n  \
+                 Syntax: Insert <value> at position <N> of <list>.\n  \
+                 Example: Insert 5 at position 1 of scores."
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        if matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("position")) {
+            self.advance();
+            self.skip_noise();
+        }
+        
+        let position = self.parse_primary()?;
+        self.skip_noise();
+        if *self.current() != Token::Of {
+            return Err(self.err(
+                "Expected 'of' after the position to insert at\n  \
+                 Example: Insert 5 at position 1 of scores."
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        let list = self.parse_list_name("'of'")?;
+        
+        Ok(Statement::ListInsert { list, value, position })
+    }
+    
+    /// Parses "Sort <list> [in ascending/descending order]", "Reverse <list>"
+    /// and "Shuffle <list>"
+    fn parse_list_reorder(&mut self) -> Result<Statement, CompileError> {
+        let action = match self.advance() {
+            Token::Identifier(word) => word.to_lowercase(),
+            _ => String::new(),
+        };
+        self.skip_noise();
+        let list = self.parse_list_name(&format!("'{}'", action))?;
+        match action.as_str() {
+            "reverse" => return Ok(Statement::ListReverse { list }),
+            "shuffle" => return Ok(Statement::ListShuffle { list }),
+            _ => {}
+        }
+        self.skip_noise();
+        
+        let mut descending = false;
+        if *self.current() == Token::In {
+            if let Token::Identifier(order) = self.peek(1).clone() {
+                if order.eq_ignore_ascii_case("ascending") || order.eq_ignore_ascii_case("descending") {
+                    descending = order.eq_ignore_ascii_case("descending");
+                    self.pos += 2;
+                    self.skip_noise();
+                    if matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("order")) {
+                        self.advance();
+                    }
+                }
+            }
+        }
+        
+        Ok(Statement::ListSort { list, descending })
+    }
+    
+    /// Parses the elements after "contains": "1, 2 and 3"
+    fn parse_list_contents(&mut self) -> Result<Expr, CompileError> {
+        let mut elements = vec![self.parse_additive()?];
+        loop {
+            self.skip_noise();
+            match self.current() {
+                Token::Comma => {
+                    self.advance();
+                    self.skip_noise();
+                    if *self.current() == Token::And {
+                        self.advance();
+                        self.skip_noise();
+                    }
+                }
+                Token::And => {
+                    self.advance();
+                    self.skip_noise();
+                }
+                _ => break,
+            }
+            elements.push(self.parse_additive()?);
+        }
+        Ok(Expr::ListLit { elements })
+    }
+    
+    /// A list name, optionally after "the"
+    fn parse_list_name(&mut self, after: &str) -> Result<String, CompileError> {
+        if *self.current() == Token::The {
+            self.advance();
+            self.skip_noise();
+        }
+        match self.current().clone() {
+            Token::Identifier(n) | Token::StringLiteral(n) => { self.advance(); Ok(n) }
+            other => Err(self.err_expected(&format!("list name after {}", after), &other)),
+        }
+    }
+    
     fn parse_library_decl(&mut self) -> Result<Statement, CompileError> {
         // Library "name" version "1.0".
         self.advance(); // consume 'library'
@@ -2841,6 +4418,10 @@ impl Parser {
     }
     
     fn parse_identifier_statement(&mut self) -> Result<Statement, CompileError> {
+        if *self.peek(1) == Token::Apostrophe {
+            return self.parse_field_set();
+        }
+        
         let name = match self.current().clone() {
             Token::Identifier(n) => { self.advance(); n }
             _ => return Err(self.err("Expected identifier")),
@@ -2858,6 +4439,7 @@ impl Parser {
         Ok(Statement::FunctionCall {
             name,
             args: vec![],
+            named: vec![],
         })
     }
     
@@ -2880,6 +4462,7 @@ impl Parser {
         
         // Parse parameters: "with <name>" or "with a <type> called <name> and ..."
         let mut params = Vec::new();
+        let mut defaults = Vec::new();
         if *self.current() == Token::With || *self.current() == Token::Of {
             self.advance();
             self.skip_noise();
@@ -2888,7 +4471,7 @@ impl Parser {
                 self.skip_noise();
                 
                 // Check for simple parameter: just an identifier
-                if let Token::Identifier(n) = self.current().clone() {
+                if let (Token::Identifier(n), false) = (self.current().clone(), *self.peek(1) == Token::Called) {
                     // Simple parameter without type
                     self.advance();
                     params.push((n, Type::Unknown));
@@ -2901,11 +4484,19 @@ impl Parser {
                     }
                     
                     let param_type = match self.current() {
+                        _ if self.at_big_number() => { self.advance(); self.advance(); Type::BigNumber }
+                        _ if self.at_decimal_places() => self.parse_decimal_type()?,
                         Token::Number => { self.advance(); Type::Integer }
                         Token::Text => { self.advance(); Type::String }
                         Token::Boolean => { self.advance(); Type::Boolean }
                         Token::File => { self.advance(); Type::File }
-                        Token::List => { self.advance(); Type::List(Box::new(Type::Unknown)) }
+                        Token::List => { self.advance(); self.parse_list_type()? }
+                        // "a Dog called "dog""
+                        Token::Identifier(type_name) if *self.peek(1) == Token::Called => {
+                            let type_name = type_name.clone();
+                            self.advance();
+                            Self::named_type(type_name)
+                        }
                         _ => Type::Unknown,
                     };
                     
@@ -2925,10 +4516,28 @@ impl Parser {
                         )),
                     };
                     
+                    if param_type == Type::Map {
+                        self.maps.insert(param_name.clone());
+                    }
+                    if param_type == Type::File {
+                        self.files.insert(param_name.clone());
+                    }
+                    if matches!(param_type, Type::List(_)) {
+                        self.lists.insert(param_name.clone());
+                    }
                     params.push((param_name, param_type));
                 }
                 
+                // "which defaults to 3", filled in at each call that leaves it out
                 self.skip_noise();
+                if self.at_defaults_to() {
+                    self.pos += 3;
+                    self.skip_noise();
+                    let param_name = params.last().map(|(n, _)| n.clone()).unwrap_or_default();
+                    defaults.push((param_name, self.parse_expression()?));
+                    self.skip_noise();
+                }
+                
                 if *self.current() == Token::And {
                     self.advance();
                     self.skip_noise();
@@ -2953,30 +4562,16 @@ impl Parser {
             self.advance();
             self.skip_noise();
             
-            // Check for return type declaration: "Return a number," or "Return number,"
-            // Skip optional article
-            if matches!(self.current(), Token::A | Token::An) {
-                self.advance();
-                self.skip_noise();
-            }
-            
-            if matches!(self.current(), Token::Number | Token::Text | Token::Boolean | Token::File) {
-                return_type = match self.current() {
-                    Token::Number => { self.advance(); Type::Integer }
-                    Token::Text => { self.advance(); Type::String }
-                    Token::Boolean => { self.advance(); Type::Boolean }
-                    Token::File => { self.advance(); Type::File }
-                    _ => Type::Void,
-                };
-                self.skip_noise();
-                self.expect(&Token::Comma);
-                self.skip_noise();
-            }
-            
-            // Parse the return expression
-            let expr = self.parse_condition()?;
-            body.push(Statement::Return { value: Some(expr) });
+            // "Return a number," or "Return a number and a boolean,"
+            let types = self.parse_return_types()?;
+            let values = match types.len() {
+                0 | 1 => vec![self.parse_condition()?],
+                count => self.parse_return_values(count)?,
+            };
+            return_type = Self::return_type_from(types);
+            body.push(Statement::Return { values });
         }
+        let outer_return_type = self.body_return_type.take();
         
         // Continue parsing body until paragraph break
         while !matches!(self.current(), Token::ParagraphBreak | Token::EOF) {
@@ -2993,8 +4588,7 @@ impl Parser {
             if matches!(self.current(), Token::ParagraphBreak | Token::EOF) {
                 break;
             }
-            let stmt = self.parse_statement()?;
-            body.push(stmt);
+            self.parse_statement_into(&mut body)?;
 
             self.skip_noise();
             if *self.current() == Token::Comma {
@@ -3008,9 +4602,17 @@ impl Parser {
             self.advance();
         }
         
+        // "To check with ..., Return a number and a boolean, ..." declares its
+        // types in the body
+        if let (Type::Void, Some(declared)) = (&return_type, self.body_return_type.take()) {
+            return_type = declared;
+        }
+        self.body_return_type = outer_return_type;
+        
         Ok(Statement::FunctionDef {
             name,
             params,
+            defaults,
             return_type,
             body,
         })
@@ -3044,14 +4646,15 @@ impl Parser {
                 };
                 let call_stmt = Statement::FunctionCall { 
                     name: name.clone(),
-                    args: vec![arg_expr]
+                    args: vec![arg_expr],
+                    named: Vec::new(),
                 };
                 return self.wrap_in_loop_expansion(variable, collection, call_stmt);
             }
             
             // Parse arguments separated by 'and'
-            loop {
-                let arg = self.parse_expression()?;
+            while !self.named_arg_at(0) {
+                let arg = self.parse_call_argument()?;
                 args.push(arg);
                 
                 self.skip_noise();
@@ -3063,16 +4666,64 @@ impl Parser {
                 }
             }
         }
+        let named = self.parse_named_args()?;
         
-        Ok(Statement::FunctionCall { name, args })
+        Ok(Statement::FunctionCall { name, args, named })
+    }
+    
+    fn parse_call_argument(&mut self) -> Result<Expr, CompileError> {
+        let outer = std::mem::replace(&mut self.in_call_arguments, true);
+        let arg = self.parse_expression();
+        self.in_call_arguments = outer;
+        arg
+    }
+    
+    fn at_defaults_to(&self) -> bool {
+        matches!(self.current(), Token::Identifier(w) if w.eq_ignore_ascii_case("which"))
+            && matches!(self.peek(1), Token::Identifier(w) if w.eq_ignore_ascii_case("defaults"))
+            && *self.peek(2) == Token::To
+    }
+    
+    /// Whether the tokens at `offset` start a named argument: "retries as 5"
+    fn named_arg_at(&self, offset: usize) -> bool {
+        matches!(self.peek(offset), Token::Identifier(_) | Token::StringLiteral(_))
+            && *self.peek(offset + 1) == Token::As
+    }
+    
+    /// Named arguments after the positional ones: "with retries as 5 and
+    /// verbose as true". The 'with' is already gone when it introduced the call.
+    fn parse_named_args(&mut self) -> Result<Vec<(String, Expr)>, CompileError> {
+        let mut named = Vec::new();
+        self.skip_noise();
+        if *self.current() == Token::With && self.named_arg_at(1) {
+            self.advance();
+            self.skip_noise();
+        }
+        while self.named_arg_at(0) {
+            let param = match self.advance() {
+                Token::Identifier(n) | Token::StringLiteral(n) => n,
+                _ => unreachable!("named_arg_at checked the name"),
+            };
+            self.advance(); // consume 'as'
+            self.skip_noise();
+            named.push((param, self.parse_expression()?));
+            
+            self.skip_noise();
+            if *self.current() == Token::And && self.named_arg_at(1) {
+                self.advance();
+                self.skip_noise();
+            } else {
+                break;
+            }
+        }
+        Ok(named)
     }
     
     fn parse_block(&mut self) -> Result<Vec<Statement>, CompileError> {
         let mut statements = Vec::new();
         
-        let stmt = self.parse_statement()?;
-        statements.push(stmt);
-        let mut last_stmt_was_on_error = matches!(statements.last(), Some(Statement::OnError { .. }));
+        self.parse_statement_into(&mut statements)?;
+        let mut last_stmt_was_on_error = Self::ends_with_on_error(&statements);
         
         loop {
             let continue_with_comma = matches!(self.current(), Token::Comma)
@@ -3103,9 +4754,8 @@ impl Parser {
                 }
             }
 
-            let stmt = self.parse_statement()?;
-            last_stmt_was_on_error = matches!(stmt, Statement::OnError { .. });
-            statements.push(stmt);
+            self.parse_statement_into(&mut statements)?;
+            last_stmt_was_on_error = Self::ends_with_on_error(&statements);
         }
         
         Ok(statements)
@@ -3125,8 +4775,7 @@ impl Parser {
                 break;
             }
             
-            let stmt = self.parse_statement()?;
-            statements.push(stmt);
+            self.parse_statement_into(&mut statements)?;
             self.skip_noise();
             
             // Comma continues to next action, period ends
@@ -3393,6 +5042,7 @@ impl Parser {
     
     fn parse_expression(&mut self) -> Result<Expr, CompileError> {
         let mut expr = self.parse_additive()?;
+        expr = self.parse_text_operations(expr)?;
         
         // Check for type casting with 'as' keyword
         self.skip_noise();
@@ -3408,6 +5058,8 @@ impl Parser {
             
             // Parse target type
             let target_type = match self.current() {
+                _ if self.at_big_number() => { self.advance(); self.advance(); Type::BigNumber }
+                _ if self.at_decimal_places() => self.parse_decimal_type()?,
                 Token::Number | Token::Int => { self.advance(); Type::Integer }
                 Token::Text => { self.advance(); Type::String }
                 Token::Boolean => { self.advance(); Type::Boolean }
@@ -3424,15 +5076,79 @@ impl Parser {
         Ok(expr)
     }
     
-    fn parse_additive(&mut self) -> Result<Expr, CompileError> {
-        let mut left = self.parse_multiplicative()?;
-        
+    /// Text operations written after their text: "line split by \",\"",
+    /// "words joined with \" \"", "name in uppercase", "line trimmed",
+    /// "line starts with \"#\"", "line ends with \".\"", "line contains \"error\"",
+    /// and "first followed by second" for lists (which also take "contains")
+    fn parse_text_operations(&mut self, mut expr: Expr) -> Result<Expr, CompileError> {
         loop {
             self.skip_noise();
-            let op = match self.current() {
-                Token::Add => Some(BinaryOperator::Add),
-                Token::Subtract => Some(BinaryOperator::Subtract),
-                _ => None,
+            let word = match self.current() {
+                Token::Identifier(word) => word.to_lowercase(),
+                Token::In => "in".to_string(),
+                _ => break,
+            };
+            let text = Box::new(expr);
+            expr = match (word.as_str(), self.peek(1)) {
+                ("split", Token::By) => {
+                    self.pos += 2;
+                    self.skip_noise();
+                    Expr::TextSplit { text, separator: Box::new(self.parse_additive()?) }
+                }
+                ("joined", Token::With) => {
+                    self.pos += 2;
+                    self.skip_noise();
+                    Expr::TextJoin { list: text, separator: Box::new(self.parse_additive()?) }
+                }
+                ("in", Token::Identifier(case))
+                    if case.eq_ignore_ascii_case("uppercase") || case.eq_ignore_ascii_case("lowercase") =>
+                {
+                    let upper = case.eq_ignore_ascii_case("uppercase");
+                    self.pos += 2;
+                    Expr::TextCase { text, upper }
+                }
+                ("trimmed", _) => {
+                    self.advance();
+                    Expr::TextTrim { text }
+                }
+                ("starts" | "ends", Token::With) => {
+                    let check = if word == "starts" { TextCheckKind::StartsWith } else { TextCheckKind::EndsWith };
+                    self.pos += 2;
+                    self.skip_noise();
+                    Expr::TextCheck { text, check, part: Box::new(self.parse_additive()?) }
+                }
+                ("contains", _) => {
+                    self.advance();
+                    self.skip_noise();
+                    Expr::TextCheck { text, check: TextCheckKind::Contains, part: Box::new(self.parse_additive()?) }
+                }
+                ("followed", Token::By) => {
+                    self.pos += 2;
+                    self.skip_noise();
+                    Expr::ListConcat { left: text, right: Box::new(self.parse_additive()?) }
+                }
+                ("rounded", _) => {
+                    self.advance();
+                    self.parse_rounding(text)?
+                }
+                _ => {
+                    expr = *text;
+                    break;
+                }
+            };
+        }
+        Ok(expr)
+    }
+    
+    fn parse_additive(&mut self) -> Result<Expr, CompileError> {
+        let mut left = self.parse_multiplicative()?;
+        
+        loop {
+            self.skip_noise();
+            let op = match self.current() {
+                Token::Add => Some(BinaryOperator::Add),
+                Token::Subtract => Some(BinaryOperator::Subtract),
+                _ => None,
             };
             
             if let Some(operator) = op {
@@ -3482,7 +5198,7 @@ impl Parser {
     }
     
     fn parse_bitwise(&mut self) -> Result<Expr, CompileError> {
-        let mut left = self.parse_primary()?;
+        let mut left = self.parse_power()?;
         
         loop {
             self.skip_noise();
@@ -3498,7 +5214,7 @@ impl Parser {
             if let Some(operator) = op {
                 self.advance();
                 self.skip_noise();
-                let right = self.parse_primary()?;
+                let right = self.parse_power()?;
                 left = Expr::BinaryOp {
                     left: Box::new(left),
                     op: operator,
@@ -3512,6 +5228,24 @@ impl Parser {
         Ok(left)
     }
     
+    /// x to the power of y, binding tighter than the arithmetic operators
+    /// and grouping to the right: 2 to the power of 3 to the power of 2 is 2^9
+    fn parse_power(&mut self) -> Result<Expr, CompileError> {
+        let base = self.parse_primary()?;
+        self.skip_noise();
+        let the = usize::from(*self.peek(1) == Token::The);
+        let at_power = *self.current() == Token::To
+            && matches!(self.peek(1 + the), Token::Identifier(word) if word.eq_ignore_ascii_case("power"))
+            && *self.peek(2 + the) == Token::Of;
+        if !at_power {
+            return Ok(base);
+        }
+        self.pos += 3 + the;
+        self.skip_noise();
+        let exponent = self.parse_power()?;
+        Ok(Expr::Power { base: Box::new(base), exponent: Box::new(exponent) })
+    }
+    
     fn parse_format_string(&self, s: &str) -> Vec<FormatPart> {
         let mut parts = Vec::new();
         let mut current_literal = String::new();
@@ -3599,6 +5333,8 @@ impl Parser {
         let mut lexer = Lexer::new(content);
         let tokens = lexer.tokenize();
         let mut parser = Parser::new(tokens);
+        parser.structs = self.structs.clone();
+        parser.maps = self.maps.clone();
         // Use parse_and_expr to handle comparisons like "0 is equal to 0"
         match parser.parse_and_expr() {
             Ok(expr) => {
@@ -3613,9 +5349,304 @@ impl Parser {
         }
     }
     
+    /// Whether the current tokens are a possessive 's
+    fn at_possessive(&self) -> bool {
+        *self.current() == Token::Apostrophe
+            && matches!(self.peek(1), Token::Identifier(s) if s.eq_ignore_ascii_case("s"))
+    }
+    
+    /// Parses "'s <property>" after `object`, including "'s duration in seconds"
+    fn parse_property_access(&mut self, object: String) -> Result<Expr, CompileError> {
+        if !self.at_possessive() {
+            return Err(self.err("Expected 's after apostrophe for property access"));
+        }
+        self.advance();
+        self.advance();
+        self.skip_noise();
+        
+        // ages's "Alice" and ages's name look up a key; built-in words stay properties
+        if self.maps.contains(&object) {
+            match self.current().clone() {
+                Token::StringLiteral(key) => {
+                    self.advance();
+                    return Ok(Expr::MapGet { map: object, key: Box::new(Expr::StringLit(key)) });
+                }
+                Token::Identifier(word) if word.eq_ignore_ascii_case("count") => {
+                    self.advance();
+                    return Ok(Expr::PropertyAccess { object, property: ObjectProperty::Size });
+                }
+                Token::Identifier(word) if ObjectProperty::from_word(&word).is_none() => {
+                    self.advance();
+                    return Ok(Expr::MapGet { map: object, key: Box::new(Expr::Identifier(word)) });
+                }
+                _ => {}
+            }
+        }
+        let property = self.parse_property()?;
+        
+        // Check for "in seconds" / "in milliseconds" or just "seconds"/"milliseconds" for duration/elapsed
+        if matches!(property, ObjectProperty::Duration | ObjectProperty::Elapsed) {
+            self.skip_noise();
+            // Handle both "elapsed in seconds" and "elapsed seconds"
+            if *self.current() == Token::In {
+                self.advance();
+                self.skip_noise();
+            }
+            // Now check for unit
+            let unit = match self.current() {
+                Token::Seconds | Token::Second => Some(ast::TimeUnit::Seconds),
+                Token::Milliseconds | Token::Millisecond => Some(ast::TimeUnit::Milliseconds),
+                _ => None,
+            };
+            if let Some(unit) = unit {
+                self.advance();
+                return Ok(Expr::DurationCast {
+                    value: Box::new(Expr::PropertyAccess { object, property }),
+                    unit,
+                });
+            }
+        }
+        
+        Ok(Expr::PropertyAccess { object, property })
+    }
+    
+    /// Parses the property after an object's 's. The lexer reads the word
+    /// after 's as a plain identifier, so a struct can have fields called
+    /// `first` or `hour`; such a word only names the built-in property when
+    /// no defined struct has a field spelled that way.
+    fn parse_property(&mut self) -> Result<ObjectProperty, CompileError> {
+        let word = match self.current() {
+            Token::Identifier(word) => word.to_lowercase(),
+            other => return Err(self.err_expected("property name", other)),
+        };
+        self.advance();
+        
+        if self.structs.values().flatten().any(|field| *field == word) {
+            return Ok(ObjectProperty::Field(word));
+        }
+        match ObjectProperty::from_word(&word) {
+            // "start time" and "end time"
+            Some(property @ (ObjectProperty::StartTime | ObjectProperty::EndTime)) => {
+                if *self.current() == Token::Time {
+                    self.advance();
+                }
+                Ok(property)
+            }
+            Some(property) => Ok(property),
+            // The analyzer checks the field against the object's struct
+            None => Ok(ObjectProperty::Field(word)),
+        }
+    }
+    
+    /// Parses the property after "arguments's"
+    fn parse_arguments_property(&mut self) -> Result<Expr, CompileError> {
+        let word = match self.current() {
+            Token::Identifier(word) => word.to_lowercase(),
+            _ => String::new(),
+        };
+        let expr = match word.as_str() {
+            "count" => Expr::ArgumentCount,
+            "name" => Expr::ArgumentName,
+            "first" => Expr::ArgumentFirst,
+            "second" => Expr::ArgumentSecond,
+            "last" => Expr::ArgumentLast,
+            "empty" => Expr::ArgumentEmpty,
+            "all" => Expr::ArgumentAll,
+            "raw" => Expr::ArgumentRaw,
+            _ => return Err(self.err_expected("arguments property (count, first, last, empty, all, raw)", self.current())),
+        };
+        self.advance();
+        Ok(expr)
+    }
+    
+    /// Parses the property after "environment's"
+    fn parse_environment_property(&mut self) -> Result<Expr, CompileError> {
+        let expr = match self.current() {
+            Token::StringLiteral(env_name) => Expr::EnvironmentVariable {
+                name: Box::new(Expr::StringLit(env_name.clone())),
+            },
+            Token::Identifier(word) => match word.to_lowercase().as_str() {
+                "count" => Expr::EnvironmentVariableCount,
+                "first" => Expr::EnvironmentVariableFirst,
+                "last" => Expr::EnvironmentVariableLast,
+                "empty" => Expr::EnvironmentVariableEmpty,
+                _ => return Err(self.err_expected("environment property", self.current())),
+            },
+            _ => return Err(self.err_expected("environment property", self.current())),
+        };
+        self.advance();
+        Ok(expr)
+    }
+    
+    /// `any`, `all` or `none` followed by `of`, or by a variable and `in`/`from`
+    fn at_quantifier(&self) -> Option<QuantifierKind> {
+        let kind = match self.current() {
+            Token::All => QuantifierKind::All,
+            Token::Identifier(word) if word.eq_ignore_ascii_case("any") => QuantifierKind::Any,
+            Token::Identifier(word) if word.eq_ignore_ascii_case("none") => QuantifierKind::None,
+            _ => return None,
+        };
+        let starts = match self.peek(1) {
+            Token::Of => true,
+            Token::Identifier(_) | Token::Number => matches!(self.peek(2), Token::In | Token::From),
+            _ => false,
+        };
+        starts.then_some(kind)
+    }
+    
+    /// Parses "any n in scores is greater than 90", "all n from 1 to 5 are
+    /// positive" or "none of the arguments are \"-h\"". The rest of the sentence
+    /// is a comparison on the element, so the element is put back in front of it.
+    fn parse_quantifier(&mut self, kind: QuantifierKind) -> Result<Expr, CompileError> {
+        self.advance();
+        self.skip_noise();
+        
+        let variable = match self.advance() {
+            Token::Of => QUANTIFIED_ELEMENT.to_string(),
+            Token::Identifier(name) => name,
+            _ => "number".to_string(),
+        };
+        if variable != QUANTIFIED_ELEMENT {
+            self.advance(); // "in" or "from"
+        }
+        self.skip_noise();
+        let collection = self.parse_quantified_collection()?;
+        self.skip_noise();
+        
+        let (line, column) = self.tokens.get(self.pos).map_or((0, 0), |t| (t.line, t.column));
+        self.tokens.insert(self.pos, TokenInfo { token: Token::Identifier(variable.clone()), line, column });
+        let condition = self.parse_comparison()?;
+        
+        Ok(Expr::Quantifier {
+            kind,
+            variable,
+            collection: Box::new(collection),
+            condition: Box::new(condition),
+        })
+    }
+    
+    /// A list, `arguments's all`, "the arguments", or a range "1 to 10"
+    fn parse_quantified_collection(&mut self) -> Result<Expr, CompileError> {
+        let offset = usize::from(*self.current() == Token::The);
+        if *self.peek(offset) == Token::Arguments && *self.peek(offset + 1) != Token::Apostrophe {
+            self.pos += offset + 1;
+            return Ok(Expr::ArgumentAll);
+        }
+        
+        let start = self.parse_primary()?;
+        self.skip_noise();
+        if self.at_range_to() {
+            return self.parse_range_to(start, true);
+        }
+        Ok(match start {
+            Expr::StringLit(name) => Expr::Identifier(name),
+            other => other,
+        })
+    }
+    
+    /// `character`/`characters` followed by a position
+    fn at_text_slice(&self) -> bool {
+        matches!(self.current(), Token::Identifier(word)
+            if word.eq_ignore_ascii_case("characters") || word.eq_ignore_ascii_case("character"))
+            && matches!(self.peek(1), Token::IntegerLiteral(_) | Token::FloatLiteral(_) | Token::Identifier(_))
+    }
+    
+    /// Parses "characters 2 to 5 of name" or "character 3 of name"
+    fn parse_text_slice(&mut self) -> Result<Expr, CompileError> {
+        self.advance();
+        self.skip_noise();
+        let start = self.parse_primary()?;
+        self.skip_noise();
+        let end = if *self.current() == Token::To {
+            self.advance();
+            self.skip_noise();
+            let end = self.parse_primary()?;
+            self.skip_noise();
+            Some(Box::new(end))
+        } else {
+            None
+        };
+        if *self.current() != Token::Of {
+            return Err(self.err(
+                "Expected 'of' after the character positions\n  \
+                 Example: characters 2 to 5 of name"
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        let text = self.parse_primary()?;
+        Ok(Expr::TextSlice {
+            text: Box::new(text),
+            start: Box::new(start),
+            end,
+        })
+    }
+    
+    fn at_list_slice(&self) -> bool {
+        matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("elements"))
+            && matches!(self.peek(1), Token::IntegerLiteral(_) | Token::Identifier(_))
+    }
+    
+    /// Parses "elements 2 to 4 of scores"
+    fn parse_list_slice(&mut self) -> Result<Expr, CompileError> {
+        self.advance();
+        self.skip_noise();
+        let start = self.parse_primary()?;
+        self.skip_noise();
+        if *self.current() != Token::To {
+            return Err(self.err(
+                "Expected 'to' after the first element position\n  \
+                 Example: elements 2 to 4 of scores"
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        let end = self.parse_primary()?;
+        self.skip_noise();
+        if *self.current() != Token::Of {
+            return Err(self.err(
+                "Expected 'of' after the element positions\n  \
+                 Example: elements 2 to 4 of scores"
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        let list = self.parse_primary()?;
+        Ok(Expr::ListSlice {
+            list: Box::new(list),
+            start: Box::new(start),
+            end: Box::new(end),
+        })
+    }
+    
+    /// Parses "position of \",\" in line"
+    fn parse_text_find(&mut self) -> Result<Expr, CompileError> {
+        self.pos += 2; // "position of"
+        self.skip_noise();
+        let part = self.parse_primary()?;
+        self.skip_noise();
+        if *self.current() != Token::In {
+            return Err(self.err(
+                "Expected 'in' after the text to look for\n  \
+                 Example: position of \",\" in line"
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        let text = self.parse_primary()?;
+        Ok(Expr::TextFind {
+            part: Box::new(part),
+            text: Box::new(text),
+        })
+    }
+    
     fn parse_primary(&mut self) -> Result<Expr, CompileError> {
         self.skip_noise();
         
+        if let Some(kind) = self.at_quantifier() {
+            return self.parse_quantifier(kind);
+        }
+        
         match self.current().clone() {
             Token::Not => {
                 self.advance();
@@ -3635,6 +5666,14 @@ impl Parser {
                     operand: Box::new(operand),
                 })
             }
+            // A lone "byte" is the loop variable of "for each byte in ..."
+            Token::Byte if !matches!(
+                self.peek(1),
+                Token::IntegerLiteral(_) | Token::FloatLiteral(_) | Token::Identifier(_) | Token::StringLiteral(_)
+            ) => {
+                self.advance();
+                Ok(Expr::Identifier("byte".to_string()))
+            }
             Token::Byte => {
                 // byte N of buffer
                 self.advance();
@@ -3673,6 +5712,10 @@ impl Parser {
                 self.advance();
                 Ok(Expr::IntegerLit(n))
             }
+            Token::BigIntegerLiteral(digits) => {
+                self.advance();
+                Ok(Expr::BigLit(digits))
+            }
             Token::FloatLiteral(n) => {
                 self.advance();
                 Ok(Expr::FloatLit(n))
@@ -3690,13 +5733,16 @@ impl Parser {
                 }
                 
                 // Check if this is a function call: "name" of/to/with/on args
-                if matches!(self.current(), Token::Of | Token::To | Token::With | Token::On) {
+                // ("to standard error" after a print is an output stream instead)
+                let to_stream = *self.current() == Token::To && matches!(self.peek(1), Token::Standard | Token::Stderr);
+                let names_outer_parameter = self.in_call_arguments && *self.current() == Token::With && self.named_arg_at(1);
+                if matches!(self.current(), Token::Of | Token::To | Token::With | Token::On) && !to_stream && !names_outer_parameter {
                     self.advance();
                     self.skip_noise();
                     
                     let mut args = Vec::new();
-                    loop {
-                        let arg = self.parse_expression()?;
+                    while !self.named_arg_at(0) {
+                        let arg = self.parse_call_argument()?;
                         args.push(arg);
                         
                         self.skip_noise();
@@ -3726,102 +5772,11 @@ impl Parser {
                         }
                     }
                     
-                    Ok(Expr::FunctionCall { name: s, args })
+                    let named = self.parse_named_args()?;
+                    Ok(Expr::FunctionCall { name: s, args, named })
                 } else if *self.current() == Token::Apostrophe {
                     // Property access on quoted variable: "job timer"'s duration
-                    self.advance();
-                    if let Token::Identifier(prop_s) = self.current().clone() {
-                        if prop_s.to_lowercase() == "s" {
-                            self.advance();
-                            self.skip_noise();
-                            
-                            let property = match self.current() {
-                                // Buffer properties
-                                Token::Size => ObjectProperty::Size,
-                                Token::Capacity => ObjectProperty::Capacity,
-                                Token::Empty => ObjectProperty::Empty,
-                                Token::Full => ObjectProperty::Full,
-                                // Time properties
-                                Token::Hour => ObjectProperty::Hour,
-                                Token::Minute => ObjectProperty::Minute,
-                                Token::Second => ObjectProperty::Second,
-                                Token::Day => ObjectProperty::Day,
-                                Token::Month => ObjectProperty::Month,
-                                Token::Year => ObjectProperty::Year,
-                                Token::Unix => ObjectProperty::Unix,
-                                // Timer properties
-                                Token::Duration => ObjectProperty::Duration,
-                                Token::Elapsed => ObjectProperty::Elapsed,
-                                Token::Running => ObjectProperty::Running,
-                                // Handle single-quoted multi-word property names and 'start'
-                                Token::Identifier(ref prop_name) => {
-                                    match prop_name.to_lowercase().as_str() {
-                                        "start" => ObjectProperty::StartTime,
-                                        "start time" => ObjectProperty::StartTime,
-                                        "end time" => ObjectProperty::EndTime,
-                                        "duration" => ObjectProperty::Duration,
-                                        "elapsed" => ObjectProperty::Elapsed,
-                                        "running" => ObjectProperty::Running,
-                                        _ => return Err(self.err_expected("property name", self.current())),
-                                    }
-                                }
-                                _ => return Err(self.err_expected("property name", self.current())),
-                            };
-                            self.advance();
-                            
-                            // Check for "in seconds" / "in milliseconds" or just "seconds"/"milliseconds" for duration/elapsed
-                            if matches!(property, ObjectProperty::Duration | ObjectProperty::Elapsed) {
-                                self.skip_noise();
-                                // Handle both "elapsed in seconds" and "elapsed seconds"
-                                if *self.current() == Token::In {
-                                    self.advance();
-                                    self.skip_noise();
-                                }
-                                // Now check for unit
-                                if matches!(self.current(), Token::Seconds | Token::Second | Token::Milliseconds | Token::Millisecond) {
-                                    let unit = match self.current() {
-                                        Token::Seconds | Token::Second => {
-                                            self.advance();
-                                            ast::TimeUnit::Seconds
-                                        }
-                                        Token::Milliseconds | Token::Millisecond => {
-                                            self.advance();
-                                            ast::TimeUnit::Milliseconds
-                                        }
-                                        _ => unreachable!(),
-                                    };
-                                    return Ok(Expr::DurationCast {
-                                        value: Box::new(Expr::PropertyAccess { object: s, property }),
-                                        unit,
-                                    });
-                                }
-                            }
-                            
-                            return Ok(Expr::PropertyAccess { object: s, property });
-                        }
-                    }
-                    // Handle "start time" and "end time" as multi-word properties
-                    if let Token::Identifier(ref id) = self.current() {
-                        if id == "start" {
-                            self.advance();
-                            self.skip_noise();
-                            if *self.current() == Token::Time {
-                                self.advance();
-                                return Ok(Expr::PropertyAccess { object: s, property: ObjectProperty::StartTime });
-                            }
-                        }
-                    }
-                    if let Token::Identifier(ref id) = self.current() {
-                        if id.to_lowercase() == "end" {
-                            self.advance();
-                            self.skip_noise();
-                            if *self.current() == Token::Time {
-                                self.advance();
-                                return Ok(Expr::PropertyAccess { object: s, property: ObjectProperty::EndTime });
-                            }
-                        }
-                    }
-                    Err(self.err("Expected 's after apostrophe for property access"))
+                    self.parse_property_access(s)
                 } else {
                     Ok(Expr::StringLit(s))
                 }
@@ -3844,32 +5799,27 @@ impl Parser {
                     self.skip_noise();
                     
                     // Check for property access: "current time's hour"
-                    if *self.current() == Token::Apostrophe {
+                    if self.at_possessive() {
                         self.advance();
-                        if let Token::Identifier(s) = self.current().clone() {
-                            if s.to_lowercase() == "s" {
-                                self.advance();
-                                self.skip_noise();
-                                
-                                let property = match self.current() {
-                                    Token::Hour => ObjectProperty::Hour,
-                                    Token::Minute => ObjectProperty::Minute,
-                                    Token::Second => ObjectProperty::Second,
-                                    Token::Day => ObjectProperty::Day,
-                                    Token::Month => ObjectProperty::Month,
-                                    Token::Year => ObjectProperty::Year,
-                                    Token::Unix => ObjectProperty::Unix,
-                                    _ => return Err(self.err_expected("time property (hour, minute, second, day, month, year)", self.current())),
-                                };
-                                self.advance();
-                                
-                                // Return property access on current time
-                                return Ok(Expr::PropertyAccess {
-                                    object: "_current_time".to_string(),
-                                    property,
-                                });
-                            }
-                        }
+                        self.advance();
+                        self.skip_noise();
+                        
+                        let property = match self.parse_property()? {
+                            property @ (ObjectProperty::Hour
+                            | ObjectProperty::Minute
+                            | ObjectProperty::Second
+                            | ObjectProperty::Day
+                            | ObjectProperty::Month
+                            | ObjectProperty::Year
+                            | ObjectProperty::Unix) => property,
+                            _ => return Err(self.err("Expected time property (hour, minute, second, day, month, year)")),
+                        };
+                        
+                        // Return property access on current time
+                        return Ok(Expr::PropertyAccess {
+                            object: "_current_time".to_string(),
+                            property,
+                        });
                     }
                     
                     Ok(Expr::CurrentTime)
@@ -3894,199 +5844,114 @@ impl Parser {
                     }
                 }
                 
-                if *self.current() == Token::Apostrophe {
+                if self.at_possessive() {
                     self.advance();
-                    if let Token::Identifier(s) = self.current().clone() {
-                        if s.to_lowercase() == "s" {
-                            self.advance();
-                            self.skip_noise();
-                            
-                            return match self.current() {
-                                Token::Count => { self.advance(); Ok(Expr::ArgumentCount) }
-                                Token::Identifier(ref id) if id.to_lowercase() == "name" => { 
-                                    self.advance(); Ok(Expr::ArgumentName) 
-                                }
-                                Token::First => { self.advance(); Ok(Expr::ArgumentFirst) }
-                                Token::Identifier(ref id) if id.to_lowercase() == "second" => { 
-                                    self.advance(); Ok(Expr::ArgumentSecond) 
-                                }
-                                Token::Last => { self.advance(); Ok(Expr::ArgumentLast) }
-                                Token::Empty => { self.advance(); Ok(Expr::ArgumentEmpty) }
-                                Token::All => { self.advance(); Ok(Expr::ArgumentAll) }
-                                Token::Raw => { self.advance(); Ok(Expr::ArgumentRaw) }
-                                _ => Err(self.err_expected("arguments property (count, first, last, empty, all, raw)", self.current())),
-                            };
-                        }
-                    }
+                    self.advance();
+                    self.skip_noise();
+                    return self.parse_arguments_property();
                 }
                 Err(self.err("Expected 's after 'arguments'"))
             }
             
+            Token::Error => {
+                self.advance();
+                self.skip_noise();
+                self.parse_error_property()
+                    .ok_or_else(|| self.err("Expected 's code or 's message after 'error'"))
+            }
+            
             Token::Environment => {
                 self.advance();
                 self.skip_noise();
                 
-                if *self.current() == Token::Apostrophe {
+                if self.at_possessive() {
                     self.advance();
-                    if let Token::Identifier(s) = self.current().clone() {
-                        if s.to_lowercase() == "s" {
-                            self.advance();
-                            self.skip_noise();
-                            
-                            return match self.current() {
-                                Token::Count => { self.advance(); Ok(Expr::EnvironmentVariableCount) }
-                                Token::First => { self.advance(); Ok(Expr::EnvironmentVariableFirst) }
-                                Token::Last => { self.advance(); Ok(Expr::EnvironmentVariableLast) }
-                                Token::Empty => { self.advance(); Ok(Expr::EnvironmentVariableEmpty) }
-                                Token::StringLiteral(env_name) => {
-                                    let env_name = env_name.clone();
-                                    self.advance();
-                                    Ok(Expr::EnvironmentVariable { name: Box::new(Expr::StringLit(env_name)) })
-                                }
-                                _ => Err(self.err_expected("environment property", self.current())),
-                            };
-                        }
-                    }
+                    self.advance();
+                    self.skip_noise();
+                    return self.parse_environment_property();
                 }
                 Err(self.err("Expected 's after 'environment'"))
             }
             
+            Token::Identifier(_) if self.at_random() => self.parse_random(),
+            Token::Identifier(_) if self.at_math_function() => self.parse_math_function(),
+            Token::Identifier(_) if self.at_text_slice() => self.parse_text_slice(),
+            Token::Identifier(_) if self.at_list_slice() => self.parse_list_slice(),
+            Token::Identifier(word) if word.eq_ignore_ascii_case("position") && *self.peek(1) == Token::Of => {
+                self.parse_text_find()
+            }
             Token::Identifier(name) => {
                 self.advance();
                 self.skip_noise();
                 
-                // Check for property access: identifier's property
-                if *self.current() == Token::Apostrophe {
-                    self.advance();
-                    // Expect 's' followed by property name
-                    if let Token::Identifier(s) = self.current().clone() {
-                        if s.to_lowercase() == "s" {
+                // ages has "Bob"
+                if self.maps.contains(&name) {
+                    if let Token::Identifier(word) = self.current() {
+                        if word.eq_ignore_ascii_case("has") {
                             self.advance();
                             self.skip_noise();
-                            
-                            // Special handling for arguments's and environment's
-                            let name_lower = name.to_lowercase();
-                            if name_lower == "arguments" || name_lower == "args" {
-                                return match self.current() {
-                                    Token::Count => { self.advance(); Ok(Expr::ArgumentCount) }
-                                    Token::Identifier(ref id) if id.to_lowercase() == "name" => { 
-                                        self.advance(); Ok(Expr::ArgumentName) 
-                                    }
-                                    Token::First => { self.advance(); Ok(Expr::ArgumentFirst) }
-                                    Token::Identifier(ref id) if id.to_lowercase() == "second" => { 
-                                        self.advance(); Ok(Expr::ArgumentSecond) 
-                                    }
-                                    Token::Last => { self.advance(); Ok(Expr::ArgumentLast) }
-                                    Token::Empty => { self.advance(); Ok(Expr::ArgumentEmpty) }
-                                    Token::All => { self.advance(); Ok(Expr::ArgumentAll) }
-                                    Token::Raw => { self.advance(); Ok(Expr::ArgumentRaw) }
-                                    _ => Err(self.err_expected("arguments property (count, first, last, empty, all, raw)", self.current())),
-                                };
-                            }
-                            
-                            if name_lower == "environment" || name_lower == "env" {
-                                return match self.current() {
-                                    Token::Count => { self.advance(); Ok(Expr::EnvironmentVariableCount) }
-                                    Token::First => { self.advance(); Ok(Expr::EnvironmentVariableFirst) }
-                                    Token::Last => { self.advance(); Ok(Expr::EnvironmentVariableLast) }
-                                    Token::Empty => { self.advance(); Ok(Expr::EnvironmentVariableEmpty) }
-                                    Token::StringLiteral(env_name) => {
-                                        let env_name = env_name.clone();
-                                        self.advance();
-                                        Ok(Expr::EnvironmentVariable { name: Box::new(Expr::StringLit(env_name)) })
-                                    }
-                                    _ => Err(self.err_expected("environment property", self.current())),
-                                };
-                            }
-                            
-                            // Check if user meant 'arguments' or 'environment' but made a typo
-                            // If so, the property they're accessing might be valid for that object
-                            let is_arguments_property = matches!(self.current(), 
-                                Token::Count | Token::First | Token::Last | Token::Empty | Token::All);
-                            let is_env_property = matches!(self.current(),
-                                Token::Count | Token::First | Token::Last | Token::Empty);
-                            
-                            if is_arguments_property {
-                                if let Some(suggestion) = find_similar_keyword(&name, &["arguments", "args"]) {
-                                    return Err(self.err(&format!(
-                                        "Unknown identifier '{}' - did you mean '{}'?",
-                                        name, suggestion
-                                    )));
-                                }
-                            }
-                            if is_env_property {
-                                if let Some(suggestion) = find_similar_keyword(&name, &["environment", "env"]) {
-                                    return Err(self.err(&format!(
-                                        "Unknown identifier '{}' - did you mean '{}'?",
-                                        name, suggestion
-                                    )));
-                                }
-                            }
-                            
-                            // Parse property name for other objects
-                            let property = match self.current() {
-                                // Buffer properties
-                                Token::Size => ObjectProperty::Size,
-                                Token::Capacity => ObjectProperty::Capacity,
-                                Token::Empty => ObjectProperty::Empty,
-                                Token::Full => ObjectProperty::Full,
-                                
-                                // File properties
-                                Token::Descriptor => ObjectProperty::Descriptor,
-                                Token::Modified => ObjectProperty::Modified,
-                                Token::Accessed => ObjectProperty::Accessed,
-                                Token::Permissions => ObjectProperty::Permissions,
-                                Token::Readable => ObjectProperty::Readable,
-                                Token::Writable => ObjectProperty::Writable,
-                                
-                                // List properties
-                                Token::First => ObjectProperty::First,
-                                Token::Last => ObjectProperty::Last,
-                                
-                                // Number properties
-                                Token::Absolute => ObjectProperty::Absolute,
-                                Token::Sign => ObjectProperty::Sign,
-                                Token::Even => ObjectProperty::Even,
-                                Token::Odd => ObjectProperty::Odd,
-                                Token::Positive => ObjectProperty::Positive,
-                                Token::Negative => ObjectProperty::Negative,
-                                Token::Zero => ObjectProperty::Zero,
-                                
-                                // Time properties
-                                Token::Hour => ObjectProperty::Hour,
-                                Token::Minute => ObjectProperty::Minute,
-                                Token::Second => ObjectProperty::Second,
-                                Token::Day => ObjectProperty::Day,
-                                Token::Month => ObjectProperty::Month,
-                                Token::Year => ObjectProperty::Year,
-                                Token::Unix => ObjectProperty::Unix,
-                                
-                                // Timer properties
-                                Token::Duration => ObjectProperty::Duration,
-                                Token::Elapsed => ObjectProperty::Elapsed,
-                                Token::Identifier(ref id) if id.to_lowercase() == "start" => ObjectProperty::StartTime,
-                                Token::Identifier(ref id) if id.to_lowercase() == "end" => ObjectProperty::EndTime,
-                                Token::Running => ObjectProperty::Running,
-                                
-                                _ => return Err(self.err_expected("property name", self.current())),
-                            };
-                            self.advance();
-                            return Ok(Expr::PropertyAccess {
-                                object: name,
-                                property,
-                            });
+                            let key = self.parse_primary()?;
+                            return Ok(Expr::MapHas { map: name, key: Box::new(key) });
                         }
                     }
                 }
                 
-                Ok(Expr::Identifier(name))
-            }
-            Token::OpenBracket => {
-                self.advance();
-                self.skip_noise();
-                
-                let mut elements = Vec::new();
-                
+                // Check for property access: identifier's property
+                if *self.current() == Token::Apostrophe {
+                    // Special handling for arguments's and environment's
+                    let name_lower = name.to_lowercase();
+                    if self.at_possessive() && (name_lower == "arguments" || name_lower == "args") {
+                        self.advance();
+                        self.advance();
+                        self.skip_noise();
+                        return self.parse_arguments_property();
+                    }
+                    if self.at_possessive() && (name_lower == "environment" || name_lower == "env") {
+                        self.advance();
+                        self.advance();
+                        self.skip_noise();
+                        return self.parse_environment_property();
+                    }
+                    
+                    // Check if user meant 'arguments' or 'environment' but made a typo
+                    // If so, the property they're accessing might be valid for that object
+                    let word = match self.peek(2) {
+                        Token::Identifier(word) => word.to_lowercase(),
+                        _ => String::new(),
+                    };
+                    let is_arguments_property = matches!(word.as_str(), "count" | "first" | "last" | "empty" | "all");
+                    let is_env_property = matches!(word.as_str(), "count" | "first" | "last" | "empty");
+                    let is_struct_field = self.structs.values().flatten().any(|field| *field == word);
+                    let is_map = self.maps.contains(&name);
+                    
+                    if is_arguments_property && !is_struct_field && !is_map {
+                        if let Some(suggestion) = find_similar_keyword(&name, &["arguments", "args"]) {
+                            return Err(self.err(&format!(
+                                "Unknown identifier '{}' - did you mean '{}'?",
+                                name, suggestion
+                            )));
+                        }
+                    }
+                    if is_env_property && !is_struct_field && !is_map {
+                        if let Some(suggestion) = find_similar_keyword(&name, &["environment", "env"]) {
+                            return Err(self.err(&format!(
+                                "Unknown identifier '{}' - did you mean '{}'?",
+                                name, suggestion
+                            )));
+                        }
+                    }
+                    
+                    return self.parse_property_access(name);
+                }
+                
+                Ok(Expr::Identifier(name))
+            }
+            Token::OpenBracket => {
+                self.advance();
+                self.skip_noise();
+                
+                let mut elements = Vec::new();
+                
                 // Empty list
                 if *self.current() == Token::CloseBracket {
                     self.advance();
@@ -4129,11 +5994,7 @@ impl Parser {
                     
                     let end = self.parse_primary()?;
                     
-                    Ok(Expr::Range {
-                        start: Box::new(start),
-                        end: Box::new(end),
-                        inclusive,
-                    })
+                    self.finish_range(start, end, inclusive, false)
                 } else {
                     Err(self.err("Expected 'from' or 'between' after 'all the numbers'"))
                 }
@@ -4201,56 +6062,21 @@ impl Parser {
                             }
                         }
                     }
+                    // "the error's code" or "the error's message"
+                    Token::Error => {
+                        self.advance();
+                        self.skip_noise();
+                        self.parse_error_property()
+                            .ok_or_else(|| self.err("Expected 's code or 's message after 'the error'"))
+                    }
+                    Token::Identifier(_) if self.at_math_function() => self.parse_math_function(),
                     Token::Identifier(name) => {
                         self.advance();
                         self.skip_noise();
                         
                         // Check for property access: "the now's hour"
                         if *self.current() == Token::Apostrophe {
-                            self.advance();
-                            if let Token::Identifier(prop_s) = self.current().clone() {
-                                if prop_s.to_lowercase() == "s" {
-                                    self.advance();
-                                    self.skip_noise();
-                                    
-                                    let property = match self.current() {
-                                        // Time properties
-                                        Token::Hour => ObjectProperty::Hour,
-                                        Token::Minute => ObjectProperty::Minute,
-                                        Token::Second => ObjectProperty::Second,
-                                        Token::Day => ObjectProperty::Day,
-                                        Token::Month => ObjectProperty::Month,
-                                        Token::Year => ObjectProperty::Year,
-                                        Token::Unix => ObjectProperty::Unix,
-                                        // Timer properties
-                                        Token::Duration => ObjectProperty::Duration,
-                                        Token::Elapsed => ObjectProperty::Elapsed,
-                                        Token::Running => ObjectProperty::Running,
-                                        // Other properties
-                                        Token::Size => ObjectProperty::Size,
-                                        Token::Capacity => ObjectProperty::Capacity,
-                                        Token::Empty => ObjectProperty::Empty,
-                                        Token::Full => ObjectProperty::Full,
-                                        // Handle single-quoted multi-word property names and 'start'
-                                        Token::Identifier(ref prop_name) => {
-                                            match prop_name.to_lowercase().as_str() {
-                                                "start" => ObjectProperty::StartTime,
-                                                "start time" => ObjectProperty::StartTime,
-                                                "end time" => ObjectProperty::EndTime,
-                                                "duration" => ObjectProperty::Duration,
-                                                "elapsed" => ObjectProperty::Elapsed,
-                                                "running" => ObjectProperty::Running,
-                                                _ => return Err(self.err_expected("property name", self.current())),
-                                            }
-                                        }
-                                        _ => return Err(self.err_expected("property name", self.current())),
-                                    };
-                                    self.advance();
-                                    
-                                    return Ok(Expr::PropertyAccess { object: name, property });
-                                }
-                            }
-                            return Err(self.err("Expected 's after apostrophe for property access"));
+                            return self.parse_property_access(name);
                         }
                         
                         Ok(Expr::Identifier(name))
@@ -4261,99 +6087,7 @@ impl Parser {
                         
                         // Check for property access: "the "job timer"'s duration"
                         if *self.current() == Token::Apostrophe {
-                            self.advance();
-                            if let Token::Identifier(prop_s) = self.current().clone() {
-                                if prop_s.to_lowercase() == "s" {
-                                    self.advance();
-                                    self.skip_noise();
-                                    
-                                    let property = match self.current() {
-                                        // Time properties
-                                        Token::Hour => ObjectProperty::Hour,
-                                        Token::Minute => ObjectProperty::Minute,
-                                        Token::Second => ObjectProperty::Second,
-                                        Token::Day => ObjectProperty::Day,
-                                        Token::Month => ObjectProperty::Month,
-                                        Token::Year => ObjectProperty::Year,
-                                        Token::Unix => ObjectProperty::Unix,
-                                        // Timer properties
-                                        Token::Duration => ObjectProperty::Duration,
-                                        Token::Elapsed => ObjectProperty::Elapsed,
-                                        Token::Running => ObjectProperty::Running,
-                                        // Other properties
-                                        Token::Size => ObjectProperty::Size,
-                                        Token::Capacity => ObjectProperty::Capacity,
-                                        Token::Empty => ObjectProperty::Empty,
-                                        Token::Full => ObjectProperty::Full,
-                                        // Handle single-quoted multi-word property names and 'start'
-                                        Token::Identifier(ref prop_name) => {
-                                            match prop_name.to_lowercase().as_str() {
-                                                "start" => ObjectProperty::StartTime,
-                                                "start time" => ObjectProperty::StartTime,
-                                                "end time" => ObjectProperty::EndTime,
-                                                "duration" => ObjectProperty::Duration,
-                                                "elapsed" => ObjectProperty::Elapsed,
-                                                "running" => ObjectProperty::Running,
-                                                _ => return Err(self.err_expected("property name", self.current())),
-                                            }
-                                        }
-                                        _ => return Err(self.err_expected("property name", self.current())),
-                                    };
-                                    self.advance();
-                                    
-                                    // Check for "in seconds" / "in milliseconds" or just "seconds"/"milliseconds" for duration/elapsed
-                                    if matches!(property, ObjectProperty::Duration | ObjectProperty::Elapsed) {
-                                        self.skip_noise();
-                                        // Handle both "elapsed in seconds" and "elapsed seconds"
-                                        if *self.current() == Token::In {
-                                            self.advance();
-                                            self.skip_noise();
-                                        }
-                                        // Now check for unit
-                                        if matches!(self.current(), Token::Seconds | Token::Second | Token::Milliseconds | Token::Millisecond) {
-                                            let unit = match self.current() {
-                                                Token::Seconds | Token::Second => {
-                                                    self.advance();
-                                                    ast::TimeUnit::Seconds
-                                                }
-                                                Token::Milliseconds | Token::Millisecond => {
-                                                    self.advance();
-                                                    ast::TimeUnit::Milliseconds
-                                                }
-                                                _ => unreachable!(),
-                                            };
-                                            return Ok(Expr::DurationCast {
-                                                value: Box::new(Expr::PropertyAccess { object: name, property }),
-                                                unit,
-                                            });
-                                        }
-                                    }
-                                    
-                                    return Ok(Expr::PropertyAccess { object: name, property });
-                                }
-                            }
-                            // Handle "start time" and "end time" as multi-word properties
-                            if let Token::Identifier(ref id) = self.current() {
-                                if id == "start" {
-                                    self.advance();
-                                    self.skip_noise();
-                                    if *self.current() == Token::Time {
-                                        self.advance();
-                                        return Ok(Expr::PropertyAccess { object: name, property: ObjectProperty::StartTime });
-                                    }
-                                }
-                            }
-                            if let Token::Identifier(ref id) = self.current() {
-                                if id.to_lowercase() == "end" {
-                                    self.advance();
-                                    self.skip_noise();
-                                    if *self.current() == Token::Time {
-                                        self.advance();
-                                        return Ok(Expr::PropertyAccess { object: name, property: ObjectProperty::EndTime });
-                                    }
-                                }
-                            }
-                            return Err(self.err("Expected 's after apostrophe for property access"));
+                            return self.parse_property_access(name);
                         }
                         
                         Ok(Expr::Identifier(name))
@@ -4393,7 +6127,7 @@ impl Parser {
                 self.skip_noise();
                 
                 // If followed by a type keyword, treat as article and parse the type expression
-                if matches!(self.current(), Token::Number | Token::Text | Token::Boolean | Token::List) {
+                if matches!(self.current(), Token::Number | Token::Text | Token::Boolean | Token::List) || self.at_random() {
                     self.parse_primary()
                 } else {
                     // Otherwise, treat "a" or "an" as an identifier
@@ -4501,7 +6235,241 @@ impl Parser {
             }
         }
         
-        Err(self.err("Expected 'current time into <name>' after 'get'"))
+        // "Get [N] random bytes into <buffer>"
+        if matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("random"))
+            || matches!(self.peek(1), Token::Identifier(word) if word.eq_ignore_ascii_case("random"))
+        {
+            return self.parse_random_bytes();
+        }
+        
+        Err(self.err("Expected 'current time into <name>' or 'random bytes into <buffer>' after 'get'"))
+    }
+    
+    // ========================================================================
+    // Random numbers
+    // ========================================================================
+    
+    /// `random` followed by what kind of random value
+    fn at_random(&self) -> bool {
+        matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("random"))
+            && matches!(self.peek(1), Token::Number | Token::Float | Token::Element)
+    }
+    
+    /// Parses "random number between 1 and 6", "random float" and
+    /// "random element of names"
+    fn parse_random(&mut self) -> Result<Expr, CompileError> {
+        self.advance(); // consume 'random'
+        match self.advance() {
+            Token::Float => Ok(Expr::RandomFloat),
+            Token::Element => {
+                self.skip_noise();
+                if *self.current() != Token::Of {
+                    return Err(self.err(
+                        "Expected 'of' after 'random element'\n  \
+                         Example: a random element of names"
+                    ));
+                }
+                self.advance();
+                self.skip_noise();
+                let list = self.parse_primary()?;
+                Ok(Expr::RandomElement { list: Box::new(list) })
+            }
+            _ => {
+                self.skip_noise();
+                if *self.current() != Token::Between {
+                    return Err(self.err(
+                        "Expected 'between' after 'random number'\n  \
+                         Example: a random number between 1 and 6"
+                    ));
+                }
+                self.advance();
+                self.skip_noise();
+                let low = self.parse_additive()?;
+                self.skip_noise();
+                if *self.current() != Token::And {
+                    return Err(self.err(
+                        "Expected 'and' between the bounds of a random number\n  \
+                         Example: a random number between 1 and 6"
+                    ));
+                }
+                self.advance();
+                self.skip_noise();
+                let high = self.parse_additive()?;
+                Ok(Expr::RandomNumber { low: Box::new(low), high: Box::new(high) })
+            }
+        }
+    }
+    
+    /// Parses the rest of "Get [N] random bytes into <buffer>"
+    fn parse_random_bytes(&mut self) -> Result<Statement, CompileError> {
+        let count = if matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("random")) {
+            None
+        } else {
+            Some(self.parse_primary()?)
+        };
+        self.skip_noise();
+        self.advance(); // consume 'random'
+        self.skip_noise();
+        if !matches!(self.current(), Token::Bytes | Token::Byte) {
+            return Err(self.err(
+                "Expected 'bytes' after 'random'\n  \
+                 Example: Get 16 random bytes into key."
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        if *self.current() != Token::Into {
+            return Err(self.err(
+                "Expected 'into' after 'random bytes'\n  \
+                 Example: Get 16 random bytes into key."
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        let buffer = match self.current().clone() {
+            Token::StringLiteral(n) | Token::Identifier(n) => { self.advance(); n }
+            other => return Err(self.err_expected("buffer name after 'into'", &other)),
+        };
+        Ok(Statement::RandomBytes { buffer, count })
+    }
+    
+    /// Parses "Seed the random generator with <N>"
+    fn parse_seed_random(&mut self) -> Result<Statement, CompileError> {
+        self.advance(); // consume 'seed'
+        self.skip_noise();
+        self.expect(&Token::The);
+        self.skip_noise();
+        if !matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("random")) {
+            return Err(self.err(
+                "Expected 'random generator' after 'seed'\n  \
+                 Example: Seed the random generator with 42."
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        if matches!(self.current(), Token::Identifier(word) if word.eq_ignore_ascii_case("generator")) {
+            self.advance();
+            self.skip_noise();
+        }
+        if *self.current() != Token::With {
+            return Err(self.err(
+                "Expected 'with' and a seed\n  \
+                 Example: Seed the random generator with 42."
+            ));
+        }
+        self.advance();
+        self.skip_noise();
+        let seed = self.parse_expression()?;
+        Ok(Statement::SeedRandom { seed })
+    }
+    
+    // ========================================================================
+    // Math
+    // ========================================================================
+    
+    /// "square root of", "sine of", "minimum of", ... - one or two words
+    /// before "of"
+    fn at_math_function(&self) -> bool {
+        let Token::Identifier(word) = self.current() else {
+            return false;
+        };
+        match word.to_lowercase().as_str() {
+            "square" => {
+                matches!(self.peek(1), Token::Identifier(root) if root.eq_ignore_ascii_case("root"))
+                    && *self.peek(2) == Token::Of
+            }
+            "sine" | "cosine" | "tangent" | "logarithm" | "minimum" | "maximum" => *self.peek(1) == Token::Of,
+            _ => false,
+        }
+    }
+    
+    /// Parses "square root of x", "sine of x", "cosine of x", "tangent of x",
+    /// "logarithm of x" and "minimum/maximum of a and b"
+    fn parse_math_function(&mut self) -> Result<Expr, CompileError> {
+        let word = match self.advance() {
+            Token::Identifier(word) => word.to_lowercase(),
+            _ => unreachable!("at_math_function checked the word"),
+        };
+        let function = match word.as_str() {
+            "square" => {
+                self.advance(); // consume 'root'
+                MathFunction::SquareRoot
+            }
+            "sine" => MathFunction::Sine,
+            "cosine" => MathFunction::Cosine,
+            "tangent" => MathFunction::Tangent,
+            "logarithm" => MathFunction::Logarithm,
+            _ => return self.parse_min_max(word == "maximum"),
+        };
+        self.advance(); // consume 'of'
+        self.skip_noise();
+        let operand = self.parse_power()?;
+        Ok(Expr::MathFunction { function, operand: Box::new(operand) })
+    }
+    
+    /// Parses the rest of "minimum of a and b" after "minimum"
+    fn parse_min_max(&mut self, maximum: bool) -> Result<Expr, CompileError> {
+        self.advance(); // consume 'of'
+        self.skip_noise();
+        let left = self.parse_additive()?;
+        self.skip_noise();
+        if *self.current() != Token::And {
+            let word = if maximum { "maximum" } else { "minimum" };
+            return Err(self.err(&format!(
+                "Expected 'and' between the two values\n  \
+                 Example: the {} of a and b",
+                word
+            )));
+        }
+        self.advance();
+        self.skip_noise();
+        let right = self.parse_additive()?;
+        Ok(Expr::MinMax { left: Box::new(left), right: Box::new(right), maximum })
+    }
+    
+    /// Parses what follows "rounded": "up" or "down", then "to N places"
+    fn parse_rounding(&mut self, value: Box<Expr>) -> Result<Expr, CompileError> {
+        // "up" reads as "to": it is "up" when "to N places" or the end of
+        // the value follows; a "to" before another value is left alone
+        let starts_value = matches!(
+            self.peek(1),
+            Token::Identifier(_)
+                | Token::StringLiteral(_)
+                | Token::IntegerLiteral(_)
+                | Token::BigIntegerLiteral(_)
+                | Token::FloatLiteral(_)
+                | Token::The
+                | Token::Minus
+        );
+        let mode = match self.current() {
+            Token::To if !self.at_rounding_places(0) && (self.at_rounding_places(1) || !starts_value) => {
+                RoundingMode::Up
+            }
+            Token::Identifier(word) if word.eq_ignore_ascii_case("down") => RoundingMode::Down,
+            _ => RoundingMode::Nearest,
+        };
+        if mode != RoundingMode::Nearest {
+            self.advance();
+        }
+        if !self.at_rounding_places(0) {
+            return Ok(Expr::Rounded { value, mode, places: None });
+        }
+        self.advance(); // consume 'to'
+        self.skip_noise();
+        let places = self.parse_primary()?;
+        self.skip_noise();
+        self.advance(); // consume 'places'
+        Ok(Expr::Rounded { value, mode, places: Some(Box::new(places)) })
+    }
+    
+    /// "to N places" (or "to -N places") starting `offset` tokens ahead
+    fn at_rounding_places(&self, offset: usize) -> bool {
+        let count = if *self.peek(offset + 1) == Token::Minus { 3 } else { 2 };
+        *self.peek(offset) == Token::To
+            && matches!(
+                self.peek(offset + count),
+                Token::Identifier(word) if word.eq_ignore_ascii_case("places") || word.eq_ignore_ascii_case("place")
+            )
     }
 }
 
@@ -4657,3 +6625,1176 @@ mod buffer_declaration_tests {
         // Note: Warning should be emitted to stderr during parsing
     }
 }
+
+#[cfg(test)]
+mod struct_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Result<Program, CompileError> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse()
+    }
+
+    const SAMPLE: &str =
+        "Define a struct called \"Sample\" with a number called \"first\" and a float called \"hour\".\n";
+
+    #[test]
+    fn struct_definition_lists_fields_in_order() {
+        let program = parse_input(SAMPLE).expect("should parse");
+        match &program.statements[0] {
+            Statement::StructDef { name, fields } => {
+                assert_eq!(name, "Sample");
+                assert_eq!(
+                    fields,
+                    &vec![("first".to_string(), Type::Integer), ("hour".to_string(), Type::Float)]
+                );
+            }
+            other => panic!("Expected StructDef, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn repeated_field_is_an_error() {
+        let input = "Define a struct called \"Dog\" with a number called \"age\" and a number called \"age\".";
+        let err = parse_input(input).unwrap_err();
+        assert!(err.to_string().contains("already has a property called 'age'"));
+    }
+
+    #[test]
+    fn builtin_property_words_after_possessive_are_struct_fields() {
+        let input = format!("{}a Sample called \"s\" with first 1 and hour 2.5.\nPrint s's hour.", SAMPLE);
+        let program = parse_input(&input).expect("should parse");
+        match &program.statements[1] {
+            Statement::VarDecl { var_type, value: Some(Expr::StructLit { name, fields }), .. } => {
+                assert_eq!(var_type, &Some(Type::Struct("Sample".to_string())));
+                assert_eq!(name, "Sample");
+                assert_eq!(fields[0].0, "first");
+                assert_eq!(fields[1].0, "hour");
+            }
+            other => panic!("Expected struct VarDecl, got {:?}", other),
+        }
+        match &program.statements[2] {
+            Statement::Print { value: Expr::PropertyAccess { property, .. }, .. } => {
+                assert!(matches!(property, ObjectProperty::Field(f) if f == "hour"));
+            }
+            other => panic!("Expected Print of a field, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn set_and_plain_assignment_write_fields() {
+        let input = format!("{}a Sample called \"s\".\nSet s's first to 3.\ns's first is 4.", SAMPLE);
+        let program = parse_input(&input).expect("should parse");
+        for stmt in &program.statements[2..] {
+            match stmt {
+                Statement::FieldSet { object, field, .. } => {
+                    assert_eq!(object, "s");
+                    assert_eq!(field, "first");
+                }
+                other => panic!("Expected FieldSet, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn struct_parameters_take_the_struct_type() {
+        let input = format!("{}To \"show\" with a Sample called \"s\".\n  Print s's first.", SAMPLE);
+        let program = parse_input(&input).expect("should parse");
+        match &program.statements[1] {
+            Statement::FunctionDef { params, .. } => {
+                assert_eq!(params, &vec![("s".to_string(), Type::Struct("Sample".to_string()))]);
+            }
+            other => panic!("Expected FunctionDef, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod map_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Result<Program, CompileError> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse()
+    }
+
+    #[test]
+    fn map_declaration_has_no_initial_value() {
+        for input in ["a map called \"ages\".", "a dictionary called \"ages\"."] {
+            let program = parse_input(input).expect("should parse");
+            match &program.statements[0] {
+                Statement::VarDecl { name, var_type, value } => {
+                    assert_eq!(name, "ages");
+                    assert_eq!(var_type, &Some(Type::Map));
+                    assert!(value.is_none());
+                }
+                other => panic!("Expected map VarDecl, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn set_get_has_and_remove() {
+        let input = "a map called \"ages\".\nset \"alice\" in ages to 30.\nPrint ages's \"alice\".\n\
+                     Print ages has \"alice\".\nremove \"alice\" from ages.";
+        let program = parse_input(input).expect("should parse");
+        match &program.statements[1] {
+            Statement::MapSet { map, key: Expr::StringLit(key), value: Expr::IntegerLit(30) } => {
+                assert_eq!(map, "ages");
+                assert_eq!(key, "alice");
+            }
+            other => panic!("Expected MapSet, got {:?}", other),
+        }
+        assert!(matches!(
+            &program.statements[2],
+            Statement::Print { value: Expr::MapGet { map, key }, .. }
+                if map == "ages" && matches!(key.as_ref(), Expr::StringLit(k) if k == "alice")
+        ));
+        assert!(matches!(
+            &program.statements[3],
+            Statement::Print { value: Expr::MapHas { map, .. }, .. } if map == "ages"
+        ));
+        assert!(matches!(
+            &program.statements[4],
+            Statement::MapRemove { map, key: Expr::StringLit(k) } if map == "ages" && k == "alice"
+        ));
+    }
+
+    #[test]
+    fn possessive_word_after_a_map_is_a_variable_key() {
+        let input = "a map called \"counts\".\na text called \"word\" is \"cat\".\n\
+                     set word in counts to counts's word add 1.\nPrint counts's count.";
+        let program = parse_input(input).expect("should parse");
+        match &program.statements[2] {
+            Statement::MapSet { key: Expr::Identifier(key), value: Expr::BinaryOp { left, .. }, .. } => {
+                assert_eq!(key, "word");
+                assert!(matches!(
+                    left.as_ref(),
+                    Expr::MapGet { key, .. } if matches!(key.as_ref(), Expr::Identifier(k) if k == "word")
+                ));
+            }
+            other => panic!("Expected MapSet with a variable key, got {:?}", other),
+        }
+        assert!(matches!(
+            &program.statements[3],
+            Statement::Print { value: Expr::PropertyAccess { property: ObjectProperty::Size, .. }, .. }
+        ));
+    }
+
+    #[test]
+    fn for_each_key_and_value_in_map() {
+        let input = "a map called \"ages\".\nFor each person and age in ages,\n    Print person.";
+        let program = parse_input(input).expect("should parse");
+        match &program.statements[1] {
+            Statement::ForEachEntry { key, value, map, body } => {
+                assert_eq!(key, "person");
+                assert_eq!(value, "age");
+                assert_eq!(map, "ages");
+                assert_eq!(body.len(), 1);
+            }
+            other => panic!("Expected ForEachEntry, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn map_parameters_take_the_map_type() {
+        let input = "To \"oldest\" with a map called \"ages\".\n  Return a number, ages's \"alice\".";
+        let program = parse_input(input).expect("should parse");
+        match &program.statements[0] {
+            Statement::FunctionDef { params, .. } => {
+                assert_eq!(params, &vec![("ages".to_string(), Type::Map)]);
+            }
+            other => panic!("Expected FunctionDef, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod depending_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Result<Program, CompileError> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse()
+    }
+
+    #[test]
+    fn arms_and_otherwise_on_one_line() {
+        let input = "Depending on the command, when \"start\" then print \"go\", \
+                     when \"stop\" then print \"halt\", otherwise print \"what\".";
+        let program = parse_input(input).expect("should parse");
+        match &program.statements[0] {
+            Statement::Depending { subject: Expr::Identifier(name), arms, otherwise, .. } => {
+                assert_eq!(name, "command");
+                assert_eq!(arms.len(), 2);
+                assert!(matches!(&arms[0].0[..], [MatchPattern::Value(Expr::StringLit(s))] if s == "start"));
+                assert_eq!(arms[1].1.len(), 1);
+                assert_eq!(otherwise.as_ref().map(Vec::len), Some(1));
+            }
+            other => panic!("Expected Depending, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn several_values_and_ranges_per_arm() {
+        let input = "Depending on n,\n    when 1 or 2 then print \"small\",\n    when 3 to 9, 20 then print \"other\".";
+        let program = parse_input(input).expect("should parse");
+        match &program.statements[0] {
+            Statement::Depending { arms, otherwise: None, .. } => {
+                assert_eq!(arms[0].0.len(), 2);
+                assert!(matches!(
+                    &arms[1].0[..],
+                    [MatchPattern::Range { start: Expr::IntegerLit(3), end: Expr::IntegerLit(9) },
+                     MatchPattern::Value(Expr::IntegerLit(20))]
+                ));
+            }
+            other => panic!("Expected Depending, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn arm_bodies_hold_several_actions() {
+        let input = "Depending on n, when 1 then increment n, print n, when 2 then print \"two\".";
+        let program = parse_input(input).expect("should parse");
+        match &program.statements[0] {
+            Statement::Depending { arms, .. } => {
+                assert_eq!(arms.len(), 2);
+                assert_eq!(arms[0].1.len(), 2);
+            }
+            other => panic!("Expected Depending, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn when_without_then_is_an_error() {
+        let result = parse_input("Depending on n, when 1 print \"one\".");
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(test)]
+mod quantifier_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_condition_of(input: &str) -> Expr {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().expect("should parse");
+        match program.statements.into_iter().next() {
+            Some(Statement::If { condition, .. }) => condition,
+            other => panic!("Expected If, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn any_with_a_comparison_on_the_element() {
+        let condition = parse_condition_of("If any score in scores is greater than 90 then, print \"high\".");
+        match condition {
+            Expr::Quantifier { kind: QuantifierKind::Any, variable, collection, condition } => {
+                assert_eq!(variable, "score");
+                assert!(matches!(*collection, Expr::Identifier(ref name) if name == "scores"));
+                assert!(matches!(
+                    *condition,
+                    Expr::BinaryOp { ref left, op: BinaryOperator::Greater, .. }
+                        if matches!(**left, Expr::Identifier(ref name) if name == "score")
+                ));
+            }
+            other => panic!("Expected Quantifier, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn element_arithmetic_belongs_to_the_condition() {
+        let condition = parse_condition_of("If all n in values modulo 2 is equal to 0 then, print \"even\".");
+        match condition {
+            Expr::Quantifier { kind: QuantifierKind::All, condition, .. } => {
+                assert!(matches!(
+                    *condition,
+                    Expr::BinaryOp { ref left, op: BinaryOperator::Equal, .. }
+                        if matches!(**left, Expr::BinaryOp { op: BinaryOperator::Modulo, .. })
+                ));
+            }
+            other => panic!("Expected Quantifier, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn none_of_the_arguments() {
+        let condition = parse_condition_of("If none of the arguments are \"-h\" then, print \"ok\".");
+        match condition {
+            Expr::Quantifier { kind: QuantifierKind::None, variable, collection, .. } => {
+                assert_eq!(variable, QUANTIFIED_ELEMENT);
+                assert!(matches!(*collection, Expr::ArgumentAll));
+            }
+            other => panic!("Expected Quantifier, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ranges_after_from_and_in() {
+        for input in [
+            "If any n from 1 to 10 is equal to 7 then, print \"seven\".",
+            "If all n in 1 to 5 are positive then, print \"positive\".",
+        ] {
+            match parse_condition_of(input) {
+                Expr::Quantifier { collection, .. } => assert!(matches!(*collection, Expr::Range { .. })),
+                other => panic!("Expected Quantifier, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn quantifiers_combine_with_and() {
+        let condition = parse_condition_of("If any x in xs is 1 and none of ys are 2 then, print \"both\".");
+        assert!(matches!(
+            condition,
+            Expr::BinaryOp { ref left, op: BinaryOperator::And, ref right }
+                if matches!(**left, Expr::Quantifier { kind: QuantifierKind::Any, .. })
+                    && matches!(**right, Expr::Quantifier { kind: QuantifierKind::None, .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod text_operation_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_printed(input: &str) -> Expr {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().expect("should parse");
+        match program.statements.into_iter().next() {
+            Some(Statement::Print { value, .. }) => value,
+            other => panic!("Expected Print, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn characters_with_and_without_an_end() {
+        match parse_printed("Print characters 2 to 5 of name.") {
+            Expr::TextSlice { text, start, end: Some(end) } => {
+                assert!(matches!(*text, Expr::Identifier(ref name) if name == "name"));
+                assert!(matches!(*start, Expr::IntegerLit(2)));
+                assert!(matches!(*end, Expr::IntegerLit(5)));
+            }
+            other => panic!("Expected TextSlice, got {:?}", other),
+        }
+        match parse_printed("Print character i of name.") {
+            Expr::TextSlice { start, end: None, .. } => {
+                assert!(matches!(*start, Expr::Identifier(ref name) if name == "i"));
+            }
+            other => panic!("Expected TextSlice, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn position_of_part_in_text() {
+        match parse_printed("Print position of \",\" in line.") {
+            Expr::TextFind { part, text } => {
+                assert!(matches!(*part, Expr::StringLit(ref s) if s == ","));
+                assert!(matches!(*text, Expr::Identifier(ref name) if name == "line"));
+            }
+            other => panic!("Expected TextFind, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn operations_after_the_text_chain() {
+        match parse_printed("Print line trimmed in uppercase.") {
+            Expr::TextCase { text, upper: true } => assert!(matches!(*text, Expr::TextTrim { .. })),
+            other => panic!("Expected TextCase, got {:?}", other),
+        }
+        match parse_printed("Print line split by \",\" joined with \" \".") {
+            Expr::TextJoin { list, .. } => assert!(matches!(*list, Expr::TextSplit { .. })),
+            other => panic!("Expected TextJoin, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn checks_work_as_conditions() {
+        let mut lexer = Lexer::new("If line starts with \"#\" or line contains \"error\" then, print \"skip\".");
+        let tokens = lexer.tokenize();
+        let program = Parser::new(tokens).parse().expect("should parse");
+        match &program.statements[0] {
+            Statement::If { condition: Expr::BinaryOp { left, op: BinaryOperator::Or, right }, .. } => {
+                assert!(matches!(**left, Expr::TextCheck { check: TextCheckKind::StartsWith, .. }));
+                assert!(matches!(**right, Expr::TextCheck { check: TextCheckKind::Contains, .. }));
+            }
+            other => panic!("Expected If with or, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn for_each_over_split_text() {
+        let mut lexer = Lexer::new("For each field in line split by \",\", print field.");
+        let tokens = lexer.tokenize();
+        let program = Parser::new(tokens).parse().expect("should parse");
+        match &program.statements[0] {
+            Statement::ForEach { variable, collection, .. } => {
+                assert_eq!(variable, "field");
+                assert!(matches!(collection, Expr::TextSplit { .. }));
+            }
+            other => panic!("Expected ForEach, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod line_loop_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn for_each_line_in_an_open_file() {
+        let statements = parse_input(
+            "open a file for reading called source at \"notes.txt\".\n\
+             For each line in source, print line."
+        );
+        match &statements[1] {
+            Statement::ForEach { variable, collection: Expr::FileLines { source }, .. } => {
+                assert_eq!(variable, "line");
+                assert_eq!(source, "source");
+            }
+            other => panic!("Expected ForEach over FileLines, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn each_line_from_standard_input() {
+        let statements = parse_input("Print each line from standard input.");
+        match &statements[0] {
+            Statement::ForEach { collection: Expr::FileLines { source }, body, .. } => {
+                assert_eq!(source, "stdin");
+                assert!(matches!(body[0], Statement::Print { .. }));
+            }
+            other => panic!("Expected ForEach over FileLines, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn each_character_and_byte_of_text() {
+        let statements = parse_input("Print each character in name.");
+        match &statements[0] {
+            Statement::ForEach { variable, collection, .. } => {
+                assert_eq!(variable, "character");
+                assert!(matches!(collection, Expr::Identifier(name) if name == "name"));
+            }
+            other => panic!("Expected ForEach, got {:?}", other),
+        }
+        let statements = parse_input("For each byte in data, print byte.");
+        match &statements[0] {
+            Statement::ForEach { variable, collection, body } => {
+                assert_eq!(variable, "byte");
+                assert!(matches!(collection, Expr::TextBytes { text } if matches!(&**text, Expr::Identifier(name) if name == "data")));
+                assert!(matches!(&body[0], Statement::Print { value: Expr::Identifier(name), .. } if name == "byte"));
+            }
+            other => panic!("Expected ForEach, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bytes_come_from_the_phrase_not_the_name() {
+        let statements = parse_input("For each b in the bytes of data, print b.\nPrint each code from bytes of data.");
+        for statement in &statements {
+            match statement {
+                Statement::ForEach { collection, .. } => assert!(matches!(collection, Expr::TextBytes { .. })),
+                other => panic!("Expected ForEach, got {:?}", other),
+            }
+        }
+        let statements = parse_input("For each b in data, print b.");
+        assert!(matches!(&statements[0], Statement::ForEach { collection: Expr::Identifier(_), .. }));
+        // Ranges are never bytes
+        let statements = parse_input("For each byte from 1 to 3, print byte.");
+        assert!(matches!(&statements[0], Statement::ForRange { .. }));
+    }
+
+    #[test]
+    fn byte_with_an_index_still_reads_a_buffer() {
+        let statements = parse_input("Print byte 1 of data.");
+        assert!(matches!(&statements[0], Statement::Print { value: Expr::ByteAccess { .. }, .. }));
+    }
+}
+
+#[cfg(test)]
+mod list_operation_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn insert_and_remove_by_position() {
+        let statements = parse_input(
+            "a list called scores is [1, 2].\n\
+             Insert 5 at position 1 of scores.\n\
+             Remove element 2 from scores."
+        );
+        match &statements[1] {
+            Statement::ListInsert { list, value: Expr::IntegerLit(5), position: Expr::IntegerLit(1) } => {
+                assert_eq!(list, "scores");
+            }
+            other => panic!("Expected ListInsert, got {:?}", other),
+        }
+        assert!(matches!(
+            &statements[2],
+            Statement::ListRemoveAt { list, position: Expr::IntegerLit(2) } if list == "scores"
+        ));
+    }
+
+    #[test]
+    fn remove_from_a_list_is_by_value_and_from_a_map_by_key() {
+        let statements = parse_input(
+            "a list called scores is [1, 2].\n\
+             a map called ages.\n\
+             Remove 2 from scores.\n\
+             Remove \"bob\" from ages."
+        );
+        assert!(matches!(&statements[2], Statement::ListRemove { list, value: Expr::IntegerLit(2) } if list == "scores"));
+        assert!(matches!(&statements[3], Statement::MapRemove { map, .. } if map == "ages"));
+    }
+
+    #[test]
+    fn sort_and_reverse() {
+        let statements = parse_input(
+            "Sort names.\n\
+             Sort scores in descending order.\n\
+             Reverse the names."
+        );
+        assert!(matches!(&statements[0], Statement::ListSort { list, descending: false } if list == "names"));
+        assert!(matches!(&statements[1], Statement::ListSort { list, descending: true } if list == "scores"));
+        assert!(matches!(&statements[2], Statement::ListReverse { list } if list == "names"));
+    }
+
+    #[test]
+    fn slices_and_concatenation_are_new_lists() {
+        let statements = parse_input(
+            "a list called middle is elements 2 to 4 of scores.\n\
+             a list called both is middle followed by scores."
+        );
+        match &statements[0] {
+            Statement::VarDecl { value: Some(Expr::ListSlice { list, start, end }), .. } => {
+                assert!(matches!(list.as_ref(), Expr::Identifier(name) if name == "scores"));
+                assert!(matches!(start.as_ref(), Expr::IntegerLit(2)));
+                assert!(matches!(end.as_ref(), Expr::IntegerLit(4)));
+            }
+            other => panic!("Expected ListSlice, got {:?}", other),
+        }
+        assert!(matches!(
+            &statements[1],
+            Statement::VarDecl { value: Some(Expr::ListConcat { .. }), .. }
+        ));
+    }
+
+    #[test]
+    fn quoted_text_can_be_inserted() {
+        let statements = parse_input("Insert \"kiwi\" at position 2 of names.");
+        assert!(matches!(
+            &statements[0],
+            Statement::ListInsert { value: Expr::StringLit(text), .. } if text == "kiwi"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod typed_list_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    fn declared_type(statement: &Statement) -> &Type {
+        match statement {
+            Statement::VarDecl { var_type: Some(t), .. } => t,
+            other => panic!("Expected typed VarDecl, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn list_of_element_type() {
+        let statements = parse_input(
+            "a list of floats called prices is [1.5, 2.0].\n\
+             a list of texts called names.\n\
+             a list of values called things is [1, \"two\"]."
+        );
+        assert_eq!(declared_type(&statements[0]), &Type::List(Box::new(Type::Float)));
+        assert_eq!(declared_type(&statements[1]), &Type::List(Box::new(Type::String)));
+        assert_eq!(declared_type(&statements[2]), &Type::List(Box::new(Type::Any)));
+        assert!(matches!(&statements[1], Statement::VarDecl { value: None, .. }));
+    }
+
+    #[test]
+    fn list_of_lists() {
+        let statements = parse_input("a list of lists of numbers called grid is [[1, 2], [3]].");
+        assert_eq!(
+            declared_type(&statements[0]),
+            &Type::List(Box::new(Type::List(Box::new(Type::Integer))))
+        );
+        match &statements[0] {
+            Statement::VarDecl { value: Some(Expr::ListLit { elements }), .. } => {
+                assert!(matches!(&elements[0], Expr::ListLit { elements } if elements.len() == 2));
+            }
+            other => panic!("Expected nested ListLit, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn contains_lists_the_elements() {
+        let statements = parse_input("a list of strings called names contains \"Alice\", \"Bob\" and \"Carol\".");
+        match &statements[0] {
+            Statement::VarDecl { value: Some(Expr::ListLit { elements }), .. } => assert_eq!(elements.len(), 3),
+            other => panic!("Expected ListLit, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nested_element_access() {
+        let statements = parse_input("Print element 2 of element 1 of grid.");
+        match &statements[0] {
+            Statement::Print { value: Expr::ElementAccess { list, index }, .. } => {
+                assert!(matches!(index.as_ref(), Expr::IntegerLit(2)));
+                assert!(matches!(list.as_ref(), Expr::ElementAccess { index, .. } if matches!(index.as_ref(), Expr::IntegerLit(1))));
+            }
+            other => panic!("Expected nested ElementAccess, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod multiple_return_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn function_returns_several_values() {
+        let statements = parse_input(
+            "To divmod with a number called x and a number called y. Return a number and a number, x divide y and x modulo y."
+        );
+        match &statements[0] {
+            Statement::FunctionDef { return_type, body, .. } => {
+                assert_eq!(return_type, &Type::Tuple(vec![Type::Integer, Type::Integer]));
+                assert!(matches!(&body[0], Statement::Return { values } if values.len() == 2));
+            }
+            other => panic!("Expected FunctionDef, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn return_statement_with_several_values() {
+        let statements = parse_input(
+            "To check with a number called n,\n\
+             If n is 0 then, Return a number and a boolean, 0 and false.\n\
+             Return a number and a boolean, n and true."
+        );
+        match &statements[0] {
+            Statement::FunctionDef { body, .. } => match body.last() {
+                Some(Statement::Return { values }) => {
+                    assert!(matches!(values.as_slice(), [Expr::Identifier(_), Expr::BoolLit(true)]));
+                }
+                other => panic!("Expected Return, got {:?}", other),
+            },
+            other => panic!("Expected FunctionDef, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn set_several_names_from_a_call() {
+        let statements = parse_input("Set quotient and rest to \"divmod\" of 17 and 5.");
+        match &statements[0] {
+            Statement::Destructure { names, value: Expr::FunctionCall { name, args, .. } } => {
+                assert_eq!(names, &["quotient".to_string(), "rest".to_string()]);
+                assert_eq!(name, "divmod");
+                assert_eq!(args.len(), 2);
+            }
+            other => panic!("Expected Destructure, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod named_argument_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn parameters_with_defaults() {
+        let statements = parse_input(
+            "To \"open log\" with a text called \"path\" and a number called \"retries\" which defaults to 3,\n\
+             Print path."
+        );
+        match &statements[0] {
+            Statement::FunctionDef { params, defaults, .. } => {
+                assert_eq!(params.len(), 2);
+                assert!(matches!(defaults.as_slice(), [(name, Expr::IntegerLit(3))] if name == "retries"));
+            }
+            other => panic!("Expected FunctionDef, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn named_arguments_follow_positional_ones() {
+        let statements = parse_input(
+            "\"open log\" of \"app.log\" with retries as 5 and verbose as true.\n\
+             Print \"scale\" with factor as 10."
+        );
+        match &statements[0] {
+            Statement::FunctionCall { args, named, .. } => {
+                assert!(matches!(args.as_slice(), [Expr::StringLit(path)] if path == "app.log"));
+                assert_eq!(named.len(), 2);
+                assert_eq!(named[0].0, "retries");
+                assert!(matches!(named[1], (ref name, Expr::BoolLit(true)) if name == "verbose"));
+            }
+            other => panic!("Expected FunctionCall, got {:?}", other),
+        }
+        match &statements[1] {
+            Statement::Print { value: Expr::FunctionCall { args, named, .. }, .. } => {
+                assert!(args.is_empty());
+                assert!(matches!(named.as_slice(), [(name, Expr::IntegerLit(10))] if name == "factor"));
+            }
+            other => panic!("Expected Print of a call, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod constant_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn constant_with_and_without_a_type() {
+        let statements = parse_input(
+            "a constant number called \"limit\" is 10.\n\
+             Create a constant called \"greeting\" is \"hello\"."
+        );
+        assert!(matches!(
+            &statements[0],
+            Statement::ConstDecl { name, var_type: Some(Type::Integer), value: Expr::IntegerLit(10) } if name == "limit"
+        ));
+        assert!(matches!(
+            &statements[1],
+            Statement::ConstDecl { name, var_type: None, value: Expr::StringLit(text) } if name == "greeting" && text == "hello"
+        ));
+    }
+
+    #[test]
+    fn constant_needs_a_value() {
+        let mut lexer = Lexer::new("a constant number called \"limit\".");
+        let mut parser = Parser::new(lexer.tokenize());
+        let err = parser.parse().expect_err("a constant without a value should not parse");
+        assert!(err.message.contains("The constant 'limit' needs a value"));
+    }
+
+    #[test]
+    fn variable_called_constant_is_still_a_variable() {
+        let statements = parse_input("Set constant to 5.");
+        assert!(matches!(&statements[0], Statement::VarDecl { name, .. } if name == "constant"));
+    }
+}
+
+#[cfg(test)]
+mod named_loop_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_input(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn loop_name_follows_the_header() {
+        let statements = parse_input(
+            "For each row from 1 to 10, called \"rows\",\n\
+             \x20   For each col from 1 to 10, if col is equal to 3 then, break out of rows."
+        );
+        match &statements[0] {
+            Statement::NamedLoop { name, inner } => {
+                assert_eq!(name, "rows");
+                assert!(matches!(inner.as_ref(), Statement::ForRange { variable, .. } if variable == "row"));
+            }
+            other => panic!("Expected NamedLoop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn break_and_continue_name_their_loop() {
+        let statements = parse_input(
+            "While 1 is equal to 1, called \"outer\", continue outer.\n\
+             Loop forever, called \"spin\", break out of spin.\n\
+             Loop forever, break."
+        );
+        match &statements[0] {
+            Statement::NamedLoop { inner, .. } => match inner.as_ref() {
+                Statement::While { body, .. } => {
+                    assert!(matches!(&body[0], Statement::Continue { loop_name: Some(name) } if name == "outer"));
+                }
+                other => panic!("Expected While, got {:?}", other),
+            },
+            other => panic!("Expected NamedLoop, got {:?}", other),
+        }
+        assert!(matches!(&statements[1], Statement::NamedLoop { name, .. } if name == "spin"));
+        match &statements[2] {
+            Statement::Loop { body } => assert!(matches!(body[0], Statement::Break { loop_name: None })),
+            other => panic!("Expected Loop, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ensure_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_with_source(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens).with_source("checks.en", input);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn ensure_keeps_its_location_and_text() {
+        let statements = parse_with_source(
+            "Set total to 5.\n\
+             Ensure total is greater than 1, otherwise \"total is too small\"."
+        );
+        match &statements[1] {
+            Statement::Ensure { message, location, text, .. } => {
+                assert_eq!(location, "checks.en:2");
+                assert_eq!(text, "total is greater than 1");
+                assert!(matches!(message, Some(Expr::StringLit(m)) if m == "total is too small"));
+            }
+            other => panic!("Expected Ensure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn example_holds_its_checks() {
+        let statements = parse_with_source(
+            "Example \"counting\":\n\
+             \x20   Set n to 2,\n\
+             \x20   ensure n is 2,\n\
+             \x20   ensure n is less than 3.\n\
+             Print 1."
+        );
+        match &statements[0] {
+            Statement::Example { name, body } => {
+                assert_eq!(name, "counting");
+                assert_eq!(body.len(), 3);
+                assert!(matches!(&body[2], Statement::Ensure { message: None, .. }));
+            }
+            other => panic!("Expected Example, got {:?}", other),
+        }
+        assert_eq!(statements.len(), 2);
+    }
+
+    #[test]
+    fn variable_called_example_is_still_a_variable() {
+        let statements = parse_with_source("Set example to 3.\nPrint example.");
+        assert!(matches!(&statements[0], Statement::VarDecl { name, .. } if name == "example"));
+    }
+}
+
+#[cfg(test)]
+mod random_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_statements(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn random_number_between_bounds() {
+        let statements = parse_statements("a number called \"roll\" is a random number between 1 and 6.");
+        match &statements[0] {
+            Statement::VarDecl { value: Some(Expr::RandomNumber { low, high }), .. } => {
+                assert!(matches!(**low, Expr::IntegerLit(1)));
+                assert!(matches!(**high, Expr::IntegerLit(6)));
+            }
+            other => panic!("Expected VarDecl of RandomNumber, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn random_float_and_element() {
+        let statements = parse_statements("Print a random float.\nPrint random element of names.");
+        assert!(matches!(&statements[0], Statement::Print { value: Expr::RandomFloat, .. }));
+        match &statements[1] {
+            Statement::Print { value: Expr::RandomElement { list }, .. } => {
+                assert!(matches!(**list, Expr::Identifier(ref name) if name == "names"));
+            }
+            other => panic!("Expected Print of RandomElement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn shuffle_seed_and_random_bytes() {
+        let statements = parse_statements(
+            "Shuffle names.\n\
+             Seed the random generator with 42.\n\
+             Get 16 random bytes into key.\n\
+             Get random bytes into key."
+        );
+        assert!(matches!(&statements[0], Statement::ListShuffle { list } if list == "names"));
+        assert!(matches!(&statements[1], Statement::SeedRandom { seed: Expr::IntegerLit(42) }));
+        assert!(matches!(
+            &statements[2],
+            Statement::RandomBytes { buffer, count: Some(Expr::IntegerLit(16)) } if buffer == "key"
+        ));
+        assert!(matches!(&statements[3], Statement::RandomBytes { count: None, .. }));
+    }
+
+    #[test]
+    fn a_variable_called_random_is_still_a_variable() {
+        let statements = parse_statements("Set random to 3.\nPrint random.");
+        assert!(matches!(&statements[1], Statement::Print { value: Expr::Identifier(name), .. } if name == "random"));
+    }
+}
+
+#[cfg(test)]
+mod math_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_value(input: &str) -> Expr {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        match parser.parse().expect("should parse").statements.remove(0) {
+            Statement::Print { value, .. } => value,
+            other => panic!("Expected Print, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn functions_of_a_value() {
+        assert!(matches!(
+            parse_value("Print the square root of x."),
+            Expr::MathFunction { function: MathFunction::SquareRoot, .. }
+        ));
+        assert!(matches!(parse_value("Print sine of 1."), Expr::MathFunction { function: MathFunction::Sine, .. }));
+        assert!(matches!(
+            parse_value("Print logarithm of 10."),
+            Expr::MathFunction { function: MathFunction::Logarithm, .. }
+        ));
+        match parse_value("Print the maximum of a and b add 1.") {
+            Expr::MinMax { right, maximum: true, .. } => assert!(matches!(*right, Expr::BinaryOp { .. })),
+            other => panic!("Expected MinMax, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn power_binds_tighter_than_arithmetic() {
+        match parse_value("Print 3 multiply 2 to the power of 10.") {
+            Expr::BinaryOp { right, .. } => assert!(matches!(*right, Expr::Power { .. })),
+            other => panic!("Expected BinaryOp, got {:?}", other),
+        }
+        match parse_value("Print 2 to the power of 3 to the power of 2.") {
+            Expr::Power { exponent, .. } => assert!(matches!(*exponent, Expr::Power { .. })),
+            other => panic!("Expected Power, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rounding_modes_and_places() {
+        assert!(matches!(
+            parse_value("Print x rounded."),
+            Expr::Rounded { mode: RoundingMode::Nearest, places: None, .. }
+        ));
+        assert!(matches!(parse_value("Print x rounded up."), Expr::Rounded { mode: RoundingMode::Up, .. }));
+        assert!(matches!(parse_value("Print x rounded down."), Expr::Rounded { mode: RoundingMode::Down, .. }));
+        match parse_value("Print price add tax rounded to 2 places.") {
+            Expr::Rounded { value, places: Some(places), .. } => {
+                assert!(matches!(*value, Expr::BinaryOp { .. }));
+                assert!(matches!(*places, Expr::IntegerLit(2)));
+            }
+            other => panic!("Expected Rounded, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rounding_up_or_down_to_places() {
+        match parse_value("Print x rounded up to 2 places.") {
+            Expr::Rounded { mode: RoundingMode::Up, places: Some(places), .. } => {
+                assert!(matches!(*places, Expr::IntegerLit(2)));
+            }
+            other => panic!("Expected Rounded, got {:?}", other),
+        }
+        assert!(matches!(
+            parse_value("Print x rounded down to 1 place."),
+            Expr::Rounded { mode: RoundingMode::Down, places: Some(_), .. }
+        ));
+        assert!(matches!(
+            parse_value("Print x rounded to -2 places."),
+            Expr::Rounded { mode: RoundingMode::Nearest, places: Some(_), .. }
+        ));
+    }
+}
+
+#[cfg(test)]
+mod big_number_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_statements(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn declared_with_a_literal_past_64_bits() {
+        match &parse_statements("a big number called \"huge\" is 123456789012345678901234567890.")[0] {
+            Statement::VarDecl { var_type: Some(Type::BigNumber), value: Some(Expr::BigLit(digits)), .. } => {
+                assert_eq!(digits, "123456789012345678901234567890");
+            }
+            other => panic!("Expected a big number VarDecl, got {:?}", other),
+        }
+        assert!(matches!(
+            &parse_statements("a big number called \"total\".")[0],
+            Statement::VarDecl { var_type: Some(Type::BigNumber), value: None, .. }
+        ));
+    }
+
+    #[test]
+    fn cast_and_function_types() {
+        match &parse_statements("Print digits as a big number.")[0] {
+            Statement::Print { value: Expr::Cast { target_type, .. }, .. } => assert_eq!(*target_type, Type::BigNumber),
+            other => panic!("Expected a Cast, got {:?}", other),
+        }
+        match &parse_statements("To \"double\" with a big number called \"n\".\n    Return a big number, n multiply 2.")[0] {
+            Statement::FunctionDef { params, return_type, .. } => {
+                assert_eq!(params[0].1, Type::BigNumber);
+                assert_eq!(*return_type, Type::BigNumber);
+            }
+            other => panic!("Expected a FunctionDef, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_variable_called_big_is_still_a_name() {
+        assert!(matches!(
+            &parse_statements("a number called \"big\" is 5.\nPrint big add 1.")[1],
+            Statement::Print { value: Expr::BinaryOp { .. }, .. }
+        ));
+    }
+}
+
+#[cfg(test)]
+mod decimal_tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_statements(input: &str) -> Vec<Statement> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let mut parser = Parser::new(tokens);
+        parser.parse().expect("should parse").statements
+    }
+
+    #[test]
+    fn declared_with_places() {
+        assert!(matches!(
+            &parse_statements("a decimal with 2 places called \"price\" is 19.99.")[0],
+            Statement::VarDecl { var_type: Some(Type::Decimal(2)), value: Some(Expr::FloatLit(_)), .. }
+        ));
+        assert!(matches!(
+            &parse_statements("a constant decimal with 1 place called \"rate\" is 0.5.")[0],
+            Statement::ConstDecl { var_type: Some(Type::Decimal(1)), .. }
+        ));
+        assert!(matches!(
+            &parse_statements("a list of decimals with 2 places called \"prices\" is [1.5].")[0],
+            Statement::VarDecl { var_type: Some(Type::List(element)), .. } if **element == Type::Decimal(2)
+        ));
+    }
+
+    #[test]
+    fn places_are_required() {
+        for input in ["a decimal called \"temperature\" is 2.5.", "Print 2 as a decimal."] {
+            let mut parser = Parser::new(Lexer::new(input).tokenize());
+            let err = parser.parse().expect_err("a decimal without places should not parse");
+            assert!(err.message.contains("A decimal needs the number of places it keeps"), "got: {}", err.message);
+        }
+    }
+
+    #[test]
+    fn cast_and_function_types() {
+        match &parse_statements("Print entered as a decimal with 3 places.")[0] {
+            Statement::Print { value: Expr::Cast { target_type, .. }, .. } => assert_eq!(*target_type, Type::Decimal(3)),
+            other => panic!("Expected a Cast, got {:?}", other),
+        }
+        match &parse_statements("To \"fee\" with a decimal with 2 places called \"amount\".\n    Return a decimal with 2 places, amount multiply 0.05.")[0] {
+            Statement::FunctionDef { params, return_type, .. } => {
+                assert_eq!(params[0].1, Type::Decimal(2));
+                assert_eq!(*return_type, Type::Decimal(2));
+            }
+            other => panic!("Expected a FunctionDef, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn places_past_eighteen_are_an_error() {
+        let mut lexer = Lexer::new("a decimal with 19 places called \"tiny\" is 0.");
+        let mut parser = Parser::new(lexer.tokenize());
+        let err = parser.parse().expect_err("19 places should not parse");
+        assert!(err.message.contains("at most 18 places"), "got: {}", err.message);
+    }
+}
@@ -87,7 +87,9 @@ run_test() {
     local test_name="${test_file%.vox}"
     local expected_file="${test_name}.expected"
     local expected_exit_file="${test_name}.exit"
+    local expected_stderr_file="${test_name}.stderr"
     local args_file="${test_name}.args"
+    local flags_file="${test_name}.flags"
     local basename=$(basename "$test_name")
     
     # Check if expected file exists
@@ -100,6 +102,7 @@ run_test() {
     # Create temp files for output
     local tmp_out=$(mktemp)
     local tmp_err=$(mktemp)
+    local tmp_run_err=$(mktemp)
     
     # Load runtime arguments from optional .args file (one arg per non-empty line)
     local run_args=()
@@ -110,13 +113,25 @@ run_test() {
         done < "$args_file"
     fi
 
+    # Load compiler flags from optional .flags file (one flag per non-empty line)
+    local compile_flags=()
+    if [[ -f "$flags_file" ]]; then
+        while IFS= read -r line; do
+            [[ -z "$line" ]] && continue
+            compile_flags+=("$line")
+        done < "$flags_file"
+    fi
+
     # Compile then run the produced executable so we can pass runtime args
     local actual_exit=0
     local exe_path="$SCRIPT_DIR/$basename"
-    "$VOX_BIN" "$test_file" > "$tmp_err" 2>&1 || actual_exit=$?
+    # Relative to the project, so messages naming the source match .stderr files
+    local source_path="${test_file#"$SCRIPT_DIR"/}"
+    "$VOX_BIN" "$source_path" "${compile_flags[@]}" > "$tmp_err" 2>&1 || actual_exit=$?
 
     if [[ "$actual_exit" == "0" ]]; then
-        "$exe_path" "${run_args[@]}" > "$tmp_out" 2>> "$tmp_err" || actual_exit=$?
+        "$exe_path" "${run_args[@]}" > "$tmp_out" 2> "$tmp_run_err" || actual_exit=$?
+        cat "$tmp_run_err" >> "$tmp_err"
     fi
     
     # Check expected exit code if specified
@@ -125,8 +140,14 @@ run_test() {
         expected_exit=$(cat "$expected_exit_file" | tr -d '[:space:]')
     fi
     
+    # Compare the program's stderr when an optional .stderr file gives it
+    local stderr_matches=1
+    if [[ -f "$expected_stderr_file" ]] && ! diff -q "$expected_stderr_file" "$tmp_run_err" > /dev/null 2>&1; then
+        stderr_matches=0
+    fi
+    
     # Compare output
-    if diff -q "$expected_file" "$tmp_out" > /dev/null 2>&1 && [[ "$actual_exit" == "$expected_exit" ]]; then
+    if diff -q "$expected_file" "$tmp_out" > /dev/null 2>&1 && [[ "$actual_exit" == "$expected_exit" ]] && [[ $stderr_matches -eq 1 ]]; then
         echo -e "  ${GREEN}PASS${NC} $basename"
         ((PASSED++))
     else
@@ -138,6 +159,10 @@ run_test() {
                 echo -e "    ${YELLOW}Output diff:${NC}"
                 diff -u "$expected_file" "$tmp_out" | head -20 | sed 's/^/    /'
             fi
+            if [[ $stderr_matches -eq 0 ]]; then
+                echo -e "    ${YELLOW}Stderr diff:${NC}"
+                diff -u "$expected_stderr_file" "$tmp_run_err" | head -20 | sed 's/^/    /'
+            fi
             if [[ "$actual_exit" != "$expected_exit" ]]; then
                 echo -e "    ${YELLOW}Exit code: expected $expected_exit, got $actual_exit${NC}"
             fi
@@ -150,7 +175,7 @@ run_test() {
     
     # Cleanup temp files and generated executable
     rm -f "$exe_path"
-    rm -f "$tmp_out" "$tmp_err"
+    rm -f "$tmp_out" "$tmp_err" "$tmp_run_err"
 }
 
 # Collect test files
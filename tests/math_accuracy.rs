@@ -0,0 +1,288 @@
+//! Compares coreasm's math routines with Rust's f64 functions (libm).
+//!
+//! A small program built from coreasm/x86_64/math.asm runs every input and
+//! writes back each result with `_last_error`. Needs `nasm` and `ld`; the
+//! test is skipped when NASM is missing.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Largest distance from libm's result, in units in the last place.
+const MAX_ULPS: u64 = 3;
+
+/// Angles from here on are outside the domain (see math.asm)
+const ANGLE_LIMIT: f64 = 1647098.0;
+
+const ERR_DOMAIN: u64 = 263;
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Sin,
+    Cos,
+    Tan,
+    Log,
+    Power,
+    Sqrt,
+}
+
+impl Op {
+    const ALL: [Op; 6] = [Op::Sin, Op::Cos, Op::Tan, Op::Log, Op::Power, Op::Sqrt];
+
+    fn routine(self) -> &'static str {
+        match self {
+            Op::Sin => "_math_sin",
+            Op::Cos => "_math_cos",
+            Op::Tan => "_math_tan",
+            Op::Log => "_math_log",
+            Op::Power => "_math_power",
+            Op::Sqrt => "_math_sqrt",
+        }
+    }
+
+    fn libm(self, x: f64, y: f64) -> f64 {
+        match self {
+            Op::Sin => x.sin(),
+            Op::Cos => x.cos(),
+            Op::Tan => x.tan(),
+            Op::Log => x.ln(),
+            Op::Power => x.powf(y),
+            Op::Sqrt => x.sqrt(),
+        }
+    }
+}
+
+struct Case {
+    op: Op,
+    x: f64,
+    y: f64,
+}
+
+/// xorshift64, so every run checks the same inputs
+struct Inputs(u64);
+
+impl Inputs {
+    fn unit(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn between(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.unit()
+    }
+}
+
+fn angle_cases(cases: &mut Vec<Case>, inputs: &mut Inputs) {
+    let limit = ANGLE_LIMIT * (1.0 - f64::EPSILON);
+    for op in [Op::Sin, Op::Cos, Op::Tan] {
+        for _ in 0..4000 {
+            cases.push(Case { op, x: inputs.between(-10.0, 10.0), y: 0.0 });
+            cases.push(Case { op, x: inputs.between(-limit, limit), y: 0.0 });
+        }
+        // Next to multiples of pi/2, where reducing loses the most digits
+        for _ in 0..4000 {
+            let k = (inputs.unit() * (1u64 << 20) as f64).floor().max(1.0);
+            let x = k * std::f64::consts::FRAC_PI_2;
+            let steps = (inputs.unit() * 8.0) as u64;
+            let near = f64::from_bits(x.to_bits() + steps - 4);
+            if near.abs() < limit {
+                cases.push(Case { op, x: near, y: 0.0 });
+            }
+        }
+        cases.push(Case { op, x: 0.0, y: 0.0 });
+        cases.push(Case { op, x: 1e-300, y: 0.0 });
+    }
+}
+
+fn log_cases(cases: &mut Vec<Case>, inputs: &mut Inputs) {
+    for _ in 0..4000 {
+        cases.push(Case { op: Op::Log, x: inputs.between(0.5, 2.0), y: 0.0 });
+        cases.push(Case { op: Op::Log, x: inputs.between(-700.0, 700.0).exp(), y: 0.0 });
+    }
+    for x in [1.0, f64::MIN_POSITIVE, 5e-324, f64::MAX] {
+        cases.push(Case { op: Op::Log, x, y: 0.0 });
+    }
+}
+
+fn power_cases(cases: &mut Vec<Case>, inputs: &mut Inputs) {
+    let mut push = |x: f64, y: f64| {
+        // Results past the largest float overflow, which math.asm reports
+        if x.powf(y).is_finite() {
+            cases.push(Case { op: Op::Power, x, y });
+        }
+    };
+    for _ in 0..4000 {
+        let x = inputs.between(-20.0, 20.0).exp();
+        push(x, inputs.between(-30.0, 30.0));
+        let whole = (inputs.unit() * 129.0).floor() - 64.0;
+        push(x, whole);
+        push(-x, whole);
+    }
+    for (x, y) in [(2.0, 1023.5), (2.0, -1074.0), (10.0, 308.0), (10.0, -320.0), (-2.0, 3.0)] {
+        push(x, y);
+    }
+}
+
+fn sqrt_cases(cases: &mut Vec<Case>, inputs: &mut Inputs) {
+    for _ in 0..4000 {
+        cases.push(Case { op: Op::Sqrt, x: inputs.between(-700.0, 700.0).exp(), y: 0.0 });
+    }
+}
+
+/// Calls each case's routine and writes its result and `_last_error`
+fn driver(cases: &[Case]) -> String {
+    let mut asm = String::new();
+    asm.push_str("%include \"coreasm/x86_64/core.asm\"\n");
+    asm.push_str("%include \"coreasm/x86_64/math.asm\"\n\n");
+    asm.push_str("section .rodata\n    routines:\n");
+    for op in Op::ALL {
+        writeln!(asm, "        dq {}", op.routine()).unwrap();
+    }
+    asm.push_str("    cases:\n");
+    for case in cases {
+        writeln!(
+            asm,
+            "        dq {}, 0x{:016X}, 0x{:016X}",
+            case.op as u64,
+            case.x.to_bits(),
+            case.y.to_bits()
+        )
+        .unwrap();
+    }
+    writeln!(asm, "\nsection .bss\n    results: resq {}\n", cases.len() * 2).unwrap();
+    writeln!(
+        asm,
+        "section .text
+global _start
+_start:
+    lea rbx, [rel cases]
+    lea r12, [rel results]
+    mov r13, {count}
+.next:
+    mov qword [rel _last_error], 0
+    movsd xmm0, [rbx + 8]
+    movsd xmm1, [rbx + 16]
+    mov rax, [rbx]
+    lea rcx, [rel routines]
+    call [rcx + rax*8]
+    movsd [r12], xmm0
+    mov rax, [rel _last_error]
+    mov [r12 + 8], rax
+    add rbx, 24
+    add r12, 16
+    dec r13
+    jnz .next
+    mov rax, 1
+    mov rdi, 1
+    lea rsi, [rel results]
+    mov rdx, {bytes}
+    syscall
+    mov rax, 60
+    xor edi, edi
+    syscall",
+        count = cases.len(),
+        bytes = cases.len() * 16
+    )
+    .unwrap();
+    asm
+}
+
+/// Runs the cases through coreasm, or None when NASM is not installed
+fn run(name: &str, cases: &[Case]) -> Option<Vec<(f64, u64)>> {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let dir = std::env::temp_dir().join(format!("vox_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let asm_path = dir.join("driver.asm");
+    let obj_path = dir.join("driver.o");
+    let exe_path = dir.join("driver");
+    fs::write(&asm_path, driver(cases)).unwrap();
+
+    let include = format!("-I{}/", root.display());
+    let nasm = Command::new("nasm")
+        .args(["-f", "elf64", &include, "-o"])
+        .arg(&obj_path)
+        .arg(&asm_path)
+        .status();
+    let status = match nasm {
+        Ok(status) => status,
+        Err(_) => {
+            eprintln!("skipping math accuracy test: nasm not found");
+            let _ = fs::remove_dir_all(&dir);
+            return None;
+        }
+    };
+    assert!(status.success(), "NASM assembly failed");
+    let status = Command::new("ld").arg("-o").arg(&exe_path).arg(&obj_path).status().unwrap();
+    assert!(status.success(), "Linking failed");
+    let output = Command::new(&exe_path).output().unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), cases.len() * 16);
+
+    let word = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap());
+    Some(
+        output
+            .stdout
+            .chunks(16)
+            .map(|pair| (f64::from_bits(word(&pair[..8])), word(&pair[8..])))
+            .collect(),
+    )
+}
+
+/// Distance between two floats in units in the last place
+fn ulps(a: f64, b: f64) -> u64 {
+    let ordered = |x: f64| {
+        let bits = x.to_bits() as i64;
+        if bits < 0 { i64::MIN.wrapping_sub(bits) } else { bits }
+    };
+    ordered(a).abs_diff(ordered(b))
+}
+
+#[test]
+fn math_matches_libm() {
+    let mut inputs = Inputs(88172645463325252);
+    let mut cases = Vec::new();
+    angle_cases(&mut cases, &mut inputs);
+    log_cases(&mut cases, &mut inputs);
+    power_cases(&mut cases, &mut inputs);
+    sqrt_cases(&mut cases, &mut inputs);
+
+    let Some(results) = run("math_matches_libm", &cases) else { return };
+    let mut failures = Vec::new();
+    for (case, &(got, error)) in cases.iter().zip(&results) {
+        let want = case.op.libm(case.x, case.y);
+        if error != 0 || ulps(got, want) > MAX_ULPS {
+            failures.push(format!(
+                "{:?}({:e}, {:e}) = {:e}, error {}; libm gives {:e} ({} ulps)",
+                case.op, case.x, case.y, got, error, want, ulps(got, want)
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} cases off:\n{}",
+        failures.len(),
+        cases.len(),
+        failures[..failures.len().min(20)].join("\n")
+    );
+}
+
+#[test]
+fn angles_past_the_limit_are_domain_errors() {
+    let mut cases = Vec::new();
+    for op in [Op::Sin, Op::Cos, Op::Tan] {
+        for x in [ANGLE_LIMIT, -ANGLE_LIMIT, 1e7, 4e18, f64::MAX] {
+            cases.push(Case { op, x, y: 0.0 });
+        }
+        cases.push(Case { op, x: ANGLE_LIMIT - 1.0, y: 0.0 });
+    }
+
+    let Some(results) = run("angle_limit", &cases) else { return };
+    for (case, &(_, error)) in cases.iter().zip(&results) {
+        let expected = if case.x.abs() < ANGLE_LIMIT { 0 } else { ERR_DOMAIN };
+        assert_eq!(error, expected, "{:?}({:e})", case.op, case.x);
+    }
+}
@@ -0,0 +1,74 @@
+//! `vox test <file>`: builds a file's examples, runs them and reports each
+//! one, ending with a summary. Needs `nasm` and `ld`; the tests are skipped
+//! when NASM is missing.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const DOUBLE: &str = r#"To "double" with a number called "x". Return a number, x plus x.
+"#;
+
+/// Runs `vox test` on the source in a directory of its own, or None when
+/// NASM is not installed
+fn vox_test(name: &str, source: &str) -> Option<Output> {
+    if Command::new("nasm").arg("-v").output().is_err() {
+        eprintln!("skipping vox test command test: nasm not found");
+        return None;
+    }
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let dir = std::env::temp_dir().join(format!("vox_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(format!("{}.vox", name)), source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vox"))
+        .args(["test", &format!("{}.vox", name)])
+        .current_dir(&dir)
+        .env("EC_CORE_PATH", root.join("coreasm"))
+        .output()
+        .unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    Some(output)
+}
+
+#[test]
+fn passing_examples() {
+    let source = format!(
+        r#"{}
+Example "doubles": ensure "double" of 4 is 8.
+
+Example "doubles zero": ensure "double" of 0 is 0.
+
+Print "program ran".
+"#,
+        DOUBLE
+    );
+    let Some(output) = vox_test("passing_examples", &source) else { return };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "PASS doubles\nPASS doubles zero\n2 examples: 2 passed, 0 failed\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn failing_example() {
+    let source = format!(
+        r#"{}
+Example "doubles": ensure "double" of 4 is 8.
+
+Example "doubles wrongly": ensure "double" of 2 is 5, otherwise "two doubled".
+"#,
+        DOUBLE
+    );
+    let Some(output) = vox_test("failing_example", &source) else { return };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "PASS doubles\n\
+         FAIL doubles wrongly\n  \
+         failing_example.vox:5: ensure failed: \"double\" of 2 is 5 - two doubled\n\
+         2 examples: 1 passed, 1 failed\n"
+    );
+    assert_eq!(output.status.code(), Some(1));
+}